@@ -0,0 +1,149 @@
+//! Structured model of a Todo list for round-trip editing
+//!
+//! A [`Document`] keeps every line of a Todo list as it was read, tagged with what it is (heading,
+//! task, task comment or any other text). Edits only touch the lines they are about, so writing a
+//! document back gives the Todo list as it was read, but for the edits.
+use crate::task::parse_tasks;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// What a line of a Todo list is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// Markdown heading. Argument is its level (`#` is 1, `##` is 2...).
+    Heading(usize),
+    /// Task of the task list
+    Task { done: bool },
+    /// Comment of the task above (indented line starting with `>`)
+    Comment,
+    /// Anything else
+    Text,
+}
+
+/// Line of a Todo list
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub raw: String,
+    pub kind: Kind,
+}
+
+impl Line {
+    /// Returns the text of the task without its checkbox, or None if the line is not a task
+    pub fn task_text(&self) -> Option<&str> {
+        match self.kind {
+            Kind::Task { .. } => Some(self.raw[6..].trim_end()),
+            _ => None,
+        }
+    }
+}
+
+/// Todo list as a sequence of lines
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    pub lines: Vec<Line>,
+    /// True if the Todo list ends with a newline
+    newline_at_end: bool,
+}
+
+impl Document {
+    /// Returns the document of the Todo list. Tasks are the ones of [`parse_tasks`].
+    pub fn parse(todo_raw: &str) -> Document {
+        let mut lines = todo_raw
+            .lines()
+            .map(|raw| Line {
+                raw: raw.to_string(),
+                kind: match raw.find(|c| c != '#') {
+                    Some(level) if level > 0 && raw[level..].starts_with(' ') => {
+                        Kind::Heading(level)
+                    }
+                    _ => Kind::Text,
+                },
+            })
+            .collect::<Vec<_>>();
+        for task in parse_tasks(todo_raw) {
+            lines[task.line].kind = Kind::Task { done: task.done };
+            for comment in &mut lines[task.line + 1..=task.last_line] {
+                comment.kind = Kind::Comment;
+            }
+        }
+        Document {
+            lines,
+            newline_at_end: todo_raw.ends_with('\n'),
+        }
+    }
+
+    /// Returns the title of the Todo list (`# Title`)
+    pub fn title(&self) -> Option<&str> {
+        self.lines
+            .iter()
+            .find(|l| l.kind == Kind::Heading(1))
+            .map(|l| l.raw[2..].trim())
+    }
+
+    /// Returns the indices of the lines holding tasks
+    pub fn tasks(&self) -> Vec<usize> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| matches!(l.kind, Kind::Task { .. }))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Checks or unchecks the task at line `i`. Returns false if there is no task at that line.
+    pub fn set_done(&mut self, i: usize, done: bool) -> bool {
+        let line = match self.lines.get_mut(i) {
+            Some(line) if matches!(line.kind, Kind::Task { .. }) => line,
+            _ => return false,
+        };
+        line.raw
+            .replace_range(0..6, if done { "* [x] " } else { "* [ ] " });
+        line.kind = Kind::Task { done };
+        true
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", line.raw)?;
+        }
+        if self.newline_at_end {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn edit_tasks_round_trip() {
+        let todo_raw = "# Groceries\n\n## Description\n\nLABEL=home\n\n## Todo list\n\n* [ ] milk\n  > the fresh one\n* [x] bread\n\n### Dairy\n\n* [ ] cheese\n\n## Archive\n\n* [x] eggs\n";
+        let mut document = Document::parse(todo_raw);
+        assert_eq!(document.to_string(), todo_raw);
+        assert_eq!(document.title(), Some("Groceries"));
+        assert_eq!(document.tasks(), vec![8, 10, 14]);
+        assert_eq!(document.lines[9].kind, Kind::Comment);
+        assert_eq!(document.lines[12].kind, Kind::Heading(3));
+        assert_eq!(document.lines[8].task_text(), Some("milk"));
+
+        assert!(document.set_done(8, true));
+        assert!(document.set_done(10, false));
+        // archived tasks are not tasks of the task list
+        assert!(!document.set_done(18, false));
+        assert_eq!(
+            document.to_string(),
+            todo_raw
+                .replace("* [ ] milk", "* [x] milk")
+                .replace("* [x] bread", "* [ ] bread")
+        );
+    }
+}
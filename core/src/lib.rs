@@ -0,0 +1,35 @@
+//! Parser and model of Todo lists, free of IO
+//!
+//! The todo CLI reads and writes files, this crate only turns the text of a Todo list into tasks,
+//! sections and documents and back. It does not need std, only an allocator, so it builds for
+//! `wasm32-unknown-unknown`: a web viewer can render and tick Todo lists with the parsing logic of
+//! the CLI.
+//!
+//! * [`todo_list`]: title, labels, description, task list and motives of a Todo list
+//! * [`task`]: tasks and sections of the task list
+//! * [`document`]: round-trip editing of a Todo list, line by line
+#![no_std]
+
+extern crate alloc;
+
+pub mod document;
+pub mod task;
+pub mod todo_list;
+
+use alloc::boxed::Box;
+use once_cell::race::OnceBox;
+use regex::Regex;
+
+pub use document::{Document, Kind, Line};
+pub use task::{
+    parse_due_date, parse_priority, parse_sections, parse_tasks, Section, Task, ARCHIVE_HEADING,
+};
+pub use todo_list::{
+    parse_todo_list, parse_todo_list_motives, parse_todo_list_parts, ParseError, ParsedTodoList,
+    TodoListParts,
+};
+
+/// Returns the regex of `cell`, compiled from `pattern` on first use
+pub(crate) fn regex(cell: &'static OnceBox<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Box::new(Regex::new(pattern).unwrap()))
+}
@@ -0,0 +1,268 @@
+//! Tasks and sections of the task list of a Todo list
+//!
+//! The task list is the `## Todo list` section of a Todo list, or the whole Todo list but its
+//! archive when it has no such section. Tasks are checkbox lines (`* [ ] task`, `* [x] task`) and
+//! headings deeper than `## ` start sections.
+use crate::regex;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use once_cell::race::OnceBox;
+use regex::Regex;
+
+/// Heading of the archive of a Todo list, where done tasks are kept out of the task list
+pub const ARCHIVE_HEADING: &str = "## Archive";
+
+/// Represents a task of the task list of a Todo list
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Task {
+    /// True if the task is checked
+    pub done: bool,
+    /// Text of the task without its checkbox
+    pub text: String,
+    /// Name of the innermost task list section (`### Section`, `#### Subsection`...) the task
+    /// belongs to, if any
+    pub section: Option<String>,
+    /// Comments attached to the task (indented lines starting with `>` below the task)
+    pub comments: Vec<String>,
+    /// Index of the line of the task inside the Todo list
+    pub line: usize,
+    /// Index of the last line belonging to the task (comments included)
+    pub last_line: usize,
+}
+
+impl Task {
+    /// Returns a task outside of any section and without comments, not yet part of a Todo list
+    pub fn new(text: &str, done: bool) -> Task {
+        Task {
+            done,
+            text: text.to_string(),
+            section: None,
+            comments: vec![],
+            line: 0,
+            last_line: 0,
+        }
+    }
+
+    /// Returns the priority of the task, see [`parse_priority`]
+    pub fn priority(&self) -> Option<char> {
+        parse_priority(self.text.as_str())
+    }
+}
+
+/// Represents a section of the task list of a Todo list
+///
+/// Any heading deeper than `## ` inside the task list starts a section, which ends at the next
+/// heading of the same or a shallower depth. Sections started by deeper headings inside a section
+/// are its subsections. Direct tasks of a section are the ones outside its subsections, while
+/// `done` and `total` count tasks of its subsections too.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Section {
+    pub name: String,
+    /// Number of `#` of the heading, 3 for `### Section`
+    pub depth: usize,
+    /// Name of the enclosing section, if this is a subsection
+    pub parent: Option<String>,
+    /// Index of the line of the heading inside the Todo list
+    pub line: usize,
+    /// Index of the last line belonging to the section (subsections included)
+    pub last_line: usize,
+    pub direct_done: usize,
+    pub direct_total: usize,
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Section {
+    /// Returns true if the task belongs to the section or one of its subsections
+    pub fn contains(&self, task: &Task) -> bool {
+        self.line < task.line && task.line <= self.last_line
+    }
+
+    /// Returns true if the section has tasks inside subsections
+    pub fn has_nested_tasks(&self) -> bool {
+        self.direct_total != self.total
+    }
+}
+
+/// Returns the priority of a task whose text starts with an uppercase letter in parentheses, like
+/// `(A) call dentist`. `A` is the highest priority.
+pub fn parse_priority(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+        (Some('('), Some(priority @ 'A'..='Z'), Some(')'), Some(c)) if c.is_whitespace() => {
+            Some(priority)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the due date of a task annotated with `@due(YYYY-MM-DD)`
+pub fn parse_due_date(text: &str) -> Option<chrono::NaiveDate> {
+    static DUE_DATE_RE: OnceBox<Regex> = OnceBox::new();
+    let caps = regex(&DUE_DATE_RE, r"@due\((?P<date>\d{4}-\d{2}-\d{2})\)").captures(text)?;
+    chrono::NaiveDate::parse_from_str(&caps["date"], "%Y-%m-%d").ok()
+}
+
+/// Returns all tasks of the task list of given Todo list
+///
+/// Tasks are read line by line inside the `## Todo list` section (or anywhere when the Todo list
+/// has no such section). Indented lines starting with `>` below a task are comments of that task.
+pub fn parse_tasks(todo_raw: &str) -> Vec<Task> {
+    let mut tasks: Vec<Task> = vec![];
+    let strict = has_task_list_heading(todo_raw);
+    let mut in_task_list = !strict;
+    // enclosing sections by depth, innermost last
+    let mut sections: Vec<(usize, String)> = vec![];
+    let mut last_task_in_section = false;
+    for (i, line) in todo_raw.lines().enumerate() {
+        if line.trim_end() == "## Todo list" {
+            in_task_list = true;
+            continue;
+        }
+        if !in_task_list {
+            continue;
+        }
+        if line.starts_with("## ") {
+            if strict || line.trim_end() == ARCHIVE_HEADING {
+                break;
+            }
+            sections.clear();
+            last_task_in_section = false;
+            continue;
+        }
+        if let Some((depth, name)) = section_heading(line) {
+            sections.retain(|(d, _)| *d < depth);
+            sections.push((depth, name.to_string()));
+            last_task_in_section = false;
+            continue;
+        }
+
+        let checkbox = line.get(0..6);
+        if checkbox == Some("* [ ] ") || checkbox == Some("* [x] ") {
+            tasks.push(Task {
+                done: checkbox == Some("* [x] "),
+                text: line[6..].trim_end().to_string(),
+                section: sections.last().map(|(_, name)| name.clone()),
+                comments: vec![],
+                line: i,
+                last_line: i,
+            });
+            last_task_in_section = true;
+            continue;
+        }
+
+        let is_comment = line.starts_with([' ', '\t']) && line.trim_start().starts_with('>');
+        if is_comment && last_task_in_section {
+            let task = tasks.last_mut().unwrap();
+            task.comments
+                .push(line.trim_start()[1..].trim().to_string());
+            task.last_line = i;
+        }
+    }
+
+    tasks
+}
+
+/// Returns the sections of the task list of given Todo list with their task counts, in order
+pub fn parse_sections(todo_raw: &str) -> Vec<Section> {
+    let strict = has_task_list_heading(todo_raw);
+    let mut in_task_list = !strict;
+    let mut sections: Vec<Section> = vec![];
+    // indices of sections still open, innermost last
+    let mut open: Vec<usize> = vec![];
+    let mut last_line = 0;
+    for (i, line) in todo_raw.lines().enumerate() {
+        if line.trim_end() == "## Todo list" {
+            in_task_list = true;
+            continue;
+        }
+        if !in_task_list {
+            continue;
+        }
+        if line.starts_with("## ") {
+            for s in open.drain(..) {
+                sections[s].last_line = i - 1;
+            }
+            if strict || line.trim_end() == ARCHIVE_HEADING {
+                break;
+            }
+            continue;
+        }
+        last_line = i;
+        if let Some((depth, name)) = section_heading(line) {
+            while let Some(s) = open.last().copied().filter(|s| sections[*s].depth >= depth) {
+                sections[s].last_line = i - 1;
+                open.pop();
+            }
+            sections.push(Section {
+                name: name.to_string(),
+                depth,
+                parent: open.last().map(|s| sections[*s].name.clone()),
+                line: i,
+                last_line: i,
+                direct_done: 0,
+                direct_total: 0,
+                done: 0,
+                total: 0,
+            });
+            open.push(sections.len() - 1);
+        }
+    }
+    for s in open {
+        sections[s].last_line = last_line;
+    }
+
+    for task in parse_tasks(todo_raw) {
+        let mut innermost = None;
+        for (i, section) in sections.iter_mut().enumerate() {
+            if section.contains(&task) {
+                section.total += 1;
+                section.done += usize::from(task.done);
+                innermost = Some(i);
+            }
+        }
+        if let Some(i) = innermost {
+            sections[i].direct_total += 1;
+            sections[i].direct_done += usize::from(task.done);
+        }
+    }
+    sections
+}
+
+/// Returns depth and name of a section heading (`### Section`, `#### Subsection`...)
+pub fn section_heading(line: &str) -> Option<(usize, &str)> {
+    let depth = line.chars().take_while(|c| *c == '#').count();
+    if depth < 3 {
+        return None;
+    }
+    line[depth..]
+        .strip_prefix(' ')
+        .map(|name| (depth, name.trim()))
+}
+
+/// Returns true if Todo list keeps its tasks inside a `## Todo list` section
+pub fn has_task_list_heading(todo_raw: &str) -> bool {
+    todo_raw.lines().any(|l| l.trim_end() == "## Todo list")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tasks_and_sections() {
+        let todo_raw = "# t\n\n## Todo list\n\n* [ ] (A) call dentist\n  > at noon\n\n### Home\n\n* [x] buy milk\n\n## Archive\n\n* [x] old\n";
+        let tasks = parse_tasks(todo_raw);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].priority(), Some('A'));
+        assert_eq!(tasks[0].comments, vec!["at noon".to_string()]);
+        assert_eq!(tasks[1].section.as_deref(), Some("Home"));
+        let sections = parse_sections(todo_raw);
+        assert_eq!((sections[0].done, sections[0].total), (1, 1));
+        assert!(sections[0].contains(&tasks[1]));
+        assert_eq!(parse_priority("(A)call"), None);
+    }
+}
@@ -0,0 +1,263 @@
+//! Title, labels, description, task list and motives of a Todo list
+//!
+//! A Todo list written by `todo create` looks like:
+//!
+//! ```text
+//! # Title
+//!
+//! ## Description
+//!
+//! LABEL=work,home
+//!
+//! ## Todo list
+//!
+//! * [ ] task
+//!
+//! ## Motives
+//!
+//! * motive
+//! ```
+use crate::regex;
+use crate::task::{has_task_list_heading, parse_tasks, ARCHIVE_HEADING};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use once_cell::race::OnceBox;
+use regex::Regex;
+
+/// Represents a parsed Todo list.
+///
+/// A parsed Todo list has many relevant informations such as its name and its task list status.
+pub struct ParsedTodoList {
+    pub raw: String,
+    pub title: String,
+    pub labels: Vec<String>,
+    pub done: usize,
+    pub total: usize,
+}
+
+impl ParsedTodoList {
+    /// Returns true if all items from task list are checked
+    pub fn tasks_are_all_done(&self) -> bool {
+        self.done == self.total
+    }
+}
+
+/// Represents the parts of a Todo list, each without its heading
+///
+/// Parts missing from the Todo list are empty.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TodoListParts {
+    pub title: String,
+    /// Content of `## Description` without the labels
+    pub description: String,
+    /// Content of `## Todo list`, sections included
+    pub tasks: String,
+    /// Content of `## Motives`
+    pub motives: String,
+}
+
+/// Reasons a file is not a Todo list
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParseError {
+    /// The first line is not a title (`# Title`)
+    NoTitle,
+    /// No `LABEL=` line below `## Description`
+    NoLabels,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::NoTitle => write!(f, "Todo list does not have a title"),
+            ParseError::NoLabels => write!(
+                f,
+                "Todo list does not have labels (LABEL= below ## Description)"
+            ),
+        }
+    }
+}
+
+// Regexes which are used at several places
+static TODO_LIST_RE: OnceBox<Regex> = OnceBox::new();
+
+/// Returns parsed Todo list
+///
+/// The motivation for this function is that instead of saving all the content through serializing
+/// with a crate like Serde, the user can open the file and find it editable (think editing a json
+/// vs xml file).
+pub fn parse_todo_list(todo_raw: &str) -> Result<ParsedTodoList, ParseError> {
+    let title = parse_todo_list_title(todo_raw).ok_or(ParseError::NoTitle)?;
+    let labels = parse_todo_list_labels(todo_raw)?;
+    let (done, total) = parse_todo_list_tasks_status(todo_raw);
+    Ok(ParsedTodoList {
+        raw: todo_raw.to_string(),
+        title,
+        labels,
+        done,
+        total,
+    })
+}
+
+/// Returns the parts of the Todo list: title, description, task list and motives
+pub fn parse_todo_list_parts(todo_raw: &str) -> TodoListParts {
+    let description = parse_todo_list_part(todo_raw, "Description")
+        .lines()
+        .filter(|l| !l.starts_with("LABEL="))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let tasks = match todo_list_re().captures(todo_raw) {
+        Some(cap) => normalize_task(cap.name("list").unwrap().as_str()),
+        // tasks are spread throughout the Todo list
+        None => {
+            let lines = todo_raw.lines().collect::<Vec<_>>();
+            parse_tasks(todo_raw)
+                .iter()
+                .map(|task| lines[task.line..=task.last_line].join("\n"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    };
+    TodoListParts {
+        title: parse_todo_list_title(todo_raw).unwrap_or_default(),
+        description: description.trim().to_string(),
+        tasks,
+        motives: parse_todo_list_part(todo_raw, "Motives"),
+    }
+}
+
+/// Returns the motives of Todo list (items of `## Motives`) without their list markers
+pub fn parse_todo_list_motives(todo_raw: &str) -> Vec<String> {
+    static MOTIVE_RE: OnceBox<Regex> = OnceBox::new();
+    let motive_re = regex(&MOTIVE_RE, r"^(?:\d+\.|[*-])\s+(?P<motive>.+)$");
+    parse_todo_list_part(todo_raw, "Motives")
+        .lines()
+        .filter_map(|l| motive_re.captures(l.trim()))
+        .map(|cap| cap["motive"].trim().to_string())
+        .collect()
+}
+
+/// Returns the content of a part (`## Heading`) of Todo list without its heading, trimmed. The
+/// heading is compared case-insensitively.
+fn parse_todo_list_part(todo_raw: &str, heading: &str) -> String {
+    let mut lines = todo_raw.lines().skip_while(|l| {
+        l.strip_prefix("## ")
+            .is_none_or(|h| !h.trim().eq_ignore_ascii_case(heading))
+    });
+    if lines.next().is_none() {
+        return String::new();
+    }
+    lines
+        .take_while(|l| !l.starts_with("## ") && !l.starts_with("# "))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Returns the regex capturing the task list (`list`) of a Todo list with a `## Todo list` section
+fn todo_list_re() -> &'static Regex {
+    regex(
+        &TODO_LIST_RE,
+        "\n## Todo list\n\n(?sm)(?P<list>.*?)(?-m:$|\n## .*)",
+    )
+}
+
+/// Returns the task list of Todo list
+///
+/// The task list is the content of the `## Todo list` section. Todo lists without that section may
+/// keep tasks under headings of their own, in which case checkboxes are looked up in the whole Todo
+/// list but its archive.
+pub fn parse_task_list(todo_raw: &str) -> String {
+    match todo_list_re().captures(todo_raw) {
+        Some(cap) => cap.name("list").unwrap().as_str().to_string(),
+        None if has_task_list_heading(todo_raw) => String::new(),
+        None => todo_raw
+            .lines()
+            .take_while(|l| l.trim_end() != ARCHIVE_HEADING)
+            .filter(|l| !l.starts_with("# ") && !l.starts_with("## "))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Returns captured task without the whitespace the user might have used to make their Todo list
+/// more readable: trailing spaces and blank lines after the task
+pub fn normalize_task(task: &str) -> String {
+    task.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
+}
+
+/// Returns title from Todo list
+pub fn parse_todo_list_title(todo_raw: &str) -> Option<String> {
+    static TITLE_RE: OnceBox<Regex> = OnceBox::new();
+    regex(&TITLE_RE, r"^# (.+)\n")
+        .captures(todo_raw)
+        .and_then(|caps| caps.get(1))
+        .map(|title| title.as_str().to_string())
+}
+
+/// Returns the detailed informations about the task list of given Todo list. Tasks can be spread
+/// throughout the file. Like [`crate::task::parse_tasks`], only `[ ]` and `[x]` are checkboxes.
+pub fn parse_todo_list_tasks_status(todo_raw: &str) -> (usize, usize) {
+    let todo_list = parse_task_list(todo_raw);
+    static DONE_RE: OnceBox<Regex> = OnceBox::new();
+    let done_re = regex(&DONE_RE, r"(?m)^\* \[[ x]\] .+$");
+    let mut done = 0;
+    let total = done_re.find_iter(todo_list.as_str()).count();
+    for mat in done_re.find_iter(todo_list.as_str()) {
+        if mat.as_str().starts_with("* [x] ") {
+            done += 1;
+        }
+    }
+    (done, total)
+}
+
+/// Returns labels of Todo list
+fn parse_todo_list_labels(todo_raw: &str) -> Result<Vec<String>, ParseError> {
+    static LABEL_RE: OnceBox<Regex> = OnceBox::new();
+    let label_matches = regex(&LABEL_RE, r"## Description\n\nLABEL=(.*)")
+        .captures(todo_raw)
+        .and_then(|caps| caps.get(1))
+        .ok_or(ParseError::NoLabels)?;
+
+    // does collect to vec[""] but empty label is not a valid label
+    Ok(label_matches
+        .as_str()
+        .split(',')
+        .map(|s| s.to_string())
+        .filter(|s| !s.is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn parsed_todo_lists() {
+        let todo_raw = "# Groceries\n\n## Description\n\nLABEL=home,\n\n## Todo list\n\n* [x] milk\n* [ ] eggs\n";
+        let todo_list = parse_todo_list(todo_raw).unwrap();
+        assert_eq!(todo_list.title, "Groceries");
+        assert_eq!(todo_list.labels, vec!["home"]);
+        assert_eq!((todo_list.done, todo_list.total), (1, 2));
+        assert_eq!(
+            parse_todo_list_tasks_status("# T\n\n## Todo list\n\n* [😀] x\n* [x] 日本語\n"),
+            (1, 1)
+        );
+
+        assert_eq!(
+            parse_todo_list("Groceries\n").err(),
+            Some(ParseError::NoTitle)
+        );
+        assert_eq!(
+            parse_todo_list("# Groceries\n\n## Todo list\n").err(),
+            Some(ParseError::NoLabels)
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Parses arbitrary Todo lists: parsers must not panic and must agree on task counts
+//!
+//! Run with `cargo +nightly fuzz run parse` from the repository root.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use todo::parse::{
+    parse_sections, parse_tasks, parse_todo_list, parse_todo_list_sections, parse_todo_list_tasks,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let todo_raw = match std::str::from_utf8(data) {
+        Ok(todo_raw) => todo_raw,
+        Err(_) => return,
+    };
+    let tasks = parse_tasks(todo_raw);
+    let _ = parse_todo_list_sections(todo_raw);
+    let _ = parse_todo_list_tasks(todo_raw, true, true, true, None);
+    let _ = parse_todo_list_tasks(todo_raw, true, true, false, None);
+    for section in parse_sections(todo_raw) {
+        assert!(section.direct_total <= section.total);
+        assert_eq!(
+            section.total,
+            tasks.iter().filter(|t| section.contains(t)).count()
+        );
+        let _ = parse_todo_list_tasks(todo_raw, true, true, false, Some(&section.name));
+    }
+    if let Ok(todo_list) = parse_todo_list(todo_raw) {
+        assert!(todo_list.done <= todo_list.total);
+    }
+});
@@ -0,0 +1,293 @@
+//! Activity feed of git-backed Todo contexts
+//!
+//! Commits touching Todo lists are read from git history and each change is described with the
+//! tasks it affects ("checked 3 tasks in release-1.2") rather than with the raw patch.
+use crate::parse::{parse_tasks, Task};
+use crate::{Configuration, Context};
+use chrono::NaiveDate;
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::path::Path;
+use std::process::Command;
+
+/// Name, previous and new content of a Todo list changed by a commit
+type ChangedTodoList = (String, Option<String>, Option<String>);
+
+/// Returns the Log command
+pub fn log_command() -> App<'static, 'static> {
+    App::new("log")
+        .about("Show activity of git-backed Todo context from git history")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("since")
+                .long("since")
+                .value_name("SINCE")
+                .help("Only show activity more recent than SINCE (e.g. 3d, 1w, 2m, 1y or a date)")
+                .takes_value(true),
+        )
+}
+
+/// Prints activity of active Todo context, most recent commit first
+pub fn log_command_process(
+    args: &ArgMatches,
+    ctx: &Context,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    trace!("log subcommand");
+    let folder = ctx.folder_location.as_str();
+    let date_format = config.date_format(ctx);
+    for (hash, date) in commits(folder, args.value_of("since"))? {
+        let mut changes = vec![];
+        for (name, before, after) in changed_todo_lists(ctx, hash.as_str())? {
+            changes.extend(describe_changes(
+                name.as_str(),
+                before.as_deref(),
+                after.as_deref(),
+            ));
+        }
+        if !changes.is_empty() {
+            // git prints short dates in ISO 8601
+            let date = match NaiveDate::parse_from_str(date.as_str(), "%Y-%m-%d") {
+                Ok(d) => d.format(date_format.as_str()).to_string(),
+                Err(_) => date,
+            };
+            println!("{date} {}", changes.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Returns title and text of tasks checked inside Todo context more recently than `since` (e.g. 1d)
+pub fn checked_tasks(ctx: &Context, since: &str) -> Result<Vec<(String, String)>, std::io::Error> {
+    let mut checked = vec![];
+    for (hash, _) in commits(ctx.folder_location.as_str(), Some(since))? {
+        for (name, before, after) in changed_todo_lists(ctx, hash.as_str())? {
+            let after = match after {
+                Some(after) => after,
+                None => continue,
+            };
+            let before_tasks = parse_tasks(before.as_deref().unwrap_or(""));
+            let title = title(after.as_str(), name.as_str());
+            for task in parse_tasks(after.as_str()).iter().filter(|t| t.done) {
+                if before_tasks
+                    .iter()
+                    .any(|t| !t.done && t.text == task.text && t.section == task.section)
+                {
+                    checked.push((title.clone(), task.text.clone()));
+                }
+            }
+        }
+    }
+    Ok(checked)
+}
+
+/// Returns titles of Todo lists created inside Todo context more recently than `since` (e.g. 1w),
+/// most recent first
+pub fn created_todo_lists(ctx: &Context, since: &str) -> Result<Vec<String>, std::io::Error> {
+    let mut created = vec![];
+    for (hash, _) in commits(ctx.folder_location.as_str(), Some(since))? {
+        for (name, before, after) in changed_todo_lists(ctx, hash.as_str())? {
+            if let (None, Some(after)) = (before, after) {
+                created.push(title(after.as_str(), name.as_str()));
+            }
+        }
+    }
+    Ok(created)
+}
+
+/// Returns hash and date of commits touching `folder`, most recent first
+fn commits(folder: &str, since: Option<&str>) -> Result<Vec<(String, String)>, std::io::Error> {
+    let mut log_args = vec![
+        "log".to_string(),
+        "--format=%H%x09%ad".to_string(),
+        "--date=short".to_string(),
+    ];
+    if let Some(since) = since {
+        log_args.push(format!("--since={}", git_since(since)));
+    }
+    log_args.push("--".to_string());
+    log_args.push(".".to_string());
+    Ok(git(folder, &log_args)?
+        .lines()
+        .filter_map(|commit| commit.split_once('\t'))
+        .map(|(hash, date)| (hash.to_string(), date.to_string()))
+        .collect())
+}
+
+/// Returns name, previous and new content of Todo lists changed by commit. A missing content
+/// means the Todo list was created or deleted.
+fn changed_todo_lists(ctx: &Context, hash: &str) -> Result<Vec<ChangedTodoList>, std::io::Error> {
+    debug!("commit {hash}");
+    let folder = ctx.folder_location.as_str();
+    let changed_files = git(
+        folder,
+        &[
+            "diff-tree",
+            "--root",
+            "--no-commit-id",
+            "--name-only",
+            "-r",
+            hash,
+            "--",
+            ".",
+        ],
+    )?;
+    let mut changed = vec![];
+    for file in changed_files
+        .lines()
+        .filter(|f| ctx.is_todo_list_file(Path::new(f)))
+    {
+        // paths of diff-tree are relative to the root of the repository
+        let before = git(folder, &["show", format!("{hash}^:{file}").as_str()]).ok();
+        let after = git(folder, &["show", format!("{hash}:{file}").as_str()]).ok();
+        let name = Path::new(file)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| file.to_string());
+        changed.push((name, before, after));
+    }
+    Ok(changed)
+}
+
+/// Returns stdout of git command run inside `folder`
+pub(crate) fn git<S: AsRef<std::ffi::OsStr>>(
+    folder: &str,
+    args: &[S],
+) -> Result<String, std::io::Error> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(folder)
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Returns the `--since` value understood by git. Durations like `1w` are expanded, anything
+/// else is passed as is.
+fn git_since(since: &str) -> String {
+    let unit = match since.chars().last() {
+        Some('d') => "days",
+        Some('w') => "weeks",
+        Some('m') => "months",
+        Some('y') => "years",
+        _ => return since.to_string(),
+    };
+    match since[..since.len() - 1].parse::<u32>() {
+        Ok(count) => format!("{count} {unit} ago"),
+        Err(_) => since.to_string(),
+    }
+}
+
+/// Returns the title of a Todo list or else `name`
+fn title(todo_raw: &str, name: &str) -> String {
+    match todo_raw.lines().next().and_then(|l| l.strip_prefix("# ")) {
+        Some(title) if !title.trim().is_empty() => title.trim().to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Returns "1 task" or "N tasks"
+fn tasks(count: usize) -> String {
+    if count == 1 {
+        "1 task".to_string()
+    } else {
+        format!("{count} tasks")
+    }
+}
+
+/// Returns human description of the changes made to Todo list between `before` and `after`. A
+/// missing version means the Todo list was created or deleted.
+fn describe_changes(name: &str, before: Option<&str>, after: Option<&str>) -> Vec<String> {
+    let (before, after) = match (before, after) {
+        (None, Some(after)) => return vec![format!("created {}", title(after, name))],
+        (Some(before), None) => return vec![format!("deleted {}", title(before, name))],
+        (Some(before), Some(after)) => (before, after),
+        (None, None) => return vec![],
+    };
+    let title = title(after, name);
+    let before_tasks = parse_tasks(before);
+    let after_tasks = parse_tasks(after);
+    let find = |tasks: &[Task], task: &Task| {
+        tasks
+            .iter()
+            .find(|t| t.text == task.text && t.section == task.section)
+            .map(|t| t.done)
+    };
+
+    let (mut added, mut checked, mut unchecked, mut removed) = (0, 0, 0, 0);
+    for task in &after_tasks {
+        match find(&before_tasks, task) {
+            None => added += 1,
+            Some(false) if task.done => checked += 1,
+            Some(true) if !task.done => unchecked += 1,
+            _ => {}
+        }
+    }
+    for task in &before_tasks {
+        if find(&after_tasks, task).is_none() {
+            removed += 1;
+        }
+    }
+
+    let mut changes = vec![];
+    if added > 0 {
+        changes.push(format!("added {} to {title}", tasks(added)));
+    }
+    if checked > 0 {
+        changes.push(format!("checked {} in {title}", tasks(checked)));
+    }
+    if unchecked > 0 {
+        changes.push(format!("unchecked {} in {title}", tasks(unchecked)));
+    }
+    if removed > 0 {
+        changes.push(format!("removed {} from {title}", tasks(removed)));
+    }
+    if changes.is_empty() && before != after {
+        changes.push(format!("edited {title}"));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn since_durations() {
+        assert_eq!(git_since("1w"), "1 weeks ago");
+        assert_eq!(git_since("3d"), "3 days ago");
+        assert_eq!(git_since("2024-05-01"), "2024-05-01");
+        assert_eq!(git_since("yesterday"), "yesterday");
+    }
+
+    #[test]
+    fn describe_task_changes() {
+        let before = "# release-1.2\n\n## Todo list\n\n* [ ] a\n* [ ] b\n* [ ] c\n* [x] d\n";
+        let after = "# release-1.2\n\n## Todo list\n\n* [x] a\n* [x] b\n* [x] c\n* [ ] e\n";
+        assert_eq!(
+            describe_changes("release", Some(before), Some(after)),
+            vec![
+                "added 1 task to release-1.2",
+                "checked 3 tasks in release-1.2",
+                "removed 1 task from release-1.2"
+            ]
+        );
+        assert_eq!(
+            describe_changes("shopping", None, Some("# shopping\n")),
+            vec!["created shopping"]
+        );
+        assert_eq!(
+            describe_changes("notes", Some("no title\n"), None),
+            vec!["deleted notes"]
+        );
+        assert_eq!(
+            describe_changes("notes", Some("# notes\n"), Some("# notes\n\nmore\n")),
+            vec!["edited notes"]
+        );
+    }
+}
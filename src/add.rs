@@ -0,0 +1,136 @@
+//! Add tasks to a Todo list of active Todo context without opening an editor
+//!
+//! `todo add -t TITLE "buy milk"` adds `* [ ] buy milk` after the last task of the task list
+//! (`## Todo list`). With `--section NAME`, the task is added to that section of the task list
+//! instead. The rest of the Todo list is left as it is.
+use crate::lock::ensure_unlocked;
+use crate::parse::{insert_task_lines, parse_todo_list_sections};
+use crate::{path_arg, todo_path_from_args, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::fs::read_to_string;
+
+/// Returns the Add command
+pub fn add_command() -> App<'static, 'static> {
+    App::new("add")
+        .about("Add a task to todo list within Todo context")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .help("Title of todo list")
+                .takes_value(true)
+                .required_unless("path"),
+        )
+        .arg(path_arg())
+        .arg(
+            Arg::with_name("task")
+                .value_name("TASK")
+                .index(1)
+                .help("Text of the task to add")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("section")
+                .long("section")
+                .value_name("SECTION")
+                .help("Adds the task to this section of the task list (case insensitive)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Add task even if todo list is locked"),
+        )
+}
+
+/// Adds an open task to a Todo list in active Todo context
+pub fn add_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("add subcommand");
+    let task = args.value_of("task").unwrap().trim();
+    if task.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Task is empty",
+        ));
+    }
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    let todo_raw = read_to_string(&filepath)?;
+    let todo_raw = add_task(todo_raw.as_str(), task, args.value_of("section"))?;
+    std::fs::write(&filepath, todo_raw)?;
+    debug!("wrote {filepath}");
+    println!("Added \"{task}\" to \"{title}\"");
+    Ok(())
+}
+
+/// Returns Todo list with an open task added to the task list, or to the section of the task list
+pub(crate) fn add_task(
+    todo_raw: &str,
+    task: &str,
+    section: Option<&str>,
+) -> Result<String, std::io::Error> {
+    let section = match section {
+        Some(section) => {
+            let available = parse_todo_list_sections(todo_raw);
+            match available
+                .iter()
+                .find(|s| s.to_lowercase() == section.to_lowercase())
+            {
+                Some(s) => Some(s.to_string()),
+                None if available.is_empty() => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("No section \"{section}\" in Todo list which has no sections"),
+                    ))
+                }
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "No section \"{section}\" in Todo list. Available sections: {}",
+                            available.join(", ")
+                        ),
+                    ))
+                }
+            }
+        }
+        None => None,
+    };
+    Ok(insert_task_lines(
+        todo_raw,
+        section.as_deref(),
+        &[format!("* [ ] {task}")],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_task_to_section() {
+        let todo_raw = "# Groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] bread\n\n### Dairy\n\n* [ ] cheese\n\n### Hardware\n";
+        assert_eq!(
+            add_task(todo_raw, "buy milk", Some("dairy")).unwrap(),
+            "# Groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] bread\n\n### Dairy\n\n* [ ] cheese\n* [ ] buy milk\n\n### Hardware\n"
+        );
+        assert_eq!(
+            add_task(todo_raw, "nails", Some("Hardware")).unwrap(),
+            "# Groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] bread\n\n### Dairy\n\n* [ ] cheese\n\n### Hardware\n\n* [ ] nails\n"
+        );
+        assert_eq!(
+            add_task(todo_raw, "eggs", None).unwrap(),
+            "# Groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] bread\n* [ ] eggs\n\n### Dairy\n\n* [ ] cheese\n\n### Hardware\n"
+        );
+        let missing = add_task(todo_raw, "seeds", Some("Garden")).unwrap_err();
+        assert!(missing
+            .to_string()
+            .ends_with("Available sections: Dairy, Hardware"));
+    }
+}
@@ -0,0 +1,216 @@
+//! Age of open tasks
+//!
+//! Todo lists do not record when a task was written. When a Todo context tracks ages, `todo list`
+//! records the day it first sees each open task inside the `.todo-first-seen.toml` file of the Todo
+//! folder. Tasks are identified by the title of their Todo list and their text: rewording a task
+//! makes it new. Tasks which are done or removed are forgotten.
+//!
+//! Ages are tracked when the Todo context sets `stale_after_days`, which flags older open tasks in
+//! `todo list` output, or when `todo list --stale AGE` shows only open tasks older than AGE.
+use crate::parse::parse_tasks;
+use crate::single_file::title_of;
+use crate::Context;
+use chrono::NaiveDate;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File of the Todo folder recording when open tasks were first seen
+pub const FIRST_SEEN_FILE: &str = ".todo-first-seen.toml";
+
+/// Format of recorded days
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+/// Day each open task was first seen
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct FirstSeen {
+    /// Days by title of Todo list, then by task text
+    #[serde(default)]
+    tasks: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl FirstSeen {
+    /// Returns what was recorded for the Todo context. Nothing is recorded without file.
+    pub fn read(ctx: &Context) -> Result<FirstSeen, std::io::Error> {
+        match std::fs::read_to_string(first_seen_path(ctx)) {
+            Ok(raw) => Ok(toml::from_str(raw.as_str())?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(FirstSeen::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Records `today` for open tasks never seen and forgets tasks which are no longer open.
+    /// `todo_raws` are all Todo lists of the Todo context.
+    ///
+    /// Returns true if anything changed.
+    pub fn update<'a>(
+        &mut self,
+        todo_raws: impl Iterator<Item = &'a str>,
+        today: NaiveDate,
+    ) -> bool {
+        let today = today.format(DAY_FORMAT).to_string();
+        let mut tasks: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for todo_raw in todo_raws {
+            let title = title_of(todo_raw);
+            let seen = self.tasks.get(title);
+            let open_tasks = tasks.entry(title.to_string()).or_default();
+            for task in parse_tasks(todo_raw).into_iter().filter(|t| !t.done) {
+                let day = seen
+                    .and_then(|s| s.get(&task.text))
+                    .cloned()
+                    .unwrap_or_else(|| today.clone());
+                open_tasks.insert(task.text, day);
+            }
+        }
+        tasks.retain(|_, open_tasks| !open_tasks.is_empty());
+        let changed = tasks != self.tasks;
+        self.tasks = tasks;
+        changed
+    }
+
+    /// Returns the number of open tasks recorded
+    pub fn open_tasks(&self) -> usize {
+        self.tasks.values().map(|open_tasks| open_tasks.len()).sum()
+    }
+
+    /// Writes what was recorded inside the Todo folder of the Todo context
+    pub fn write(&self, ctx: &Context) -> Result<(), std::io::Error> {
+        let path = first_seen_path(ctx);
+        std::fs::write(&path, toml::to_string(self).map_err(std::io::Error::other)?)?;
+        debug!("wrote {}", path.display());
+        Ok(())
+    }
+
+    /// Returns the age in days of the open task of the Todo list titled `title`, if it was seen
+    fn age(&self, title: &str, task: &str, today: NaiveDate) -> Option<u64> {
+        let day = self.tasks.get(title)?.get(task)?;
+        let day = NaiveDate::parse_from_str(day, DAY_FORMAT).ok()?;
+        Some((today - day).num_days().max(0) as u64)
+    }
+}
+
+/// Ages of open tasks of a Todo context, as of today
+pub struct TaskAges {
+    first_seen: FirstSeen,
+    today: NaiveDate,
+    /// Open tasks at least this many days old are flagged
+    pub flag_after: Option<u64>,
+    /// Only open tasks at least this many days old are shown
+    pub only_older_than: Option<u64>,
+}
+
+impl TaskAges {
+    pub fn new(first_seen: FirstSeen, today: NaiveDate) -> TaskAges {
+        TaskAges {
+            first_seen,
+            today,
+            flag_after: None,
+            only_older_than: None,
+        }
+    }
+
+    /// Records open tasks of `todo_raws`, all Todo lists of the Todo context, seen for the first
+    /// time today
+    pub fn record<'a>(
+        mut self,
+        ctx: &Context,
+        todo_raws: impl Iterator<Item = &'a str>,
+    ) -> Result<(), std::io::Error> {
+        if self.first_seen.update(todo_raws, self.today) {
+            self.first_seen.write(ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the age in days of the open task of the Todo list titled `title`, if it was seen
+    pub fn age(&self, title: &str, task: &str) -> Option<u64> {
+        self.first_seen.age(title, task, self.today)
+    }
+
+    /// Returns `text` of the Todo list titled `title` where open tasks older than `flag_after` are
+    /// flagged with their age, and colored when `color` is set
+    pub fn flag(&self, title: &str, text: &str, color: bool) -> String {
+        let flag_after = match self.flag_after {
+            Some(days) => days,
+            None => return text.to_string(),
+        };
+        let mut flagged = String::new();
+        for line in text.split_inclusive('\n') {
+            let (content, newline) = match line.strip_suffix('\n') {
+                Some(content) => (content, "\n"),
+                None => (line, ""),
+            };
+            let age = content
+                .strip_prefix("* [ ] ")
+                .and_then(|task| self.age(title, task.trim_end()))
+                .filter(|age| *age >= flag_after);
+            match age {
+                Some(age) if color => {
+                    flagged.push_str(format!("\x1b[33m{content} ({age} days old)\x1b[0m").as_str())
+                }
+                Some(age) => flagged.push_str(format!("{content} ({age} days old)").as_str()),
+                None => flagged.push_str(content),
+            }
+            flagged.push_str(newline);
+        }
+        flagged
+    }
+}
+
+/// Returns true if `todo list` tracks ages of open tasks of the Todo context
+pub fn tracks_ages(ctx: &Context, stale: bool) -> bool {
+    (stale || ctx.stale_after_days.is_some()) && Path::new(ctx.folder_location.as_str()).is_dir()
+}
+
+/// Returns the number of days of an age like `90d`, `12w` or `90`
+pub fn parse_age(age: &str) -> Result<u64, std::io::Error> {
+    let (count, days) = match age.strip_suffix('w') {
+        Some(weeks) => (weeks, 7),
+        None => (age.strip_suffix('d').unwrap_or(age), 1),
+    };
+    count.parse::<u64>().map(|count| count * days).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("\"{age}\" is not an age like 90d, 12w or 90 (days)"),
+        )
+    })
+}
+
+/// Returns the path of the file recording when open tasks of the Todo context were first seen
+fn first_seen_path(ctx: &Context) -> PathBuf {
+    Path::new(ctx.folder_location.as_str()).join(FIRST_SEEN_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_seen_ages() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, DAY_FORMAT).unwrap();
+        let release =
+            "# Release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] tag\n* [x] changelog\n";
+        let mut first_seen = FirstSeen::default();
+        assert!(first_seen.update([release].into_iter(), day("2026-01-01")));
+        assert!(!first_seen.update([release].into_iter(), day("2026-02-01")));
+
+        let release = release.replace("* [ ] tag\n", "* [ ] tag\n* [ ] announce\n");
+        assert!(first_seen.update([release.as_str()].into_iter(), day("2026-03-01")));
+        let mut ages = TaskAges::new(first_seen, day("2026-04-01"));
+        assert_eq!(ages.age("Release", "tag"), Some(90));
+        assert_eq!(ages.age("Release", "announce"), Some(31));
+        assert_eq!(ages.age("Release", "changelog"), None);
+
+        ages.flag_after = Some(60);
+        assert_eq!(
+            ages.flag("Release", "* [ ] tag\n* [ ] announce\n", false),
+            "* [ ] tag (90 days old)\n* [ ] announce\n"
+        );
+
+        assert_eq!(parse_age("90d").unwrap(), 90);
+        assert_eq!(parse_age("2w").unwrap(), 14);
+        assert_eq!(parse_age("3").unwrap(), 3);
+        assert!(parse_age("soon").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Put away fully completed Todo lists with `todo archive`
+//!
+//! `todo archive TITLE` moves a Todo list whose tasks are all done into the `archive/` subfolder of
+//! the Todo folder. `todo list` skips that folder unless `--archived` is given, in which case only
+//! archived Todo lists are listed. `todo unarchive TITLE` moves an archived Todo list back into the
+//! Todo folder. Parts of Todo lists (see [`crate::parts`]) move along. Todo contexts stored inside
+//! a single file have no archive folder.
+use crate::lock::ensure_unlocked;
+use crate::parse::parse_todo_list;
+use crate::parts::{is_part, part_paths, Parts};
+use crate::{resolve_todo_path, Context, ARCHIVE_FOLDER};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Returns the Archive command
+pub fn archive_command() -> App<'static, 'static> {
+    App::new("archive")
+        .about("Move fully completed todo list into the archive folder of Todo context")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .index(1)
+                .help("Title of todo list")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Archive todo list even if it is locked"),
+        )
+}
+
+/// Returns the Unarchive command
+pub fn unarchive_command() -> App<'static, 'static> {
+    App::new("unarchive")
+        .about("Move archived todo list back into the folder of Todo context")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .index(1)
+                .help("Title of archived todo list")
+                .takes_value(true)
+                .required(true),
+        )
+}
+
+/// Moves the fully completed Todo list into the archive folder of active Todo context
+pub fn archive_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("archive subcommand");
+    let title = args.value_of("title").unwrap();
+    ensure_archive_folder_is_supported(ctx)?;
+    let filepath = resolve_todo_path(ctx, title)?;
+    if Path::new(filepath.as_str()).starts_with(archive_folder(ctx)) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Todo list \"{title}\" is already archived"),
+        ));
+    }
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    let new_path = move_into_archive(ctx, filepath.as_str())?;
+    println!("Archived \"{title}\" into {}", new_path.display());
+    Ok(())
+}
+
+/// Moves the archived Todo list back into the folder of active Todo context
+pub fn unarchive_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("unarchive subcommand");
+    let title = args.value_of("title").unwrap();
+    ensure_archive_folder_is_supported(ctx)?;
+    let filepath = find_archived(ctx, title)?;
+    let new_path = Path::new(ctx.folder_location.as_str()).join(filepath.file_name().unwrap());
+    if new_path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+                "{} already exists, archived Todo list \"{title}\" was not restored",
+                new_path.display()
+            ),
+        ));
+    }
+    let filepath = filepath.to_string_lossy().to_string();
+    for path in std::iter::once(filepath.clone()).chain(part_paths(filepath.as_str())) {
+        let new_path =
+            Path::new(ctx.folder_location.as_str()).join(Path::new(&path).file_name().unwrap());
+        std::fs::rename(&path, &new_path)?;
+        debug!("moved {path} to {}", new_path.display());
+    }
+    println!("Unarchived \"{title}\"");
+    Ok(())
+}
+
+/// Returns the archive folder of the Todo context
+pub fn archive_folder(ctx: &Context) -> PathBuf {
+    Path::new(ctx.folder_location.as_str()).join(ARCHIVE_FOLDER)
+}
+
+/// Moves the fully completed Todo list at `filepath` into the archive folder of the Todo context,
+/// keeping its file name. Returns the new path of the Todo list.
+pub(crate) fn move_into_archive(ctx: &Context, filepath: &str) -> Result<PathBuf, std::io::Error> {
+    let parts = Parts::read(filepath)?;
+    let todo_list = parse_todo_list(parts.joined().as_str()).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{filepath} is not a Todo list: {e}"),
+        )
+    })?;
+    if todo_list.total == 0 || !todo_list.tasks_are_all_done() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Todo list \"{}\" is not fully completed ({}/{} tasks done)",
+                todo_list.title, todo_list.done, todo_list.total
+            ),
+        ));
+    }
+    let archive_folder = archive_folder(ctx);
+    let new_path = archive_folder.join(Path::new(filepath).file_name().unwrap());
+    if new_path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} is already archived", new_path.display()),
+        ));
+    }
+    std::fs::create_dir_all(&archive_folder)?;
+    for path in &parts.paths {
+        std::fs::rename(
+            path,
+            archive_folder.join(Path::new(path).file_name().unwrap()),
+        )?;
+        debug!("moved {path} into {}", archive_folder.display());
+    }
+    Ok(new_path)
+}
+
+/// Returns the path of the archived Todo list titled `title`, or whose file is named after `title`
+fn find_archived(ctx: &Context, title: &str) -> Result<PathBuf, std::io::Error> {
+    let archive_folder = archive_folder(ctx);
+    let not_found = || {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No archived Todo list is titled \"{title}\""),
+        )
+    };
+    if !archive_folder.is_dir() {
+        return Err(not_found());
+    }
+    for entry in WalkDir::new(&archive_folder) {
+        let entry = entry.map_err(std::io::Error::other)?;
+        if !entry.file_type().is_file()
+            || !ctx.is_todo_list_file(entry.path())
+            || is_part(entry.path())
+        {
+            continue;
+        }
+        let named_after_title = entry.path().file_stem().is_some_and(|stem| stem == title);
+        let todo_raw = std::fs::read_to_string(entry.path())?;
+        if named_after_title
+            || parse_todo_list(todo_raw.as_str()).is_ok_and(|todo_list| todo_list.title == title)
+        {
+            return Ok(entry.into_path());
+        }
+    }
+    Err(not_found())
+}
+
+/// Returns an error for Todo contexts stored inside a single file, which have no archive folder
+fn ensure_archive_folder_is_supported(ctx: &Context) -> Result<(), std::io::Error> {
+    if ctx.is_single_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!(
+                "Todo context \"{}\" stores its Todo lists inside a single file and has no archive folder",
+                ctx.name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn archive_and_unarchive() {
+        let root = TempFolder::new("archive");
+        let done = root.join("done.md");
+        std::fs::write(
+            &done,
+            "# finished\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] first\n",
+        )
+        .unwrap();
+        let open = root.join("open.md");
+        std::fs::write(
+            &open,
+            "# open\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first\n",
+        )
+        .unwrap();
+        let ctx = Context {
+            name: "ctx".to_string(),
+            folder_location: root.location(),
+            ..Default::default()
+        };
+
+        assert!(move_into_archive(&ctx, open.to_str().unwrap()).is_err());
+        let archived = move_into_archive(&ctx, done.to_str().unwrap()).unwrap();
+        assert_eq!(archived, root.join(ARCHIVE_FOLDER).join("done.md"));
+        assert!(!done.exists());
+
+        assert_eq!(find_archived(&ctx, "finished").unwrap(), archived);
+        assert_eq!(find_archived(&ctx, "done").unwrap(), archived);
+        assert!(find_archived(&ctx, "open").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Run many subcommands in a single process
+//!
+//! `todo batch` reads one invocation per line from stdin, either as a shell-like command line
+//! (`list --open`, `quick "pay rent" --due 2024-07-01`) or as a JSON array of arguments
+//! (`["quick", "pay rent"]`). Blank lines and lines starting with `#` are skipped. The configuration
+//! is parsed once for all invocations.
+use crate::json::{parse_json, Json};
+use clap::{crate_authors, App, Arg};
+use std::io::Read;
+
+/// Returns the Batch command
+pub fn batch_command() -> App<'static, 'static> {
+    App::new("batch")
+        .about("Run subcommands read from stdin, one per line, in a single process")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("keep-going")
+                .short("k")
+                .long("keep-going")
+                .help("Run remaining invocations after one fails"),
+        )
+}
+
+/// Returns the invocations read from stdin with their line number
+pub fn read_invocations() -> Result<Vec<(usize, Vec<String>)>, std::io::Error> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    parse_invocations(input.as_str())
+}
+
+/// Returns the arguments of each invocation with its line number
+fn parse_invocations(input: &str) -> Result<Vec<(usize, Vec<String>)>, std::io::Error> {
+    let mut invocations = vec![];
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let args = if line.starts_with('[') {
+            json_arguments(line)
+        } else {
+            split_command_line(line)
+        };
+        match args {
+            Ok(args) => invocations.push((i + 1, args)),
+            Err(e) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("line {}: {e}", i + 1),
+                ))
+            }
+        }
+    }
+    Ok(invocations)
+}
+
+/// Returns the arguments of a JSON array of strings
+fn json_arguments(line: &str) -> Result<Vec<String>, String> {
+    match parse_json(line).map_err(|e| e.to_string())? {
+        Json::Array(values) => values
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(|a| a.to_string())
+                    .ok_or_else(|| "arguments must be strings".to_string())
+            })
+            .collect(),
+        _ => Err("expected an array of arguments".to_string()),
+    }
+}
+
+/// Returns the arguments of a command line split at whitespace outside quotes. Backslashes escape
+/// the next character outside single quotes.
+fn split_command_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = vec![];
+    let mut arg: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', Some('"') | None) => {
+                let escaped = chars.next().ok_or("trailing backslash")?;
+                arg.get_or_insert_with(String::new).push(escaped);
+            }
+            (c, Some(q)) if c == q => quote = None,
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                arg.get_or_insert_with(String::new);
+            }
+            (c, None) if c.is_whitespace() => args.extend(arg.take()),
+            (c, _) => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_batch_input() {
+        let input = "# morning routine\nlist --open\n\nquick \"pay rent\" '' it\\'s\n[\"create\", \"Trip \\\"2024\\\"\"]\n";
+        assert_eq!(
+            parse_invocations(input).unwrap(),
+            vec![
+                (2, vec!["list".to_string(), "--open".to_string()]),
+                (
+                    4,
+                    vec![
+                        "quick".to_string(),
+                        "pay rent".to_string(),
+                        "".to_string(),
+                        "it's".to_string()
+                    ]
+                ),
+                (5, vec!["create".to_string(), "Trip \"2024\"".to_string()]),
+            ]
+        );
+        assert!(parse_invocations("list\nquick \"pay rent").is_err());
+        assert!(parse_invocations("[\"list\", 1]").is_err());
+    }
+}
@@ -0,0 +1,648 @@
+//! Synchronise Todo lists with CalDAV task collections (VTODO)
+//!
+//! Each Todo list of a Todo context is mapped to the calendar named after its file inside the
+//! calendar home set with `caldav.url` in the configuration. Tasks are VTODOs matched by text and
+//! section (stored as category): a checked task is a completed VTODO. Requests are made with
+//! `curl` and credentials come from `~/.netrc`.
+//!
+//! `pull` applies the calendar to the Todo list, `push` applies the Todo list to the calendar and
+//! `sync` does both, resolving tasks checked on one side only with the conflict policy of the Todo
+//! context. Deleted tasks are not synchronised. Nothing is pulled into locked Todo lists unless
+//! `--force` is given.
+//!
+//! Todo lists are transferred concurrently (see [`crate::runtime`]).
+use crate::lock::is_locked;
+use crate::merge_file::insert_task;
+use crate::parse::{parse_tasks, Task};
+use crate::parts::Parts;
+use crate::progress::Progress;
+use crate::runtime::{block_on, concurrently};
+use crate::{read_todo_lists, Context};
+use clap::{crate_authors, App, AppSettings, Arg, ArgMatches};
+use lazy_static::lazy_static;
+use log::{debug, trace, warn};
+use regex::Regex;
+use std::path::Path;
+
+lazy_static! {
+    static ref RESPONSE_RE: Regex =
+        Regex::new(r"(?s)<(?:[\w-]+:)?response[\s>].*?</(?:[\w-]+:)?response>").unwrap();
+    static ref HREF_RE: Regex =
+        Regex::new(r"(?s)<(?:[\w-]+:)?href>(.*?)</(?:[\w-]+:)?href>").unwrap();
+    static ref CALENDAR_DATA_RE: Regex =
+        Regex::new(r"(?s)<(?:[\w-]+:)?calendar-data[^>]*>(.*?)</(?:[\w-]+:)?calendar-data>")
+            .unwrap();
+}
+
+/// Fetches every VTODO of a calendar
+const CALENDAR_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>
+"#;
+
+/// Which side wins when a task is checked on one side only
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ConflictPolicy {
+    /// Checked task wins
+    Completed,
+    Local,
+    Remote,
+}
+
+impl ConflictPolicy {
+    fn parse(policy: Option<&str>) -> Result<ConflictPolicy, std::io::Error> {
+        match policy {
+            None | Some("completed") => Ok(ConflictPolicy::Completed),
+            Some("local") => Ok(ConflictPolicy::Local),
+            Some("remote") => Ok(ConflictPolicy::Remote),
+            Some(p) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unknown CalDAV conflict policy \"{p}\" (completed, local or remote)"),
+            )),
+        }
+    }
+}
+
+/// Task stored on the CalDAV server
+#[derive(Clone, Debug, PartialEq)]
+struct VTodo {
+    /// Path of the resource on the server, None when not uploaded yet
+    href: Option<String>,
+    uid: String,
+    summary: String,
+    done: bool,
+    /// Section of the task
+    category: Option<String>,
+}
+
+impl VTodo {
+    /// Returns the iCalendar object of the task
+    fn to_ics(&self, dtstamp: &str) -> String {
+        let mut ics = format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//todo//EN\r\nBEGIN:VTODO\r\nUID:{}\r\nDTSTAMP:{dtstamp}\r\nSUMMARY:{}\r\nSTATUS:{}\r\n",
+            escape(self.uid.as_str()),
+            escape(self.summary.as_str()),
+            if self.done { "COMPLETED" } else { "NEEDS-ACTION" }
+        );
+        if let Some(category) = &self.category {
+            ics.push_str(format!("CATEGORIES:{}\r\n", escape(category)).as_str());
+        }
+        ics.push_str("END:VTODO\r\nEND:VCALENDAR\r\n");
+        ics
+    }
+}
+
+/// Changes to make on both sides
+#[derive(Debug, PartialEq)]
+struct Reconciliation {
+    /// Todo list with remote changes applied
+    todo_raw: String,
+    /// Number of tasks changed inside the Todo list
+    pulled: usize,
+    /// Tasks to upload
+    to_push: Vec<VTodo>,
+}
+
+/// Returns caldav command which is comprised of multiple subcommands
+pub fn caldav_command() -> App<'static, 'static> {
+    App::new("caldav")
+        .about("Synchronise todo lists of active Todo context with CalDAV task collections")
+        .author(crate_authors!())
+        .setting(AppSettings::SubcommandRequired)
+        .subcommand(
+            App::new("pull")
+                .about("Apply CalDAV tasks to todo lists")
+                .author(crate_authors!())
+                .arg(force_arg()),
+        )
+        .subcommand(
+            App::new("push")
+                .about("Apply todo lists to CalDAV tasks")
+                .author(crate_authors!()),
+        )
+        .subcommand(
+            App::new("sync")
+                .about("Pull then push, resolving conflicts with the context conflict policy")
+                .author(crate_authors!())
+                .arg(force_arg()),
+        )
+}
+
+fn force_arg() -> Arg<'static, 'static> {
+    Arg::with_name("force")
+        .short("f")
+        .long("force")
+        .help("Pull into todo lists even if they are locked")
+}
+
+/// Executes caldav command
+pub fn caldav_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    let settings = match &ctx.caldav {
+        Some(settings) => settings,
+        None => {
+            return Err(std::io::Error::other(format!(
+                "Todo context \"{}\" has no CalDAV settings",
+                ctx.name
+            )))
+        }
+    };
+    let (pull, push, policy) = match args.subcommand_name() {
+        Some("pull") => (true, false, ConflictPolicy::Remote),
+        Some("push") => (false, true, ConflictPolicy::Local),
+        Some("sync") => (
+            true,
+            true,
+            ConflictPolicy::parse(settings.conflict_policy.as_deref())?,
+        ),
+        _ => {
+            warn!("unrecognised command");
+            return Err(std::io::Error::other("Unrecognised command"));
+        }
+    };
+    trace!("caldav {:?}", args.subcommand_name());
+    let force = args
+        .subcommand()
+        .1
+        .is_some_and(|args| args.is_present("force"));
+
+    let home = format!("{}/", settings.url.trim_end_matches('/'));
+    let todo_lists = read_todo_lists(ctx)?;
+    let mut progress = Progress::start(args, "caldav", todo_lists.len());
+    let transfers = todo_lists.into_iter().map(|(filepath, todo_raw)| {
+        // locked Todo lists are left as they are
+        let locked = pull && !force && is_locked(todo_raw.as_str());
+        if locked {
+            eprintln!("Warning: {filepath} is locked, nothing is pulled into it (see --force)");
+        }
+        transfer(
+            home.clone(),
+            filepath,
+            todo_raw,
+            pull && !locked,
+            push,
+            policy,
+        )
+    });
+    block_on(concurrently(transfers, |transferred| {
+        if let Some((name, pulled, pushed)) = transferred {
+            progress.advance(name.as_str());
+            progress.println(
+                format!("{name}: {pulled} task(s) pulled, {pushed} task(s) pushed").as_str(),
+            );
+        }
+    }))?;
+    progress.finish();
+    Ok(())
+}
+
+/// Pulls and/or pushes the tasks of the Todo list at `filepath` from and to its calendar. Returns
+/// the name of the calendar with the number of tasks pulled and pushed, or None when there is
+/// nothing to pull from.
+async fn transfer(
+    home: String,
+    filepath: String,
+    todo_raw: String,
+    pull: bool,
+    push: bool,
+    policy: ConflictPolicy,
+) -> Result<Option<(String, usize, usize)>, std::io::Error> {
+    let name = match Path::new(filepath.as_str()).file_stem() {
+        Some(stem) => stem.to_string_lossy().to_string(),
+        None => return Ok(None),
+    };
+    let calendar = format!("{home}{name}/");
+    let remote = match fetch_vtodos(calendar.as_str()).await? {
+        Some(remote) => remote,
+        None if push => {
+            make_calendar(calendar.as_str(), name.as_str()).await?;
+            vec![]
+        }
+        None => {
+            debug!("no calendar for {filepath}");
+            return Ok(None);
+        }
+    };
+
+    let reconciliation = reconcile(todo_raw.as_str(), &remote, policy);
+    let mut pulled = 0;
+    if pull && reconciliation.todo_raw != todo_raw {
+        let mut parts = Parts::read(filepath.as_str())?;
+        parts.set(reconciliation.todo_raw.as_str())?;
+        parts.write()?;
+        pulled = reconciliation.pulled;
+    }
+    let mut pushed = 0;
+    if push {
+        let dtstamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        for vtodo in &reconciliation.to_push {
+            let url = match &vtodo.href {
+                Some(href) => resource_url(home.as_str(), href),
+                None => format!("{calendar}{}.ics", vtodo.uid),
+            };
+            let (status, _) = curl(
+                "PUT",
+                url.as_str(),
+                &["Content-Type: text/calendar; charset=utf-8"],
+                Some(vtodo.to_ics(dtstamp.as_str()).as_str()),
+            )
+            .await?;
+            if !(200..300).contains(&status) {
+                return Err(std::io::Error::other(format!(
+                    "Task \"{}\" could not be uploaded (HTTP {status})",
+                    vtodo.summary
+                )));
+            }
+            debug!("uploaded {url}");
+            pushed += 1;
+        }
+    }
+    Ok(Some((name, pulled, pushed)))
+}
+
+/// Returns the HTTP status and body of the response. Credentials are read from `~/.netrc`.
+async fn curl(
+    method: &str,
+    url: &str,
+    headers: &[&str],
+    body: Option<&str>,
+) -> Result<(u16, String), std::io::Error> {
+    let mut args = vec![
+        "-s",
+        "--netrc-optional",
+        "-X",
+        method,
+        "-w",
+        "\n%{http_code}",
+        url,
+    ];
+    for header in headers {
+        args.extend(["-H", header]);
+    }
+    if body.is_some() {
+        args.extend(["--data-binary", "@-"]);
+    }
+    let output = crate::runtime::curl(&args, body).await?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "{method} {url} failed (curl exit status {})",
+            output.status
+        )));
+    }
+    let output = String::from_utf8_lossy(&output.stdout).to_string();
+    let (body, status) = output.rsplit_once('\n').unwrap_or(("", output.as_str()));
+    let status = status.trim().parse::<u16>().unwrap_or(0);
+    Ok((status, body.to_string()))
+}
+
+/// Returns the tasks of `calendar`, None when the calendar does not exist
+async fn fetch_vtodos(calendar: &str) -> Result<Option<Vec<VTodo>>, std::io::Error> {
+    let (status, body) = curl(
+        "REPORT",
+        calendar,
+        &["Depth: 1", "Content-Type: application/xml; charset=utf-8"],
+        Some(CALENDAR_QUERY),
+    )
+    .await?;
+    match status {
+        404 => Ok(None),
+        207 => Ok(Some(parse_multistatus(body.as_str()))),
+        _ => Err(std::io::Error::other(format!(
+            "Tasks of \"{calendar}\" could not be fetched (HTTP {status})"
+        ))),
+    }
+}
+
+/// Creates a calendar holding tasks
+async fn make_calendar(calendar: &str, name: &str) -> Result<(), std::io::Error> {
+    let body = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:mkcalendar xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:set><d:prop>
+    <d:displayname>{}</d:displayname>
+    <c:supported-calendar-component-set><c:comp name="VTODO"/></c:supported-calendar-component-set>
+  </d:prop></d:set>
+</c:mkcalendar>
+"#,
+        name.replace('&', "&amp;").replace('<', "&lt;")
+    );
+    let (status, _) = curl(
+        "MKCALENDAR",
+        calendar,
+        &["Content-Type: application/xml; charset=utf-8"],
+        Some(body.as_str()),
+    )
+    .await?;
+    if !(200..300).contains(&status) {
+        return Err(std::io::Error::other(format!(
+            "Calendar \"{calendar}\" could not be created (HTTP {status})"
+        )));
+    }
+    debug!("created calendar {calendar}");
+    Ok(())
+}
+
+/// Returns the URL of the resource at `href` (absolute path) on the server of `home`
+fn resource_url(home: &str, href: &str) -> String {
+    if href.contains("://") {
+        return href.to_string();
+    }
+    let origin_end = home
+        .find("://")
+        .and_then(|i| home[i + 3..].find('/').map(|j| i + 3 + j))
+        .unwrap_or(home.len());
+    format!("{}{href}", &home[..origin_end])
+}
+
+/// Returns the tasks of a WebDAV multistatus response
+fn parse_multistatus(body: &str) -> Vec<VTodo> {
+    let mut vtodos = vec![];
+    for response in RESPONSE_RE.find_iter(body) {
+        let response = response.as_str();
+        let href = HREF_RE
+            .captures(response)
+            .map(|caps| unescape_xml(caps[1].trim()));
+        if let Some(caps) = CALENDAR_DATA_RE.captures(response) {
+            for mut vtodo in parse_ics(unescape_xml(&caps[1]).as_str()) {
+                vtodo.href = href.clone();
+                vtodos.push(vtodo);
+            }
+        }
+    }
+    vtodos
+}
+
+/// Returns the VTODOs of an iCalendar object
+fn parse_ics(ics: &str) -> Vec<VTodo> {
+    let unfolded = ics
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut vtodos = vec![];
+    let mut current: Option<VTodo> = None;
+    for line in unfolded.lines() {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.split(';').next().unwrap_or(name), value),
+            None => continue,
+        };
+        match (name.to_uppercase().as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == "VTODO" => {
+                current = Some(VTodo {
+                    href: None,
+                    uid: String::new(),
+                    summary: String::new(),
+                    done: false,
+                    category: None,
+                })
+            }
+            ("END", Some(_)) if value == "VTODO" => vtodos.extend(current.take()),
+            ("UID", Some(vtodo)) => vtodo.uid = unescape(value),
+            ("SUMMARY", Some(vtodo)) => vtodo.summary = unescape(value).replace('\n', " "),
+            ("STATUS", Some(vtodo)) => vtodo.done = value.eq_ignore_ascii_case("COMPLETED"),
+            ("CATEGORIES", Some(vtodo)) if !value.is_empty() => {
+                vtodo.category = Some(unescape(value))
+            }
+            _ => {}
+        }
+    }
+    vtodos
+}
+
+/// Returns iCalendar text value
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Returns text of iCalendar text value
+fn unescape(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(c) => text.push(c),
+            None => {}
+        }
+    }
+    text
+}
+
+/// Returns text of XML character data
+fn unescape_xml(data: &str) -> String {
+    let data = data.trim();
+    if let Some(cdata) = data
+        .strip_prefix("<![CDATA[")
+        .and_then(|d| d.strip_suffix("]]>"))
+    {
+        return cdata.to_string();
+    }
+    data.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&")
+}
+
+/// Returns changes to make on both sides so they hold the same tasks. Tasks checked on one side
+/// only are resolved with `policy`.
+fn reconcile(todo_raw: &str, remote: &[VTodo], policy: ConflictPolicy) -> Reconciliation {
+    let tasks = parse_tasks(todo_raw);
+    let mut lines = todo_raw.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+    let mut pulled = 0;
+    let mut to_push = vec![];
+    let matches =
+        |vtodo: &VTodo, task: &Task| vtodo.summary == task.text && vtodo.category == task.section;
+
+    for task in &tasks {
+        let vtodo = match remote.iter().find(|v| matches(v, task)) {
+            Some(vtodo) => vtodo,
+            None => {
+                to_push.push(VTodo {
+                    href: None,
+                    uid: task_uid(task),
+                    summary: task.text.clone(),
+                    done: task.done,
+                    category: task.section.clone(),
+                });
+                continue;
+            }
+        };
+        if vtodo.done == task.done {
+            continue;
+        }
+        let done = match policy {
+            ConflictPolicy::Completed => true,
+            ConflictPolicy::Local => task.done,
+            ConflictPolicy::Remote => vtodo.done,
+        };
+        if done != task.done {
+            lines[task.line] = task_line(done, task.text.as_str());
+            pulled += 1;
+        } else {
+            to_push.push(VTodo {
+                done,
+                ..vtodo.clone()
+            });
+        }
+    }
+
+    let mut todo_raw_pulled = lines.join("\n");
+    if todo_raw.ends_with('\n') {
+        todo_raw_pulled.push('\n');
+    }
+    for vtodo in remote {
+        if tasks.iter().any(|t| matches(vtodo, t)) {
+            continue;
+        }
+        let mut task = Task::new(vtodo.summary.as_str(), vtodo.done);
+        task.section = vtodo.category.clone();
+        todo_raw_pulled = insert_task(
+            todo_raw_pulled.as_str(),
+            &task,
+            vec![task_line(task.done, task.text.as_str())],
+        );
+        pulled += 1;
+    }
+
+    Reconciliation {
+        todo_raw: todo_raw_pulled,
+        pulled,
+        to_push,
+    }
+}
+
+/// Returns the markdown line of a task
+fn task_line(done: bool, text: &str) -> String {
+    format!("* [{}] {text}", if done { "x" } else { " " })
+}
+
+/// Returns a stable UID for a task created locally
+fn task_uid(task: &Task) -> String {
+    use sha2::{Digest, Sha256};
+
+    let key = format!("{}\n{}", task.section.as_deref().unwrap_or(""), task.text);
+    let hash = Sha256::digest(key.as_bytes())
+        .iter()
+        .take(16)
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    format!("todo-{hash}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TODO: &str = "# Release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] tag\n* [x] changelog\n\n### Docs\n\n* [ ] readme\n";
+
+    fn vtodo(summary: &str, done: bool, category: Option<&str>) -> VTodo {
+        VTodo {
+            href: Some(format!("/dav/release/{summary}.ics")),
+            uid: format!("remote-{summary}"),
+            summary: summary.to_string(),
+            done,
+            category: category.map(|c| c.to_string()),
+        }
+    }
+
+    #[test]
+    fn ics_roundtrip() {
+        let task = VTodo {
+            href: None,
+            uid: "todo-1".to_string(),
+            summary: "milk, eggs; bread".to_string(),
+            done: true,
+            category: Some("Food".to_string()),
+        };
+        let ics = task.to_ics("20240501T120000Z");
+        assert!(ics.contains("SUMMARY:milk\\, eggs\\; bread\r\n"));
+        assert_eq!(parse_ics(ics.as_str()), vec![task]);
+
+        let folded = "BEGIN:VCALENDAR\nBEGIN:VTODO\nUID:1\nSUMMARY:a very\n  long task\nSTATUS:NEEDS-ACTION\nEND:VTODO\nEND:VCALENDAR\n";
+        assert_eq!(parse_ics(folded)[0].summary, "a very long task");
+    }
+
+    #[test]
+    fn parse_multistatus_response() {
+        let body = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+ <d:response>
+  <d:href>/dav/calendars/me/release/1.ics</d:href>
+  <d:propstat><d:prop><cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VTODO
+UID:1
+SUMMARY:tag &amp; push
+STATUS:COMPLETED
+END:VTODO
+END:VCALENDAR
+</cal:calendar-data></d:prop></d:propstat>
+ </d:response>
+</d:multistatus>"#;
+        let vtodos = parse_multistatus(body);
+        assert_eq!(vtodos.len(), 1);
+        assert_eq!(
+            vtodos[0].href.as_deref(),
+            Some("/dav/calendars/me/release/1.ics")
+        );
+        assert_eq!(vtodos[0].summary, "tag & push");
+        assert!(vtodos[0].done);
+        assert_eq!(
+            resource_url("https://cloud.example.com/dav/calendars/me/", "/dav/1.ics"),
+            "https://cloud.example.com/dav/1.ics"
+        );
+    }
+
+    #[test]
+    fn reconcile_with_policies() {
+        let remote = vec![
+            vtodo("tag", true, None),
+            vtodo("changelog", false, None),
+            vtodo("blog post", false, Some("Docs")),
+        ];
+
+        let r = reconcile(TODO, &remote, ConflictPolicy::Completed);
+        assert_eq!(
+            r.todo_raw,
+            "# Release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] tag\n* [x] changelog\n\n### Docs\n\n* [ ] readme\n* [ ] blog post\n"
+        );
+        assert_eq!(r.pulled, 2);
+        assert_eq!(
+            r.to_push
+                .iter()
+                .map(|v| (v.summary.as_str(), v.done, v.href.is_some()))
+                .collect::<Vec<_>>(),
+            vec![("changelog", true, true), ("readme", false, false)]
+        );
+
+        let r = reconcile(TODO, &remote, ConflictPolicy::Remote);
+        assert!(r.todo_raw.contains("* [x] tag\n* [ ] changelog\n"));
+        assert_eq!(r.to_push.len(), 1);
+
+        let r = reconcile(TODO, &remote, ConflictPolicy::Local);
+        assert!(r.todo_raw.contains("* [ ] tag\n* [x] changelog\n"));
+        assert_eq!(r.to_push.len(), 3);
+    }
+
+    #[test]
+    fn conflict_policies() {
+        assert_eq!(
+            ConflictPolicy::parse(None).unwrap(),
+            ConflictPolicy::Completed
+        );
+        assert_eq!(
+            ConflictPolicy::parse(Some("remote")).unwrap(),
+            ConflictPolicy::Remote
+        );
+        assert!(ConflictPolicy::parse(Some("newest")).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Print a month grid of due tasks
+//!
+//! Each day shows the number of open tasks due that day (`@due(YYYY-MM-DD)`). Past days with open
+//! tasks are marked overdue with `!`.
+use crate::parse::{parse_due_date, parse_tasks};
+use crate::{read_todo_lists, Context};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::trace;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Width of a day inside the grid
+const CELL_WIDTH: usize = 7;
+
+/// Returns the Calendar command
+pub fn calendar_command() -> App<'static, 'static> {
+    App::new("calendar")
+        .about("Show a month grid of open tasks by due date")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("month")
+                .long("month")
+                .value_name("YYYY-MM")
+                .help("Month to show (default is the current month)")
+                .takes_value(true),
+        )
+}
+
+/// Prints month grid of due tasks of active Todo context
+pub fn calendar_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("calendar subcommand");
+    let today = Local::today().naive_local();
+    let month = match args.value_of("month") {
+        Some(month) => {
+            match NaiveDate::parse_from_str(format!("{month}-01").as_str(), "%Y-%m-%d") {
+                Ok(month) => month,
+                Err(e) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Invalid month \"{month}\" (YYYY-MM): {e}"),
+                    ))
+                }
+            }
+        }
+        None => today.with_day(1).unwrap(),
+    };
+
+    let mut due = BTreeMap::new();
+    if Path::new(ctx.folder_location.as_str()).is_dir() {
+        for (_, todo_raw) in read_todo_lists(ctx)? {
+            for task in parse_tasks(todo_raw.as_str()).iter().filter(|t| !t.done) {
+                if let Some(date) = parse_due_date(task.text.as_str()) {
+                    *due.entry(date).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    print!("{}", render(month, today, &due));
+    Ok(())
+}
+
+/// Returns month grid starting on `month` (first day of the month) with the number of open tasks
+/// due each day
+fn render(month: NaiveDate, today: NaiveDate, due: &BTreeMap<NaiveDate, usize>) -> String {
+    let title = format!(
+        "{:^width$}",
+        month.format("%B %Y").to_string(),
+        width = CELL_WIDTH * 6 + 2
+    );
+    let mut grid = format!("{}\n", title.trim_end());
+    for weekday in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"] {
+        grid.push_str(format!("{weekday:>2}{:width$}", "", width = CELL_WIDTH - 2).as_str());
+    }
+    grid = grid.trim_end().to_string();
+    grid.push('\n');
+
+    let offset = month.weekday().num_days_from_monday() as usize;
+    let mut line = " ".repeat(CELL_WIDTH * offset);
+    let mut day = month;
+    while day.month() == month.month() {
+        let tag = match due.get(&day) {
+            Some(count) if day < today => format!("[{count}]!"),
+            Some(count) => format!("[{count}]"),
+            None => String::new(),
+        };
+        line.push_str(format!("{:>2}{tag:<width$}", day.day(), width = CELL_WIDTH - 2).as_str());
+        if day.weekday().num_days_from_monday() == 6 {
+            grid.push_str(format!("{}\n", line.trim_end()).as_str());
+            line.clear();
+        }
+        day += Duration::days(1);
+    }
+    if !line.trim().is_empty() {
+        grid.push_str(format!("{}\n", line.trim_end()).as_str());
+    }
+
+    let overdue_before: usize = due.range(..month.min(today)).map(|(_, count)| count).sum();
+    if overdue_before > 0 {
+        grid.push_str(format!("\n{overdue_before} overdue task(s) before this month\n").as_str());
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_month_grid() {
+        let date = |d| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let due = BTreeMap::from([
+            (date("2024-05-30"), 1),
+            (date("2024-06-03"), 2),
+            (date("2024-06-12"), 1),
+        ]);
+        let grid = render(date("2024-06-01"), date("2024-06-10"), &due);
+        assert_eq!(
+            grid,
+            "                 June 2024\n\
+             Mo     Tu     We     Th     Fr     Sa     Su\n\
+             \x20                                   1      2\n\
+             \x203[2]!  4      5      6      7      8      9\n\
+             10     11     12[1]  13     14     15     16\n\
+             17     18     19     20     21     22     23\n\
+             24     25     26     27     28     29     30\n\
+             \n1 overdue task(s) before this month\n"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Comment tasks of a Todo list in active Todo context
+//!
+//! Comments are indented lines starting with `>` right below the task they belong to.
+use crate::lock::ensure_unlocked;
+use crate::parse::{find_tasks, insert_lines, parse_tasks};
+use crate::{path_arg, todo_path_from_args, Configuration, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use core::fmt;
+use log::{debug, trace};
+use std::fs::read_to_string;
+
+/// Errors for comment command
+#[derive(Debug)]
+pub enum Error {
+    /// The Todo list could not be read or written
+    Io(std::io::Error),
+    /// No task matches the given pattern. Argument is the pattern.
+    NoMatchingTask(String),
+    /// Many tasks match the given pattern.
+    ///
+    /// First argument is the pattern. Second argument are the matching tasks.
+    AmbiguousTask(String, Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => writeln!(f, "{e}"),
+            Error::NoMatchingTask(pattern) => writeln!(f, "No task matches \"{pattern}\"."),
+            Error::AmbiguousTask(pattern, tasks) => {
+                writeln!(f, "Many tasks match \"{pattern}\":")?;
+                for task in tasks {
+                    writeln!(f, "- {task}")?;
+                }
+                writeln!(f, "Please use a more specific pattern.")
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Returns the Comment command
+pub fn comment_command() -> App<'static, 'static> {
+    App::new("comment")
+        .about("Comment a task of todo list within Todo context")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .help("Title of todo list")
+                .takes_value(true)
+                .required_unless("path"),
+        )
+        .arg(path_arg())
+        .arg(
+            Arg::with_name("task")
+                .value_name("TASK")
+                .index(1)
+                .help("Part of the text of the task to comment")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("comment")
+                .value_name("COMMENT")
+                .index(2)
+                .help("Comment to attach to the task")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Comment task even if todo list is locked"),
+        )
+}
+
+/// Appends a timestamped comment to a task of a Todo list in active Todo context
+pub fn comment_command_process(
+    args: &ArgMatches,
+    ctx: &Context,
+    config: &Configuration,
+) -> Result<(), Error> {
+    trace!("comment subcommand");
+    let pattern = args.value_of("task").unwrap();
+    let comment = args.value_of("comment").unwrap();
+
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    let todo_raw = read_to_string(&filepath)?;
+    let timestamp = chrono::Local::now()
+        .format(format!("{} %H:%M", config.date_format(ctx)).as_str())
+        .to_string();
+    let todo_raw = add_comment(todo_raw.as_str(), pattern, comment, timestamp.as_str())?;
+    std::fs::write(&filepath, todo_raw)?;
+    debug!("wrote {filepath}");
+    println!("Commented task matching \"{pattern}\" in \"{title}\"");
+
+    Ok(())
+}
+
+/// Returns Todo list with the comment attached below the last comment of the matching task
+fn add_comment(
+    todo_raw: &str,
+    pattern: &str,
+    comment: &str,
+    timestamp: &str,
+) -> Result<String, Error> {
+    let tasks = parse_tasks(todo_raw);
+    let matches = find_tasks(&tasks, pattern);
+    let task = match matches.as_slice() {
+        [] => return Err(Error::NoMatchingTask(pattern.to_string())),
+        [task] => task,
+        _ => {
+            return Err(Error::AmbiguousTask(
+                pattern.to_string(),
+                matches.iter().map(|t| t.text.to_string()).collect(),
+            ))
+        }
+    };
+
+    Ok(insert_lines(
+        todo_raw,
+        task.last_line + 1,
+        &[format!("  > {timestamp} {comment}")],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TODO_RAW: &str = "\
+# Title
+
+## Description
+
+LABEL=
+
+## Todo list
+
+* [ ] call plumber
+  > 2024-05-01 10:00 left a message
+* [ ] buy paint
+* [ ] buy brush
+";
+
+    #[test]
+    fn comment_is_appended_below_task_comments() {
+        let todo_raw =
+            add_comment(TODO_RAW, "plumber", "waiting on Bob", "2024-05-02 09:00").unwrap();
+        let expected = "\
+# Title
+
+## Description
+
+LABEL=
+
+## Todo list
+
+* [ ] call plumber
+  > 2024-05-01 10:00 left a message
+  > 2024-05-02 09:00 waiting on Bob
+* [ ] buy paint
+* [ ] buy brush
+";
+        assert_eq!(todo_raw, expected);
+        let tasks = parse_tasks(todo_raw.as_str());
+        assert_eq!(tasks[0].comments.len(), 2);
+    }
+
+    #[test]
+    fn comment_requires_exactly_one_task() {
+        match add_comment(TODO_RAW, "dentist", "c", "t") {
+            Err(Error::NoMatchingTask(pattern)) => assert_eq!(pattern, "dentist"),
+            _ => unreachable!(),
+        }
+        match add_comment(TODO_RAW, "buy", "c", "t") {
+            Err(Error::AmbiguousTask(_, tasks)) => {
+                assert_eq!(
+                    tasks,
+                    vec!["buy paint".to_string(), "buy brush".to_string()]
+                )
+            }
+            _ => unreachable!(),
+        }
+    }
+}
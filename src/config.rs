@@ -2,6 +2,7 @@
 use crate::config_active_context::{active_context_command, active_context_command_process};
 use crate::config_create_context::{config_create_context_process, create_context_command};
 use crate::config_get_contexts::{get_contexts_command, get_contexts_command_process};
+use crate::config_schema::{schema_command, schema_command_process};
 use crate::config_set_context::{set_context_command, set_context_command_process};
 use clap::{crate_authors, App, AppSettings, ArgMatches};
 use log::warn;
@@ -16,6 +17,7 @@ pub fn config_command() -> App<'static, 'static> {
         .subcommand(active_context_command())
         .subcommand(get_contexts_command())
         .subcommand(set_context_command())
+        .subcommand(schema_command())
 }
 
 /// Executes configuration command
@@ -44,9 +46,10 @@ pub fn config_command_process(
         );
     }
 
+    if args.subcommand_matches("schema").is_some() {
+        return schema_command_process();
+    }
+
     warn!("unrecognised command");
-    Err(std::io::Error::new(
-        std::io::ErrorKind::Other,
-        "Unrecognised command",
-    ))
+    Err(std::io::Error::other("Unrecognised command"))
 }
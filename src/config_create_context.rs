@@ -1,10 +1,9 @@
 //! Create new Todo context inside configuration
 use super::{parse_configuration_file, Configuration, Context};
+use crate::config_file::write_configuration;
 use clap::{crate_authors, App, Arg, ArgMatches};
 use log::{debug, trace, warn};
 use read_input::prelude::*;
-use std::fs::File;
-use std::io::Write;
 
 /// Returns create-context subcommand from config command
 pub fn create_context_command() -> App<'static, 'static> {
@@ -47,6 +46,14 @@ pub fn create_context_command() -> App<'static, 'static> {
                 .takes_value(true)
                 .required(true),
         )
+        .arg(
+            Arg::with_name("default_labels")
+                .long("default-labels")
+                .value_name("LABELS")
+                .help("Labels of every todo list created within this context (work,urgent)")
+                .value_delimiter(",")
+                .takes_value(true),
+        )
 }
 
 /// Creates new Todo context inside configuration, then sets it to be the active context
@@ -61,6 +68,13 @@ pub fn config_create_context_process(
         name: args.value_of("name").unwrap().to_string(),
         timezone: args.value_of("timezone").unwrap().to_string(),
         folder_location: args.value_of("todo_folder").unwrap().to_string(),
+        default_labels: args
+            .values_of("default_labels")
+            .unwrap_or_default()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect(),
+        ..Default::default()
     };
 
     let config = parse_configuration_file(Some(todo_configuration_path), raw_config);
@@ -86,6 +100,7 @@ pub fn config_create_context_process(
             Configuration {
                 active_ctx_name: String::from(""),
                 ctxs: vec![],
+                ..Default::default()
             }
         }
         Ok(config) => config,
@@ -100,19 +115,9 @@ pub fn config_create_context_process(
         c.name == config.active_ctx_name
     }) {
         warn!("No contexts matched active context");
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "No contexts matched active context",
-        ));
+        return Err(std::io::Error::other("No contexts matched active context"));
     }
-    let mut file = std::fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(todo_configuration_path)?;
-    let raw_config = toml::to_string(&config).unwrap();
-    debug!("raw_config:\n{}", raw_config);
-    File::write(&mut file, raw_config.as_bytes())?;
+    write_configuration(todo_configuration_path, &config)?;
 
     println!(
         "Successfully updated configuration at \"{}\"\nConfiguration was switched to `{}`",
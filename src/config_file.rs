@@ -0,0 +1,184 @@
+//! Write the configuration file without losing what the user wrote
+//!
+//! Commands changing the configuration (`todo config set-context`, `todo config create-context`,
+//! `todo pin`...) update the configuration file in place: comments, formatting and the order of
+//! keys and tables are preserved, and only changed values are rewritten.
+use crate::Configuration;
+use log::debug;
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, Value};
+
+/// Writes `config` to the configuration file, updating the configuration file already there
+pub(crate) fn write_configuration(
+    todo_configuration_path: &str,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    let raw_config = toml::to_string(config).map_err(std::io::Error::other)?;
+    let raw_config = match std::fs::read_to_string(todo_configuration_path) {
+        Ok(existing) => updated(existing.as_str(), raw_config.as_str()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => raw_config,
+        Err(e) => return Err(e),
+    };
+    std::fs::write(todo_configuration_path, raw_config)?;
+    debug!("wrote {todo_configuration_path}");
+    Ok(())
+}
+
+/// Returns `existing` configuration updated to hold what `new` configuration holds. `new` is
+/// returned as is when `existing` is not valid TOML.
+fn updated(existing: &str, new: &str) -> String {
+    let (mut document, new) = match (existing.parse::<DocumentMut>(), new.parse::<DocumentMut>()) {
+        (Ok(document), Ok(new)) => (document, new),
+        _ => return new.to_string(),
+    };
+    update_table(document.as_table_mut(), new.as_table());
+    document.to_string()
+}
+
+/// Updates keys of `table` which `new` holds, adds the others and removes keys `new` does not hold
+fn update_table(table: &mut Table, new: &Table) {
+    let removed = table
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect::<Vec<_>>();
+    for key in removed {
+        table.remove(key.as_str());
+    }
+    for (key, item) in new.iter() {
+        match table.get_mut(key) {
+            Some(existing) => update_item(existing, item),
+            None => {
+                table.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+fn update_item(item: &mut Item, new: &Item) {
+    match (item, new) {
+        (Item::Table(table), Item::Table(new)) => update_table(table, new),
+        (Item::ArrayOfTables(tables), Item::ArrayOfTables(new)) => {
+            update_array_of_tables(tables, new)
+        }
+        (Item::Value(value), Item::Value(new)) => {
+            if !same_value(value, new) {
+                // comments next to the value are kept
+                let decor = value.decor().clone();
+                *value = new.clone();
+                *value.decor_mut() = decor;
+            }
+        }
+        (item, new) => *item = new.clone(),
+    }
+}
+
+/// Updates tables of `tables` (Todo contexts) named like tables of `new`, or else at the same
+/// index. Tables are ordered like `new`.
+fn update_array_of_tables(tables: &mut ArrayOfTables, new: &ArrayOfTables) {
+    let name = |table: &Table| {
+        table
+            .get("name")
+            .and_then(|n| n.as_str())
+            .map(str::to_string)
+    };
+    let mut existing = tables.iter().cloned().map(Some).collect::<Vec<_>>();
+    tables.clear();
+    for (i, new) in new.iter().enumerate() {
+        let position = match name(new) {
+            Some(new_name) => existing
+                .iter()
+                .position(|t| t.as_ref().and_then(name).as_ref() == Some(&new_name)),
+            None => existing.get(i).and_then(|t| t.as_ref()).map(|_| i),
+        };
+        match position.and_then(|p| existing[p].take()) {
+            Some(mut table) => {
+                update_table(&mut table, new);
+                tables.push(table);
+            }
+            None => tables.push(new.clone()),
+        }
+    }
+}
+
+/// Returns true if both values are equal, however they are formatted
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.value() == b.value(),
+        (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+        (Value::Float(a), Value::Float(b)) => a.value() == b.value(),
+        (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+        (Value::Datetime(a), Value::Datetime(b)) => a.value() == b.value(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_value(a, b))
+        }
+        (Value::InlineTable(a), Value::InlineTable(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| same_value(a, b)))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_configuration_file;
+
+    #[test]
+    fn comments_and_order_are_preserved() {
+        let existing = "# my todo setup
+active_ctx_name = 'home' # switched often
+
+[[ctxs]]
+# the job
+name = \"work\"
+folder_location = \"/work\"
+timezone = \"CET\"
+ide = \"vim\"
+pinned = [ \"release\" ]
+
+[[ctxs]]
+name = \"home\"
+ide = \"vim\"
+timezone = \"CET\"
+folder_location = \"/home\" # synced
+
+[aliases]
+# what is due
+today = [\"list\", \"--open\"]
+";
+        let mut config = parse_configuration_file(None, Some(existing)).unwrap();
+        config.update_active_ctx("work").unwrap();
+        config.ctxs.retain(|ctx| ctx.name == "work");
+        let mut new_ctx = config.ctxs[0].clone();
+        new_ctx.name = "side".to_string();
+        new_ctx.pinned = vec![];
+        config.ctxs.push(new_ctx);
+        let new = toml::to_string(&config).unwrap();
+        assert_eq!(
+            updated(existing, new.as_str()),
+            "# my todo setup
+active_ctx_name = \"work\" # switched often
+
+[[ctxs]]
+# the job
+name = \"work\"
+folder_location = \"/work\"
+timezone = \"CET\"
+ide = \"vim\"
+pinned = [ \"release\" ]
+
+[[ctxs]]
+ide = \"vim\"
+name = \"side\"
+timezone = \"CET\"
+folder_location = \"/work\"
+
+[aliases]
+# what is due
+today = [\"list\", \"--open\"]
+"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! Display all available Todo contexts from configuration
-use super::parse_configuration_file;
+use super::{parse_configuration_file, Configuration};
+use crate::health::check;
+use crate::json::quote;
 use clap::{crate_authors, App, Arg, ArgMatches};
 use log::{debug, trace};
 
@@ -12,7 +14,16 @@ pub fn get_contexts_command() -> App<'static, 'static> {
             Arg::with_name("full")
                 .short("f")
                 .long("full")
-                .help("Display all information about Todo context"),
+                .help("Display all information about Todo context, with the health of its folder"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FORMAT")
+                .help("Print contexts with all their settings and an `active` flag for tooling")
+                .possible_values(&["text", "json", "toml"])
+                .takes_value(true),
         )
 }
 
@@ -28,16 +39,29 @@ pub fn get_contexts_command_process(
     debug!("args: {:?}", args);
     debug!("full: {}", full);
 
+    match args.value_of("output") {
+        Some("json") => {
+            println!("{}", to_json(&contexts_value(&config)?));
+            return Ok(());
+        }
+        Some("toml") => {
+            print!("{}", toml::to_string(&contexts_value(&config)?).unwrap());
+            return Ok(());
+        }
+        _ => {}
+    }
+
     if full {
         config.ctxs.into_iter().for_each(|ctx| {
             if config.active_ctx_name == ctx.name {
                 println!(
-            "--- Context (active) ---\nname: {}\nide: {}\ntimezone: {}\nfolder location: {}\n",
-            ctx.name, ctx.ide, ctx.timezone, ctx.folder_location
+            "--- Context (active) ---\nname: {}\nide: {}\ntimezone: {}\nfolder location: {}{}",
+            ctx.name, ctx.ide, ctx.timezone, ctx.folder_location, ctx.purpose()
         )
             } else {
-                println!("{}", ctx)
+                print!("{}", ctx)
             }
+            println!("status: {}\n", check(&ctx));
         });
     } else {
         config.ctxs.into_iter().for_each(|ctx| {
@@ -54,3 +78,62 @@ pub fn get_contexts_command_process(
     }
     Ok(())
 }
+
+/// Returns all contexts, as in the configuration file, with an `active` flag
+fn contexts_value(config: &Configuration) -> Result<toml::Value, std::io::Error> {
+    let mut ctxs = vec![];
+    for ctx in &config.ctxs {
+        let mut value = toml::Value::try_from(ctx).map_err(std::io::Error::other)?;
+        if let toml::Value::Table(table) = &mut value {
+            table.insert(
+                "active".to_string(),
+                toml::Value::Boolean(ctx.name == config.active_ctx_name),
+            );
+        }
+        ctxs.push(value);
+    }
+    let mut contexts = toml::value::Table::new();
+    contexts.insert("ctxs".to_string(), toml::Value::Array(ctxs));
+    Ok(toml::Value::Table(contexts))
+}
+
+/// Returns `value` as JSON. Dates are written as strings.
+fn to_json(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => quote(s),
+        toml::Value::Integer(i) => i.to_string(),
+        toml::Value::Float(f) => f.to_string(),
+        toml::Value::Boolean(b) => b.to_string(),
+        toml::Value::Datetime(d) => quote(d.to_string().as_str()),
+        toml::Value::Array(values) => format!(
+            "[{}]",
+            values.iter().map(to_json).collect::<Vec<_>>().join(",")
+        ),
+        toml::Value::Table(table) => format!(
+            "{{{}}}",
+            table
+                .iter()
+                .map(|(k, v)| format!("{}:{}", quote(k), to_json(v)))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn machine_readable_contexts() {
+        let raw_config = "active_ctx_name = \"home\"\n\n[[ctxs]]\nname = \"work\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_location = \"/work\"\ndefault_command = [\"list\"]\n\n[[ctxs]]\nname = \"home\"\nide = \"code \\\"-w\\\"\"\ntimezone = \"UTC\"\nfolder_location = \"/home\"\n";
+        let config = parse_configuration_file(None, Some(raw_config)).unwrap();
+        let contexts = contexts_value(&config).unwrap();
+        assert_eq!(
+            to_json(&contexts),
+            r#"{"ctxs":[{"active":false,"default_command":["list"],"folder_location":"/work","ide":"vim","name":"work","timezone":"UTC"},{"active":true,"folder_location":"/home","ide":"code \"-w\"","name":"home","timezone":"UTC"}]}"#
+        );
+        let toml = toml::to_string(&contexts).unwrap();
+        assert!(toml.contains("[[ctxs]]\nactive = true\nfolder_location = \"/home\"\n"));
+    }
+}
@@ -0,0 +1,335 @@
+//! Describe accepted configuration keys and report unknown ones
+//!
+//! Unknown keys are otherwise silently ignored by serde, which hides typos such as
+//! `folder_loction`.
+use clap::{crate_authors, App};
+use log::trace;
+
+/// Key, type, default and description of an entry of a configuration table
+type SchemaEntry = (&'static str, &'static str, &'static str, &'static str);
+
+/// Entries of the top-level table
+const GLOBAL_KEYS: [SchemaEntry; 8] = [
+    (
+        "active_ctx_name",
+        "string",
+        "required",
+        "Name of the active Todo context",
+    ),
+    (
+        "ctxs",
+        "array of tables",
+        "required",
+        "Todo contexts (see [[ctxs]])",
+    ),
+    (
+        "date_format",
+        "string",
+        "\"%Y-%m-%d\"",
+        "Format of printed dates (strftime syntax)",
+    ),
+    (
+        "usage_stats",
+        "boolean",
+        "false",
+        "Record local usage statistics (`todo stats usage`)",
+    ),
+    (
+        "default_command",
+        "array of strings",
+        "none",
+        "Arguments run by a bare `todo`",
+    ),
+    (
+        "detect_context",
+        "boolean",
+        "false",
+        "Use the context named by the closest .todo-context file",
+    ),
+    (
+        "views",
+        "table of arrays of strings",
+        "empty",
+        "Saved list arguments by view name",
+    ),
+    (
+        "aliases",
+        "table of arrays of strings",
+        "empty",
+        "Arguments run by `todo NAME` by alias name",
+    ),
+];
+
+/// Entries of each `[[ctxs]]` table
+const CONTEXT_KEYS: [SchemaEntry; 21] = [
+    ("name", "string", "required", "Name of the Todo context"),
+    ("ide", "string", "required", "Program opening Todo lists"),
+    (
+        "timezone",
+        "string",
+        "required",
+        "Timezone of the Todo context (UTC, Europe/Paris...), telling when tasks are due",
+    ),
+    (
+        "folder_location",
+        "string",
+        "required",
+        "Folder of the Todo lists (or ssh://host/path)",
+    ),
+    (
+        "description",
+        "string",
+        "none",
+        "Purpose of the Todo context, atop `todo list`",
+    ),
+    ("goal", "string", "none", "Current goal, atop `todo list`"),
+    (
+        "auto_archive_after_days",
+        "integer",
+        "none",
+        "Archive completed Todo lists after N days",
+    ),
+    (
+        "max_open_tasks",
+        "integer",
+        "none",
+        "Warn when open tasks exceed N",
+    ),
+    (
+        "max_lists",
+        "integer",
+        "none",
+        "Warn when Todo lists exceed N",
+    ),
+    (
+        "stale_after_days",
+        "integer",
+        "none",
+        "Flag open tasks first seen N days ago",
+    ),
+    (
+        "date_format",
+        "string",
+        "global date_format",
+        "Format of printed dates",
+    ),
+    (
+        "default_command",
+        "array of strings",
+        "global default_command",
+        "Arguments run by a bare `todo`",
+    ),
+    ("default_view", "string", "none", "View run by `todo view`"),
+    (
+        "default_labels",
+        "array of strings",
+        "none",
+        "Labels of Todo lists made by `todo create`",
+    ),
+    (
+        "pinned",
+        "array of strings",
+        "none",
+        "Titles of Todo lists listed first",
+    ),
+    (
+        "order",
+        "array of strings",
+        "none",
+        "Titles of Todo lists listed after pinned ones",
+    ),
+    (
+        "storage",
+        "string",
+        "\"folder\"",
+        "folder (one file per Todo list) or single-file (todo.md)",
+    ),
+    (
+        "confirm",
+        "string",
+        "none",
+        "always, destructive-only or never (none: overwrite, restore and quick --explain ask)",
+    ),
+    (
+        "extensions",
+        "array of strings",
+        "[\"md\", \"txt\"]",
+        "Extensions of Todo list files, the first naming new ones",
+    ),
+    (
+        "caldav",
+        "table",
+        "none",
+        "CalDAV server (see [ctxs.caldav])",
+    ),
+    (
+        "ide_by_extension",
+        "table of strings",
+        "empty",
+        "IDE or URI template by extension (md, txt)",
+    ),
+];
+
+/// Entries of each `[ctxs.caldav]` table
+const CALDAV_KEYS: [SchemaEntry; 2] = [
+    ("url", "string", "required", "Calendar home of the user"),
+    (
+        "conflict_policy",
+        "string",
+        "\"completed\"",
+        "completed, local or remote",
+    ),
+];
+
+/// Returns schema subcommand from config command
+pub fn schema_command() -> App<'static, 'static> {
+    App::new("schema")
+        .about("Print accepted configuration keys with their types and defaults")
+        .author(crate_authors!())
+}
+
+/// Prints accepted configuration keys
+pub fn schema_command_process() -> Result<(), std::io::Error> {
+    trace!("schema");
+    print!("{}", schema());
+    Ok(())
+}
+
+/// Returns accepted configuration keys, one table after another
+fn schema() -> String {
+    let mut schema = String::new();
+    for (table, entries) in [
+        ("(top-level)", &GLOBAL_KEYS[..]),
+        ("[[ctxs]]", &CONTEXT_KEYS[..]),
+        ("[ctxs.caldav]", &CALDAV_KEYS[..]),
+    ] {
+        if !schema.is_empty() {
+            schema.push('\n');
+        }
+        schema.push_str(format!("{table}\n").as_str());
+        for (key, kind, default, description) in entries {
+            schema.push_str(format!("  {key:<24}{kind:<28}{default:<26}{description}\n").as_str());
+        }
+    }
+    schema
+}
+
+/// Returns a warning for each unknown key of the raw configuration
+pub fn unknown_keys(raw_config: &str) -> Vec<String> {
+    let config = match raw_config.parse::<toml::Value>() {
+        Ok(toml::Value::Table(config)) => config,
+        // parsing errors are reported when deserializing the configuration
+        _ => return vec![],
+    };
+    let mut warnings = check_table(&config, &GLOBAL_KEYS, "");
+    let ctxs = config.get("ctxs").and_then(|c| c.as_array());
+    for (i, ctx) in ctxs.into_iter().flatten().enumerate() {
+        let ctx = match ctx.as_table() {
+            Some(ctx) => ctx,
+            None => continue,
+        };
+        let name = ctx
+            .get("name")
+            .and_then(|n| n.as_str())
+            .map(|n| format!("ctxs.{n}."))
+            .unwrap_or_else(|| format!("ctxs.{i}."));
+        warnings.extend(check_table(ctx, &CONTEXT_KEYS, name.as_str()));
+        if let Some(caldav) = ctx.get("caldav").and_then(|c| c.as_table()) {
+            warnings.extend(check_table(
+                caldav,
+                &CALDAV_KEYS,
+                format!("{name}caldav.").as_str(),
+            ));
+        }
+    }
+    warnings
+}
+
+/// Returns a warning for each key of `table` missing from `entries`, with the closest known key
+fn check_table(table: &toml::value::Table, entries: &[SchemaEntry], prefix: &str) -> Vec<String> {
+    table
+        .keys()
+        .filter(|key| !entries.iter().any(|(k, _, _, _)| k == key))
+        .map(|key| {
+            let suggestion = entries
+                .iter()
+                .map(|(k, _, _, _)| (strsim::levenshtein(key, k), k))
+                .filter(|(distance, _)| *distance <= 2)
+                .min();
+            match suggestion {
+                Some((_, k)) => {
+                    format!("unknown configuration key \"{prefix}{key}\" (did you mean \"{k}\"?)")
+                }
+                None => format!("unknown configuration key \"{prefix}{key}\""),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CalDavSettings, Configuration, Context};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn schema_covers_configuration() {
+        let ctx = Context {
+            name: "work".to_string(),
+            description: Some("Day job".to_string()),
+            goal: Some("Ship v2 this quarter".to_string()),
+            auto_archive_after_days: Some(30),
+            max_open_tasks: Some(100),
+            max_lists: Some(20),
+            stale_after_days: Some(90),
+            date_format: Some("%d.%m.%Y".to_string()),
+            default_command: Some(vec!["list".to_string()]),
+            default_view: Some("morning".to_string()),
+            default_labels: vec!["work".to_string()],
+            pinned: vec!["Release".to_string()],
+            order: vec!["Groceries".to_string()],
+            storage: Some("single-file".to_string()),
+            confirm: Some("never".to_string()),
+            extensions: vec!["markdown".to_string()],
+            caldav: Some(CalDavSettings {
+                url: "https://example.com/".to_string(),
+                conflict_policy: Some("local".to_string()),
+            }),
+            ide_by_extension: BTreeMap::from([("md".to_string(), "code".to_string())]),
+            ..Default::default()
+        };
+        let config = Configuration {
+            active_ctx_name: "work".to_string(),
+            ctxs: vec![ctx],
+            date_format: Some("%m/%d/%Y".to_string()),
+            usage_stats: Some(true),
+            default_command: Some(vec!["view".to_string()]),
+            detect_context: Some(true),
+            configured_active_ctx_name: None,
+            views: BTreeMap::from([("morning".to_string(), vec!["--open".to_string()])]),
+            aliases: BTreeMap::from([(
+                "today".to_string(),
+                vec!["list".to_string(), "--open".to_string()],
+            )]),
+        };
+        let raw_config = toml::to_string(&config).unwrap();
+        assert_eq!(unknown_keys(raw_config.as_str()), Vec::<String>::new());
+        assert_eq!(
+            schema().lines().filter(|l| l.starts_with("  ")).count(),
+            GLOBAL_KEYS.len() + CONTEXT_KEYS.len() + CALDAV_KEYS.len()
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_reported() {
+        let raw_config = "active_ctx_name = \"work\"\nusage_stat = true\n\n[[ctxs]]\nname = \"work\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_loction = \"/tmp\"\ncolour = \"red\"\n";
+        assert_eq!(
+            unknown_keys(raw_config),
+            vec![
+                "unknown configuration key \"usage_stat\" (did you mean \"usage_stats\"?)",
+                "unknown configuration key \"ctxs.work.colour\"",
+                "unknown configuration key \"ctxs.work.folder_loction\" (did you mean \"folder_location\"?)",
+            ]
+        );
+    }
+}
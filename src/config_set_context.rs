@@ -1,9 +1,9 @@
 //! Set active Todo context among available contexts from configuration
 use super::parse_configuration_file;
+use crate::config_file::write_configuration;
+use crate::pick::pick_ctx;
 use clap::{crate_authors, App, Arg, ArgMatches};
 use log::{debug, trace};
-use std::fs::File;
-use std::io::Write;
 
 /// Returns set-context subcommand from config commmand
 pub fn set_context_command() -> App<'static, 'static> {
@@ -12,8 +12,9 @@ pub fn set_context_command() -> App<'static, 'static> {
         .author(crate_authors!())
         .arg(
             Arg::with_name("context")
+                .value_name("CONTEXT")
+                .help("Name of the Todo context (picked interactively when omitted)")
                 .takes_value(true)
-                .required(true)
                 .index(1),
         )
 }
@@ -26,27 +27,24 @@ pub fn set_context_command_process(
 ) -> Result<(), std::io::Error> {
     trace!("set-context");
     debug!("set_context_matches: {:?}", args);
-    let new_context = args.value_of("context").unwrap().to_string();
-    debug!("new context: {}", new_context);
     match parse_configuration_file(Some(todo_configuration_path), raw_config) {
         Ok(mut config) => {
-            let update = config.update_active_ctx(&new_context);
-            if update.is_err() {
-                eprintln!("{}", update.err().unwrap());
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    update.err().unwrap(),
-                ));
+            let new_context = match args.value_of("context") {
+                Some(name) => name.to_string(),
+                None => pick_ctx(
+                    &config,
+                    "Set active context",
+                    None,
+                    args.is_present("no-input"),
+                )?,
+            };
+            debug!("new context: {}", new_context);
+            if let Err(e) = config.update_active_ctx(&new_context) {
+                eprintln!("{e}");
+                return Err(std::io::Error::other(e));
             }
 
-            trace!("Opening configuration file with write access...");
-            let mut file = std::fs::OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .create(true)
-                .open(todo_configuration_path)?;
-            trace!("Writting to file");
-            File::write(&mut file, toml::to_string(&config).unwrap().as_bytes())?;
+            write_configuration(todo_configuration_path, &config)?;
 
             println!("Context was set to \"{}\"", config.active_ctx_name);
             Ok(())
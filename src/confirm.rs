@@ -0,0 +1,127 @@
+//! Ask for confirmation according to the confirmation policy of the Todo context
+//!
+//! `confirm` of a Todo context is one of:
+//!
+//! * `always`: every operation below asks
+//! * `destructive-only`: only operations losing data ask (overwriting a Todo list on create,
+//!   deleting a Todo list, restoring a snapshot)
+//! * `never`: no operation asks
+//!
+//! Without policy, overwriting a Todo list, restoring a snapshot and adding the task interpreted by
+//! `todo quick --explain` ask, as they always did, while deleting a Todo list does not, so scripts
+//! calling `todo delete` keep working. Creating a missing Todo folder and migrating the folder
+//! layout only ask with `always`. Without a terminal to ask, operations requiring confirmation fail.
+//!
+//! Checking off many tasks at once and pruning Todo lists have no operation: `todo done` checks off
+//! a single task and no subcommand removes Todo lists in bulk. `todo maintain` only archives them,
+//! which `todo unarchive` undoes.
+use crate::Context;
+use dialoguer::Confirm;
+use log::{debug, warn};
+use std::io::IsTerminal;
+
+/// Accepted values of `confirm`
+pub const CONFIRM_POLICIES: [&str; 3] = ["always", "destructive-only", "never"];
+
+/// Operation which may ask for confirmation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    /// Replacing an existing Todo list with `todo create`
+    Overwrite,
+    Delete,
+    /// Replacing all Todo lists of a Todo context with a snapshot
+    Restore,
+    CreateFolder,
+    Migrate,
+    /// Adding the task interpreted by `todo quick --explain`
+    Quick,
+}
+
+impl Operation {
+    /// Returns true if the operation loses data
+    fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            Operation::Overwrite | Operation::Delete | Operation::Restore
+        )
+    }
+
+    /// Returns true if the operation asks when the Todo context has no confirmation policy
+    fn asks_by_default(&self) -> bool {
+        matches!(
+            self,
+            Operation::Overwrite | Operation::Restore | Operation::Quick
+        )
+    }
+}
+
+/// Returns true if the operation may proceed, asking with `prompt` when the confirmation policy of
+/// the Todo context requires it
+pub fn confirm(ctx: &Context, operation: Operation, prompt: &str) -> Result<bool, std::io::Error> {
+    if !requires_confirmation(ctx.confirm.as_deref(), operation) {
+        debug!(
+            "{operation:?} proceeds without confirmation in context {}",
+            ctx.name
+        );
+        return Ok(true);
+    }
+    // prompts would wait forever inside scripts
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!(
+                "No terminal to confirm \"{operation:?}\" ({prompt}). Set confirm = \"never\" for context {} to proceed without confirmation.",
+                ctx.name
+            ),
+        ));
+    }
+    Confirm::new()
+        .with_prompt(prompt)
+        .interact()
+        .map_err(std::io::Error::other)
+}
+
+/// Returns true if `policy` asks before `operation`. Invalid policies are ignored.
+fn requires_confirmation(policy: Option<&str>, operation: Operation) -> bool {
+    match policy {
+        Some("always") => true,
+        Some("never") => false,
+        Some("destructive-only") => operation.is_destructive(),
+        Some(policy) => {
+            warn!("Ignoring invalid confirmation policy \"{policy}\"");
+            operation.asks_by_default()
+        }
+        None => operation.asks_by_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirmation_policies() {
+        assert!(!requires_confirmation(None, Operation::Delete));
+        assert!(requires_confirmation(None, Operation::Overwrite));
+        assert!(!requires_confirmation(None, Operation::CreateFolder));
+        assert!(requires_confirmation(
+            Some("destructive-only"),
+            Operation::Delete
+        ));
+        assert!(requires_confirmation(Some("always"), Operation::Migrate));
+        assert!(!requires_confirmation(Some("never"), Operation::Restore));
+        assert!(requires_confirmation(None, Operation::Quick));
+        assert!(!requires_confirmation(
+            Some("destructive-only"),
+            Operation::Quick
+        ));
+        assert!(!requires_confirmation(
+            Some("destructive-only"),
+            Operation::Migrate
+        ));
+        assert!(requires_confirmation(
+            Some("sometimes"),
+            Operation::Overwrite
+        ));
+    }
+}
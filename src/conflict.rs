@@ -0,0 +1,306 @@
+//! Resolve conflicts when a Todo list would overwrite another one with different content
+//!
+//! Commands bringing a Todo list where a different one already exists ask the user to keep the
+//! existing (local) Todo list, take the incoming one or merge both section by section. Sections are
+//! delimited by markdown headings (`# `, `## `, `### `...).
+//!
+//! `--on-collision` replaces the question with a fixed answer: overwrite the existing Todo list,
+//! abort, or write the incoming Todo list beside it with a numbered title (`Groceries 2`).
+use crate::single_file::{retitle, title_of};
+use clap::{Arg, ArgMatches};
+use dialoguer::Select;
+
+/// Values of `--on-collision`
+const ON_COLLISION_VALUES: [&str; 4] = ["prompt", "overwrite", "abort", "suffix"];
+
+/// What to do when a Todo list is brought where a different one already exists
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnCollision {
+    /// Asks the user, see [`resolve_conflict`]
+    Prompt,
+    /// Incoming Todo list replaces the existing one
+    Overwrite,
+    /// Nothing is written
+    Abort,
+    /// Incoming Todo list is written beside the existing one with a numbered title
+    Suffix,
+}
+
+impl OnCollision {
+    /// Returns what to do on collision according to `--on-collision`
+    pub fn from_args(args: &ArgMatches) -> OnCollision {
+        match args.value_of("on collision") {
+            Some("overwrite") => OnCollision::Overwrite,
+            Some("abort") => OnCollision::Abort,
+            Some("suffix") => OnCollision::Suffix,
+            _ => OnCollision::Prompt,
+        }
+    }
+}
+
+/// Returns the argument telling what to do when a different Todo list already exists at
+/// destination
+pub fn on_collision_arg() -> Arg<'static, 'static> {
+    Arg::with_name("on collision")
+        .long("on-collision")
+        .value_name("ACTION")
+        .help("What to do when a different todo list already exists at destination")
+        .possible_values(&ON_COLLISION_VALUES)
+        .default_value("prompt")
+        .takes_value(true)
+}
+
+/// Todo list to write once a collision is resolved
+#[derive(Debug, PartialEq)]
+pub enum Placement {
+    /// Written in place of the existing Todo list, if any
+    Replace(String),
+    /// Written beside the existing Todo list. First argument is the new title.
+    Beside(String, String),
+}
+
+/// Outcome of a conflict between a local and an incoming Todo list
+#[derive(Debug, PartialEq)]
+pub enum Resolution {
+    /// Local Todo list is left untouched
+    KeepLocal,
+    /// Incoming Todo list replaces the local one
+    TakeIncoming,
+    /// Merged content replaces the local one
+    Merged(String),
+}
+
+/// Choice of the user for a section which differs between local and incoming Todo lists
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SectionChoice {
+    Local,
+    Incoming,
+    /// Local section followed by the lines of the incoming section it does not have
+    Both,
+}
+
+/// Asks the user how to resolve the conflict at `filepath` between `local` and `incoming` Todo lists
+pub fn resolve_conflict(
+    filepath: &str,
+    local: &str,
+    incoming: &str,
+) -> Result<Resolution, std::io::Error> {
+    let choice = Select::new()
+        .with_prompt(format!(
+            "A different Todo list already exists at \"{filepath}\""
+        ))
+        .items(&[
+            "Keep local Todo list",
+            "Take incoming Todo list",
+            "Merge sections interactively",
+        ])
+        .default(0)
+        .interact()
+        .map_err(std::io::Error::other)?;
+    match choice {
+        0 => Ok(Resolution::KeepLocal),
+        1 => Ok(Resolution::TakeIncoming),
+        _ => {
+            let mut error = None;
+            let merged = merge_sections(local, incoming, |heading, _, _| {
+                match Select::new()
+                    .with_prompt(format!("Section \"{heading}\" differs"))
+                    .items(&["Keep local", "Take incoming", "Keep both"])
+                    .default(2)
+                    .interact()
+                {
+                    Ok(0) => SectionChoice::Local,
+                    Ok(1) => SectionChoice::Incoming,
+                    Ok(_) => SectionChoice::Both,
+                    Err(e) => {
+                        error.get_or_insert(e);
+                        SectionChoice::Local
+                    }
+                }
+            });
+            match error {
+                Some(e) => Err(std::io::Error::other(e)),
+                None => Ok(Resolution::Merged(merged)),
+            }
+        }
+    }
+}
+
+/// Resolves the collision at `place` of the `incoming` Todo list with the `local` one as told by
+/// `on_collision`. Without `local` Todo list, there is no collision.
+///
+/// * `is_taken` - returns true if a Todo list at destination already has the given title
+pub fn resolve_collision(
+    place: &str,
+    local: Option<&str>,
+    incoming: &str,
+    on_collision: OnCollision,
+    is_taken: impl Fn(&str) -> bool,
+) -> Result<Placement, std::io::Error> {
+    let local = match local {
+        Some(local) => local,
+        None => return Ok(Placement::Replace(incoming.to_string())),
+    };
+    let already_exists = |message: String| {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            message,
+        ))
+    };
+    match on_collision {
+        OnCollision::Prompt => match resolve_conflict(place, local, incoming)? {
+            Resolution::KeepLocal => already_exists(format!("kept Todo list at \"{place}\"")),
+            Resolution::TakeIncoming => Ok(Placement::Replace(incoming.to_string())),
+            Resolution::Merged(merged) => Ok(Placement::Replace(merged)),
+        },
+        OnCollision::Overwrite => Ok(Placement::Replace(incoming.to_string())),
+        OnCollision::Abort => already_exists(format!(
+            "a different Todo list already exists at \"{place}\""
+        )),
+        OnCollision::Suffix => {
+            let title = title_of(incoming);
+            let title = (2..)
+                .map(|n| format!("{title} {n}"))
+                .find(|t| !is_taken(t))
+                .unwrap();
+            let todo_raw = retitle(incoming, title.as_str());
+            Ok(Placement::Beside(title, todo_raw))
+        }
+    }
+}
+
+/// Returns local Todo list where sections differing from the incoming Todo list are resolved with
+/// `choose`. Sections only found in the incoming Todo list are appended.
+///
+/// * `choose` - called with the heading, the local section and the incoming section
+pub fn merge_sections<F>(local: &str, incoming: &str, mut choose: F) -> String
+where
+    F: FnMut(&str, &str, &str) -> SectionChoice,
+{
+    let local_sections = split_sections(local);
+    let incoming_sections = split_sections(incoming);
+    let mut merged = String::new();
+    for (heading, local_section) in &local_sections {
+        let incoming_section = incoming_sections
+            .iter()
+            .find(|(h, _)| h == heading)
+            .map(|(_, s)| s.as_str());
+        let section = match incoming_section {
+            Some(incoming_section) if incoming_section != local_section => {
+                match choose(heading, local_section, incoming_section) {
+                    SectionChoice::Local => local_section.to_string(),
+                    SectionChoice::Incoming => incoming_section.to_string(),
+                    SectionChoice::Both => union(local_section, incoming_section),
+                }
+            }
+            _ => local_section.to_string(),
+        };
+        merged.push_str(section.as_str());
+    }
+    for (heading, incoming_section) in &incoming_sections {
+        if !local_sections.iter().any(|(h, _)| h == heading) {
+            while !merged.is_empty() && !merged.ends_with("\n\n") {
+                merged.push('\n');
+            }
+            merged.push_str(incoming_section);
+        }
+    }
+    merged
+}
+
+/// Returns the sections of a Todo list with their heading. Content before the first heading has an
+/// empty heading.
+fn split_sections(todo_raw: &str) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = vec![];
+    for line in todo_raw.split_inclusive('\n') {
+        if line.starts_with('#') || sections.is_empty() {
+            let heading = if line.starts_with('#') {
+                line.trim_end().to_string()
+            } else {
+                String::new()
+            };
+            sections.push((heading, String::new()));
+        }
+        sections.last_mut().unwrap().1.push_str(line);
+    }
+    sections
+}
+
+/// Returns local section followed by the non-blank lines of the incoming section it does not have
+fn union(local_section: &str, incoming_section: &str) -> String {
+    let mut section = local_section.trim_end().to_string();
+    for line in incoming_section.lines() {
+        if !line.trim().is_empty() && !local_section.lines().any(|l| l == line) {
+            section.push('\n');
+            section.push_str(line);
+        }
+    }
+    // keep the blank lines separating this section from the next one
+    section.push_str(&local_section[local_section.trim_end().len()..]);
+    if !section.ends_with('\n') {
+        section.push('\n');
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCAL: &str =
+        "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] milk\n* [x] bread\n";
+    const INCOMING: &str =
+        "# Title\n\n## Description\n\nLABEL=home\n\n## Todo list\n\n* [ ] milk\n* [ ] eggs\n\n## Notes\n\nfresh\n";
+
+    #[test]
+    fn merge_each_differing_section() {
+        let mut asked = vec![];
+        let merged = merge_sections(LOCAL, INCOMING, |heading, _, _| {
+            asked.push(heading.to_string());
+            if heading == "## Description" {
+                SectionChoice::Incoming
+            } else {
+                SectionChoice::Both
+            }
+        });
+        assert_eq!(asked, vec!["## Description", "## Todo list"]);
+        assert_eq!(
+            merged,
+            "# Title\n\n## Description\n\nLABEL=home\n\n## Todo list\n\n* [ ] milk\n* [x] bread\n* [ ] eggs\n\n## Notes\n\nfresh\n"
+        );
+    }
+
+    #[test]
+    fn keep_local_sections() {
+        let merged = merge_sections(LOCAL, INCOMING, |_, _, _| SectionChoice::Local);
+        assert_eq!(merged, format!("{LOCAL}\n## Notes\n\nfresh\n"));
+        let merged = merge_sections(LOCAL, LOCAL, |_, _, _| unreachable!());
+        assert_eq!(merged, LOCAL);
+    }
+
+    #[test]
+    fn collisions_without_prompt() {
+        let resolve = |local, on_collision| {
+            resolve_collision("here", local, INCOMING, on_collision, |t| t == "Title 2")
+        };
+        assert_eq!(
+            resolve(None, OnCollision::Abort).unwrap(),
+            Placement::Replace(INCOMING.to_string())
+        );
+        assert_eq!(
+            resolve(Some(LOCAL), OnCollision::Overwrite).unwrap(),
+            Placement::Replace(INCOMING.to_string())
+        );
+        assert_eq!(
+            resolve(Some(LOCAL), OnCollision::Abort).unwrap_err().kind(),
+            std::io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(
+            resolve(Some(LOCAL), OnCollision::Suffix).unwrap(),
+            Placement::Beside(
+                "Title 3".to_string(),
+                INCOMING.replacen("# Title", "# Title 3", 1)
+            )
+        );
+    }
+}
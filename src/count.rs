@@ -0,0 +1,150 @@
+//! Count tasks for shell prompts and scripts with `todo count`
+//!
+//! `todo count` prints the number of tasks of the Todo lists of active Todo context and nothing
+//! else. `--open` and `--done` count only open or done tasks, `--label` only Todo lists with all
+//! given labels and `-t TITLE` only one Todo list. Counts come from the metadata cache of the Todo
+//! folder (see [`crate::metadata`]), so only Todo lists modified since the last count are read.
+use crate::metadata::{Metadata, MetadataCache};
+use crate::pin::same_title;
+use crate::Context;
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+
+/// Tasks which are counted
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Counted {
+    All,
+    Open,
+    Done,
+}
+
+/// Returns the Count command
+pub fn count_command() -> App<'static, 'static> {
+    App::new("count")
+        .about("Print the number of tasks of todo lists within Todo context, and nothing else")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("open")
+                .long("open")
+                .help("Counts only open tasks")
+                .conflicts_with("done"),
+        )
+        .arg(
+            Arg::with_name("done")
+                .long("done")
+                .help("Counts only done tasks"),
+        )
+        .arg(
+            Arg::with_name("label")
+                .short("l")
+                .long("label")
+                .value_name("LABEL")
+                .help("Counts only tasks of todo lists with this label, repeat for more")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .help("Counts only tasks of this todo list")
+                .takes_value(true),
+        )
+}
+
+/// Prints the number of tasks of the Todo lists of active Todo context
+pub fn count_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("count subcommand");
+    let counted = if args.is_present("open") {
+        Counted::Open
+    } else if args.is_present("done") {
+        Counted::Done
+    } else {
+        Counted::All
+    };
+    let labels = args
+        .values_of("label")
+        .unwrap_or_default()
+        .collect::<Vec<_>>();
+
+    let mut cache = MetadataCache::read(ctx)?;
+    if cache.refresh(ctx)? {
+        // a read-only Todo folder is counted all the same, only slower
+        if let Err(e) = cache.write(ctx) {
+            debug!("metadata cache could not be written: {e}");
+        }
+    }
+    let count = count(cache.todo_lists(), args.value_of("title"), &labels, counted)?;
+    println!("{count}");
+    Ok(())
+}
+
+/// Returns the number of `counted` tasks of Todo lists titled `title` (all by default) with every
+/// label of `labels`
+fn count<'a>(
+    todo_lists: impl Iterator<Item = &'a Metadata>,
+    title: Option<&str>,
+    labels: &[&str],
+    counted: Counted,
+) -> Result<usize, std::io::Error> {
+    let mut titled = false;
+    let mut count = 0;
+    for todo_list in todo_lists {
+        if title.is_some_and(|title| !same_title(title, todo_list.title.as_str())) {
+            continue;
+        }
+        titled = true;
+        if !labels
+            .iter()
+            .all(|l| todo_list.labels.iter().any(|tl| tl == l))
+        {
+            continue;
+        }
+        count += match counted {
+            Counted::All => todo_list.total,
+            Counted::Open => todo_list.total - todo_list.done,
+            Counted::Done => todo_list.done,
+        };
+    }
+    match title {
+        Some(title) if !titled => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No Todo list is titled \"{title}\""),
+        )),
+        _ => Ok(count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_tasks() {
+        let metadata = |title: &str, labels: &[&str], done, total| Metadata {
+            title: title.to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            done,
+            total,
+        };
+        let todo_lists = [
+            metadata("groceries", &["home"], 1, 3),
+            metadata("release", &["work", "urgent"], 2, 7),
+            metadata("taxes", &["home", "urgent"], 0, 2),
+        ];
+        let count =
+            |title, labels: &[&str], counted| count(todo_lists.iter(), title, labels, counted);
+        assert_eq!(count(None, &[], Counted::All).unwrap(), 12);
+        assert_eq!(count(None, &[], Counted::Open).unwrap(), 9);
+        assert_eq!(count(None, &[], Counted::Done).unwrap(), 3);
+        assert_eq!(count(None, &["home"], Counted::Open).unwrap(), 4);
+        assert_eq!(count(None, &["home", "urgent"], Counted::All).unwrap(), 2);
+        assert_eq!(count(Some("release"), &[], Counted::Open).unwrap(), 5);
+        assert_eq!(count(Some(" Release"), &[], Counted::Open).unwrap(), 5);
+        // a Todo list without the label counts no task
+        assert_eq!(count(Some("release"), &["home"], Counted::All).unwrap(), 0);
+        assert!(count(Some("unknown"), &[], Counted::All).is_err());
+    }
+}
@@ -1,13 +1,19 @@
 //! Create Todo list in active Todo context inside configuration
+//!
+//! New Todo lists get the default labels of the Todo context (`default_labels`) before the labels
+//! given with `--label`.
+use super::confirm::{confirm, Operation};
+use super::lock::ensure_unlocked;
+use super::single_file::SingleFile;
 use super::{prompt_for_todo_folder_if_not_exists, todo_path, Context, TodoList};
 use clap::{crate_authors, App, Arg, ArgMatches};
-use dialoguer::Confirm;
-use log::trace;
+use log::{debug, trace};
 use std::fs::read_to_string;
 
 /// Returns Todo create command
 pub fn create_command() -> App<'static, 'static> {
     App::new("create")
+        .visible_alias("new")
         .about("Create a new todo list within Todo context")
         .author(crate_authors!())
         .arg(
@@ -56,6 +62,12 @@ pub fn create_command() -> App<'static, 'static> {
                 .help("list of motives that appears in reverse order of the todo")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Overwrite todo list even if it is locked"),
+        )
 }
 
 /// Creates a new Todo list in active Todo context
@@ -65,11 +77,7 @@ pub fn create_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), st
         title: args.value_of("title").unwrap().to_string(),
         description: args.value_of("content").unwrap_or("").to_string(),
         // https://stackoverflow.com/a/37547426/16631150
-        labels: args
-            .values_of("label")
-            .unwrap_or_default()
-            .map(|s| s.to_string())
-            .collect(),
+        labels: labels(ctx, args.values_of("label").unwrap_or_default()),
         list_items: args
             .values_of("item")
             .unwrap_or_default()
@@ -80,29 +88,48 @@ pub fn create_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), st
             .unwrap_or_default()
             .map(|s| s.to_string())
             .collect(),
+        tasks: vec![],
     };
 
     // Individual files allow for manual editing without the pain of scrolling through
     // all other todo's.
-    let filepath = todo_path(ctx.folder_location.as_str(), todo.title.as_str());
+    let filepath = todo_path(ctx, todo.title.as_str());
 
     if let Err(e) = prompt_for_todo_folder_if_not_exists(ctx) {
         eprintln!("Error: {e}");
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Todo creation error",
-        ));
+        return Err(std::io::Error::other("Todo creation error"));
     }
 
-    match read_to_string(&filepath) {
-        Ok(_) => {
-            if !Confirm::new()
-                .with_prompt(format!(
+    if ctx.is_single_file() {
+        let mut single_file = SingleFile::read(ctx)?;
+        match single_file.position(todo.title.as_str())? {
+            Some(i) => {
+                single_file.position_of_unlocked(todo.title.as_str(), args.is_present("force"))?;
+                let prompt = format!(
                     "This operation will overwrite todo \"{}\". Continue?",
                     todo.title
-                ))
-                .interact()?
-            {
+                );
+                if !confirm(ctx, Operation::Overwrite, prompt.as_str())? {
+                    return Ok(());
+                }
+                single_file.todo_lists[i] = format!("{}", todo);
+            }
+            None => single_file.todo_lists.push(format!("{}", todo)),
+        }
+        single_file.write()?;
+        println!("Saved todo \"{}\" ({})", todo.title, single_file.path);
+        return Ok(());
+    }
+
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+
+    match read_to_string(&filepath) {
+        Ok(_) => {
+            let prompt = format!(
+                "This operation will overwrite todo \"{}\". Continue?",
+                todo.title
+            );
+            if !confirm(ctx, Operation::Overwrite, prompt.as_str())? {
                 return Ok(());
             }
         }
@@ -113,7 +140,37 @@ pub fn create_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), st
     }
 
     std::fs::write(&filepath, format!("{}", todo))?;
+    debug!("wrote {filepath}");
     println!("Saved todo \"{}\" ({})", todo.title, ctx.folder_location);
 
     Ok(())
 }
+
+/// Returns the default labels of the Todo context followed by `labels`, without duplicates
+fn labels<'a>(ctx: &'a Context, labels: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut all: Vec<String> = vec![];
+    for label in ctx.default_labels.iter().map(|l| l.as_str()).chain(labels) {
+        if !all.iter().any(|l| l == label) {
+            all.push(label.to_string());
+        }
+    }
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_labels_come_first() {
+        let ctx = Context {
+            default_labels: vec!["work".to_string(), "urgent".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            labels(&ctx, ["release", "work"]),
+            vec!["work", "urgent", "release"]
+        );
+        assert_eq!(labels(&Context::default(), ["release"]), vec!["release"]);
+    }
+}
@@ -0,0 +1,499 @@
+//! Keep Todo lists in memory between invocations
+//!
+//! `todo daemon` reads the configuration and the Todo lists of every Todo context once, then polls
+//! Todo folders for changes. `todo client ARGS...` sends `list` invocations to the daemon over a unix
+//! socket next to the configuration file (`.todo_daemon.sock`) and runs any other invocation, or
+//! any invocation the daemon cannot answer, by itself.
+//!
+//! A request is one line holding a JSON array: name and Todo folder of the active Todo context of
+//! the client followed by the `list` arguments. The response starts with `ok COUNT` or
+//! `error MESSAGE` on its own line, followed by the printed Todo lists. Clients are answered
+//! concurrently (see [`crate::runtime`]).
+use crate::archive::archive_folder;
+use crate::json::{parse_json, quote, Json};
+use crate::list::{list_command, list_entries_process};
+use crate::parts::{is_part, part_paths, Parts};
+#[cfg(unix)]
+use crate::runtime::block_on;
+use crate::single_file::{single_file_path, SingleFile};
+use crate::{parse_configuration_file, Configuration, Context};
+use clap::{crate_authors, App, AppSettings, Arg, ArgMatches};
+use log::{debug, trace, warn};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Name of the socket of the daemon, next to the configuration file
+const DAEMON_SOCKET: &str = ".todo_daemon.sock";
+
+/// Returns the Daemon command
+pub fn daemon_command() -> App<'static, 'static> {
+    App::new("daemon")
+        .about("Keep Todo lists in memory and answer list invocations of todo client")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .value_name("SECONDS")
+                .help("Seconds between checks of Todo folders for changes")
+                .default_value("2")
+                .takes_value(true),
+        )
+}
+
+/// Returns the Client command
+pub fn client_command() -> App<'static, 'static> {
+    App::new("client")
+        .about("Run a subcommand, through the running daemon for list invocations")
+        .author(crate_authors!())
+        .setting(AppSettings::TrailingVarArg)
+        .arg(
+            Arg::with_name("arguments")
+                .value_name("ARGS")
+                .help("Subcommand and its arguments (list --open)")
+                .multiple(true)
+                .allow_hyphen_values(true)
+                .required(true),
+        )
+}
+
+/// Returns the path of the socket of the daemon
+fn socket_path(todo_configuration_path: &str) -> PathBuf {
+    Path::new(todo_configuration_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(DAEMON_SOCKET)
+}
+
+/// Last modification of each part of a Todo list and the Todo list with its parts joined
+type CachedTodoList = (Vec<Option<SystemTime>>, String);
+
+/// Configuration and raw Todo lists of every Todo context, refreshed when files change
+struct Cache {
+    todo_configuration_path: String,
+    /// Raw configuration given on the command line, never reloaded
+    raw_configuration: Option<String>,
+    configuration: Configuration,
+    configuration_modified: Option<SystemTime>,
+    /// Todo lists of each Todo context by path, by name of the Todo context
+    todo_lists: BTreeMap<String, BTreeMap<String, CachedTodoList>>,
+}
+
+impl Cache {
+    fn load(
+        todo_configuration_path: &str,
+        raw_configuration: Option<&str>,
+    ) -> Result<Cache, std::io::Error> {
+        let mut cache = Cache {
+            todo_configuration_path: todo_configuration_path.to_string(),
+            raw_configuration: raw_configuration.map(|r| r.to_string()),
+            configuration: parse_configuration_file(
+                Some(todo_configuration_path),
+                raw_configuration,
+            )?,
+            configuration_modified: modified(todo_configuration_path),
+            todo_lists: BTreeMap::new(),
+        };
+        cache.refresh()?;
+        Ok(cache)
+    }
+
+    /// Reloads the configuration and the Todo list files changed since the last refresh
+    fn refresh(&mut self) -> Result<(), std::io::Error> {
+        if self.raw_configuration.is_none() {
+            let configuration_modified = modified(self.todo_configuration_path.as_str());
+            if configuration_modified != self.configuration_modified {
+                self.configuration =
+                    parse_configuration_file(Some(self.todo_configuration_path.as_str()), None)?;
+                self.configuration_modified = configuration_modified;
+                debug!("reloaded {}", self.todo_configuration_path);
+            }
+        }
+
+        let mut todo_lists = BTreeMap::new();
+        for ctx in &self.configuration.ctxs {
+            let mut cached = self.todo_lists.remove(&ctx.name).unwrap_or_default();
+            let mut files = BTreeMap::new();
+            for filepath in todo_list_files(ctx) {
+                // parts are added, removed and changed along with their Todo list
+                let modified = std::iter::once(filepath.clone())
+                    .chain(part_paths(filepath.as_str()))
+                    .map(|path| modified(path.as_str()))
+                    .collect::<Vec<_>>();
+                if modified[0].is_none() {
+                    continue;
+                }
+                match cached.remove(&filepath) {
+                    Some((m, todo_raw)) if m == modified => {
+                        files.insert(filepath, (m, todo_raw));
+                    }
+                    _ => {
+                        let todo_raw = if ctx.is_single_file() {
+                            std::fs::read_to_string(&filepath)?
+                        } else {
+                            Parts::read(filepath.as_str())?.joined()
+                        };
+                        debug!("read {filepath}");
+                        files.insert(filepath, (modified, todo_raw));
+                    }
+                }
+            }
+            todo_lists.insert(ctx.name.clone(), files);
+        }
+        self.todo_lists = todo_lists;
+        Ok(())
+    }
+
+    /// Returns the number of Todo lists printed by the list invocation of `request` and its output
+    fn answer(&mut self, request: &str) -> Result<(usize, Vec<u8>), std::io::Error> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+        let request = match parse_json(request)? {
+            Json::Array(values) => values
+                .iter()
+                .map(|v| v.as_str().map(|s| s.to_string()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| invalid("request arguments must be strings".to_string()))?,
+            _ => return Err(invalid("request must be an array".to_string())),
+        };
+        if request.len() < 3 {
+            return Err(invalid(
+                "request misses the active Todo context or list".to_string(),
+            ));
+        }
+        self.refresh()?;
+
+        let (name, folder) = (request[0].as_str(), request[1].as_str());
+        let mut configuration = self.configuration.clone();
+        let known = configuration
+            .ctxs
+            .iter()
+            .any(|ctx| ctx.name == name && ctx.folder_location == folder);
+        if !known {
+            return Err(invalid(format!(
+                "Todo context {name} ({folder}) is unknown"
+            )));
+        }
+        configuration
+            .update_active_ctx(name)
+            .map_err(|e| invalid(e.to_string()))?;
+        let args = list_command()
+            .get_matches_from_safe(request[2..].iter())
+            .map_err(|e| invalid(e.message))?;
+        if args.is_present("label-add") || args.is_present("label-remove") {
+            return Err(invalid("labels are stamped by the client".to_string()));
+        }
+        if args.is_present("archived") {
+            return Err(invalid("archived Todo lists are not cached".to_string()));
+        }
+
+        let chunks = configuration
+            .ctxs
+            .iter()
+            .map(|ctx| self.raw_todo_lists(ctx))
+            .collect::<Vec<_>>();
+        let entries = chunks
+            .iter()
+            .map(|todo_lists| todo_lists.iter().map(|t| t.as_str()).collect())
+            .collect();
+        let mut output = vec![];
+        let count = list_entries_process(&args, &configuration, entries, &mut output)?;
+        Ok((count, output))
+    }
+
+    /// Returns the raw Todo lists of the Todo context
+    fn raw_todo_lists(&self, ctx: &Context) -> Vec<String> {
+        let files = match self.todo_lists.get(&ctx.name) {
+            Some(files) => files,
+            None => return vec![],
+        };
+        if ctx.is_single_file() {
+            let path = single_file_path(ctx);
+            return match files.get(&path) {
+                Some((_, raw)) => SingleFile::parse(path, raw.as_str()).todo_lists,
+                None => vec![],
+            };
+        }
+        files
+            .values()
+            .map(|(_, todo_raw)| todo_raw.clone())
+            .collect()
+    }
+}
+
+/// Returns the last modification of the file, if it exists
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Returns the Todo list files of the Todo context, without archived Todo lists and parts
+fn todo_list_files(ctx: &Context) -> Vec<String> {
+    if ctx.is_single_file() {
+        return vec![single_file_path(ctx)];
+    }
+    let archive_folder = archive_folder(ctx);
+    WalkDir::new(ctx.folder_location.as_str())
+        .into_iter()
+        .filter_entry(|entry| entry.path() != archive_folder)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| ctx.is_todo_list_file(entry.path()) && !is_part(entry.path()))
+        .map(|entry| entry.path().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Answers list invocations of clients until interrupted. Clients are answered concurrently.
+#[cfg(unix)]
+pub fn daemon_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    raw_configuration: Option<&str>,
+) -> Result<(), std::io::Error> {
+    use std::os::unix::net::UnixStream;
+    use std::sync::{Arc, Mutex, PoisonError};
+
+    trace!("daemon subcommand");
+    let interval = args
+        .value_of("interval")
+        .unwrap()
+        .parse::<u64>()
+        .map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("--interval: {e}"))
+        })?;
+    let socket = socket_path(todo_configuration_path);
+    if UnixStream::connect(&socket).is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("A daemon already listens on {}", socket.display()),
+        ));
+    }
+    // left behind by a daemon that did not shut down cleanly
+    if socket.exists() {
+        std::fs::remove_file(&socket)?;
+    }
+
+    let cache = Arc::new(Mutex::new(Cache::load(
+        todo_configuration_path,
+        raw_configuration,
+    )?));
+    block_on::<()>(async move {
+        let listener = tokio::net::UnixListener::bind(&socket)?;
+        println!("Listening on {}", socket.display());
+
+        let watched = Arc::clone(&cache);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(std::time::Duration::from_secs(interval));
+            // the first tick completes right away
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let watched = Arc::clone(&watched);
+                let refresh = move || {
+                    watched
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .refresh()
+                };
+                match tokio::task::spawn_blocking(refresh).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => eprintln!("Warning: {e}"),
+                    Err(e) => eprintln!("Warning: {e}"),
+                }
+            }
+        });
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("client could not connect: {e}");
+                    continue;
+                }
+            };
+            let cache = Arc::clone(&cache);
+            tokio::spawn(async move {
+                if let Err(e) = serve(stream, cache).await {
+                    warn!("client could not be answered: {e}");
+                }
+            });
+        }
+    })
+}
+
+/// Reads the request of the client on `stream` and writes the response
+#[cfg(unix)]
+async fn serve(
+    stream: tokio::net::UnixStream,
+    cache: std::sync::Arc<std::sync::Mutex<Cache>>,
+) -> Result<(), std::io::Error> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut request = String::new();
+    BufReader::new(reader).read_line(&mut request).await?;
+    // reading Todo lists and listing them blocks. A client whose answer panicked leaves the cache
+    // poisoned but usable: it is refreshed from the files before every answer.
+    let answer = move || {
+        cache
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .answer(request.trim())
+    };
+    let response = tokio::task::spawn_blocking(answer)
+        .await
+        .map_err(std::io::Error::other)?;
+    match response {
+        Ok((count, output)) => {
+            writer.write_all(format!("ok {count}\n").as_bytes()).await?;
+            writer.write_all(&output).await
+        }
+        Err(e) => {
+            let message = format!("error {}\n", e.to_string().replace('\n', " "));
+            writer.write_all(message.as_bytes()).await
+        }
+    }
+}
+
+/// Answers list invocations of clients until interrupted
+#[cfg(not(unix))]
+pub fn daemon_command_process(
+    _args: &ArgMatches,
+    _todo_configuration_path: &str,
+    _raw_configuration: Option<&str>,
+) -> Result<(), std::io::Error> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "todo daemon needs unix sockets",
+    ))
+}
+
+/// Returns the number of Todo lists printed by the daemon for the list invocation and its output,
+/// or None when no daemon could answer
+///
+/// * `args` - list arguments, starting with `list`
+#[cfg(unix)]
+pub fn query(
+    todo_configuration_path: &str,
+    ctx: &Context,
+    args: &[String],
+) -> Option<(usize, Vec<u8>)> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket_path(todo_configuration_path)).ok()?;
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .ok()?;
+    let request = [&ctx.name, &ctx.folder_location]
+        .into_iter()
+        .chain(args.iter())
+        .map(|a| quote(a))
+        .collect::<Vec<_>>();
+    writeln!(stream, "[{}]", request.join(",")).ok()?;
+    let mut response = vec![];
+    stream.read_to_end(&mut response).ok()?;
+
+    let newline = response.iter().position(|b| *b == b'\n')?;
+    let status = String::from_utf8_lossy(&response[..newline]).to_string();
+    match status.strip_prefix("ok ") {
+        Some(count) => Some((count.parse().ok()?, response[newline + 1..].to_vec())),
+        None => {
+            debug!("daemon could not answer: {status}");
+            None
+        }
+    }
+}
+
+/// Returns the number of Todo lists printed by the daemon for the list invocation and its output,
+/// or None when no daemon could answer
+#[cfg(not(unix))]
+pub fn query(
+    _todo_configuration_path: &str,
+    _ctx: &Context,
+    _args: &[String],
+) -> Option<(usize, Vec<u8>)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn answer_from_cache() {
+        let folder = TempFolder::new("daemon");
+        let todo_folder = folder.join("todo");
+        std::fs::create_dir_all(&todo_folder).unwrap();
+        let todo_folder = todo_folder.to_string_lossy().to_string();
+        let configuration_path = folder.join("todo.toml").to_string_lossy().to_string();
+        std::fs::write(
+            &configuration_path,
+            format!(
+                "active_ctx_name = \"work\"\n\n[[ctxs]]\nname = \"work\"\nfolder_location = \"{todo_folder}\"\nide = \"vim\"\ntimezone = \"UTC\"\n"
+            ),
+        )
+        .unwrap();
+        let groceries = Path::new(todo_folder.as_str()).join("groceries.md");
+        std::fs::write(
+            &groceries,
+            "# Groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] milk\n",
+        )
+        .unwrap();
+
+        let mut cache = Cache::load(configuration_path.as_str(), None).unwrap();
+        let request = format!("[\"work\",{},\"list\"]", quote(todo_folder.as_str()));
+        let (count, output) = cache.answer(request.as_str()).unwrap();
+        assert_eq!(count, 1);
+        assert!(String::from_utf8(output).unwrap().contains("Groceries"));
+
+        std::fs::remove_file(&groceries).unwrap();
+        assert_eq!(cache.answer(request.as_str()).unwrap().0, 0);
+
+        // archived Todo lists are not listed and parts are read with their Todo list
+        let archive_folder = archive_folder(&cache.configuration.ctxs[0]);
+        std::fs::create_dir_all(&archive_folder).unwrap();
+        std::fs::write(
+            archive_folder.join("old.md"),
+            "# Old\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] done\n",
+        )
+        .unwrap();
+        let live = Path::new(todo_folder.as_str()).join("live.md");
+        std::fs::write(
+            &live,
+            "# Live\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n",
+        )
+        .unwrap();
+        std::fs::write(
+            Path::new(todo_folder.as_str()).join("live.1.md"),
+            "* [ ] b\n",
+        )
+        .unwrap();
+        let all = format!(
+            "[\"work\",{},\"list\",\"--all\"]",
+            quote(todo_folder.as_str())
+        );
+        let (count, output) = cache.answer(all.as_str()).unwrap();
+        assert_eq!(count, 1);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("* [ ] b") && !output.contains("Old"));
+
+        let archived = format!(
+            "[\"work\",{},\"list\",\"--archived\"]",
+            quote(todo_folder.as_str())
+        );
+        assert!(cache.answer(archived.as_str()).is_err());
+        assert!(cache.answer("[".repeat(200_000).as_str()).is_err());
+        assert!(cache.answer("[\"home\",\"/home\",\"list\"]").is_err());
+        assert!(cache
+            .answer(
+                format!(
+                    "[\"work\",{},\"list\",\"--label-add\",\"x\"]",
+                    quote(todo_folder.as_str())
+                )
+                .as_str()
+            )
+            .is_err());
+    }
+}
@@ -1,14 +1,22 @@
 //! Delete Todo list from active Todo context inside configuration
-use super::todo_path;
+//!
+//! Parts of the Todo list (see [`crate::parts`]) are deleted along with it.
+use super::confirm::{confirm, Operation};
+use super::lock::ensure_unlocked;
+use super::parts::part_paths;
+use super::single_file::SingleFile;
 use super::Context;
+use super::{path_arg, todo_path_from_args};
 use clap::crate_authors;
 use clap::{App, Arg, ArgMatches};
-use log::trace;
+use log::{debug, trace};
 use std::fs::remove_file;
+use std::path::Path;
 
 /// Returns Delete Todo command
 pub fn delete_command() -> App<'static, 'static> {
     App::new("delete")
+        .visible_alias("rm")
         .about("Delete todo list by name within Todo context")
         .author(crate_authors!())
         .arg(
@@ -19,7 +27,20 @@ pub fn delete_command() -> App<'static, 'static> {
                 .index(1)
                 .help("Title of todo to delete")
                 .takes_value(true)
-                .required(true),
+                .required_unless("path"),
+        )
+        .arg(path_arg())
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Delete todo list even if it is locked"),
+        )
+        .arg(
+            Arg::with_name("yes")
+                .short("y")
+                .long("yes")
+                .help("Delete todo list without asking for confirmation"),
         )
 }
 
@@ -27,9 +48,48 @@ pub fn delete_command() -> App<'static, 'static> {
 pub fn delete_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
     trace!("delete subcommand");
 
-    let title = args.value_of("title").unwrap();
-    match remove_file(todo_path(ctx.folder_location.as_str(), title)) {
-        Ok(_) => println!("Successfully removed {}", title),
+    if ctx.is_single_file() && !args.is_present("path") {
+        let title = args.value_of("title").unwrap();
+        let mut single_file = SingleFile::read(ctx)?;
+        let i = single_file.position_of_unlocked(title, args.is_present("force"))?;
+        if !args.is_present("yes")
+            && !confirm(
+                ctx,
+                Operation::Delete,
+                format!("Delete todo \"{title}\"?").as_str(),
+            )?
+        {
+            return Ok(());
+        }
+        single_file.todo_lists.remove(i);
+        single_file.write()?;
+        println!("Successfully removed {}", title);
+        return Ok(());
+    }
+
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    if Path::new(filepath.as_str()).exists()
+        && !args.is_present("yes")
+        && !confirm(
+            ctx,
+            Operation::Delete,
+            format!("Delete todo \"{title}\"?").as_str(),
+        )?
+    {
+        return Ok(());
+    }
+    let parts = part_paths(filepath.as_str());
+    match remove_file(&filepath) {
+        Ok(_) => {
+            debug!("removed {filepath}");
+            for part in parts {
+                remove_file(&part)?;
+                debug!("removed {part}");
+            }
+            println!("Successfully removed {}", title)
+        }
         Err(_) => eprintln!("Error: File does not exist"),
     }
 
@@ -0,0 +1,153 @@
+//! Describe a Todo list of active Todo context without opening an editor
+//!
+//! `todo describe -t TITLE "description"` replaces the description of the Todo list, which is the
+//! text of `## Description` below the `LABEL=` line. With `--append`, the text is added as a new
+//! paragraph instead. Labels, annotations (`<!-- todo:locked -->`) and the rest of the Todo list are
+//! left as they are.
+use crate::lock::ensure_unlocked;
+use crate::{path_arg, todo_path_from_args, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::fs::read_to_string;
+
+/// Returns the Describe command
+pub fn describe_command() -> App<'static, 'static> {
+    App::new("describe")
+        .about("Replace the description of todo list within Todo context")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .help("Title of todo list")
+                .takes_value(true)
+                .required_unless("path"),
+        )
+        .arg(path_arg())
+        .arg(
+            Arg::with_name("description")
+                .value_name("DESCRIPTION")
+                .index(1)
+                .help("New description of todo list")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("append")
+                .short("a")
+                .long("append")
+                .help("Adds DESCRIPTION as a new paragraph of the description instead"),
+        )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Describe todo list even if it is locked"),
+        )
+}
+
+/// Replaces (or appends to) the description of a Todo list in active Todo context
+pub fn describe_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("describe subcommand");
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    let todo_raw = read_to_string(&filepath)?;
+    let todo_raw = describe(
+        todo_raw.as_str(),
+        args.value_of("description").unwrap(),
+        args.is_present("append"),
+    )?;
+    std::fs::write(&filepath, todo_raw)?;
+    debug!("wrote {filepath}");
+    println!("Described \"{title}\"");
+    Ok(())
+}
+
+/// Returns Todo list with `description` replacing its description, or added to it with `append`
+fn describe(todo_raw: &str, description: &str, append: bool) -> Result<String, std::io::Error> {
+    let lines = todo_raw.lines().collect::<Vec<_>>();
+    let label = lines
+        .iter()
+        .position(|l| l.trim_end() == "## Description")
+        .and_then(|d| {
+            lines[d..]
+                .iter()
+                .position(|l| l.starts_with("LABEL="))
+                .map(|i| d + i)
+        })
+        .ok_or_else(|| {
+            std::io::Error::other("Todo list does not have labels (LABEL= below ## Description)")
+        })?;
+    let end = lines[label + 1..]
+        .iter()
+        .position(|l| l.starts_with("## ") || l.starts_with("# "))
+        .map_or(lines.len(), |i| label + 1 + i);
+    let body = &lines[label + 1..end];
+
+    // annotations are kept, below the description
+    let annotations = body
+        .iter()
+        .filter(|l| l.trim_start().starts_with("<!--"))
+        .copied()
+        .collect::<Vec<_>>();
+    let existing = body
+        .iter()
+        .filter(|l| !l.trim_start().starts_with("<!--"))
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = match existing.trim() {
+        existing if append && !existing.is_empty() => {
+            format!("{existing}\n\n{}", description.trim())
+        }
+        _ => description.trim().to_string(),
+    };
+
+    let mut described = lines[..=label]
+        .iter()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>();
+    if body.first().is_some_and(|l| l.trim().is_empty()) {
+        described.push(String::new());
+    }
+    described.extend(text.lines().map(str::to_string));
+    if !annotations.is_empty() {
+        described.push(String::new());
+        described.extend(annotations.iter().map(|l| l.to_string()));
+    }
+    if end < lines.len() {
+        described.push(String::new());
+    }
+    described.extend(lines[end..].iter().map(|l| l.to_string()));
+    let mut described = described.join("\n");
+    if todo_raw.ends_with('\n') {
+        described.push('\n');
+    }
+    Ok(described)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_todo_list() {
+        let todo_raw = "# Release\n\n## Description\n\nLABEL=work\n\nShip it.\n\n<!-- todo:pinned -->\n\n## Todo list\n\n* [ ] tag\n";
+        assert_eq!(
+            describe(todo_raw, "Ship the second version.", false).unwrap(),
+            "# Release\n\n## Description\n\nLABEL=work\n\nShip the second version.\n\n<!-- todo:pinned -->\n\n## Todo list\n\n* [ ] tag\n"
+        );
+        assert_eq!(
+            describe(todo_raw, "Then announce it.", true).unwrap(),
+            "# Release\n\n## Description\n\nLABEL=work\n\nShip it.\n\nThen announce it.\n\n<!-- todo:pinned -->\n\n## Todo list\n\n* [ ] tag\n"
+        );
+        // as written by `todo create`
+        assert_eq!(
+            describe("# Title\n\n## Description\n\nLABEL=\n", "Hello", true).unwrap(),
+            "# Title\n\n## Description\n\nLABEL=\nHello\n"
+        );
+        assert!(describe("# Title\n\n* [ ] tag\n", "Hello", false).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! Post a summary of a Todo context to a chat webhook
+//!
+//! The digest lists tasks checked during the period (from git history of git-backed Todo contexts),
+//! overdue tasks and tasks due today (`@due(YYYY-MM-DD)`, in the timezone of the Todo context) and
+//! open tasks of each Todo list. Supported destinations:
+//!
+//! * `slack://hooks.slack.com/services/...`: Slack incoming webhook
+//! * `matrix://HOMESERVER/ROOM_ID?token=ACCESS_TOKEN`: Matrix room
+//!
+//! `--post` may be repeated to post the digest to many webhooks at once.
+use crate::activity::checked_tasks;
+use crate::json::quote;
+use crate::list::Due;
+use crate::parse::{parse_tasks, parse_todo_list_due_tasks};
+use crate::runtime::{block_on, concurrently};
+use crate::single_file::title_of;
+use crate::{read_todo_lists, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace, warn};
+
+/// Returns the Digest command
+pub fn digest_command() -> App<'static, 'static> {
+    App::new("digest")
+        .about("Post a summary of active Todo context to Slack or Matrix")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("post")
+                .long("post")
+                .value_name("URL")
+                .help("Webhook receiving the digest (slack://... or matrix://HOMESERVER/ROOM?token=TOKEN), repeat for more")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required_unless("stdout"),
+        )
+        .arg(
+            Arg::with_name("stdout")
+                .long("stdout")
+                .help("Print the digest instead of posting it")
+                .conflicts_with("post"),
+        )
+        .arg(
+            Arg::with_name("weekly")
+                .long("weekly")
+                .help("Summarise the last week instead of the last day"),
+        )
+}
+
+/// Renders digest of active Todo context and posts it
+pub fn digest_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("digest subcommand");
+    let weekly = args.is_present("weekly");
+    let folder = ctx.folder_location.as_str();
+    let checked = match checked_tasks(ctx, if weekly { "1w" } else { "1d" }) {
+        Ok(checked) => Some(checked),
+        Err(e) => {
+            warn!("no git history for {folder}: {e}");
+            None
+        }
+    };
+    let today = ctx.today();
+    let (mut overdue, mut due_today, mut open) = (vec![], vec![], vec![]);
+    for (filepath, todo_raw) in read_todo_lists(ctx)? {
+        let count = parse_tasks(todo_raw.as_str())
+            .iter()
+            .filter(|t| !t.done)
+            .count();
+        if count == 0 {
+            continue;
+        }
+        let title = match title_of(todo_raw.as_str()) {
+            "" => filepath.as_str(),
+            title => title,
+        };
+        for (due, tasks) in [(Due::Overdue, &mut overdue), (Due::Today, &mut due_today)] {
+            for task in
+                parse_todo_list_due_tasks(todo_raw.as_str(), true, |date| due.keeps(date, today))
+            {
+                let text = task.strip_prefix("* [ ] ").unwrap_or(task.as_str());
+                tasks.push((title.to_string(), text.to_string()));
+            }
+        }
+        open.push((title.to_string(), count));
+    }
+    let due = Scheduled {
+        overdue: &overdue,
+        today: &due_today,
+    };
+    let digest = render(ctx.name.as_str(), weekly, checked.as_deref(), due, &open);
+
+    match args.values_of("post") {
+        Some(urls) => {
+            let posts = urls
+                .map(|url| post(url.to_string(), digest.clone()))
+                .collect::<Vec<_>>();
+            block_on(concurrently(posts, |()| println!("Posted digest")))
+        }
+        None => {
+            print!("{digest}");
+            Ok(())
+        }
+    }
+}
+
+/// Title and text of open tasks with a due date
+#[derive(Clone, Copy, Default)]
+struct Scheduled<'a> {
+    overdue: &'a [(String, String)],
+    today: &'a [(String, String)],
+}
+
+/// Returns digest text
+///
+/// * `checked` - title and text of checked tasks, None without history
+/// * `due` - overdue tasks and tasks due today
+/// * `open` - title and number of open tasks of Todo lists
+fn render(
+    ctx_name: &str,
+    weekly: bool,
+    checked: Option<&[(String, String)]>,
+    due: Scheduled,
+    open: &[(String, usize)],
+) -> String {
+    let mut digest = format!(
+        "{} digest of \"{ctx_name}\"\n",
+        if weekly { "Weekly" } else { "Daily" }
+    );
+    let period = if weekly { "last week" } else { "yesterday" };
+    match checked {
+        Some([]) => digest.push_str(format!("\nNothing completed since {period}\n").as_str()),
+        Some(checked) => {
+            digest.push_str(format!("\nCompleted since {period}:\n").as_str());
+            for (title, task) in checked {
+                digest.push_str(format!("- {title}: {task}\n").as_str());
+            }
+        }
+        None => {}
+    }
+    for (heading, tasks) in [("Overdue", due.overdue), ("Due today", due.today)] {
+        if tasks.is_empty() {
+            continue;
+        }
+        digest.push_str(format!("\n{heading}:\n").as_str());
+        for (title, task) in tasks {
+            digest.push_str(format!("- {title}: {task}\n").as_str());
+        }
+    }
+    if open.is_empty() {
+        digest.push_str("\nNo open tasks\n");
+    } else {
+        digest.push_str("\nOpen:\n");
+        for (title, count) in open {
+            digest.push_str(format!("- {title}: {count} task(s)\n").as_str());
+        }
+    }
+    digest
+}
+
+/// Returns the method, HTTPS URL, headers and JSON body of the request posting `text` to `url`
+fn webhook_request(url: &str, text: &str) -> Result<(String, String, Vec<String>, String), String> {
+    if let Some(rest) = url.strip_prefix("slack://") {
+        return Ok((
+            "POST".to_string(),
+            format!("https://{rest}"),
+            vec![],
+            format!("{{\"text\":{}}}", quote(text)),
+        ));
+    }
+    if let Some(rest) = url.strip_prefix("matrix://") {
+        let (location, token) = match rest.split_once("?token=") {
+            Some(parts) => parts,
+            None => return Err("Matrix webhook has no access token (?token=...)".to_string()),
+        };
+        let (homeserver, room) = match location.split_once('/') {
+            Some(parts) => parts,
+            None => return Err("Matrix webhook has no room".to_string()),
+        };
+        let transaction = chrono::Utc::now().timestamp_millis();
+        return Ok((
+            "PUT".to_string(),
+            format!(
+                "https://{homeserver}/_matrix/client/v3/rooms/{}/send/m.room.message/todo{transaction}",
+                room.replace('!', "%21").replace(':', "%3A")
+            ),
+            vec![format!("Authorization: Bearer {token}")],
+            format!(
+                "{{\"msgtype\":\"m.text\",\"body\":{}}}",
+                quote(text)
+            ),
+        ));
+    }
+    Err(format!(
+        "Unsupported webhook \"{url}\" (slack://... or matrix://...)"
+    ))
+}
+
+/// Returns the curl config (`curl -K`) requesting `url` with `headers` and `body`
+fn curl_config(url: &str, headers: &[String], body: &str) -> String {
+    // backslashes, quotes and line breaks are escaped inside quoted strings of curl config files
+    let quote = |s: &str| {
+        let escaped = s
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        format!("\"{escaped}\"")
+    };
+    let mut config = format!("url = {}\n", quote(url));
+    for header in
+        std::iter::once("Content-Type: application/json").chain(headers.iter().map(String::as_str))
+    {
+        config.push_str(format!("header = {}\n", quote(header)).as_str());
+    }
+    config.push_str(format!("data-binary = {}\n", quote(body)).as_str());
+    config
+}
+
+/// Posts `text` to webhook at `url` with curl. Webhook URL, access token and body are written to
+/// the standard input of curl rather than passed as arguments, which other users can read.
+async fn post(url: String, text: String) -> Result<(), std::io::Error> {
+    let (method, url, headers, body) =
+        webhook_request(url.as_str(), text.as_str()).map_err(std::io::Error::other)?;
+    let args = [
+        "-s",
+        "-f",
+        "-o",
+        "/dev/null",
+        "-X",
+        method.as_str(),
+        "-K",
+        "-",
+    ];
+    let config = curl_config(url.as_str(), &headers, body.as_str());
+    let output = crate::runtime::curl(&args, Some(config.as_str())).await?;
+    if !output.status.success() {
+        return Err(std::io::Error::other("Digest could not be posted"));
+    }
+    debug!("posted digest");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_digest() {
+        let checked = vec![("Release".to_string(), "tag".to_string())];
+        let open = vec![("Release".to_string(), 2)];
+        assert_eq!(
+            render("work", false, Some(&checked), Scheduled::default(), &open),
+            "Daily digest of \"work\"\n\nCompleted since yesterday:\n- Release: tag\n\nOpen:\n- Release: 2 task(s)\n"
+        );
+        let overdue = vec![("Release".to_string(), "tag @due(2024-06-30)".to_string())];
+        let today = vec![("Release".to_string(), "ship @due(2024-07-01)".to_string())];
+        let due = Scheduled {
+            overdue: &overdue,
+            today: &today,
+        };
+        assert_eq!(
+            render("work", false, None, due, &open),
+            "Daily digest of \"work\"\n\nOverdue:\n- Release: tag @due(2024-06-30)\n\nDue today:\n- Release: ship @due(2024-07-01)\n\nOpen:\n- Release: 2 task(s)\n"
+        );
+        assert_eq!(
+            render("work", true, None, Scheduled::default(), &[]),
+            "Weekly digest of \"work\"\n\nNo open tasks\n"
+        );
+    }
+
+    #[test]
+    fn webhook_requests() {
+        let (method, url, headers, body) =
+            webhook_request("slack://hooks.slack.com/services/T/B/X", "a \"b\"").unwrap();
+        assert_eq!(method, "POST");
+        assert_eq!(url, "https://hooks.slack.com/services/T/B/X");
+        assert!(headers.is_empty());
+        assert_eq!(body, "{\"text\":\"a \\\"b\\\"\"}");
+
+        let (method, url, headers, _) =
+            webhook_request("matrix://matrix.org/!room:matrix.org?token=secret", "a").unwrap();
+        assert_eq!(method, "PUT");
+        assert!(url.starts_with(
+            "https://matrix.org/_matrix/client/v3/rooms/%21room%3Amatrix.org/send/m.room.message/"
+        ));
+        assert_eq!(headers, vec!["Authorization: Bearer secret"]);
+
+        assert!(webhook_request("matrix://matrix.org/!room", "a").is_err());
+        assert!(webhook_request("https://example.com", "a").is_err());
+    }
+
+    #[test]
+    fn curl_configs() {
+        assert_eq!(
+            curl_config(
+                "https://matrix.org/send",
+                &["Authorization: Bearer secret".to_string()],
+                "{\"body\":\"a\\nb\"}"
+            ),
+            "url = \"https://matrix.org/send\"\n\
+header = \"Content-Type: application/json\"\n\
+header = \"Authorization: Bearer secret\"\n\
+data-binary = \"{\\\"body\\\":\\\"a\\\\nb\\\"}\"\n"
+        );
+    }
+}
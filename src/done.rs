@@ -0,0 +1,228 @@
+//! Check off tasks of a Todo list in active Todo context without opening an editor
+//!
+//! `todo done LIST TASK` flips the open task matching `TASK` from `* [ ]` to `* [x]`. `TASK` is
+//! part of the text of the task. When no task holds it, words of `TASK` may hold typos (like
+//! `todo search`). Exactly one open task must match. `todo done --path FILE TASK` checks off a task
+//! of the Todo list at `FILE`.
+//!
+//! With `--archive`, the task is moved into the archive of the Todo list (`## Archive`), which
+//! `todo list` collapses. Todo lists spanning many files (see [`crate::parts`]) are checked off as
+//! one Todo list.
+use crate::lock::ensure_unlocked;
+use crate::parse::{find_tasks, parse_tasks, Task};
+use crate::parts::Parts;
+use crate::{path_arg, resolve_todo_path, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use core::fmt;
+use log::trace;
+
+/// Errors for done command
+#[derive(Debug)]
+pub enum Error {
+    /// The Todo list could not be read or written
+    Io(std::io::Error),
+    /// No open task matches the given pattern. Argument is the pattern.
+    NoMatchingTask(String),
+    /// Many open tasks match the given pattern.
+    ///
+    /// First argument is the pattern. Second argument are the matching tasks.
+    AmbiguousTask(String, Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => writeln!(f, "{e}"),
+            Error::NoMatchingTask(pattern) => writeln!(f, "No open task matches \"{pattern}\"."),
+            Error::AmbiguousTask(pattern, tasks) => {
+                writeln!(f, "Many open tasks match \"{pattern}\":")?;
+                for task in tasks {
+                    writeln!(f, "- {task}")?;
+                }
+                writeln!(f, "Please use a more specific pattern.")
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Returns the Done command
+pub fn done_command() -> App<'static, 'static> {
+    App::new("done")
+        .about("Check off a task of todo list within Todo context")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("LIST")
+                .help("Title of todo list")
+                .takes_value(true),
+        )
+        .arg(path_arg())
+        .arg(
+            Arg::with_name("list")
+                .value_name("LIST")
+                .index(1)
+                .help("Title of todo list, or the task with --title or --path")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("task")
+                .value_name("TASK")
+                .index(2)
+                .help("Part of the text of the task to check off")
+                .takes_value(true)
+                .required_unless_one(&["title", "path"])
+                .conflicts_with_all(&["title", "path"]),
+        )
+        .arg(
+            Arg::with_name("archive")
+                .short("a")
+                .long("archive")
+                .help("Moves the task into the archive of todo list (## Archive)"),
+        )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Check off task even if todo list is locked"),
+        )
+}
+
+/// Checks off the open task matching the pattern in a Todo list of active Todo context
+pub fn done_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), Error> {
+    trace!("done subcommand");
+    // with --title or --path, the only positional argument is the task
+    let (title, pattern) = match args.value_of("task") {
+        Some(pattern) => (args.value_of("list"), pattern),
+        None => (args.value_of("title"), args.value_of("list").unwrap()),
+    };
+
+    let filepath = match args.value_of("path") {
+        Some(filepath) => filepath.to_string(),
+        None => resolve_todo_path(ctx, title.unwrap())?,
+    };
+    let title = title.unwrap_or(filepath.as_str());
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    let mut parts = Parts::read(filepath.as_str())?;
+    let (todo_raw, task) = check_off(parts.joined().as_str(), pattern)?;
+    parts.set(todo_raw.as_str())?;
+    if args.is_present("archive") {
+        parts.archive_task(&task);
+    }
+    parts.write()?;
+    println!("Checked off \"{}\" in \"{title}\"", task.text);
+
+    Ok(())
+}
+
+/// Returns Todo list where the open task matching `pattern` is done, and that task
+pub(crate) fn check_off(todo_raw: &str, pattern: &str) -> Result<(String, Task), Error> {
+    let tasks = parse_tasks(todo_raw)
+        .into_iter()
+        .filter(|t| !t.done)
+        .collect::<Vec<_>>();
+    let mut matches = find_tasks(&tasks, pattern);
+    if matches.is_empty() {
+        matches = tasks.iter().filter(|t| is_close(t, pattern)).collect();
+    }
+    let task = match matches.as_slice() {
+        [] => return Err(Error::NoMatchingTask(pattern.to_string())),
+        [task] => task,
+        _ => {
+            return Err(Error::AmbiguousTask(
+                pattern.to_string(),
+                matches.iter().map(|t| t.text.to_string()).collect(),
+            ))
+        }
+    };
+
+    let mut lines = todo_raw.lines().map(str::to_string).collect::<Vec<_>>();
+    lines[task.line] = lines[task.line].replacen("[ ]", "[x]", 1);
+    let mut todo = lines.join("\n");
+    if todo_raw.ends_with('\n') {
+        todo.push('\n');
+    }
+    let mut task = (*task).clone();
+    task.done = true;
+    Ok((todo, task))
+}
+
+/// Returns true if every word of `pattern` is a word of the task, give or take a typo or two
+fn is_close(task: &Task, pattern: &str) -> bool {
+    let text = task.text.to_lowercase();
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let pattern = pattern.to_lowercase();
+    let mut terms = pattern.split_whitespace().peekable();
+    terms.peek().is_some()
+        && terms.all(|term| {
+            let typos = match term.chars().count() {
+                0..=3 => 0,
+                4..=7 => 1,
+                _ => 2,
+            };
+            words
+                .iter()
+                .any(|word| strsim::osa_distance(word, term) <= typos)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::archive_tasks;
+
+    #[test]
+    fn check_off_open_task() {
+        let todo_raw = "# Title\n\n## Todo list\n\n* [x] buy paint\n* [ ] buy brush\n* [ ] call plumber\n  > left a message\n* [ ] call painter\n";
+        let (checked, task) = check_off(todo_raw, "brush").unwrap();
+        assert_eq!(task.text, "buy brush");
+        assert_eq!(
+            checked,
+            "# Title\n\n## Todo list\n\n* [x] buy paint\n* [x] buy brush\n* [ ] call plumber\n  > left a message\n* [ ] call painter\n"
+        );
+        // done tasks are not matched again
+        assert_eq!(check_off(todo_raw, "buy").unwrap().1.text, "buy brush");
+        assert_eq!(
+            check_off(todo_raw, "plumbr").unwrap().1.text,
+            "call plumber"
+        );
+        match check_off(todo_raw, "call") {
+            Err(Error::AmbiguousTask(_, tasks)) => {
+                assert_eq!(tasks, vec!["call plumber", "call painter"])
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(check_off(todo_raw, "paint").unwrap().1.text, "call painter");
+        assert!(matches!(
+            check_off(todo_raw, "dentist"),
+            Err(Error::NoMatchingTask(_))
+        ));
+        assert_eq!(
+            archive_tasks(checked.as_str(), &[task]),
+            "# Title\n\n## Todo list\n\n* [x] buy paint\n* [ ] call plumber\n  > left a message\n* [ ] call painter\n\n## Archive\n\n* [x] buy brush\n"
+        );
+    }
+
+    #[test]
+    fn task_follows_title_or_path() {
+        let args = done_command().get_matches_from(vec!["done", "--path", "a.md", "milk"]);
+        assert_eq!(args.value_of("list"), Some("milk"));
+        assert!(done_command()
+            .get_matches_from_safe(vec!["done", "-t", "groceries", "milk"])
+            .is_ok());
+        assert!(done_command()
+            .get_matches_from_safe(vec!["done", "--path", "a.md", "groceries", "milk"])
+            .is_err());
+        assert!(done_command()
+            .get_matches_from_safe(vec!["done", "groceries"])
+            .is_err());
+    }
+}
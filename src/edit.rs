@@ -1,18 +1,48 @@
 //! Edit Todo list in active Todo context
-use super::{todo_path, Configuration, Context};
+//!
+//! Todo lists are opened with the IDE of the Todo context, or with the one configured for their
+//! extension inside `[ctxs.ide_by_extension]`. An IDE is either a program, given the path of the
+//! Todo list, or a URI template such as `obsidian://open?path={encoded_path}` or
+//! `vscode://file{path}`, opened with the URI handler of the desktop (`xdg-open`, `open` on macOS,
+//! `start` on Windows). `{path}` is replaced by the absolute path of the Todo list and
+//! `{encoded_path}` by the percent-encoded absolute path.
+use super::lock::ensure_unlocked;
+use super::single_file::edit_todo_list;
+use super::{did_you_mean, path_arg, todo_path_from_args, Configuration, Context};
 use clap::{crate_authors, App, Arg, ArgMatches};
 use core::fmt;
-use log::trace;
-use std::process::Command;
+use log::{debug, trace};
+use std::process::{Command, ExitStatus};
 
 pub enum Error {
-    UnknownContext(String),
+    /// No Todo context has the given name. Arguments are the name and the closest names of Todo
+    /// contexts.
+    UnknownContext(String, Vec<String>),
+    /// The Todo list is locked against modification. Argument is the Todo list path.
+    Locked(String),
+    /// The path of the Todo list could not be resolved from its title
+    Resolution(std::io::Error),
+    /// The IDE could not be run. Arguments are the IDE and the error.
+    Open(String, std::io::Error),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), std::fmt::Error> {
         match self {
-            Error::UnknownContext(ctx) => writeln!(f, "Unknown context \"{ctx}\" was referrenced."),
+            Error::UnknownContext(ctx, closest) => {
+                let closest = closest.iter().map(|n| n.as_str()).collect::<Vec<_>>();
+                writeln!(
+                    f,
+                    "Unknown context \"{ctx}\" was referrenced{}.",
+                    did_you_mean(&closest)
+                )
+            }
+            Error::Locked(filepath) => writeln!(
+                f,
+                "Todo list at \"{filepath}\" is locked. Unlock it or use --force."
+            ),
+            Error::Resolution(e) => writeln!(f, "{e}"),
+            Error::Open(ide, e) => writeln!(f, "Todo list could not be opened with \"{ide}\": {e}"),
         }
     }
 }
@@ -30,8 +60,9 @@ pub fn edit_command() -> App<'static, 'static> {
                 .index(1)
                 .help("Title of todo list")
                 .takes_value(true)
-                .required(true),
+                .required_unless("path"),
         )
+        .arg(path_arg())
         .arg(
             Arg::with_name("context name")
                 .short("c")
@@ -41,6 +72,12 @@ pub fn edit_command() -> App<'static, 'static> {
                 .help("Context of todo list")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Edit todo list even if it is locked"),
+        )
 }
 
 /// Edits Todo list in active Todo context with configured IDE
@@ -52,21 +89,105 @@ pub fn edit_command_process(
     trace!("edit subcommand");
     println!("Listing all todo's from {}", ctx.folder_location);
 
-    let title = args.value_of("title").unwrap();
-    let (ctx_ide, ctx_folder) = if let Some(name) = args.value_of("context name") {
+    let ctx = if let Some(name) = args.value_of("context name") {
         if let Some(ctx) = config.ctxs.iter().find(|ctx| ctx.name == name) {
-            (ctx.ide.as_str(), ctx.folder_location.as_str())
+            ctx
         } else {
-            return Err(Error::UnknownContext(name.to_string()));
+            return Err(Error::UnknownContext(
+                name.to_string(),
+                config
+                    .closest_ctx_names(name)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+            ));
         }
     } else {
-        (ctx.ide.as_str(), ctx.folder_location.as_str())
+        ctx
     };
 
-    Command::new(ctx_ide)
-        .arg(todo_path(ctx_folder, title))
-        .status()
-        .expect("IDE error");
+    if ctx.is_single_file() && !args.is_present("path") {
+        let title = args.value_of("title").unwrap();
+        return edit_todo_list(ctx, title, args.is_present("force")).map_err(Error::Resolution);
+    }
+
+    let filepath = todo_path_from_args(args, ctx).map_err(Error::Resolution)?;
+    if ensure_unlocked(filepath.as_str(), args.is_present("force")).is_err() {
+        return Err(Error::Locked(filepath));
+    }
+
+    let ide = ctx.ide_for(filepath.as_str());
+    open(ide, filepath.as_str()).map_err(|e| Error::Open(ide.to_string(), e))?;
 
     Ok(())
 }
+
+/// Opens the Todo list at `filepath` with `ide`, a program or a URI template
+fn open(ide: &str, filepath: &str) -> Result<ExitStatus, std::io::Error> {
+    if !is_uri_template(ide) {
+        return Command::new(ide).arg(filepath).status();
+    }
+    let path = std::fs::canonicalize(filepath)?;
+    let uri = uri(ide, path.to_string_lossy().as_ref());
+    debug!("opening {uri}");
+    let mut opener = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut opener = Command::new("cmd");
+        opener.args(["/C", "start", ""]);
+        opener
+    } else {
+        Command::new("xdg-open")
+    };
+    opener.arg(uri).status()
+}
+
+/// Returns true if `ide` is a URI template rather than a program
+fn is_uri_template(ide: &str) -> bool {
+    ide.contains("://")
+}
+
+/// Returns the URI of URI template `ide` opening the Todo list at absolute `path`
+fn uri(ide: &str, path: &str) -> String {
+    let encoded_path = path
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect::<String>();
+    ide.replace("{path}", path)
+        .replace("{encoded_path}", encoded_path.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ide_uri_templates() {
+        assert!(!is_uri_template("vim"));
+        assert_eq!(
+            uri("obsidian://open?path={encoded_path}", "/notes/to do.md"),
+            "obsidian://open?path=%2Fnotes%2Fto%20do.md"
+        );
+        assert_eq!(
+            uri("vscode://file{path}", "/notes/to do.md"),
+            "vscode://file/notes/to do.md"
+        );
+        let ctx = Context {
+            ide: "vim".to_string(),
+            ide_by_extension: [(
+                "md".to_string(),
+                "obsidian://open?path={encoded_path}".to_string(),
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        assert_eq!(ctx.ide_for("a/b.md"), "obsidian://open?path={encoded_path}");
+        assert_eq!(ctx.ide_for("a/b.txt"), "vim");
+    }
+}
@@ -0,0 +1,82 @@
+//! Run `todo-NAME` executables for unknown subcommands
+//!
+//! Like git or cargo, `todo NAME ARGS...` runs `todo-NAME ARGS...` found on `PATH`. The active Todo
+//! context is passed as environment variables:
+//!
+//! * `TODO_CTX_NAME`: name of the Todo context
+//! * `TODO_CTX_FOLDER`: folder of the Todo lists
+//! * `TODO_CONFIG`: path of the configuration file
+use crate::Context;
+use clap::ArgMatches;
+use log::{debug, trace};
+use std::process::Command;
+
+/// Runs the executable of external subcommand `name` with its arguments
+pub fn external_command_process(
+    name: &str,
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    ctx: &Context,
+) -> Result<(), std::io::Error> {
+    trace!("external subcommand {name}");
+    let args = args.values_of("").unwrap_or_default().collect::<Vec<_>>();
+    let mut command = external_command(name, &args, todo_configuration_path, ctx);
+    debug!("running {command:?}");
+    let status = match command.status() {
+        Ok(status) => status,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Unrecognised subcommand \"{name}\" (no todo-{name} executable on PATH)"),
+            ))
+        }
+        Err(e) => return Err(e),
+    };
+    if !status.success() {
+        return Err(std::io::Error::other(format!(
+            "todo-{name} failed ({status})"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the command running `todo-NAME` inside Todo context
+fn external_command(
+    name: &str,
+    args: &[&str],
+    todo_configuration_path: &str,
+    ctx: &Context,
+) -> Command {
+    let mut command = Command::new(format!("todo-{name}"));
+    command
+        .args(args)
+        .env("TODO_CTX_NAME", ctx.name.as_str())
+        .env("TODO_CTX_FOLDER", ctx.folder_location.as_str())
+        .env("TODO_CONFIG", todo_configuration_path);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn external_command_receives_context() {
+        let ctx = Context {
+            name: "work".to_string(),
+            folder_location: "/tmp/work".to_string(),
+            ..Default::default()
+        };
+        let command = external_command("burndown", &["--weeks", "2"], "/home/me/.todo", &ctx);
+        assert_eq!(command.get_program(), "todo-burndown");
+        assert_eq!(command.get_args().collect::<Vec<_>>(), vec!["--weeks", "2"]);
+        let envs = command.get_envs().collect::<Vec<_>>();
+        assert!(envs.contains(&(OsStr::new("TODO_CTX_NAME"), Some(OsStr::new("work")))));
+        assert!(envs.contains(&(OsStr::new("TODO_CTX_FOLDER"), Some(OsStr::new("/tmp/work")))));
+        assert!(envs.contains(&(
+            OsStr::new("TODO_CONFIG"),
+            Some(OsStr::new("/home/me/.todo"))
+        )));
+    }
+}
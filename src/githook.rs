@@ -0,0 +1,176 @@
+//! Install git hooks inside git-backed Todo contexts
+//!
+//! The pre-commit hook lints the Todo lists of the Todo context so malformed Todo lists never get
+//! committed. Hooks installed by Todo carry a marker line: hooks without it are never overwritten
+//! nor removed.
+use crate::activity::git;
+use crate::migrate::shell_quote;
+use crate::Context;
+use clap::{crate_authors, App, AppSettings, Arg, ArgMatches};
+use log::{debug, trace, warn};
+use std::path::{Path, PathBuf};
+
+/// Line identifying hooks installed by Todo
+const MARKER: &str = "# installed by `todo githook install`";
+
+/// Returns githook command which is comprised of multiple subcommands
+pub fn githook_command() -> App<'static, 'static> {
+    App::new("githook")
+        .about("Manage git hooks of git-backed Todo context")
+        .author(crate_authors!())
+        .setting(AppSettings::SubcommandRequired)
+        .subcommand(
+            App::new("install")
+                .about("Install pre-commit hook linting todo lists of active Todo context")
+                .author(crate_authors!())
+                .arg(
+                    Arg::with_name("uninstall")
+                        .long("uninstall")
+                        .help("Remove pre-commit hook previously installed")
+                        .takes_value(false),
+                ),
+        )
+}
+
+/// Executes githook command
+pub fn githook_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    if let Some(args) = args.subcommand_matches("install") {
+        trace!("githook install");
+        let folder = Path::new(ctx.folder_location.as_str());
+        let hooks_folder = hooks_folder(folder).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "Todo context \"{}\" is not inside a git repository: {e}",
+                    ctx.name
+                ),
+            )
+        })?;
+        let hook_path = hooks_folder.join("pre-commit");
+        if args.is_present("uninstall") {
+            if uninstall_hook(&hook_path)? {
+                println!("Removed pre-commit hook ({})", hook_path.display());
+            } else {
+                println!("No pre-commit hook to remove");
+            }
+        } else {
+            let folder = std::fs::canonicalize(folder)?;
+            install_hook(&hook_path, &folder)?;
+            println!("Installed pre-commit hook ({})", hook_path.display());
+        }
+        return Ok(());
+    }
+
+    warn!("unrecognised command");
+    Err(std::io::Error::other("Unrecognised command"))
+}
+
+/// Returns the hooks folder of the git repository containing `folder`, as git finds it: inside
+/// the git folder of worktrees and submodules too, or at `core.hooksPath`
+fn hooks_folder(folder: &Path) -> Result<PathBuf, std::io::Error> {
+    let hooks = git(
+        folder.to_string_lossy().as_ref(),
+        &["rev-parse", "--git-path", "hooks"],
+    )?;
+    // relative to `folder`
+    Ok(folder.join(hooks.trim_end_matches('\n')))
+}
+
+/// Returns the pre-commit hook linting Todo lists of `folder`
+fn hook_script(folder: &Path) -> String {
+    format!(
+        "#!/bin/sh\n{MARKER}\nexec todo lint --folder {}\n",
+        shell_quote(folder.display().to_string().as_str())
+    )
+}
+
+/// Writes the pre-commit hook at `hook_path`. Hooks not installed by Todo are left untouched.
+fn install_hook(hook_path: &Path, folder: &Path) -> Result<(), std::io::Error> {
+    if let Ok(existing) = std::fs::read_to_string(hook_path) {
+        if !existing.contains(MARKER) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "A pre-commit hook not installed by todo already exists ({})",
+                    hook_path.display()
+                ),
+            ));
+        }
+    }
+    if let Some(hooks_folder) = hook_path.parent() {
+        std::fs::create_dir_all(hooks_folder)?;
+    }
+    std::fs::write(hook_path, hook_script(folder))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(hook_path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    debug!("wrote {}", hook_path.display());
+    Ok(())
+}
+
+/// Removes the pre-commit hook at `hook_path` if it was installed by Todo. Returns true when
+/// removed.
+fn uninstall_hook(hook_path: &Path) -> Result<bool, std::io::Error> {
+    let existing = match std::fs::read_to_string(hook_path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if !existing.contains(MARKER) {
+        return Err(std::io::Error::other(format!(
+            "Pre-commit hook was not installed by todo, remove it by hand ({})",
+            hook_path.display()
+        )));
+    }
+    std::fs::remove_file(hook_path)?;
+    debug!("removed {}", hook_path.display());
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn install_and_uninstall_hook() {
+        let root = TempFolder::new("githook");
+        let folder = root.join("lists");
+        std::fs::create_dir_all(&folder).unwrap();
+        git(root.location().as_str(), &["init", "-q"]).unwrap();
+        let hooks = hooks_folder(&folder).unwrap();
+        assert!(hooks.ends_with(".git/hooks"));
+        let hook_path = hooks.join("pre-commit");
+
+        git(
+            root.location().as_str(),
+            &["config", "core.hooksPath", "my hooks"],
+        )
+        .unwrap();
+        assert!(hooks_folder(&folder).unwrap().ends_with("my hooks"));
+
+        install_hook(&hook_path, &folder).unwrap();
+        let hook = std::fs::read_to_string(&hook_path).unwrap();
+        assert!(hook.starts_with("#!/bin/sh\n"));
+        assert!(hook.contains("todo lint --folder '"));
+        assert_eq!(
+            hook_script(Path::new("/it's here")).lines().last(),
+            Some("exec todo lint --folder '/it'\\''s here'")
+        );
+        // reinstalling replaces our own hook
+        install_hook(&hook_path, &folder).unwrap();
+        assert!(uninstall_hook(&hook_path).unwrap());
+        assert!(!hook_path.exists());
+        assert!(!uninstall_hook(&hook_path).unwrap());
+
+        std::fs::write(&hook_path, "#!/bin/sh\nmake check\n").unwrap();
+        assert!(install_hook(&hook_path, &folder).is_err());
+        assert!(uninstall_hook(&hook_path).is_err());
+        assert_eq!(
+            std::fs::read_to_string(&hook_path).unwrap(),
+            "#!/bin/sh\nmake check\n"
+        );
+    }
+}
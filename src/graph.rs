@@ -0,0 +1,273 @@
+//! Export links between Todo lists and tasks of the active Todo context as a graph
+//!
+//! Arrows point from what waits to what it waits for:
+//!
+//! * a Todo list links to another Todo list with `[[title]]`
+//! * a task references another task with `@see(^a3f2)`
+//!
+//! Tasks appear only when they take part in a reference, next to their Todo list. Todo lists many
+//! arrows point to are the ones blocking the others. Fully completed Todo lists and done tasks are
+//! dashed.
+use crate::parse::{
+    parse_task_id, parse_task_references, parse_tasks, parse_todo_list, parse_wikilinks,
+};
+use crate::single_file::SingleFile;
+use crate::{read_todo_lists, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Kind of arrow between two nodes
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edge {
+    /// Todo list linking to another Todo list
+    Link,
+    /// Task referencing another task
+    Reference,
+    /// Todo list containing a task
+    Contains,
+}
+
+#[derive(Debug, PartialEq)]
+struct Node {
+    label: String,
+    done: bool,
+}
+
+/// Todo lists and tasks with the arrows between them
+#[derive(Debug, Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<(usize, usize, Edge)>,
+}
+
+impl Graph {
+    /// Returns the graph of the Todo lists
+    fn new(todo_raws: &[&str]) -> Graph {
+        let mut graph = Graph::default();
+        let mut lists = vec![];
+        let mut titles = BTreeMap::new();
+        for todo_raw in todo_raws {
+            let todo_list = match parse_todo_list(todo_raw) {
+                Ok(todo_list) => todo_list,
+                Err(e) => {
+                    debug!("skipping unparsable Todo list: {e}");
+                    continue;
+                }
+            };
+            let node = graph.nodes.len();
+            graph.nodes.push(Node {
+                label: format!(
+                    "{} ({}/{})",
+                    todo_list.title, todo_list.done, todo_list.total
+                ),
+                done: todo_list.total > 0 && todo_list.tasks_are_all_done(),
+            });
+            titles.entry(todo_list.title).or_insert(node);
+            lists.push((node, *todo_raw));
+        }
+
+        // tasks which take part in a reference, by list node and line
+        let mut task_nodes: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        let mut ids = BTreeMap::new();
+        for (list, todo_raw) in &lists {
+            for task in parse_tasks(todo_raw) {
+                if let Some(id) = parse_task_id(task.text.as_str()) {
+                    ids.entry(id.to_string()).or_insert((*list, task));
+                }
+            }
+        }
+        let mut task_node =
+            |graph: &mut Graph, list: usize, line: usize, text: &str, done: bool| {
+                *task_nodes.entry((list, line)).or_insert_with(|| {
+                    graph.nodes.push(Node {
+                        label: text.to_string(),
+                        done,
+                    });
+                    graph.add_edge(list, graph.nodes.len() - 1, Edge::Contains);
+                    graph.nodes.len() - 1
+                })
+            };
+
+        for (list, todo_raw) in &lists {
+            // the title line of the Todo list names itself
+            let body = todo_raw.split_once('\n').map(|(_, b)| b).unwrap_or("");
+            for title in parse_wikilinks(body) {
+                match titles.get(title) {
+                    Some(linked) if linked != list => graph.add_edge(*list, *linked, Edge::Link),
+                    Some(_) => {}
+                    None => debug!("no Todo list titled \"{title}\" to link to"),
+                }
+            }
+            for task in parse_tasks(todo_raw) {
+                for id in parse_task_references(task.text.as_str()) {
+                    let (referenced_list, referenced) = match ids.get(id) {
+                        Some(referenced) => referenced,
+                        None => {
+                            debug!("no task with ID ^{id} to reference");
+                            continue;
+                        }
+                    };
+                    let from = task_node(&mut graph, *list, task.line, &task.text, task.done);
+                    let to = task_node(
+                        &mut graph,
+                        *referenced_list,
+                        referenced.line,
+                        &referenced.text,
+                        referenced.done,
+                    );
+                    graph.add_edge(from, to, Edge::Reference);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Adds an arrow unless it already exists
+    fn add_edge(&mut self, from: usize, to: usize, edge: Edge) {
+        if !self.edges.contains(&(from, to, edge)) {
+            self.edges.push((from, to, edge));
+        }
+    }
+
+    /// Returns the graph in the DOT language of Graphviz
+    fn dot(&self) -> String {
+        let mut dot = "digraph todo {\n  rankdir=LR;\n".to_string();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let shape = if self.is_task(i) { "ellipse" } else { "box" };
+            let style = if node.done { ", style=dashed" } else { "" };
+            dot.push_str(
+                format!(
+                    "  n{i} [label=\"{}\", shape={shape}{style}];\n",
+                    node.label.replace('\\', "\\\\").replace('"', "\\\"")
+                )
+                .as_str(),
+            );
+        }
+        for (from, to, edge) in &self.edges {
+            let style = match edge {
+                Edge::Contains => " [style=dotted, arrowhead=none]",
+                _ => "",
+            };
+            dot.push_str(format!("  n{from} -> n{to}{style};\n").as_str());
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the graph as a Mermaid flowchart
+    fn mermaid(&self) -> String {
+        let mut mermaid = "flowchart LR\n".to_string();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = node.label.replace('"', "#quot;");
+            if self.is_task(i) {
+                mermaid.push_str(format!("  n{i}([\"{label}\"])\n").as_str());
+            } else {
+                mermaid.push_str(format!("  n{i}[\"{label}\"]\n").as_str());
+            }
+        }
+        for (from, to, edge) in &self.edges {
+            let arrow = match edge {
+                Edge::Contains => "-.-",
+                _ => "-->",
+            };
+            mermaid.push_str(format!("  n{from} {arrow} n{to}\n").as_str());
+        }
+        let done = (0..self.nodes.len())
+            .filter(|i| self.nodes[*i].done)
+            .map(|i| format!("n{i}"))
+            .collect::<Vec<_>>();
+        if !done.is_empty() {
+            mermaid.push_str("  classDef done stroke-dasharray: 5 5\n");
+            mermaid.push_str(format!("  class {} done\n", done.join(",")).as_str());
+        }
+        mermaid
+    }
+
+    /// Returns true if the node is a task rather than a Todo list
+    fn is_task(&self, node: usize) -> bool {
+        self.edges
+            .iter()
+            .any(|(_, to, edge)| *to == node && *edge == Edge::Contains)
+    }
+}
+
+/// Returns the Graph command
+pub fn graph_command() -> App<'static, 'static> {
+    App::new("graph")
+        .about("Export links between Todo lists ([[title]]) and tasks (@see(^id)) within Todo context as a graph")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .value_name("dot|mermaid")
+                .help("Graphviz DOT (render with `dot -Tsvg`) or Mermaid flowchart")
+                .possible_values(&["dot", "mermaid"])
+                .default_value("dot")
+                .takes_value(true),
+        )
+}
+
+/// Prints the graph of Todo lists of the Todo context
+pub fn graph_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("graph subcommand");
+    let todo_raws = if ctx.is_single_file() {
+        SingleFile::read(ctx)?.todo_lists
+    } else if Path::new(ctx.folder_location.as_str()).is_dir() {
+        read_todo_lists(ctx)?
+            .into_iter()
+            .map(|(_, todo_raw)| todo_raw)
+            .collect()
+    } else {
+        vec![]
+    };
+    let todo_raws = todo_raws.iter().map(|t| t.as_str()).collect::<Vec<_>>();
+    let graph = Graph::new(&todo_raws);
+    match args.value_of("format") {
+        Some("mermaid") => print!("{}", graph.mermaid()),
+        _ => print!("{}", graph.dot()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_of_links_and_references() {
+        let graph = Graph::new(&[
+            "# Release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] tag ^tag\n* [x] changelog\n",
+            "# docs\n\n## Description\n\nLABEL=\n\nWaits for [[Release]] and [[unknown]].\n\n## Todo list\n\n* [ ] publish @see(^tag)\n",
+        ]);
+        assert_eq!(
+            graph.dot(),
+            "digraph todo {
+  rankdir=LR;
+  n0 [label=\"Release (1/2)\", shape=box];
+  n1 [label=\"docs (0/1)\", shape=box];
+  n2 [label=\"publish @see(^tag)\", shape=ellipse];
+  n3 [label=\"tag ^tag\", shape=ellipse];
+  n1 -> n0;
+  n1 -> n2 [style=dotted, arrowhead=none];
+  n0 -> n3 [style=dotted, arrowhead=none];
+  n2 -> n3;
+}
+"
+        );
+        assert_eq!(
+            graph.mermaid(),
+            "flowchart LR
+  n0[\"Release (1/2)\"]
+  n1[\"docs (0/1)\"]
+  n2([\"publish @see(^tag)\"])
+  n3([\"tag ^tag\"])
+  n1 --> n0
+  n1 -.- n2
+  n0 -.- n3
+  n2 --> n3
+"
+        );
+    }
+}
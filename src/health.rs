@@ -0,0 +1,100 @@
+//! Check the folder of Todo contexts is usable
+//!
+//! A Todo folder which was renamed, moved or lost its permissions fails commands mid-way.
+//! `todo config get-contexts --full` shows the health of each Todo context so it is noticed
+//! beforehand, and `todo stats files` skips unhealthy Todo contexts with the same diagnostic.
+use crate::remote::parse_remote_location;
+use crate::Context;
+use core::fmt;
+use std::path::Path;
+
+/// Health of the folder of a Todo context
+#[derive(Debug, PartialEq)]
+pub enum Health {
+    /// The folder exists and can be read and written
+    Ok,
+    /// The folder is on a remote host, which is not checked
+    Remote,
+    /// The folder does not exist
+    Missing,
+    /// The folder location is not a folder
+    NotAFolder,
+    /// The folder cannot be read. Argument is the reason.
+    NotReadable(String),
+    /// Files cannot be written inside the folder. Argument is the reason.
+    NotWritable(String),
+}
+
+impl Health {
+    /// Returns true if commands can use the Todo context
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Health::Ok | Health::Remote)
+    }
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = if self.is_ok() { "✓" } else { "✗" };
+        match self {
+            Health::Ok => write!(f, "{marker} ok"),
+            Health::Remote => write!(f, "{marker} remote (not checked)"),
+            Health::Missing => write!(f, "{marker} folder does not exist"),
+            Health::NotAFolder => write!(f, "{marker} folder location is not a folder"),
+            Health::NotReadable(e) => write!(f, "{marker} folder is not readable: {e}"),
+            Health::NotWritable(e) => write!(f, "{marker} folder is not writable: {e}"),
+        }
+    }
+}
+
+/// Returns the health of the folder of the Todo context
+///
+/// Writability is checked by creating then removing an empty file inside the folder.
+pub fn check(ctx: &Context) -> Health {
+    if parse_remote_location(ctx.folder_location.as_str()).is_some() {
+        return Health::Remote;
+    }
+    let folder = Path::new(ctx.folder_location.as_str());
+    if !folder.exists() {
+        return Health::Missing;
+    }
+    if !folder.is_dir() {
+        return Health::NotAFolder;
+    }
+    if let Err(e) = std::fs::read_dir(folder) {
+        return Health::NotReadable(e.to_string());
+    }
+    let probe = folder.join(format!(".todo-health-{}", std::process::id()));
+    match std::fs::write(&probe, "") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            Health::Ok
+        }
+        Err(e) => Health::NotWritable(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn folder_health() {
+        let folder = TempFolder::new("health");
+        std::fs::write(folder.join("file.md"), "").unwrap();
+        let ctx = |folder_location: &Path| Context {
+            folder_location: folder_location.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(check(&ctx(&folder)), Health::Ok);
+        assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 1);
+        assert_eq!(check(&ctx(&folder.join("renamed"))), Health::Missing);
+        assert_eq!(check(&ctx(&folder.join("file.md"))), Health::NotAFolder);
+        assert_eq!(
+            check(&ctx(Path::new("ssh://me@server/todo"))),
+            Health::Remote
+        );
+        assert_eq!(Health::Missing.to_string(), "✗ folder does not exist");
+    }
+}
@@ -0,0 +1,92 @@
+//! Print long-form guides about Todo
+//!
+//! Guides are embedded text files from `src/help`. `todo help SUBCOMMAND` still prints the help
+//! of a subcommand.
+use clap::{crate_authors, App, Arg, ArgMatches, ErrorKind};
+use log::trace;
+
+/// Name, summary and text of guides
+const TOPICS: [(&str, &str, &str); 3] = [
+    (
+        "workflow",
+        "Day to day use of Todo contexts and Todo lists",
+        include_str!("help/workflow.txt"),
+    ),
+    (
+        "format",
+        "Markdown format of Todo lists (title, LABEL=, sections)",
+        include_str!("help/format.txt"),
+    ),
+    (
+        "annotations",
+        "Annotations of tasks and Todo lists (@due, ^id, locks...)",
+        include_str!("help/annotations.txt"),
+    ),
+];
+
+/// Returns the Help command
+pub fn help_command() -> App<'static, 'static> {
+    App::new("help")
+        .about("Print a guide or the help of a subcommand")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("topic")
+                .value_name("TOPIC")
+                .index(1)
+                .help("Guide (workflow, format, annotations) or subcommand")
+                .takes_value(true),
+        )
+}
+
+/// Prints guide, or help of `app` and its subcommands
+pub fn help_command_process(args: &ArgMatches, mut app: App) -> Result<(), std::io::Error> {
+    trace!("help subcommand");
+    let topic = match args.value_of("topic") {
+        Some(topic) => topic,
+        None => {
+            app.print_help()
+                .map_err(|e| std::io::Error::other(e.message))?;
+            println!("\n\n{}", topics());
+            return Ok(());
+        }
+    };
+    if let Some((_, _, text)) = TOPICS.iter().find(|(name, _, _)| *name == topic) {
+        print!("{text}");
+        return Ok(());
+    }
+
+    match app.get_matches_from_safe(vec!["todo", topic, "--help"]) {
+        Err(e) if e.kind == ErrorKind::HelpDisplayed => e.exit(),
+        _ => {
+            eprintln!("{}", topics());
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No guide or subcommand named \"{topic}\""),
+            ))
+        }
+    }
+}
+
+/// Returns the list of guides
+fn topics() -> String {
+    let mut topics = "GUIDES (todo help TOPIC):".to_string();
+    for (name, about, _) in TOPICS.iter() {
+        topics.push_str(format!("\n    {name:<16}{about}").as_str());
+    }
+    topics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn print_guides() {
+        let app = App::new("todo").subcommand(help_command());
+        let args = help_command().get_matches_from(vec!["help", "format"]);
+        assert!(help_command_process(&args, app.clone()).is_ok());
+        let args = help_command().get_matches_from(vec!["help", "unknown"]);
+        assert!(help_command_process(&args, app).is_err());
+        assert!(topics().contains("annotations"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Hide Todo list from `todo list` without deleting or archiving it
+//!
+//! A hidden Todo list holds the `<!-- todo:hidden -->` annotation on its own line, like a locked
+//! Todo list holds the lock annotation (see [`crate::lock`]). `todo list` skips hidden Todo lists
+//! unless `--hidden` is given. Other commands, such as `todo search` or `todo show`, still find
+//! them: hidden suits reference Todo lists which are still in use but not worked on.
+use crate::lock::{annotate, ensure_unlocked, remove_annotation};
+use crate::{path_arg, todo_path_from_args, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::fs::read_to_string;
+
+/// Annotation marking a Todo list as hidden
+pub const HIDDEN_ANNOTATION: &str = "<!-- todo:hidden -->";
+
+/// Returns the Hide Todo command
+pub fn hide_command() -> App<'static, 'static> {
+    App::new("hide")
+        .about("Hide todo list from `todo list` within Todo context (shown again with --hidden)")
+        .author(crate_authors!())
+        .arg(title_arg())
+        .arg(path_arg())
+        .arg(force_arg("Hide todo list even if it is locked"))
+}
+
+/// Returns the Unhide Todo command
+pub fn unhide_command() -> App<'static, 'static> {
+    App::new("unhide")
+        .about("Show a hidden todo list again in `todo list` within Todo context")
+        .author(crate_authors!())
+        .arg(title_arg())
+        .arg(path_arg())
+        .arg(force_arg("Unhide todo list even if it is locked"))
+}
+
+/// Returns title argument shared by hide and unhide commands
+fn title_arg() -> Arg<'static, 'static> {
+    Arg::with_name("title")
+        .short("t")
+        .long("title")
+        .value_name("TITLE")
+        .index(1)
+        .help("Title of todo list")
+        .takes_value(true)
+        .required_unless("path")
+}
+
+fn force_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("force").short("f").long("force").help(help)
+}
+
+/// Hides Todo list in active Todo context
+pub fn hide_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("hide subcommand");
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    let todo_raw = read_to_string(&filepath)?;
+    if is_hidden(todo_raw.as_str()) {
+        println!("Todo list \"{title}\" is already hidden");
+        return Ok(());
+    }
+    std::fs::write(&filepath, annotate(todo_raw.as_str(), HIDDEN_ANNOTATION))?;
+    debug!("wrote {filepath}");
+    println!("Hid \"{title}\"");
+    Ok(())
+}
+
+/// Unhides Todo list in active Todo context
+pub fn unhide_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("unhide subcommand");
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    let todo_raw = read_to_string(&filepath)?;
+    if !is_hidden(todo_raw.as_str()) {
+        println!("Todo list \"{title}\" is not hidden");
+        return Ok(());
+    }
+    std::fs::write(
+        &filepath,
+        remove_annotation(todo_raw.as_str(), HIDDEN_ANNOTATION),
+    )?;
+    debug!("wrote {filepath}");
+    println!("Unhid \"{title}\"");
+    Ok(())
+}
+
+/// Returns true if Todo list holds the hidden annotation
+pub fn is_hidden(todo_raw: &str) -> bool {
+    todo_raw.lines().any(|l| l.trim() == HIDDEN_ANNOTATION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hide_and_unhide() {
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] read\n";
+        assert!(!is_hidden(todo_raw));
+        let hidden = annotate(todo_raw, HIDDEN_ANNOTATION);
+        assert!(is_hidden(hidden.as_str()));
+        assert_eq!(
+            remove_annotation(hidden.as_str(), HIDDEN_ANNOTATION),
+            todo_raw
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Import tasks from other task managers
+//!
+//! Supported exports are:
+//!
+//! * `google-tasks`: `Tasks.json` of a Google Takeout archive
+//! * `apple-reminders`: JSON array or CSV file of reminders (columns `Title`, `List`, `Completed`,
+//!   `Due Date`, `Priority` and `Notes`), as written by export shortcuts
+//!
+//! Each list becomes a Todo list of the active Todo context. Completed tasks are checked, due dates
+//! become `@due(...)` annotations, priorities become `(A)`, `(B)` or `(C)` and notes become comments.
+//! Tasks already present in the Todo list are skipped so an export can be imported again.
+use crate::json::{parse_json, Json};
+use crate::lock::ensure_unlocked;
+use crate::merge_file::insert_task;
+use crate::parse::{parse_tasks, Task};
+use crate::progress::Progress;
+use crate::{
+    prompt_for_todo_folder_if_not_exists, read_todo_list_or_new, resolve_todo_path, Context,
+};
+use chrono::NaiveDate;
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+
+/// Task read from an export
+#[derive(Debug, PartialEq)]
+struct ImportedTask {
+    list: String,
+    title: String,
+    done: bool,
+    due: Option<NaiveDate>,
+    priority: Option<char>,
+    notes: Option<String>,
+}
+
+/// Returns the Import command
+pub fn import_command() -> App<'static, 'static> {
+    App::new("import")
+        .about("Import lists and tasks exported from Google Tasks or Apple Reminders")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("from")
+                .long("from")
+                .value_name("FORMAT")
+                .help("Application the file was exported from")
+                .possible_values(&["google-tasks", "apple-reminders"])
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("file")
+                .value_name("FILE")
+                .help("Exported file (Tasks.json of Google Takeout, JSON or CSV of reminders)")
+                .index(1)
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("list")
+                .long("list")
+                .value_name("TITLE")
+                .help("Import every task into this Todo list instead of one Todo list per list")
+                .takes_value(true),
+        )
+}
+
+/// Adds tasks of exported file to the Todo lists of active Todo context
+pub fn import_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("import subcommand");
+    let file = args.value_of("file").unwrap();
+    let raw = std::fs::read_to_string(file)?;
+    let raw = raw.trim_start_matches('\u{feff}');
+    let tasks = match args.value_of("from").unwrap() {
+        "google-tasks" => google_tasks(parse_json(raw)?),
+        _ if raw.trim_start().starts_with(['[', '{']) => apple_reminders_json(parse_json(raw)?),
+        _ => apple_reminders_csv(raw)?,
+    };
+
+    let mut lists: Vec<(String, Vec<ImportedTask>)> = vec![];
+    for task in tasks {
+        let list = args
+            .value_of("list")
+            .unwrap_or(task.list.as_str())
+            .to_string();
+        match lists.iter_mut().find(|(title, _)| *title == list) {
+            Some((_, tasks)) => tasks.push(task),
+            None => lists.push((list, vec![task])),
+        }
+    }
+    if lists.is_empty() {
+        println!("No task to import");
+        return Ok(());
+    }
+
+    if let Err(e) = prompt_for_todo_folder_if_not_exists(ctx) {
+        return Err(std::io::Error::other(e.to_string()));
+    }
+    let total = lists.iter().map(|(_, tasks)| tasks.len()).sum();
+    let mut progress = Progress::start(args, "import", total);
+    for (title, tasks) in lists {
+        let filepath = resolve_todo_path(ctx, title.as_str())?;
+        ensure_unlocked(filepath.as_str(), false)?;
+        let mut todo_raw = read_todo_list_or_new(filepath.as_str(), title.as_str())?;
+        let mut imported = 0;
+        for task in &tasks {
+            let with_task = add_task(todo_raw.as_str(), task);
+            if with_task != todo_raw {
+                todo_raw = with_task;
+                imported += 1;
+            }
+            progress.advance(task.title.as_str());
+        }
+        if imported > 0 {
+            std::fs::write(&filepath, todo_raw)?;
+            debug!("wrote {filepath}");
+        }
+        progress.println(
+            format!(
+                "Imported {imported} task(s) into \"{title}\" ({} already present)",
+                tasks.len() - imported
+            )
+            .as_str(),
+        );
+    }
+    progress.finish();
+    Ok(())
+}
+
+/// Returns Todo list with `task` added. Tasks already present (checked or not) are skipped.
+fn add_task(todo_raw: &str, task: &ImportedTask) -> String {
+    let mut text = task.title.clone();
+    if let Some(priority) = task.priority {
+        text = format!("({priority}) {text}");
+    }
+    if let Some(due) = task.due {
+        text = format!("{text} @due({})", due.format("%Y-%m-%d"));
+    }
+    if parse_tasks(todo_raw).iter().any(|t| t.text == text) {
+        return todo_raw.to_string();
+    }
+    let checkbox = if task.done { "[x]" } else { "[ ]" };
+    let mut lines = vec![format!("* {checkbox} {text}")];
+    for note in task.notes.iter().flat_map(|n| n.lines()) {
+        if !note.trim().is_empty() {
+            lines.push(format!("  > {}", note.trim_end()));
+        }
+    }
+    let task = Task::new(text.as_str(), task.done);
+    insert_task(todo_raw, &task, lines)
+}
+
+/// Returns tasks of a Google Takeout `Tasks.json`. Deleted tasks are skipped.
+fn google_tasks(export: Json) -> Vec<ImportedTask> {
+    let mut tasks = vec![];
+    for list in export.get("items").map(Json::as_array).unwrap_or_default() {
+        let list_title = list.get("title").and_then(Json::as_str).unwrap_or("Tasks");
+        for task in list.get("items").map(Json::as_array).unwrap_or_default() {
+            if task.get("deleted") == Some(&Json::Bool(true)) {
+                continue;
+            }
+            let title = match task.get("title").and_then(Json::as_str).map(str::trim) {
+                Some(title) if !title.is_empty() => title.to_string(),
+                _ => continue,
+            };
+            tasks.push(ImportedTask {
+                list: list_title.to_string(),
+                title,
+                done: task.get("status").and_then(Json::as_str) == Some("completed"),
+                due: task.get("due").and_then(Json::as_str).and_then(parse_date),
+                priority: None,
+                notes: task
+                    .get("notes")
+                    .and_then(Json::as_str)
+                    .map(|n| n.to_string()),
+            });
+        }
+    }
+    tasks
+}
+
+/// Returns reminders of a JSON array of reminder objects
+fn apple_reminders_json(export: Json) -> Vec<ImportedTask> {
+    export
+        .as_array()
+        .iter()
+        .filter_map(|reminder| match reminder {
+            Json::Object(entries) => reminder_from_fields(|field| {
+                entries
+                    .iter()
+                    .find(|(k, _)| normalize(k) == field)
+                    .and_then(|(_, v)| v.to_text())
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns reminders of a CSV file with a header row
+fn apple_reminders_csv(raw: &str) -> Result<Vec<ImportedTask>, std::io::Error> {
+    let mut rows = parse_csv(raw).into_iter();
+    let header = match rows.next() {
+        Some(header) => header.iter().map(|h| normalize(h)).collect::<Vec<_>>(),
+        None => return Ok(vec![]),
+    };
+    if !header.iter().any(|h| h == "title" || h == "name") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "CSV file has no Title column",
+        ));
+    }
+    Ok(rows
+        .filter_map(|row| {
+            reminder_from_fields(|field| {
+                header
+                    .iter()
+                    .position(|h| h == field)
+                    .and_then(|i| row.get(i))
+                    .cloned()
+            })
+        })
+        .collect())
+}
+
+/// Returns a reminder from its fields, looked up by normalized name. Reminders without title are
+/// skipped.
+fn reminder_from_fields(field: impl Fn(&str) -> Option<String>) -> Option<ImportedTask> {
+    let first = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| field(name))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let title = first(&["title", "name"])?;
+    let done = first(&["completed", "iscompleted", "done"])
+        .map(|v| matches!(v.to_lowercase().as_str(), "true" | "yes" | "1" | "x"))
+        .unwrap_or(false);
+    let priority = first(&["priority"]).and_then(|p| match p.to_lowercase().as_str() {
+        "high" | "1" | "2" | "3" | "4" => Some('A'),
+        "medium" | "5" => Some('B'),
+        "low" | "6" | "7" | "8" | "9" => Some('C'),
+        _ => None,
+    });
+    Some(ImportedTask {
+        list: first(&["list", "calendar"]).unwrap_or_else(|| "Reminders".to_string()),
+        title,
+        done,
+        due: first(&["duedate", "due"]).and_then(|d| parse_date(d.as_str())),
+        priority,
+        notes: first(&["notes", "body"]),
+    })
+}
+
+/// Returns field name in lowercase without spaces nor punctuation (`Due Date` is `duedate`)
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Returns the date at the start of `text`, as `2024-07-01[T...]` or `7/1/2024[, ...]`
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let date = text.trim().split(['T', ' ', ',']).next()?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%m/%d/%Y"))
+        .ok()
+}
+
+/// Returns rows of CSV text. Quoted fields may hold commas, newlines and doubled quotes.
+fn parse_csv(raw: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|f| !f.is_empty()));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_google_tasks() {
+        let export = r#"{"kind": "tasks#taskLists", "items": [{"kind": "tasks#taskList",
+            "title": "Groceries", "items": [
+                {"title": "Milk", "status": "needsAction", "due": "2024-07-01T00:00:00.000Z",
+                 "notes": "2 bottles\nsemi-skimmed"},
+                {"title": "Café 😀", "status": "completed"},
+                {"title": "Gone", "status": "needsAction", "deleted": true}
+            ]}]}"#;
+        let tasks = google_tasks(parse_json(export).unwrap());
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[1].title, "Café 😀");
+        assert!(tasks[1].done);
+
+        let todo_raw = read_todo_list_or_new("/nonexistent/Groceries.md", "Groceries").unwrap();
+        let todo_raw = add_task(todo_raw.as_str(), &tasks[0]);
+        let todo_raw = add_task(todo_raw.as_str(), &tasks[1]);
+        assert_eq!(
+            todo_raw,
+            "# Groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] Milk @due(2024-07-01)\n  > 2 bottles\n  > semi-skimmed\n* [x] Café 😀\n"
+        );
+        assert_eq!(add_task(todo_raw.as_str(), &tasks[0]), todo_raw);
+    }
+
+    #[test]
+    fn import_apple_reminders() {
+        let csv = "Title,List,Completed,Due Date,Priority,Notes\r\n\"Pay rent, flat\",Home,No,7/1/2024,High,\"say \"\"hi\"\"\nthen leave\"\r\nCall bank,Work,Yes,,,\r\n";
+        let expected = vec![
+            ImportedTask {
+                list: "Home".to_string(),
+                title: "Pay rent, flat".to_string(),
+                done: false,
+                due: NaiveDate::from_ymd_opt(2024, 7, 1),
+                priority: Some('A'),
+                notes: Some("say \"hi\"\nthen leave".to_string()),
+            },
+            ImportedTask {
+                list: "Work".to_string(),
+                title: "Call bank".to_string(),
+                done: true,
+                due: None,
+                priority: None,
+                notes: None,
+            },
+        ];
+        assert_eq!(apple_reminders_csv(csv).unwrap(), expected);
+
+        let json = r#"[{"title": "Pay rent, flat", "list": "Home", "isCompleted": false,
+            "dueDate": "2024-07-01T09:00:00Z", "priority": 1, "notes": "say \"hi\"\nthen leave"},
+            {"title": "Call bank", "list": "Work", "isCompleted": true, "priority": 0}]"#;
+        assert_eq!(apple_reminders_json(parse_json(json).unwrap()), expected);
+        assert!(parse_json("[1, 2").is_err());
+    }
+}
@@ -0,0 +1,340 @@
+//! Turn emails into tasks
+//!
+//! Messages of a maildir folder become tasks of the inbox Todo list: the subject is the task and
+//! the message id is attached as a `mid:` link comment. Processed messages are marked so they are
+//! ingested only once: unread messages are marked as read, or flagged messages are unflagged with
+//! `--flagged`.
+use crate::lock::ensure_unlocked;
+use crate::merge_file::insert_task;
+use crate::parse::Task;
+use crate::progress::Progress;
+use crate::{
+    prompt_for_todo_folder_if_not_exists, read_todo_list_or_new, resolve_todo_path, Context,
+};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use lazy_static::lazy_static;
+use log::{debug, trace};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// Email turned into a task
+#[derive(Debug, PartialEq)]
+struct Email {
+    subject: String,
+    message_id: Option<String>,
+}
+
+/// Returns the Ingest command
+pub fn ingest_command() -> App<'static, 'static> {
+    App::new("ingest")
+        .about("Turn emails into tasks of the inbox todo list")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("maildir")
+                .long("maildir")
+                .value_name("MAILDIR")
+                .help("Maildir folder holding emails to turn into tasks")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("flagged")
+                .long("flagged")
+                .help("Ingest flagged emails (and unflag them) instead of unread emails"),
+        )
+        .arg(
+            Arg::with_name("list")
+                .long("list")
+                .value_name("TITLE")
+                .help("Todo list receiving the tasks")
+                .default_value("Inbox")
+                .takes_value(true),
+        )
+}
+
+/// Adds tasks for emails of maildir to inbox Todo list of active Todo context
+pub fn ingest_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("ingest subcommand");
+    let maildir = Path::new(args.value_of("maildir").unwrap());
+    let flagged = args.is_present("flagged");
+    let title = args.value_of("list").unwrap();
+
+    if let Err(e) = prompt_for_todo_folder_if_not_exists(ctx) {
+        return Err(std::io::Error::other(e.to_string()));
+    }
+    let filepath = resolve_todo_path(ctx, title)?;
+    ensure_unlocked(filepath.as_str(), false)?;
+    let mut todo_raw = read_todo_list_or_new(filepath.as_str(), title)?;
+
+    let messages = maildir_messages(maildir)?
+        .into_iter()
+        .filter(|(_, flags)| flagged == flags.contains('F') && (flagged || !flags.contains('S')))
+        .collect::<Vec<_>>();
+    let mut progress = Progress::start(args, "ingest", messages.len());
+    for (path, _) in messages.iter() {
+        // emails are not always UTF-8 (latin-1...)
+        let email = parse_email(String::from_utf8_lossy(&std::fs::read(path)?).as_ref());
+        todo_raw = add_email(todo_raw.as_str(), &email);
+        progress.advance(email.subject.as_str());
+    }
+    progress.finish();
+
+    // messages are only marked once their tasks are written, so that none is lost
+    if !messages.is_empty() {
+        std::fs::write(&filepath, todo_raw)?;
+        debug!("wrote {filepath}");
+    }
+    for (path, flags) in messages.iter() {
+        mark_processed(maildir, path, flags.as_str(), flagged)?;
+    }
+    println!("Ingested {} email(s) into \"{title}\"", messages.len());
+    Ok(())
+}
+
+/// Returns the messages of the maildir with their flags
+fn maildir_messages(maildir: &Path) -> Result<Vec<(PathBuf, String)>, std::io::Error> {
+    let mut messages = vec![];
+    for folder in ["new", "cur"] {
+        let folder = maildir.join(folder);
+        if !folder.is_dir() {
+            continue;
+        }
+        for entry in std::fs::read_dir(folder)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let flags = name
+                .rsplit_once(":2,")
+                .map(|(_, flags)| flags.to_string())
+                .unwrap_or_default();
+            messages.push((path, flags));
+        }
+    }
+    messages.sort();
+    Ok(messages)
+}
+
+/// Moves message to `cur` as read, or unflagged when ingesting flagged messages
+fn mark_processed(
+    maildir: &Path,
+    path: &Path,
+    flags: &str,
+    flagged: bool,
+) -> Result<(), std::io::Error> {
+    let name = path.file_name().unwrap().to_string_lossy().to_string();
+    let unique = name.rsplit_once(":2,").map_or(name.as_str(), |(u, _)| u);
+    let mut flags = flags
+        .chars()
+        .filter(|f| !(flagged && *f == 'F'))
+        .collect::<Vec<_>>();
+    if !flagged && !flags.contains(&'S') {
+        flags.push('S');
+    }
+    // maildir flags are sorted
+    flags.sort_unstable();
+    let processed = maildir
+        .join("cur")
+        .join(format!("{unique}:2,{}", flags.iter().collect::<String>()));
+    std::fs::rename(path, &processed)?;
+    debug!("renamed {} to {}", path.display(), processed.display());
+    Ok(())
+}
+
+/// Returns Todo list with a task for `email`. Emails already ingested are skipped.
+fn add_email(todo_raw: &str, email: &Email) -> String {
+    let mut lines = vec![format!("* [ ] {}", email.subject)];
+    if let Some(message_id) = &email.message_id {
+        let comment = format!("  > mid:{message_id}");
+        if todo_raw.lines().any(|l| l == comment) {
+            return todo_raw.to_string();
+        }
+        lines.push(comment);
+    }
+    let task = Task::new(email.subject.as_str(), false);
+    insert_task(todo_raw, &task, lines)
+}
+
+/// Returns subject and message id of an email
+fn parse_email(raw: &str) -> Email {
+    let raw = raw.replace("\r\n", "\n");
+    let headers = raw.split("\n\n").next().unwrap_or("");
+    let mut subject = None;
+    let mut message_id = None;
+    // folded header lines start with whitespace
+    let unfolded = headers.replace("\n ", " ").replace("\n\t", " ");
+    for line in unfolded.lines() {
+        let (name, value) = match line.split_once(':') {
+            Some(header) => header,
+            None => continue,
+        };
+        match name.to_lowercase().as_str() {
+            "subject" if subject.is_none() => subject = Some(decode_header(value.trim())),
+            "message-id" if message_id.is_none() => {
+                message_id = Some(
+                    value
+                        .trim()
+                        .trim_start_matches('<')
+                        .trim_end_matches('>')
+                        .to_string(),
+                )
+            }
+            _ => {}
+        }
+    }
+    let subject = subject.filter(|s| !s.is_empty());
+    Email {
+        subject: subject.unwrap_or_else(|| "(no subject)".to_string()),
+        message_id: message_id.filter(|id| !id.is_empty()),
+    }
+}
+
+/// Returns header value with its encoded words (`=?charset?B|Q?text?=`) decoded. Only UTF-8 and
+/// ASCII compatible charsets are decoded.
+fn decode_header(value: &str) -> String {
+    lazy_static! {
+        static ref ENCODED_WORD_RE: Regex =
+            Regex::new(r"=\?[^?]+\?([BbQq])\?([^?]*)\?=").unwrap();
+        // whitespace between encoded words is dropped
+        static ref BETWEEN_ENCODED_WORDS_RE: Regex = Regex::new(r"\?=\s+=\?").unwrap();
+    }
+    let value = BETWEEN_ENCODED_WORDS_RE.replace_all(value, "?==?");
+    ENCODED_WORD_RE
+        .replace_all(value.as_ref(), |caps: &regex::Captures| {
+            let bytes = match &caps[1] {
+                "B" | "b" => decode_base64(&caps[2]),
+                _ => Some(decode_quoted_printable(&caps[2])),
+            };
+            match bytes {
+                Some(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+                None => caps[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+/// Returns bytes of base64 text
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut bytes = vec![];
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes().filter(|c| *c != b'=') {
+        let value = ALPHABET.iter().position(|a| *a == c)? as u32;
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// Returns bytes of quoted printable text of an encoded word
+fn decode_quoted_printable(text: &str) -> Vec<u8> {
+    let raw = text.as_bytes();
+    let mut bytes = vec![];
+    let mut i = 0;
+    while i < raw.len() {
+        let hex = raw
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (raw[i], hex) {
+            (b'_', _) => bytes.push(b' '),
+            (b'=', Some(b)) => {
+                bytes.push(b);
+                i += 2;
+            }
+            (b, _) => bytes.push(b),
+        }
+        i += 1;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn parse_emails() {
+        let raw = "From: a@example.com\r\nSubject: Renew\r\n passport\r\nMessage-ID: <1234@example.com>\r\n\r\nSubject: not a header\r\n";
+        assert_eq!(
+            parse_email(raw),
+            Email {
+                subject: "Renew passport".to_string(),
+                message_id: Some("1234@example.com".to_string()),
+            }
+        );
+        assert_eq!(
+            decode_header("=?UTF-8?B?Q2Fmw6k=?= =?utf-8?Q?_cr=C3=A8me?= today"),
+            "Café crème today"
+        );
+        assert_eq!(parse_email("\n\nbody").subject, "(no subject)");
+    }
+
+    #[test]
+    fn ingest_maildir() {
+        let root = TempFolder::new("ingest");
+        let maildir = root.join("mail");
+        std::fs::create_dir_all(maildir.join("new")).unwrap();
+        std::fs::create_dir_all(maildir.join("cur")).unwrap();
+        std::fs::write(
+            maildir.join("new/1.host"),
+            "Subject: Pay rent\nMessage-ID: <1@host>\n\n",
+        )
+        .unwrap();
+        std::fs::write(
+            maildir.join("cur/2.host:2,FS"),
+            "Subject: Call bank\nMessage-ID: <2@host>\n\n",
+        )
+        .unwrap();
+        let messages = maildir_messages(&maildir).unwrap();
+        assert_eq!(
+            messages
+                .iter()
+                .map(|(_, flags)| flags.as_str())
+                .collect::<Vec<_>>(),
+            vec!["FS", ""]
+        );
+
+        let (path, flags) = &messages[1];
+        mark_processed(&maildir, path, flags, false).unwrap();
+        assert!(maildir.join("cur/1.host:2,S").exists());
+        let (path, flags) = &messages[0];
+        mark_processed(&maildir, path, flags, true).unwrap();
+        assert!(maildir.join("cur/2.host:2,S").exists());
+
+        let inbox =
+            read_todo_list_or_new(root.join("Inbox.md").to_str().unwrap(), "Inbox").unwrap();
+        let email = Email {
+            subject: "Pay rent".to_string(),
+            message_id: Some("1@host".to_string()),
+        };
+        let inbox = add_email(inbox.as_str(), &email);
+        assert_eq!(
+            inbox,
+            "# Inbox\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] Pay rent\n  > mid:1@host\n"
+        );
+        assert_eq!(add_email(inbox.as_str(), &email), inbox);
+
+        // latin-1 emails are ingested too
+        std::fs::write(maildir.join("new/3.host"), b"Subject: Caf\xe9\n\n").unwrap();
+        let ctx = Context::new("ctx", root.location().as_str());
+        let args = ingest_command().get_matches_from(vec![
+            "ingest",
+            "--maildir",
+            maildir.to_str().unwrap(),
+        ]);
+        ingest_command_process(&args, &ctx).unwrap();
+        assert!(std::fs::read_to_string(root.join("Inbox.md"))
+            .unwrap()
+            .contains("* [ ] Caf\u{fffd}\n"));
+        assert!(maildir.join("cur/3.host:2,S").exists());
+    }
+}
@@ -0,0 +1,258 @@
+//! Read and write JSON without a JSON dependency
+//!
+//! Only what importers, batch invocations and machine-readable output need: values are parsed into
+//! a small tree, object keys keep their order.
+/// JSON value
+#[derive(Debug, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(values) => values.as_slice(),
+            _ => &[],
+        }
+    }
+
+    /// Returns scalar value as text
+    pub(crate) fn to_text(&self) -> Option<String> {
+        match self {
+            Json::Bool(b) => Some(b.to_string()),
+            Json::Number(n) => Some(n.to_string()),
+            Json::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Returns `s` as a JSON string
+pub(crate) fn quote(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(format!("\\u{:04x}", c as u32).as_str()),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Arrays and objects nested deeper are rejected, so that untrusted input (batch invocations, daemon
+/// requests) cannot overflow the stack
+const MAX_DEPTH: usize = 128;
+
+/// Returns JSON value of `raw`
+pub(crate) fn parse_json(raw: &str) -> Result<Json, std::io::Error> {
+    let chars = raw.chars().collect::<Vec<_>>();
+    let mut at = 0;
+    let value = parse_json_value(&chars, &mut at, 0);
+    skip_whitespace(&chars, &mut at);
+    match value {
+        Some(value) if at == chars.len() => Ok(value),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Invalid JSON at character {at}"),
+        )),
+    }
+}
+
+fn skip_whitespace(chars: &[char], at: &mut usize) {
+    while chars.get(*at).is_some_and(|c| c.is_whitespace()) {
+        *at += 1;
+    }
+}
+
+/// Returns the JSON value at `at`, nested inside `depth` arrays or objects
+fn parse_json_value(chars: &[char], at: &mut usize, depth: usize) -> Option<Json> {
+    skip_whitespace(chars, at);
+    if depth == MAX_DEPTH && matches!(chars.get(*at), Some('{' | '[')) {
+        return None;
+    }
+    let keyword = |keyword: &str, at: &mut usize| {
+        let end = *at + keyword.len();
+        if chars.get(*at..end)?.iter().copied().eq(keyword.chars()) {
+            *at = end;
+            Some(())
+        } else {
+            None
+        }
+    };
+    match chars.get(*at)? {
+        '{' => {
+            *at += 1;
+            let mut entries = vec![];
+            skip_whitespace(chars, at);
+            if chars.get(*at) == Some(&'}') {
+                *at += 1;
+                return Some(Json::Object(entries));
+            }
+            loop {
+                skip_whitespace(chars, at);
+                let key = parse_json_string(chars, at)?;
+                skip_whitespace(chars, at);
+                if chars.get(*at) != Some(&':') {
+                    return None;
+                }
+                *at += 1;
+                entries.push((key, parse_json_value(chars, at, depth + 1)?));
+                skip_whitespace(chars, at);
+                match chars.get(*at)? {
+                    ',' => *at += 1,
+                    '}' => {
+                        *at += 1;
+                        return Some(Json::Object(entries));
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        '[' => {
+            *at += 1;
+            let mut values = vec![];
+            skip_whitespace(chars, at);
+            if chars.get(*at) == Some(&']') {
+                *at += 1;
+                return Some(Json::Array(values));
+            }
+            loop {
+                values.push(parse_json_value(chars, at, depth + 1)?);
+                skip_whitespace(chars, at);
+                match chars.get(*at)? {
+                    ',' => *at += 1,
+                    ']' => {
+                        *at += 1;
+                        return Some(Json::Array(values));
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        '"' => parse_json_string(chars, at).map(Json::String),
+        't' => keyword("true", at).map(|_| Json::Bool(true)),
+        'f' => keyword("false", at).map(|_| Json::Bool(false)),
+        'n' => keyword("null", at).map(|_| Json::Null),
+        _ => {
+            let start = *at;
+            while chars
+                .get(*at)
+                .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+            {
+                *at += 1;
+            }
+            let number = chars[start..*at].iter().collect::<String>();
+            number.parse().ok().map(Json::Number)
+        }
+    }
+}
+
+fn parse_json_string(chars: &[char], at: &mut usize) -> Option<String> {
+    if chars.get(*at) != Some(&'"') {
+        return None;
+    }
+    *at += 1;
+    let mut s = String::new();
+    let mut high_surrogate = None;
+    loop {
+        let c = *chars.get(*at)?;
+        *at += 1;
+        // a high surrogate must be followed by a low surrogate
+        if high_surrogate.is_some() && (c != '\\' || chars.get(*at) != Some(&'u')) {
+            return None;
+        }
+        match c {
+            '"' => return Some(s),
+            '\\' => {
+                let escaped = *chars.get(*at)?;
+                *at += 1;
+                match escaped {
+                    'n' => s.push('\n'),
+                    't' => s.push('\t'),
+                    'r' => s.push('\r'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' => {
+                        let hex = chars.get(*at..*at + 4)?.iter().collect::<String>();
+                        *at += 4;
+                        let code = u32::from_str_radix(hex.as_str(), 16).ok()?;
+                        match (high_surrogate.take(), code) {
+                            (None, 0xD800..=0xDBFF) => high_surrogate = Some(code),
+                            (Some(high), 0xDC00..=0xDFFF) => s.push(char::from_u32(
+                                0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00),
+                            )?),
+                            (None, code) => s.push(char::from_u32(code)?),
+                            (Some(_), _) => return None,
+                        }
+                    }
+                    c => s.push(c),
+                }
+            }
+            c => s.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_values() {
+        let json =
+            parse_json(r#" {"a": [1, -2.5e1, true, null], "b\u00e9": "x\"\ud83d\ude00\n"} "#)
+                .unwrap();
+        assert_eq!(
+            json,
+            Json::Object(vec![
+                (
+                    "a".to_string(),
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::Number(-25.0),
+                        Json::Bool(true),
+                        Json::Null
+                    ])
+                ),
+                ("bé".to_string(), Json::String("x\"😀\n".to_string())),
+            ])
+        );
+        assert!(parse_json("[1, 2").is_err());
+        assert!(parse_json("[1] 2").is_err());
+
+        assert!(parse_json(r#""\ud83d""#).is_err());
+        assert!(parse_json(r#""\ud83dx""#).is_err());
+        assert!(parse_json(r#""\ud83d\u0041""#).is_err());
+        assert!(parse_json(r#""\ude00""#).is_err());
+
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse_json(nested(MAX_DEPTH).as_str()).is_ok());
+        assert!(parse_json(nested(MAX_DEPTH + 1).as_str()).is_err());
+        assert!(parse_json("[".repeat(200_000).as_str()).is_err());
+    }
+}
@@ -4,23 +4,104 @@
 //! respective modules.
 //!
 //! Follow the `README.md` to know more about the installation.
-use dialoguer::Confirm;
-use parse::parse_configuration_file;
+use chrono::format::{Item, StrftimeItems};
+use clap::{Arg, ArgMatches};
+use confirm::{confirm, Operation};
+use log::{debug, warn};
+use parse::{
+    parse_configuration_file, parse_tasks, parse_todo_list, parse_todo_list_motives,
+    parse_todo_list_parts, Task,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
 
+pub mod activity;
+pub mod add;
+pub mod age;
+pub mod archive;
+#[cfg(feature = "scripting")]
+pub mod batch;
+#[cfg(feature = "sync")]
+pub mod caldav;
+pub mod calendar;
+pub mod comment;
 pub mod config;
 pub mod config_active_context;
 pub mod config_create_context;
+pub mod config_file;
 pub mod config_get_contexts;
+pub mod config_schema;
 pub mod config_set_context;
+pub mod confirm;
+pub mod conflict;
+pub mod count;
 pub mod create;
+#[cfg(feature = "server")]
+pub mod daemon;
 pub mod delete;
+pub mod describe;
+#[cfg(feature = "notifications")]
+pub mod digest;
+pub use todo_core::document;
+pub mod done;
 pub mod edit;
+#[cfg(feature = "scripting")]
+pub mod external;
+#[cfg(feature = "git")]
+pub mod githook;
+pub mod graph;
+pub mod health;
+pub mod help;
+pub mod hide;
+pub mod import;
+pub mod ingest;
+pub mod json;
+pub mod lint;
 pub mod list;
+pub mod lock;
+pub mod log_file;
+pub mod maintain;
+pub mod merge_file;
+pub mod metadata;
+pub mod migrate;
 pub mod r#move;
 pub mod parse;
+pub mod parts;
+pub mod pick;
+pub mod pin;
+pub mod prelude;
+pub mod profile;
+pub mod progress;
+pub mod quick;
+pub mod quota;
+pub mod release;
+pub mod remote;
+pub mod rename;
+pub mod render;
+#[cfg(feature = "net")]
+pub mod runtime;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod seal;
+pub mod search;
+pub mod show;
+pub mod single_file;
+pub mod snapshot;
+pub mod stats;
+pub mod statusline;
+pub mod store;
+pub mod summary;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod top;
+#[cfg(feature = "tui")]
+pub mod ui;
+pub mod version;
+pub mod view;
+pub mod workspace;
 
 enum Error {
     UserCancelledAction,
@@ -40,32 +121,196 @@ impl fmt::Display for Error {
     }
 }
 
-#[derive(Clone, Deserialize, Debug, Serialize)]
+#[derive(Clone, Deserialize, Debug, Serialize, Default)]
 /// Represents a themed set of Todo lists
 ///
 /// Context is uniquely identified by its name. All related Todo lists are stored inside the same
 /// folder.
+#[non_exhaustive]
 pub struct Context {
     pub ide: String,
     pub name: String,
+    /// Timezone (`UTC`, `Europe/Paris`...) telling which day it is for due tasks
     pub timezone: String,
     pub folder_location: String,
+    /// Purpose of the Todo context, shown atop `todo list`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Current goal of the Todo context (e.g. this quarter's), shown atop `todo list`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<String>,
+    /// Fully completed Todo lists untouched for this many days are archived by `todo maintain`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_archive_after_days: Option<u64>,
+    /// Soft limit of open tasks, warned about when exceeded (see [`quota`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_open_tasks: Option<usize>,
+    /// Soft limit of Todo lists, warned about when exceeded (see [`quota`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lists: Option<usize>,
+    /// Open tasks first seen this many days ago are flagged by `todo list` (see [`age`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_after_days: Option<u64>,
+    /// Format of printed dates for this context (overrides the global date format)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// Arguments run when `todo` is invoked without subcommand (overrides the global default command)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_command: Option<Vec<String>>,
+    /// View run by `todo view` when no view is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_view: Option<String>,
+    /// Labels of every Todo list created with `todo create`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_labels: Vec<String>,
+    /// Titles of Todo lists listed first by `todo list` (see [`pin`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned: Vec<String>,
+    /// Titles of Todo lists in the order `todo list` lists them after pinned ones (see [`pin`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    /// How Todo lists are stored: `folder` (default, one file per Todo list) or `single-file` (see
+    /// [`single_file`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<String>,
+    /// Operations asking for confirmation: `always`, `destructive-only` or `never` (see
+    /// [`confirm`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<String>,
+    /// Extensions of Todo list files (e.g. `markdown`, `todo.md`), the first one naming new Todo
+    /// lists. `md` and `txt` when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    /// CalDAV task collections synchronised with `todo caldav`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caldav: Option<CalDavSettings>,
+    /// IDE opening Todo lists by file extension (e.g. `md = "obsidian://open?path={encoded_path}"`),
+    /// instead of `ide` (see [`edit`])
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ide_by_extension: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Deserialize, Debug, Serialize, Default, PartialEq)]
+/// CalDAV server of a Todo context
+///
+/// Each Todo list is synchronised with the calendar named after its file inside `url`. Credentials
+/// are read from `~/.netrc`.
+pub struct CalDavSettings {
+    /// Calendar home of the user (e.g. `https://cloud.example.com/remote.php/dav/calendars/me/`)
+    pub url: String,
+    /// Which side wins when a task is checked on one side only: `completed` (default), `local` or
+    /// `remote`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflict_policy: Option<String>,
 }
 
 impl fmt::Display for Context {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(
             f,
-            "--- Context ---\nname: {}\nide: {}\ntimezone: {}\nfolder location: {}",
-            self.name, self.ide, self.timezone, self.folder_location
+            "--- Context ---\nname: {}\nide: {}\ntimezone: {}\nfolder location: {}{}",
+            self.name,
+            self.ide,
+            self.timezone,
+            self.folder_location,
+            self.purpose()
         )
     }
 }
 
 impl Context {
+    /// Returns Todo context `name` storing its Todo lists inside `folder_location`, with every
+    /// other setting left to its default
+    pub fn new(name: &str, folder_location: &str) -> Context {
+        Context {
+            name: name.to_string(),
+            folder_location: folder_location.to_string(),
+            ..Default::default()
+        }
+    }
+
     fn short(&self) -> String {
         self.name.to_string()
     }
+
+    /// Returns today's date in the timezone of the Todo context (`UTC`, `Europe/Paris`...), or in
+    /// the local timezone when the timezone of the Todo context is not known
+    pub fn today(&self) -> chrono::NaiveDate {
+        self.date_at(chrono::Utc::now())
+    }
+
+    /// Returns the date at `now` in the timezone of the Todo context, or else in the local timezone
+    fn date_at(&self, now: chrono::DateTime<chrono::Utc>) -> chrono::NaiveDate {
+        match self.timezone.parse::<chrono_tz::Tz>() {
+            Ok(tz) => now.with_timezone(&tz).naive_local().date(),
+            Err(_) => {
+                if !self.timezone.is_empty() {
+                    debug!("unknown timezone \"{}\", using local time", self.timezone);
+                }
+                now.with_timezone(&chrono::Local).naive_local().date()
+            }
+        }
+    }
+
+    /// Returns description and goal lines of the Todo context, each preceded by a newline
+    fn purpose(&self) -> String {
+        let mut purpose = String::new();
+        if let Some(description) = &self.description {
+            purpose.push_str(format!("\ndescription: {description}").as_str());
+        }
+        if let Some(goal) = &self.goal {
+            purpose.push_str(format!("\ngoal: {goal}").as_str());
+        }
+        purpose
+    }
+
+    /// Returns the one-line banner of the Todo context with its description and goal, if any
+    pub fn banner(&self) -> Option<String> {
+        match (&self.description, &self.goal) {
+            (Some(description), Some(goal)) => Some(format!("{description} (goal: {goal})")),
+            (Some(description), None) => Some(description.to_string()),
+            (None, Some(goal)) => Some(format!("Goal: {goal}")),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the IDE opening the Todo list at `path`: the one configured for its extension, or else
+    /// the IDE of the Todo context
+    pub fn ide_for(&self, path: &str) -> &str {
+        Path::new(path)
+            .extension()
+            .and_then(|extension| self.ide_by_extension.get(extension.to_str()?))
+            .map_or(self.ide.as_str(), |ide| ide.as_str())
+    }
+
+    /// Returns the extensions of Todo list files, the first one naming new Todo lists
+    pub fn extensions(&self) -> Vec<&str> {
+        if self.extensions.is_empty() {
+            return DEFAULT_EXTENSIONS.to_vec();
+        }
+        self.extensions
+            .iter()
+            .map(|extension| extension.trim_start_matches('.'))
+            .collect()
+    }
+
+    /// Returns true if the file at `path` is a Todo list file, judging by its extension
+    pub fn is_todo_list_file(&self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        self.extensions().iter().any(|extension| {
+            name.strip_suffix(extension)
+                .and_then(|stem| stem.strip_suffix('.'))
+                .is_some_and(|stem| !stem.is_empty())
+        })
+    }
+
+    /// Returns true if all Todo lists of the Todo context are stored inside a single file
+    pub fn is_single_file(&self) -> bool {
+        self.storage.as_deref() == Some(single_file::SINGLE_FILE_STORAGE)
+    }
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone, Default)]
@@ -73,8 +318,30 @@ impl Context {
 pub struct Configuration {
     /// The name of the active context in the configuration
     active_ctx_name: String,
+    /// Format of printed dates (strftime syntax), ISO 8601 when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_format: Option<String>,
+    /// Record local usage statistics of subcommands and contexts (see `todo stats usage`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage_stats: Option<bool>,
+    /// Arguments run when `todo` is invoked without subcommand (e.g. `["list", "--short"]`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_command: Option<Vec<String>>,
+    /// Use the Todo context named by the closest `.todo-context` file above the working directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detect_context: Option<bool>,
+    /// Active Todo context of the configuration file while a workspace is the active Todo context
+    #[serde(skip)]
+    configured_active_ctx_name: Option<String>,
+    // TOML tables are written after plain values
     /// The available contexts in the configuration
     ctxs: Vec<Context>,
+    /// Saved `list` arguments by view name (see `todo view`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    views: BTreeMap<String, Vec<String>>,
+    /// Arguments run by `todo NAME` by alias name (e.g. `today = ["list", "--open", "-l", "today"]`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    aliases: BTreeMap<String, Vec<String>>,
 }
 
 impl fmt::Display for Configuration {
@@ -96,35 +363,204 @@ impl Configuration {
         Configuration {
             active_ctx_name: "".to_string(),
             ctxs: vec![],
+            ..Default::default()
         }
     }
 
+    /// Reads the configuration file at `path`
+    pub fn from_file(path: &str) -> Result<Configuration, std::io::Error> {
+        parse_configuration_file(Some(path), None)
+    }
+
+    /// Returns all Todo contexts of the configuration
+    pub fn ctxs(&self) -> &[Context] {
+        &self.ctxs
+    }
+
     /// Updates active context in configuration
     ///
     /// The active context is updated when the given name matches the one of the context inside the configuration.
-    fn update_active_ctx(&mut self, new_active_ctx_name: &str) -> Result<(), &str> {
+    fn update_active_ctx(&mut self, new_active_ctx_name: &str) -> Result<(), String> {
         if new_active_ctx_name.is_empty() {
-            return Err("Active context has no name");
+            return Err("Active context has no name".to_string());
         }
 
         let mut new_config = self.clone();
         new_config.active_ctx_name = new_active_ctx_name.to_string();
 
         if !new_config.is_valid() {
-            return Err("No matching context could be found among available contexts");
+            return Err(format!(
+                "No matching context could be found among available contexts{}",
+                did_you_mean(&self.closest_ctx_names(new_active_ctx_name))
+            ));
         }
 
         self.active_ctx_name = new_active_ctx_name.to_string();
         Ok(())
     }
 
+    /// Returns the Todo context named `name`, or else an error suggesting the closest names of
+    /// Todo contexts
+    pub fn ctx(&self, name: &str) -> Result<&Context, std::io::Error> {
+        self.ctxs
+            .iter()
+            .find(|ctx| ctx.name == name)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Unknown context \"{name}\"{}",
+                        did_you_mean(&self.closest_ctx_names(name))
+                    ),
+                )
+            })
+    }
+
+    /// Returns the names of Todo contexts a few typos away from `name` (or starting like it),
+    /// closest first
+    pub fn closest_ctx_names(&self, name: &str) -> Vec<&str> {
+        closest_names(name, self.ctxs.iter().map(|ctx| ctx.name.as_str()))
+    }
+
+    /// Returns the format of printed dates for given Todo context
+    ///
+    /// The format of the Todo context takes precedence over the global format. Invalid formats
+    /// are ignored.
+    pub fn date_format(&self, ctx: &Context) -> String {
+        [ctx.date_format.as_ref(), self.date_format.as_ref()]
+            .into_iter()
+            .flatten()
+            .find(|f| {
+                let is_valid = is_valid_date_format(f);
+                if !is_valid {
+                    warn!("Ignoring invalid date format \"{f}\"");
+                }
+                is_valid
+            })
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_string())
+    }
+
+    /// Returns the arguments run when `todo` is invoked without subcommand inside Todo context
+    ///
+    /// The default command of the Todo context takes precedence over the global default command.
+    pub fn default_command<'a>(&'a self, ctx: &'a Context) -> Option<&'a [String]> {
+        ctx.default_command
+            .as_deref()
+            .or(self.default_command.as_deref())
+    }
+
+    /// Returns the arguments run by alias `name`
+    pub fn alias(&self, name: &str) -> Option<&[String]> {
+        self.aliases.get(name).map(|args| args.as_slice())
+    }
+
+    /// Returns true if the user opted in local usage statistics
+    pub fn records_usage(&self) -> bool {
+        self.usage_stats.unwrap_or(false)
+    }
+
+    /// Returns the active Todo context
+    pub fn active_ctx(&self) -> &Context {
+        self.ctxs
+            .iter()
+            .find(|c| c.name == self.active_ctx_name)
+            .expect("No configuration matched active context name")
+    }
+
+    /// Sets the active Todo context to the one named by the closest context marker file above
+    /// `dir`, when context detection is enabled
+    pub fn detect_active_ctx(&mut self, dir: &Path) -> Result<(), std::io::Error> {
+        if !self.detect_context.unwrap_or(false) {
+            return Ok(());
+        }
+        let (marker, name) = match context_marker(dir)? {
+            Some(marker) => marker,
+            None => return Ok(()),
+        };
+        let name = name.as_str();
+        if let Err(e) = self.update_active_ctx(name) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{}: \"{name}\": {e}", marker.display()),
+            ));
+        }
+        debug!("active context {name} detected from {}", marker.display());
+        Ok(())
+    }
+
+    /// Makes the Todo lists of workspace `folder` the active Todo context
+    ///
+    /// The workspace Todo context uses the IDE and timezone of the configured active Todo context,
+    /// if any.
+    pub fn use_workspace(&mut self, folder: &Path) {
+        let configured = self
+            .ctxs
+            .iter()
+            .find(|c| c.name == self.active_ctx_name)
+            .cloned();
+        let ctx = Context {
+            name: workspace::WORKSPACE_CTX_NAME.to_string(),
+            ide: configured.as_ref().map_or_else(
+                || std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string()),
+                |c| c.ide.clone(),
+            ),
+            timezone: configured.map_or_else(|| "UTC".to_string(), |c| c.timezone),
+            folder_location: folder.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        self.ctxs.retain(|c| c.name != ctx.name);
+        if self.ctxs.iter().any(|c| c.name == self.active_ctx_name) {
+            self.configured_active_ctx_name = Some(self.active_ctx_name.clone());
+        }
+        self.active_ctx_name = ctx.name.clone();
+        self.ctxs.push(ctx);
+        debug!("using workspace {}", folder.display());
+    }
+
+    /// Overrides configuration values with the `TODO_ACTIVE_CTX`, `TODO_FOLDER` and `TODO_IDE`
+    /// environment variables for the current invocation
+    pub fn override_from_environment(&mut self) -> Result<(), std::io::Error> {
+        self.override_with(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+    }
+
+    /// Overrides active Todo context, then its folder and IDE, with values from `lookup`
+    fn override_with(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), std::io::Error> {
+        if let Some(name) = lookup(ACTIVE_CTX_VARIABLE) {
+            if let Err(e) = self.update_active_ctx(name.as_str()) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{ACTIVE_CTX_VARIABLE}=\"{name}\": {e}"),
+                ));
+            }
+            debug!("active context overridden with {name}");
+        }
+        let active_ctx_name = self.active_ctx_name.clone();
+        let ctx = match self.ctxs.iter_mut().find(|c| c.name == active_ctx_name) {
+            Some(ctx) => ctx,
+            None => return Ok(()),
+        };
+        if let Some(folder) = lookup(FOLDER_VARIABLE) {
+            debug!("folder overridden with {folder}");
+            ctx.folder_location = folder;
+        }
+        if let Some(ide) = lookup(IDE_VARIABLE) {
+            debug!("ide overridden with {ide}");
+            ctx.ide = ide;
+        }
+        Ok(())
+    }
+
     /// Returns true if configuration active context name matches with any context
     fn is_valid(&self) -> bool {
         self.ctxs.iter().any(|c| c.name == self.active_ctx_name)
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 /// Represents a Todo list
 ///
 /// Todo lists are uniquely identified by their name. Labels allows to theme your Todo list and
@@ -136,6 +572,48 @@ pub struct TodoList {
     labels: Vec<String>,
     list_items: Vec<String>,
     motives: Vec<String>,
+    /// Tasks read from an existing Todo list
+    #[serde(skip)]
+    tasks: Vec<Task>,
+}
+
+impl TodoList {
+    /// Parses raw Todo list
+    ///
+    /// Tasks lose their nesting and comments when the Todo list is written back with `to_string`.
+    pub fn parse(todo_raw: &str) -> Result<TodoList, std::io::Error> {
+        let parsed = parse_todo_list(todo_raw)?;
+        Ok(TodoList {
+            title: parsed.title,
+            description: parse_todo_list_parts(todo_raw).description,
+            labels: parsed.labels,
+            list_items: vec![],
+            motives: parse_todo_list_motives(todo_raw),
+            tasks: parse_tasks(todo_raw),
+        })
+    }
+
+    pub fn title(&self) -> &str {
+        self.title.as_str()
+    }
+
+    /// Returns the description without the labels
+    pub fn description(&self) -> &str {
+        self.description.as_str()
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn motives(&self) -> &[String] {
+        &self.motives
+    }
+
+    /// Returns the tasks of the task list, checked or not
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
 }
 
 impl fmt::Display for TodoList {
@@ -151,8 +629,11 @@ impl fmt::Display for TodoList {
             writeln!(f, "{}", self.description)?;
         }
 
-        if !self.list_items.is_empty() {
+        if !self.tasks.is_empty() || !self.list_items.is_empty() {
             writeln!(f, "\n## Todo list\n")?;
+            for task in self.tasks.iter() {
+                writeln!(f, "* [{}] {}", if task.done { 'x' } else { ' ' }, task.text)?;
+            }
             for i in self.list_items.iter() {
                 writeln!(f, "* [ ] {}", i)?;
             }
@@ -160,10 +641,8 @@ impl fmt::Display for TodoList {
 
         if !self.motives.is_empty() {
             writeln!(f, "\n## Motives\n")?;
-            let mut i = 1;
-            for m in self.motives.iter() {
+            for (i, m) in (1..).zip(self.motives.iter()) {
                 writeln!(f, "{}. {}", i, m)?;
-                i += 1;
             }
         }
 
@@ -171,23 +650,229 @@ impl fmt::Display for TodoList {
     }
 }
 
+/// Default format of printed dates (ISO 8601)
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Returns true if the strftime date format can be used to print dates
+fn is_valid_date_format(date_format: &str) -> bool {
+    !date_format.is_empty()
+        && StrftimeItems::new(date_format).all(|item| !matches!(item, Item::Error))
+}
+
+/// File naming the Todo context of a directory and its subdirectories (see `detect_context`)
+pub const CONTEXT_MARKER: &str = ".todo-context";
+
+/// Environment variable overriding the active Todo context
+pub const ACTIVE_CTX_VARIABLE: &str = "TODO_ACTIVE_CTX";
+
+/// Environment variable overriding the folder of the active Todo context
+pub const FOLDER_VARIABLE: &str = "TODO_FOLDER";
+
+/// Environment variable overriding the IDE of the active Todo context
+pub const IDE_VARIABLE: &str = "TODO_IDE";
+
+/// Exit code when a command completed but nothing matched its filters
+///
+/// Scripts can tell empty results apart from errors (exit code 1).
+pub const NO_MATCH_EXIT_CODE: i32 = 2;
+
+/// Exit code of `todo list --fail-if-open` when matching Todo lists have open tasks
+pub const OPEN_TASKS_EXIT_CODE: i32 = 3;
+
+/// Name of the folder inside a Todo context folder where archived Todo lists are kept
+pub const ARCHIVE_FOLDER: &str = "archive";
+
+/// Returns the closest context marker file above `dir` with the Todo context name it holds
+pub(crate) fn context_marker(dir: &Path) -> Result<Option<(PathBuf, String)>, std::io::Error> {
+    let marker = match dir
+        .ancestors()
+        .map(|d| d.join(CONTEXT_MARKER))
+        .find(|m| m.is_file())
+    {
+        Some(marker) => marker,
+        None => return Ok(None),
+    };
+    let raw = std::fs::read_to_string(&marker)?;
+    match raw.lines().map(|l| l.trim()).find(|l| !l.is_empty()) {
+        Some(name) => Ok(Some((marker, name.to_string()))),
+        None => {
+            warn!("Ignoring empty context marker {}", marker.display());
+            Ok(None)
+        }
+    }
+}
+
+/// Extensions of Todo list files of Todo contexts which do not configure theirs
+pub const DEFAULT_EXTENSIONS: [&str; 2] = ["md", "txt"];
+
 /// Returns the path to the Todo list from given Todo context
 ///
-/// The Todo list is always a markdown file for usability.
-pub fn todo_path(todo_folder_of_todo_ctx: &str, todo_list_name: &str) -> String {
-    format!("{}/{}.md", todo_folder_of_todo_ctx, todo_list_name)
+/// The Todo list is named with the first extension of the Todo context, markdown by default for
+/// usability.
+pub fn todo_path(ctx: &Context, todo_list_name: &str) -> String {
+    format!(
+        "{}/{}.{}",
+        ctx.folder_location,
+        todo_list_name,
+        ctx.extensions()[0]
+    )
+}
+
+/// Returns the path to the Todo list titled `title` from given Todo context
+///
+/// Title-based commands are ambiguous when many Todo lists of the Todo context share the same title,
+/// in which case an error listing their paths is returned. Todo lists inside subfolders (see
+/// `todo migrate`) are found by title when no Todo list file is named after the title. A Todo list
+/// file named after the title may have any extension of the Todo context.
+pub fn resolve_todo_path(ctx: &Context, title: &str) -> Result<String, std::io::Error> {
+    let filepath = ctx
+        .extensions()
+        .iter()
+        .map(|extension| format!("{}/{title}.{extension}", ctx.folder_location))
+        .find(|filepath| Path::new(filepath).exists())
+        .unwrap_or_else(|| todo_path(ctx, title));
+    if Path::new(ctx.folder_location.as_str()).is_dir() {
+        let todo_lists = read_todo_lists(ctx)?;
+        if let Some((_, paths)) = duplicate_titles(&todo_lists)
+            .into_iter()
+            .find(|(t, _)| t == title)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Many Todo lists are titled \"{title}\": {}. Use --path to pick one.",
+                    paths.join(", ")
+                ),
+            ));
+        }
+        if !Path::new(filepath.as_str()).exists() {
+            let titled = todo_lists.into_iter().find(|(_, todo_raw)| {
+                parse::parse_todo_list(todo_raw).is_ok_and(|todo_list| todo_list.title == title)
+            });
+            if let Some((filepath, _)) = titled {
+                return Ok(filepath);
+            }
+        }
+    }
+    Ok(filepath)
+}
+
+/// Returns the `--path` argument of title-based commands
+///
+/// Selecting the Todo list file directly bypasses title resolution, which is handy in scripts and
+/// when many Todo lists share the same title.
+pub fn path_arg() -> Arg<'static, 'static> {
+    Arg::with_name("path")
+        .long("path")
+        .value_name("FILE")
+        .help("Path to the todo list file, instead of its title")
+        .takes_value(true)
+        .conflicts_with("title")
+}
+
+/// Returns the path to the Todo list selected with either `--path` or `--title` arguments
+pub fn todo_path_from_args(args: &ArgMatches, ctx: &Context) -> Result<String, std::io::Error> {
+    match args.value_of("path") {
+        Some(filepath) => Ok(filepath.to_string()),
+        None => resolve_todo_path(ctx, args.value_of("title").unwrap()),
+    }
+}
+
+/// Returns the names among `names` a few typos away from `name` (or starting like it), closest
+/// first. Names are compared case-insensitively.
+pub fn closest_names<'a>(name: &str, names: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let name = name.to_lowercase();
+    let mut closest = names
+        .into_iter()
+        .filter_map(|n| {
+            let lowercase = n.to_lowercase();
+            let distance = strsim::levenshtein(name.as_str(), lowercase.as_str());
+            (distance <= 2 || (!name.is_empty() && lowercase.starts_with(name.as_str())))
+                .then_some((distance, n))
+        })
+        .collect::<Vec<_>>();
+    closest.sort();
+    closest.dedup();
+    closest.into_iter().map(|(_, n)| n).collect()
+}
+
+/// Returns ` (did you mean "a" or "b"?)` suggesting `names`, or nothing without names
+pub fn did_you_mean(names: &[&str]) -> String {
+    let quoted = names.iter().map(|n| format!("\"{n}\"")).collect::<Vec<_>>();
+    match quoted.as_slice() {
+        [] => String::new(),
+        [name] => format!(" (did you mean {name}?)"),
+        [names @ .., last] => format!(" (did you mean {} or {last}?)", names.join(", ")),
+    }
+}
+
+/// Returns the titles shared by many Todo lists with the paths of these Todo lists
+///
+/// * `todo_lists` - path and raw content of Todo lists
+pub fn duplicate_titles(todo_lists: &[(String, String)]) -> Vec<(String, Vec<String>)> {
+    let mut paths_by_title: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (filepath, todo_raw) in todo_lists {
+        if let Ok(todo_list) = parse::parse_todo_list(todo_raw) {
+            paths_by_title
+                .entry(todo_list.title)
+                .or_default()
+                .push(filepath.to_string());
+        }
+    }
+    paths_by_title
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect()
+}
+
+/// Returns the raw content of Todo list at `filepath`, or a new empty Todo list titled `title` when
+/// it does not exist yet
+pub fn read_todo_list_or_new(filepath: &str, title: &str) -> Result<String, std::io::Error> {
+    match std::fs::read_to_string(filepath) {
+        Ok(todo_raw) => Ok(todo_raw),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(TodoList {
+            title: title.to_string(),
+            ..Default::default()
+        }
+        .to_string()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the path and the raw content of all Todo lists inside the folder of the Todo context
+///
+/// Files with an extension that is not accepted for Todo lists of the Todo context are skipped. A
+/// Todo list spanning many files is returned once, with its parts joined (see [`parts`]).
+pub fn read_todo_lists(ctx: &Context) -> Result<Vec<(String, String)>, std::io::Error> {
+    let mut todo_lists = vec![];
+    for entry in WalkDir::new(ctx.folder_location.as_str()) {
+        let entry = entry.map_err(std::io::Error::other)?;
+        if !entry.file_type().is_file()
+            || !ctx.is_todo_list_file(entry.path())
+            || parts::is_part(entry.path())
+        {
+            continue;
+        }
+        let filepath = entry.path().to_string_lossy().to_string();
+        let todo_raw = parts::Parts::read(filepath.as_str())?.joined();
+        todo_lists.push((filepath, todo_raw));
+    }
+
+    Ok(todo_lists)
 }
 
 /// Prompts user for Todo folder creation if it does not exists. Exits if user answer is negative.
 fn prompt_for_todo_folder_if_not_exists(ctx: &Context) -> Result<(), Error> {
     if !Path::exists(Path::new(ctx.folder_location.as_str())) {
-        match Confirm::new()
-            .with_prompt(format!(
+        match confirm(
+            ctx,
+            Operation::CreateFolder,
+            format!(
                 "Todo folder location for this context does not exists. Create {} ?",
                 ctx.folder_location
-            ))
-            .interact()
-        {
+            )
+            .as_str(),
+        ) {
             Ok(user_validated) => {
                 if user_validated {
                     match std::fs::create_dir(ctx.folder_location.as_str()) {
@@ -208,6 +893,7 @@ fn prompt_for_todo_folder_if_not_exists(ctx: &Context) -> Result<(), Error> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::TempFolder;
     use simplelog::*;
 
     // TODO wait for before/after_test macro
@@ -221,7 +907,7 @@ mod tests {
         );
     }
 
-    const TODO_BAREBONES: &'static str = "\
+    const TODO_BAREBONES: &str = "\
 # Title
 
 ## Description
@@ -238,6 +924,7 @@ LABEL=
             description: String::from(""),
             list_items: vec![],
             motives: vec![],
+            tasks: vec![],
         };
         let expected = TODO_BAREBONES;
         let output = format!("{}", todo);
@@ -253,6 +940,7 @@ LABEL=
             description: String::from("This is the hello todo list"),
             list_items: vec![String::from("i1 first"), String::from("i2 second")],
             motives: vec![String::from("m1 first"), String::from("m2 second")],
+            tasks: vec![],
         };
         let expected = String::from(
             "\
@@ -284,6 +972,7 @@ This is the hello todo list
         let mut config = Configuration {
             active_ctx_name: String::from(""),
             ctxs: vec![],
+            ..Default::default()
         };
         assert!(config.update_active_ctx("").is_err());
 
@@ -295,18 +984,69 @@ This is the hello todo list
                     name: String::from("config1"),
                     timezone: String::from(""),
                     folder_location: String::from(""),
+                    ..Default::default()
                 },
                 Context {
                     ide: String::from(""),
                     name: String::from(""),
                     timezone: String::from(""),
                     folder_location: String::from(""),
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
         assert!(config.update_active_ctx("").is_err());
     }
 
+    #[test]
+    fn today_in_context_timezone() {
+        let ctx = |timezone: &str| Context {
+            timezone: timezone.to_string(),
+            ..Default::default()
+        };
+        let now = chrono::DateTime::parse_from_rfc3339("2024-07-01T20:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let date = |y, m, d| chrono::NaiveDate::from_ymd(y, m, d);
+        assert_eq!(ctx("UTC").date_at(now), date(2024, 7, 1));
+        assert_eq!(ctx("Pacific/Auckland").date_at(now), date(2024, 7, 2));
+        assert_eq!(ctx("America/New_York").date_at(now), date(2024, 7, 1));
+        assert_eq!(
+            ctx("Mars/Olympus").date_at(now),
+            now.with_timezone(&chrono::Local).naive_local().date()
+        );
+    }
+
+    #[test]
+    fn unknown_context_suggestions() {
+        let ctx = |name: &str| Context {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut config = Configuration {
+            active_ctx_name: "work".to_string(),
+            ctxs: vec![ctx("work"), ctx("personal"), ctx("worm"), ctx("side")],
+            ..Default::default()
+        };
+        assert_eq!(config.closest_ctx_names("wor"), vec!["work", "worm"]);
+        assert_eq!(config.closest_ctx_names("Pers"), vec!["personal"]);
+        assert!(config.closest_ctx_names("groceries").is_empty());
+        assert_eq!(config.ctx("side").unwrap().name, "side");
+        assert_eq!(
+            config.ctx("wor").unwrap_err().to_string(),
+            "Unknown context \"wor\" (did you mean \"work\" or \"worm\"?)"
+        );
+        assert_eq!(
+            config.ctx("groceries").unwrap_err().to_string(),
+            "Unknown context \"groceries\""
+        );
+        assert!(config
+            .update_active_ctx("persnal")
+            .unwrap_err()
+            .ends_with("(did you mean \"personal\"?)"));
+    }
+
     #[test]
     fn update_config_with_existing_config() {
         init();
@@ -318,16 +1058,191 @@ This is the hello todo list
                     name: String::from("config1"),
                     timezone: String::from(""),
                     folder_location: String::from(""),
+                    ..Default::default()
                 },
                 Context {
                     ide: String::from(""),
                     name: String::from("config2"),
                     timezone: String::from(""),
                     folder_location: String::from(""),
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
         assert!(config.update_active_ctx("config2").is_ok());
         assert_eq!(config.active_ctx_name, "config2");
     }
+
+    #[test]
+    fn duplicate_titles_are_detected() {
+        init();
+        let root = TempFolder::new("duplicates");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        let todo_raw = "# groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] milk";
+        std::fs::write(root.join("groceries.md"), todo_raw).unwrap();
+        std::fs::write(root.join("sub").join("shopping.md"), todo_raw).unwrap();
+        std::fs::write(
+            root.join("chores.md"),
+            "# chores\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] dishes",
+        )
+        .unwrap();
+        let ctx = Context {
+            folder_location: root.location(),
+            ..Default::default()
+        };
+
+        let duplicates = duplicate_titles(&read_todo_lists(&ctx).unwrap());
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].0, "groceries");
+        assert_eq!(duplicates[0].1.len(), 2);
+        assert!(resolve_todo_path(&ctx, "groceries").is_err());
+        assert_eq!(
+            resolve_todo_path(&ctx, "chores").unwrap(),
+            todo_path(&ctx, "chores")
+        );
+    }
+
+    #[test]
+    fn todo_list_files_by_extension() {
+        let root = TempFolder::new("extensions");
+        std::fs::write(root.join("notes.markdown"), "# notes\n").unwrap();
+        let mut ctx = Context {
+            folder_location: root.location(),
+            ..Default::default()
+        };
+        assert!(ctx.is_todo_list_file(Path::new("a.md")));
+        assert!(ctx.is_todo_list_file(Path::new("a.txt")));
+        assert!(!ctx.is_todo_list_file(Path::new("a.jpg")));
+        assert!(!ctx.is_todo_list_file(Path::new(".md")));
+        assert!(!ctx.is_todo_list_file(Path::new("amd")));
+
+        ctx.extensions = vec!["todo.md".to_string(), ".markdown".to_string()];
+        assert!(ctx.is_todo_list_file(Path::new("a.todo.md")));
+        assert!(ctx.is_todo_list_file(Path::new("a.markdown")));
+        assert!(!ctx.is_todo_list_file(Path::new("a.md")));
+        assert_eq!(
+            todo_path(&ctx, "release"),
+            format!("{}/release.todo.md", ctx.folder_location)
+        );
+        assert_eq!(
+            resolve_todo_path(&ctx, "notes").unwrap(),
+            format!("{}/notes.markdown", ctx.folder_location)
+        );
+    }
+
+    #[test]
+    fn path_argument_bypasses_title_resolution() {
+        let ctx = Context {
+            folder_location: "folder".to_string(),
+            ..Default::default()
+        };
+        let args = delete::delete_command().get_matches_from(vec!["delete", "--path", "a/b.md"]);
+        assert_eq!(todo_path_from_args(&args, &ctx).unwrap(), "a/b.md");
+        let args = delete::delete_command().get_matches_from(vec!["delete", "b"]);
+        assert_eq!(todo_path_from_args(&args, &ctx).unwrap(), "folder/b.md");
+        assert!(delete::delete_command()
+            .get_matches_from_safe(vec!["delete", "b", "--path", "a/b.md"])
+            .is_err());
+        assert!(delete::delete_command()
+            .get_matches_from_safe(vec!["delete"])
+            .is_err());
+    }
+
+    #[test]
+    fn date_format_precedence() {
+        init();
+        let mut config = Configuration::new();
+        let mut ctx = Context::default();
+        assert_eq!(config.date_format(&ctx), "%Y-%m-%d");
+        config.date_format = Some(String::from("%m/%d/%Y"));
+        assert_eq!(config.date_format(&ctx), "%m/%d/%Y");
+        ctx.date_format = Some(String::from("%d.%m.%Y"));
+        assert_eq!(config.date_format(&ctx), "%d.%m.%Y");
+        ctx.date_format = Some(String::from("%Q"));
+        assert_eq!(config.date_format(&ctx), "%m/%d/%Y");
+    }
+
+    #[test]
+    fn default_command_precedence() {
+        let mut config = Configuration::new();
+        let mut ctx = Context::default();
+        assert_eq!(config.default_command(&ctx), None);
+        config.default_command = Some(vec![String::from("list"), String::from("--short")]);
+        assert_eq!(
+            config.default_command(&ctx),
+            Some(&[String::from("list"), String::from("--short")][..])
+        );
+        ctx.default_command = Some(vec![String::from("view")]);
+        assert_eq!(
+            config.default_command(&ctx),
+            Some(&[String::from("view")][..])
+        );
+    }
+
+    #[test]
+    fn environment_overrides() {
+        let mut config: Configuration = toml::from_str(
+            "active_ctx_name = \"work\"\n\n[[ctxs]]\nname = \"work\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_location = \"/work\"\n\n[[ctxs]]\nname = \"home\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_location = \"/home\"\n",
+        )
+        .unwrap();
+        config
+            .override_with(|var| match var {
+                ACTIVE_CTX_VARIABLE => Some(String::from("home")),
+                IDE_VARIABLE => Some(String::from("code")),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(config.active_ctx().name, "home");
+        assert_eq!(config.active_ctx().ide, "code");
+        assert_eq!(config.active_ctx().folder_location, "/home");
+        assert_eq!(config.ctxs[0].ide, "vim");
+
+        config
+            .override_with(|var| (var == FOLDER_VARIABLE).then(|| String::from("/project")))
+            .unwrap();
+        assert_eq!(config.active_ctx().folder_location, "/project");
+
+        assert!(config
+            .override_with(|var| (var == ACTIVE_CTX_VARIABLE).then(|| String::from("unknown")))
+            .is_err());
+    }
+
+    #[test]
+    fn detect_active_ctx_from_marker() {
+        let root = TempFolder::new("detect");
+        let project = root.join("project/src");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(root.join("project").join(CONTEXT_MARKER), "\nhome\n").unwrap();
+        let mut config: Configuration = toml::from_str(
+            "active_ctx_name = \"work\"\n\n[[ctxs]]\nname = \"work\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_location = \"/work\"\n\n[[ctxs]]\nname = \"home\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_location = \"/home\"\n",
+        )
+        .unwrap();
+        config.detect_active_ctx(&project).unwrap();
+        assert_eq!(config.active_ctx().name, "work");
+
+        config.detect_context = Some(true);
+        config.detect_active_ctx(&root).unwrap();
+        assert_eq!(config.active_ctx().name, "work");
+        config.detect_active_ctx(&project).unwrap();
+        assert_eq!(config.active_ctx().name, "home");
+
+        std::fs::write(root.join("project").join(CONTEXT_MARKER), "unknown").unwrap();
+        assert!(config.detect_active_ctx(&project).is_err());
+    }
+
+    #[test]
+    fn workspace_is_active_ctx() {
+        let mut config: Configuration = toml::from_str(
+            "active_ctx_name = \"work\"\n\n[[ctxs]]\nname = \"work\"\nide = \"code\"\ntimezone = \"Europe/Paris\"\nfolder_location = \"/work\"\n",
+        )
+        .unwrap();
+        config.use_workspace(Path::new("/repo/.todo"));
+        let ctx = config.active_ctx();
+        assert_eq!(ctx.name, workspace::WORKSPACE_CTX_NAME);
+        assert_eq!(ctx.ide, "code");
+        assert_eq!(ctx.timezone, "Europe/Paris");
+        assert_eq!(ctx.folder_location, "/repo/.todo");
+        assert_eq!(config.ctxs.len(), 2);
+    }
 }
@@ -0,0 +1,159 @@
+//! Check Todo lists are well formed
+//!
+//! Malformed Todo lists are silently misread by other commands (a `- [ ]` task is not counted for
+//! instance). Linting reports every problem with its line so it can be fixed by hand.
+use crate::parse::{parse_active_context, parse_task_id, parse_tasks};
+use crate::{read_todo_lists, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use lazy_static::lazy_static;
+use log::trace;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+lazy_static! {
+    static ref CHECKBOX_RE: Regex = Regex::new(r"^\s*[*+-] ?\[[^\]]?\]").unwrap();
+    static ref TASK_RE: Regex = Regex::new(r"^\* \[[ x]\] \S").unwrap();
+}
+
+/// Returns the Lint command
+pub fn lint_command() -> App<'static, 'static> {
+    App::new("lint")
+        .about("Check todo lists of Todo context are well formed")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("folder")
+                .long("folder")
+                .value_name("FOLDER")
+                .help("Check todo lists inside FOLDER instead of active Todo context folder")
+                .takes_value(true),
+        )
+}
+
+/// Checks Todo lists of active Todo context (or given folder). Returns an error when any Todo list
+/// is malformed.
+pub fn lint_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    raw_config: Option<&str>,
+) -> Result<(), std::io::Error> {
+    trace!("lint subcommand");
+    let ctx = match args.value_of("folder") {
+        Some(folder) => Context {
+            folder_location: folder.to_string(),
+            ..Default::default()
+        },
+        None => parse_active_context(Some(todo_configuration_path), raw_config)?,
+    };
+
+    let mut problems = 0;
+    let todo_lists = read_todo_lists(&ctx)?;
+    for (filepath, todo_raw) in &todo_lists {
+        for problem in lint(todo_raw.as_str()) {
+            eprintln!("{filepath}:{problem}");
+            problems += 1;
+        }
+    }
+    for problem in duplicate_task_ids(&todo_lists) {
+        eprintln!("{problem}");
+        problems += 1;
+    }
+
+    if problems > 0 {
+        return Err(std::io::Error::other(format!(
+            "{problems} problem(s) found in todo lists"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the problems of a Todo list, each prefixed by its line number
+fn lint(todo_raw: &str) -> Vec<String> {
+    let mut problems = vec![];
+    if !todo_raw.starts_with("# ") {
+        problems.push("1: missing title (first line should be \"# Title\")".to_string());
+    }
+    if !todo_raw.contains("## Description\n\nLABEL=") {
+        problems.push(
+            "1: missing \"## Description\" section followed by a \"LABEL=\" line".to_string(),
+        );
+    }
+    for (i, line) in todo_raw.lines().enumerate() {
+        if CHECKBOX_RE.is_match(line) && !TASK_RE.is_match(line) {
+            problems.push(format!(
+                "{}: malformed task \"{}\" (tasks start with \"* [ ] \" or \"* [x] \")",
+                i + 1,
+                line.trim_end()
+            ));
+        }
+    }
+    problems
+}
+
+/// Returns a problem for every task whose ID is already used by a previous task
+fn duplicate_task_ids(todo_lists: &[(String, String)]) -> Vec<String> {
+    let mut ids: BTreeMap<String, String> = BTreeMap::new();
+    let mut problems = vec![];
+    for (filepath, todo_raw) in todo_lists {
+        for task in parse_tasks(todo_raw.as_str()) {
+            let id = match parse_task_id(task.text.as_str()) {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+            let location = format!("{filepath}:{}", task.line + 1);
+            match ids.get(id.as_str()) {
+                Some(first) => problems.push(format!(
+                    "{location}: duplicate task ID \"^{id}\" (first used at {first})"
+                )),
+                None => {
+                    ids.insert(id, location);
+                }
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn well_formed_todo_list() {
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n* [x] b\n  > comment [x]\n";
+        assert!(lint(todo_raw).is_empty());
+    }
+
+    #[test]
+    fn malformed_todo_list() {
+        let todo_raw = "Title\n\n## Todo list\n\n- [ ] a\n* [X] b\n* [] c\n* [ ]d\n* [ ] e\n";
+        assert_eq!(
+            lint(todo_raw),
+            vec![
+                "1: missing title (first line should be \"# Title\")",
+                "1: missing \"## Description\" section followed by a \"LABEL=\" line",
+                "5: malformed task \"- [ ] a\" (tasks start with \"* [ ] \" or \"* [x] \")",
+                "6: malformed task \"* [X] b\" (tasks start with \"* [ ] \" or \"* [x] \")",
+                "7: malformed task \"* [] c\" (tasks start with \"* [ ] \" or \"* [x] \")",
+                "8: malformed task \"* [ ]d\" (tasks start with \"* [ ] \" or \"* [x] \")",
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_task_ids_are_reported() {
+        let todo_lists = vec![
+            (
+                "a.md".to_string(),
+                "# a\n\n## Todo list\n\n* [ ] one ^x1\n* [ ] two ^x2\n".to_string(),
+            ),
+            (
+                "b.md".to_string(),
+                "# b\n\n## Todo list\n\n* [ ] three ^x1\n".to_string(),
+            ),
+        ];
+        assert_eq!(
+            duplicate_task_ids(&todo_lists),
+            vec!["b.md:5: duplicate task ID \"^x1\" (first used at a.md:5)"]
+        );
+    }
+}
@@ -1,11 +1,28 @@
 //! List all Todo lists in active Todo context
+use crate::archive::archive_folder;
+use crate::parts::{is_part, Parts};
 use crate::{
-    parse::{parse_todo_list, parse_todo_list_section, parse_todo_list_tasks},
+    age::{parse_age, tracks_ages, FirstSeen, TaskAges},
+    duplicate_titles,
+    hide::is_hidden,
+    lock::is_locked,
+    parse::{
+        parse_archive, parse_archived_tasks, parse_notes, parse_priority, parse_sections,
+        parse_tasks, parse_todo_list, parse_todo_list_due_tasks, parse_todo_list_motives,
+        parse_todo_list_parts, parse_todo_list_sections, parse_todo_list_tasks,
+        set_todo_list_labels, ParsedTodoList, Section,
+    },
+    pin::listing_order,
+    quota::warn_exceeded,
+    render::{output_width, width_arg, wrap_tasks},
+    single_file::SingleFile,
     Configuration, Context,
 };
+use chrono::NaiveDate;
 use clap::{crate_authors, App, Arg, ArgMatches};
 use log::debug;
-use std::{fs::read_to_string, path::Path};
+use regex::Regex;
+use std::{collections::BTreeSet, io::IsTerminal};
 use walkdir::WalkDir;
 
 /// The list of parameters for the `todo list` subcommand
@@ -35,16 +52,95 @@ pub struct Parameters<'a> {
     pub done: bool,
     entries: Option<Vec<Vec<&'a str>>>,
     pub global: bool,
+    /// Show Todo lists of the workspace and of the active Todo context of the configuration file
+    pub everywhere: bool,
+    /// Report open tasks of matching Todo lists instead of printing the Todo lists
+    pub fail_if_open: bool,
     pub labels: Vec<&'a str>,
+    /// Labels added to every printed Todo list
+    pub label_add: Vec<&'a str>,
+    /// Labels removed from every printed Todo list
+    pub label_remove: Vec<&'a str>,
     pub open: bool,
     pub short: bool,
     pub task_lists: Option<Vec<&'a str>>,
+    /// Titles of `task_lists` given as positional arguments, which may be mistaken filters
+    pub positional_titles: Vec<&'a str>,
     pub sections: Option<Vec<&'a str>>,
+    pub sections_only: bool,
+    /// Show only open tasks first seen at least this long ago (`90d`, `12w`)
+    pub stale: Option<&'a str>,
+    /// Color flagged tasks
+    pub color: bool,
+    /// Order of Todo lists which are neither pinned nor manually ordered: `title` or `open`
+    pub sort: Option<&'a str>,
+    /// Show hidden Todo lists too
+    pub hidden: bool,
+    /// Show notes (markdown files with a title but without labels) as Todo lists too
+    pub notes_too: bool,
+    /// Show only Todo lists with motives, and their motives in summaries
+    pub with_motives: bool,
+    /// Show only Todo lists with a motive containing this text
+    pub motive_contains: Option<&'a str>,
+    /// Wrap long tasks at this width
+    pub width: Option<usize>,
+    /// Parts of Todo lists shown (`title`, `description`, `tasks`, `motives`) instead of entire
+    /// Todo lists
+    pub fields: Option<Vec<&'a str>>,
+    /// Show archived tasks (`## Archive`) instead of their number
+    pub show_archive: bool,
+    /// List archived Todo lists (inside the archive folder) instead of the others
+    pub archived: bool,
+    /// Show only open tasks due as given
+    pub due: Option<Due>,
+    /// Show only open tasks of this priority (`(A) call dentist`)
+    pub priority: Option<char>,
 }
 
+/// Arguments which due date filters cannot be combined with
+const DUE_CONFLICTS: [&str; 8] = [
+    "done",
+    "fail-if-open",
+    "stale",
+    "sections",
+    "sections-only",
+    "fields",
+    "tasks",
+    "completed-tasks",
+];
+
+/// Priority of tasks counted by `--short` summaries
+const HIGH_PRIORITY: char = 'A';
+
+/// Filter of open tasks by due date (`@due(YYYY-MM-DD)`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Due {
+    /// Due before the date
+    Before(NaiveDate),
+    /// Due before today in the timezone of the Todo context
+    Overdue,
+    /// Due today in the timezone of the Todo context
+    Today,
+}
+
+impl Due {
+    /// Returns true if a task due at `due` is kept when it is `today`
+    pub(crate) fn keeps(&self, due: NaiveDate, today: NaiveDate) -> bool {
+        match self {
+            Due::Before(date) => due < *date,
+            Due::Overdue => due < today,
+            Due::Today => due == today,
+        }
+    }
+}
+
+/// Parts of Todo lists which `--fields` selects
+const FIELDS: [&str; 4] = ["title", "description", "tasks", "motives"];
+
 /// Returns Todo list command
 pub fn list_command() -> App<'static, 'static> {
     App::new("list")
+        .visible_alias("ls")
         .about("List all todo list within Todo context with tasks remaining")
         .author(crate_authors!())
         .arg(
@@ -56,23 +152,57 @@ pub fn list_command() -> App<'static, 'static> {
                 .value_delimiter(",")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("label-add")
+                .long("label-add")
+                .value_name("LABEL")
+                .help("Adds label to every todo list shown")
+                .value_delimiter(",")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("label-remove")
+                .long("label-remove")
+                .value_name("LABEL")
+                .help("Removes label from every todo list shown")
+                .value_delimiter(",")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("short")
                 .short("s")
                 .long("short")
                 .help("Displays one line summary"),
         )
+        .arg(
+            Arg::with_name("lists")
+                .long("lists")
+                .value_name("open|done|all")
+                .possible_values(&["open", "done", "all"])
+                .takes_value(true)
+                .conflicts_with_all(&["all", "done"])
+                .help("Shows Todo lists with open tasks (default), fully completed Todo lists or all of them"),
+        )
+        .arg(
+            Arg::with_name("tasks")
+                .long("tasks")
+                .value_name("open|done|all")
+                .possible_values(&["open", "done", "all"])
+                .takes_value(true)
+                .conflicts_with_all(&["open-tasks", "completed-tasks"])
+                .help("Shows only open, done or all tasks of the Todo lists instead of the entire Todo lists"),
+        )
         .arg(
             Arg::with_name("all")
                 .short("a")
                 .long("all")
-                .help("Shows all Todo lists"),
+                .help("Shows all Todo lists (alias for --lists all)"),
         )
         .arg(
             Arg::with_name("done")
                 .short("d")
                 .long("done")
-                .help("Shows only fully completed task lists"),
+                .help("Shows only fully completed task lists (alias for --lists done)"),
         )
         .arg(
             Arg::with_name("global")
@@ -80,19 +210,29 @@ pub fn list_command() -> App<'static, 'static> {
                 .long("global")
                 .help("Lists Todo lists from all contexts"),
         )
+        .arg(
+            Arg::with_name("fail-if-open")
+                .long("fail-if-open")
+                .help("Reports open tasks of matching lists and exits with code 3 if there are any")
+                .conflicts_with_all(&["done", "sections-only"]),
+        )
+        .arg(
+            Arg::with_name("everywhere")
+                .long("everywhere")
+                .help("Lists Todo lists from the project workspace and the configured active context")
+                .conflicts_with("global"),
+        )
         .arg(
             Arg::with_name("open-tasks")
                 .short("o")
                 .long("open")
-                .help("Shows only open tasks in the lists (default shows the entire task list)"),
+                .help("Shows only open tasks in the lists (alias for --tasks open)"),
         )
         .arg(
             Arg::with_name("completed-tasks")
                 .short("c")
                 .long("completed-tasks")
-                .help(
-                    "Shows only completed tasks in the lists (default shows the entire task list)",
-                ),
+                .help("Shows only completed tasks in the lists (alias for --tasks done)"),
         )
         .arg(
             Arg::with_name("sections")
@@ -100,45 +240,250 @@ pub fn list_command() -> App<'static, 'static> {
                 .multiple(true)
                 .number_of_values(1)
                 .takes_value(true)
-                .help("Shows specified section of task list"),
+                .help("Shows specified section of task list ('*' shows every section)"),
+        )
+        .arg(
+            Arg::with_name("sections-only")
+                .long("sections-only")
+                .conflicts_with("sections")
+                .help("Shows only the section names of task lists with their number of done tasks"),
+        )
+        .arg(
+            Arg::with_name("hidden")
+                .long("hidden")
+                .help("Shows hidden Todo lists too (see `todo hide`)"),
+        )
+        .arg(width_arg())
+        .args(&motive_args())
+        .arg(
+            Arg::with_name("notes-too")
+                .long("notes-too")
+                .help("Shows notes with a title too (markdown files without LABEL= below ## Description), skipped by default"),
+        )
+        .arg(
+            Arg::with_name("fields")
+                .long("fields")
+                .value_name("FIELD")
+                .possible_values(&FIELDS)
+                .value_delimiter(",")
+                .conflicts_with_all(&["short", "sections", "sections-only", "tasks", "open-tasks", "completed-tasks"])
+                .help("Shows only these parts of the Todo lists, in the order of the Todo list")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("due-before")
+                .long("due-before")
+                .value_name("DATE")
+                .help("Shows only open tasks due (@due(YYYY-MM-DD)) before DATE (YYYY-MM-DD)")
+                .validator(|date| {
+                    NaiveDate::parse_from_str(date.as_str(), "%Y-%m-%d")
+                        .map(|_| ())
+                        .map_err(|_| format!("\"{date}\" is not a date (YYYY-MM-DD)"))
+                })
+                .takes_value(true)
+                .conflicts_with_all(&DUE_CONFLICTS),
+        )
+        .arg(
+            Arg::with_name("overdue")
+                .long("overdue")
+                .help("Shows only open tasks due before today, in the timezone of the context")
+                .conflicts_with("due-before")
+                .conflicts_with_all(&DUE_CONFLICTS),
+        )
+        .arg(
+            Arg::with_name("due-today")
+                .long("due-today")
+                .help("Shows only open tasks due today, in the timezone of the context")
+                .conflicts_with_all(&["due-before", "overdue"])
+                .conflicts_with_all(&DUE_CONFLICTS),
+        )
+        .arg(
+            Arg::with_name("priority")
+                .long("priority")
+                .value_name("PRIORITY")
+                .help("Shows only open tasks of PRIORITY, a letter from A (highest) to Z: * [ ] (A) call dentist")
+                .validator(|priority| match priority.as_str() {
+                    p if p.len() == 1 && p.chars().all(|c| c.is_ascii_alphabetic()) => Ok(()),
+                    _ => Err(format!("\"{priority}\" is not a priority (A to Z)")),
+                })
+                .takes_value(true)
+                .conflicts_with_all(&["due-before", "overdue", "due-today"])
+                .conflicts_with_all(&DUE_CONFLICTS),
+        )
+        .arg(
+            Arg::with_name("show-archive")
+                .long("show-archive")
+                .help("Shows archived tasks (## Archive) instead of their number"),
+        )
+        .arg(
+            Arg::with_name("archived")
+                .long("archived")
+                .help("Lists archived todo lists (see `todo archive`) instead of the others"),
+        )
+        .arg(
+            Arg::with_name("sort")
+                .long("sort")
+                .value_name("title|open|priority")
+                .possible_values(&["title", "open", "priority"])
+                .help("Lists Todo lists by title, most open tasks first or highest priority open tasks first, after pinned and manually ordered ones (see `todo pin` and `todo order`)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stale")
+                .long("stale")
+                .value_name("AGE")
+                .help("Shows only open tasks first seen at least AGE ago (90d, 12w), one per line")
+                .takes_value(true)
+                .conflicts_with_all(&["fail-if-open", "sections-only", "done"]),
         )
         .arg(
             Arg::with_name("task-lists")
                 .short("t")
-                .long("task-lists")
-                .help("Show only specified task lists.")
+                .long("title")
+                .alias("task-lists")
+                .value_name("TITLE")
+                .help("Show only this task list, repeat for more (-t \"Weekly review\" -t groceries). Accepts glob (sprint-*) or /regex/ patterns matched against titles.")
                 .takes_value(true)
                 .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("titles")
+                .value_name("TITLE")
+                .help("Show only these task lists, like -t. Quote titles with spaces. Titles naming a filter, a label or a Todo context must match a title.")
+                .multiple(true)
                 .index(1),
         )
 }
 
 /// Lists Todo lists from Todo context while filtering by label and whether or not the task list is
 /// completed
+///
+/// Warns about soft limits exceeded by listed Todo contexts. Returns the number of Todo lists
+/// printed.
 pub fn list_command_process(
     args: &ArgMatches,
     config: &Configuration,
-) -> Result<(), std::io::Error> {
-    let parameters = Parameters {
-        all: args.is_present("all"),
-        completed: args.is_present("completed-tasks"),
+) -> Result<usize, std::io::Error> {
+    let parameters = parameters(args, config);
+    let count = list_message(&mut std::io::stdout(), &parameters)?;
+    for ctx in config.ctxs.iter().filter(|ctx| is_listed(ctx, &parameters)) {
+        warn_exceeded(ctx)?;
+    }
+    Ok(count)
+}
+
+/// Lists Todo lists already read into `stdout`, like `list_command_process` does. `entries` holds
+/// the raw Todo lists of each Todo context of the configuration. Labels are not stamped.
+///
+/// Returns the number of Todo lists printed.
+pub fn list_entries_process(
+    args: &ArgMatches,
+    config: &Configuration,
+    entries: Vec<Vec<&str>>,
+    stdout: &mut dyn std::io::Write,
+) -> Result<usize, std::io::Error> {
+    let mut parameters = parameters(args, config);
+    parameters.entries = Some(entries);
+    list_message(stdout, &parameters)
+}
+
+/// Returns list parameters from list arguments
+fn parameters<'a>(args: &'a ArgMatches, config: &Configuration) -> Parameters<'a> {
+    let (all, done, completed, open) = filters(args);
+    if done && !all && open && !completed {
+        eprintln!("Warning: fully completed Todo lists have no open tasks to show");
+    }
+
+    Parameters {
+        all,
+        completed,
         config: config.to_owned(),
-        done: args.is_present("done"),
+        done,
         entries: None,
         global: args.is_present("global"),
+        everywhere: args.is_present("everywhere"),
+        fail_if_open: args.is_present("fail-if-open"),
         labels: args
             .values_of("label")
             .unwrap_or_default()
             .collect::<Vec<_>>(),
-        open: args.is_present("open-tasks"),
+        label_add: args
+            .values_of("label-add")
+            .unwrap_or_default()
+            .collect::<Vec<_>>(),
+        label_remove: args
+            .values_of("label-remove")
+            .unwrap_or_default()
+            .collect::<Vec<_>>(),
+        open,
         short: args.is_present("short"),
-        task_lists: args
-            .values_of("task-lists")
-            .map(|ss| ss.collect::<Vec<_>>()),
+        task_lists: title_filters(args),
+        positional_titles: args.values_of("titles").unwrap_or_default().collect(),
         sections: args.values_of("sections").map(|ss| ss.collect::<Vec<_>>()),
-    };
+        sections_only: args.is_present("sections-only"),
+        stale: args.value_of("stale"),
+        color: std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        sort: args.value_of("sort"),
+        hidden: args.is_present("hidden"),
+        notes_too: args.is_present("notes-too"),
+        with_motives: args.is_present("with-motives") || args.is_present("motive-contains"),
+        motive_contains: args.value_of("motive-contains"),
+        width: output_width(args),
+        fields: args.values_of("fields").map(|fs| fs.collect::<Vec<_>>()),
+        show_archive: args.is_present("show-archive"),
+        archived: args.is_present("archived"),
+        due: due(args),
+        priority: args
+            .value_of("priority")
+            .and_then(|p| p.chars().next())
+            .map(|p| p.to_ascii_uppercase()),
+    }
+}
+
+/// Returns the due date filter of `--due-before`, `--overdue` and `--due-today`
+fn due(args: &ArgMatches) -> Option<Due> {
+    if let Some(date) = args.value_of("due-before") {
+        return NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()
+            .map(Due::Before);
+    }
+    if args.is_present("overdue") {
+        return Some(Due::Overdue);
+    }
+    args.is_present("due-today").then_some(Due::Today)
+}
+
+/// Returns title patterns of `-t` occurrences and positional titles, each being one title
+fn title_filters<'a>(args: &'a ArgMatches) -> Option<Vec<&'a str>> {
+    let titles = ["task-lists", "titles"]
+        .iter()
+        .flat_map(|name| args.values_of(name).unwrap_or_default())
+        .collect::<Vec<_>>();
+    (!titles.is_empty()).then_some(titles)
+}
 
-    list_message(&mut std::io::stdout(), &parameters)
+/// Returns `all`, `done`, `completed` and `open` filters of `todo list` command
+///
+/// `--lists` and `--tasks` are the explicit forms of the older `--all`, `--done`,
+/// `--completed-tasks` and `--open` flags.
+fn filters(args: &ArgMatches) -> (bool, bool, bool, bool) {
+    let (all, done) = match args.value_of("lists") {
+        Some("open") => (false, false),
+        Some("done") => (false, true),
+        Some("all") => (true, false),
+        _ => (args.is_present("all"), args.is_present("done")),
+    };
+    let (completed, open) = match args.value_of("tasks") {
+        Some("open") => (false, true),
+        Some("done") => (true, false),
+        Some("all") => (true, true),
+        _ => (
+            args.is_present("completed-tasks"),
+            args.is_present("open-tasks"),
+        ),
+    };
+    (all, done, completed, open)
 }
 
 /// Returns message when `todo list` command is invoked
@@ -151,17 +496,20 @@ pub fn list_command_process(
 /// * `all` - do not filter out any Todo lists within context
 /// * `done` - filter Todo lists with all tasks done
 /// * `global` - disable filtering by Todo context
+/// * `everywhere` - show the workspace and the configured active Todo context, naming the context
+///   of each folder
 /// * `entries` - when provided, don't use Todo list file entries at Todo context folder location
 /// * `task_lists` - when provided, show only specified task lists
-fn list_message(stdout: &mut dyn std::io::Write, p: &Parameters) -> Result<(), std::io::Error> {
+///
+/// Returns the number of Todo lists printed.
+fn list_message(stdout: &mut dyn std::io::Write, p: &Parameters) -> Result<usize, std::io::Error> {
     if !p.config.is_valid() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Bad configuration file",
-        ));
+        return Err(std::io::Error::other("Bad configuration file"));
     }
 
-    let task_lists = p.task_lists.clone().unwrap_or_default();
+    let mut task_lists = TitleFilter::new(&p.task_lists.clone().unwrap_or_default())?;
+    let stale = p.stale.map(parse_age).transpose()?;
+    let mut matches = 0;
 
     if p.entries.is_some() {
         let mut entries = p.entries.clone().unwrap();
@@ -176,75 +524,344 @@ fn list_message(stdout: &mut dyn std::io::Write, p: &Parameters) -> Result<(), s
 
         for ctx in p.config.ctxs.clone() {
             let directory = entries.pop().unwrap();
-            if !&p.global && ctx.name != p.config.active_ctx_name {
+            if !is_listed(&ctx, p) {
                 continue;
             }
 
-            print_todo_folder_location(stdout, &ctx)?;
+            if !p.fail_if_open {
+                print_todo_folder_location(stdout, &ctx, p.everywhere)?;
+            }
             debug!("directory: {}\n- files:\n{:?}", ctx.name, directory);
-            for todo_raw in directory {
-                let todo_list = parse_todo_list(todo_raw).unwrap();
-                if task_lists.is_empty() || task_lists.contains(&todo_list.title.as_str()) {
-                    print_todo(stdout, todo_raw, p)?;
+            let ages = task_ages(&ctx, stale)?;
+            let directory = directory
+                .into_iter()
+                .filter(|todo_raw| listed(todo_raw, p).is_some())
+                .collect::<Vec<_>>();
+            for i in listing_order(&directory, &ctx, p.sort) {
+                let todo_raw = directory[i];
+                let todo_list = listed(todo_raw, p).unwrap();
+                if task_lists.keeps(&todo_list)
+                    && print_todo(stdout, todo_raw, p, ages.as_ref(), ctx.today())?
+                {
+                    matches += 1;
                 }
             }
         }
 
-        return Ok(());
+        task_lists.check_positional_titles(p)?;
+        return Ok(matches);
     }
 
+    let mut failed = vec![];
     for ctx in &p.config.ctxs {
-        if !p.global && ctx.name != p.config.active_ctx_name {
+        if !is_listed(ctx, p) {
             continue;
         }
 
-        print_todo_folder_location(stdout, ctx)?;
+        if !p.fail_if_open {
+            print_todo_folder_location(stdout, ctx, p.everywhere)?;
+        }
 
-        for entry in WalkDir::new(ctx.folder_location.as_str()) {
-            let entry = match entry {
-                Ok(e) => e,
-                Err(e) => {
-                    eprintln!("{}", e);
-                    return Err(std::io::Error::new(std::io::ErrorKind::Other, e));
-                }
+        match list_context(stdout, ctx, p, &mut task_lists, stale) {
+            Ok(count) => matches += count,
+            // one broken Todo context does not hide the Todo lists of the others
+            Err(e) if p.global || p.everywhere => {
+                eprintln!(
+                    "Warning: Todo lists of {} could not be listed: {e}",
+                    ctx.name
+                );
+                failed.push(ctx.name.as_str());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    task_lists.check_positional_titles(p)?;
+    if !failed.is_empty() {
+        return Err(std::io::Error::other(format!(
+            "{} Todo context(s) could not be listed: {}",
+            failed.len(),
+            failed.join(", ")
+        )));
+    }
+    Ok(matches)
+}
+
+/// Prints Todo lists of the Todo context and returns how many were printed
+fn list_context(
+    stdout: &mut dyn std::io::Write,
+    ctx: &Context,
+    p: &Parameters,
+    task_lists: &mut TitleFilter,
+    stale: Option<u64>,
+) -> Result<usize, std::io::Error> {
+    let mut matches = 0;
+    let ages = task_ages(ctx, stale)?;
+    if ctx.is_single_file() {
+        if p.archived {
+            // Todo lists inside a single file cannot be archived
+            return Ok(0);
+        }
+        let mut single_file = SingleFile::read(ctx)?;
+        let mut stamped = false;
+        let todo_raws = single_file
+            .todo_lists
+            .iter()
+            .map(|t| t.as_str())
+            .collect::<Vec<_>>();
+        for i in listing_order(&todo_raws, ctx, p.sort) {
+            let todo_raw = &mut single_file.todo_lists[i];
+            let todo_list = match listed(todo_raw.as_str(), p) {
+                Some(todo_list) => todo_list,
+                None => continue,
             };
-            if !entry.file_type().is_file() {
-                // first entry is the todo folder which should be skipped
-                continue;
+            if task_lists.keeps(&todo_list)
+                && print_todo(stdout, todo_raw.as_str(), p, ages.as_ref(), ctx.today())?
+            {
+                matches += 1;
+                let path = single_file.path.as_str();
+                if let Some(with_labels) = stamped_labels(path, todo_raw, &todo_list.labels, p) {
+                    *todo_raw = with_labels;
+                    stamped = true;
+                }
             }
-            let filepath = entry.path().to_str().unwrap();
-            let extension = Path::new(&filepath).extension().unwrap().to_str().unwrap();
-            // avoid coercing .jpg files into Todo list
-            if !is_valid_extension(extension) {
-                continue;
+        }
+        if stamped {
+            single_file.write()?;
+        }
+        if let Some(ages) = ages {
+            ages.record(ctx, single_file.todo_lists.iter().map(|t| t.as_str()))?;
+        }
+        return Ok(matches);
+    }
+
+    let archive_folder = archive_folder(ctx);
+    let folder = if p.archived {
+        if !archive_folder.is_dir() {
+            return Ok(0);
+        }
+        archive_folder.clone()
+    } else {
+        std::path::PathBuf::from(ctx.folder_location.as_str())
+    };
+    let mut todo_lists = vec![];
+    for entry in WalkDir::new(folder)
+        .into_iter()
+        .filter_entry(|e| p.archived || e.path() != archive_folder)
+    {
+        let entry = entry.map_err(std::io::Error::other)?;
+        if !entry.file_type().is_file() {
+            // first entry is the todo folder which should be skipped
+            continue;
+        }
+        // avoid coercing .jpg files into Todo list
+        if !ctx.is_todo_list_file(entry.path()) {
+            continue;
+        }
+        // parts are listed with the Todo list they belong to
+        if is_part(entry.path()) {
+            continue;
+        }
+        let filepath = entry.path().to_str().unwrap();
+        let todo_raw = Parts::read(filepath)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("Cannot open {filepath}: {e}")))?
+            .joined();
+        if listed(todo_raw.as_str(), p).is_none() {
+            debug!("skipped {filepath}: not a Todo list");
+            continue;
+        }
+        todo_lists.push((filepath.to_string(), todo_raw));
+    }
+
+    // NOTE: one could form directly the path to the file and directly
+    // check if it exists or not to avoid iterating through all the
+    // files in the context.
+    let todo_raws = todo_lists
+        .iter()
+        .map(|(_, todo_raw)| todo_raw.as_str())
+        .collect::<Vec<_>>();
+    for i in listing_order(&todo_raws, ctx, p.sort) {
+        let (filepath, todo_raw) = &todo_lists[i];
+        let todo_list = listed(todo_raw.as_str(), p).unwrap();
+        if task_lists.keeps(&todo_list)
+            && print_todo(stdout, todo_raw.as_str(), p, ages.as_ref(), ctx.today())?
+        {
+            matches += 1;
+            stamp_labels(filepath, todo_raw.as_str(), &todo_list.labels, p)?;
+        }
+    }
+
+    // archived Todo lists are not all Todo lists of the Todo context, whose ages would be lost
+    if let Some(ages) = ages.filter(|_| !p.archived) {
+        ages.record(
+            ctx,
+            todo_lists.iter().map(|(_, todo_raw)| todo_raw.as_str()),
+        )?;
+    }
+    for (title, paths) in duplicate_titles(&todo_lists) {
+        eprintln!(
+            "Warning: many Todo lists are titled \"{title}\": {}",
+            paths.join(", ")
+        );
+    }
+    Ok(matches)
+}
+
+/// Returns ages of open tasks of the Todo context when they are tracked
+fn task_ages(ctx: &Context, stale: Option<u64>) -> Result<Option<TaskAges>, std::io::Error> {
+    if !tracks_ages(ctx, stale.is_some()) {
+        return Ok(None);
+    }
+    let mut ages = TaskAges::new(FirstSeen::read(ctx)?, chrono::Local::today().naive_local());
+    ages.flag_after = ctx.stale_after_days;
+    ages.only_older_than = stale;
+    Ok(Some(ages))
+}
+
+/// Title patterns of a list invocation with what they matched
+struct TitleFilter {
+    patterns: Vec<Regex>,
+    /// True for each pattern which matched the title of a Todo list
+    matched: Vec<bool>,
+    /// Labels of every Todo list seen
+    labels: BTreeSet<String>,
+}
+
+impl TitleFilter {
+    fn new(patterns: &[&str]) -> Result<TitleFilter, std::io::Error> {
+        let patterns = title_patterns(patterns)?;
+        Ok(TitleFilter {
+            matched: vec![false; patterns.len()],
+            patterns,
+            labels: BTreeSet::new(),
+        })
+    }
+
+    /// Returns true if the title of the Todo list is kept. Without patterns, every title is kept.
+    fn keeps(&mut self, todo_list: &ParsedTodoList) -> bool {
+        self.labels.extend(todo_list.labels.iter().cloned());
+        let mut keeps = self.patterns.is_empty();
+        for (re, matched) in self.patterns.iter().zip(self.matched.iter_mut()) {
+            if re.is_match(&todo_list.title) {
+                *matched = true;
+                keeps = true;
             }
-            let todo_raw = match read_to_string(filepath) {
-                Ok(content) => content,
-                Err(error) => panic!(
-                    "Cannot open {}, error: {}",
-                    entry.path().to_str().unwrap(),
-                    error
-                ),
-            };
+        }
+        keeps
+    }
 
-            // NOTE: one could form directly the path to the file and directly
-            // check if it exists or not to avoid iterating through all the
-            // files in the context.
-            let todo_list = parse_todo_list(todo_raw.as_str()).unwrap();
-            if task_lists.is_empty() || task_lists.contains(&todo_list.title.as_str()) {
-                print_todo(stdout, todo_raw.as_str(), p)?;
+    /// Returns an error for positional titles which matched no Todo list but name a filter, a label
+    /// or a Todo context, since the user most likely meant the option
+    fn check_positional_titles(&self, p: &Parameters) -> Result<(), std::io::Error> {
+        let titles = p.task_lists.clone().unwrap_or_default();
+        let mut hints = vec![];
+        for (title, matched) in titles.iter().zip(self.matched.iter()) {
+            if *matched || !p.positional_titles.contains(title) {
+                continue;
             }
+            let hint = match *title {
+                "open" => "--open".to_string(),
+                "done" | "completed" => "--done".to_string(),
+                "all" => "--all".to_string(),
+                "short" => "--short".to_string(),
+                "global" => "--global".to_string(),
+                "everywhere" => "--everywhere".to_string(),
+                label if self.labels.contains(label) => format!("--label {label}"),
+                name if p.config.ctxs.iter().any(|ctx| ctx.name == name) => {
+                    format!("--global or `todo config set-context {name}`")
+                }
+                _ => continue,
+            };
+            hints.push(format!(
+                "\"{title}\" is not the title of any Todo list, did you mean {hint}? Use -t \"{title}\" for a title."
+            ));
+        }
+        if hints.is_empty() {
+            return Ok(());
         }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            hints.join("\n"),
+        ))
     }
+}
 
+/// Adds and removes labels of `--label-add` and `--label-remove` to the Todo list at `filepath`.
+/// Locked Todo lists are left untouched.
+fn stamp_labels(
+    filepath: &str,
+    todo_raw: &str,
+    labels: &[String],
+    p: &Parameters,
+) -> Result<(), std::io::Error> {
+    if let Some(with_labels) = stamped_labels(filepath, todo_raw, labels, p) {
+        let mut parts = Parts::read(filepath)?;
+        parts.set(with_labels.as_str())?;
+        parts.write()?;
+    }
     Ok(())
 }
 
-/// Returns true if the file is markdown or in txt format
-fn is_valid_extension(ext: &str) -> bool {
-    let valid_extensions: Vec<&str> = vec!["md", "txt"];
+/// Returns Todo list with labels of `--label-add` and `--label-remove` added and removed, or None
+/// when labels do not change or the Todo list is locked
+fn stamped_labels(
+    filepath: &str,
+    todo_raw: &str,
+    labels: &[String],
+    p: &Parameters,
+) -> Option<String> {
+    if p.label_add.is_empty() && p.label_remove.is_empty() {
+        return None;
+    }
+    let mut stamped = labels
+        .iter()
+        .filter(|l| !p.label_remove.contains(&l.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    for label in &p.label_add {
+        if !stamped.iter().any(|l| l == label) {
+            stamped.push(label.to_string());
+        }
+    }
+    if stamped == labels {
+        return None;
+    }
+    if is_locked(todo_raw) {
+        eprintln!("Warning: labels of locked todo list \"{filepath}\" were not changed");
+        return None;
+    }
+    Some(set_todo_list_labels(todo_raw, &stamped))
+}
 
-    valid_extensions.contains(&ext)
+/// Returns regexes matching Todo list titles from `--task-lists` patterns
+///
+/// A pattern surrounded by slashes (`/^sprint-\d+$/`) is a regex. Any other pattern is a glob where
+/// `*` matches any number of characters and `?` matches one character. A pattern without any
+/// wildcard matches the exact title. Spaces inside globs match any run of whitespace.
+fn title_patterns(patterns: &[&str]) -> Result<Vec<Regex>, std::io::Error> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let re = match pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+                Some(re) => re.to_string(),
+                None => {
+                    let re = regex::escape(pattern.trim())
+                        .replace("\\*", ".*")
+                        .replace("\\?", ".");
+                    format!(
+                        "^{}$",
+                        re.split_whitespace().collect::<Vec<_>>().join(r"\s+")
+                    )
+                }
+            };
+            Regex::new(re.as_str()).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Invalid task list pattern \"{pattern}\": {e}"),
+                )
+            })
+        })
+        .collect()
 }
 
 /// Prints folder location from which Todo lists are being parsed
@@ -255,10 +872,28 @@ fn is_valid_extension(ext: &str) -> bool {
 fn print_todo_folder_location(
     stdout: &mut dyn std::io::Write,
     ctx: &Context,
+    with_ctx_name: bool,
 ) -> Result<(), std::io::Error> {
+    if let Some(banner) = ctx.banner() {
+        writeln!(stdout, "{banner}")?;
+    }
+    if with_ctx_name {
+        return writeln!(
+            stdout,
+            "Todo lists from {} ({})",
+            ctx.folder_location, ctx.name
+        );
+    }
     writeln!(stdout, "Todo lists from {}", ctx.folder_location)
 }
 
+/// Returns true if Todo lists of Todo context are listed
+fn is_listed(ctx: &Context, p: &Parameters) -> bool {
+    p.global
+        || ctx.name == p.config.active_ctx_name
+        || (p.everywhere && p.config.configured_active_ctx_name.as_ref() == Some(&ctx.name))
+}
+
 /// Prints out a Todo list. By default, only Todo lists with open tasks will be
 /// printed out.
 ///
@@ -268,26 +903,167 @@ fn print_todo_folder_location(
 /// * `all` - Condition to print Todo list even when it has no open tasks
 /// * `done` - Condition to print only Todo list with no open tasks
 /// * `short` - Print a short summary of Todo list which indicates the number of
-/// task done and the total number of tasks in the list
+///   task done and the total number of tasks in the list
 /// * `completed` - Print the summary of the completed tasks in the list
 /// * `open` - Print the summary of the open tasks in the list
+/// * `fail_if_open` - Print only open tasks, one per line after the title of the Todo list
+/// * `ages` - Ages of open tasks of the Todo context, when tracked, to flag old tasks or to print
+///   only those (`stale`)
+/// * `today` - Today in the timezone of the Todo context, to print only overdue tasks or tasks due
+///   today (`due`)
+///
+/// Returns true if the Todo list was printed.
 fn print_todo(
     stdout: &mut dyn std::io::Write,
     todo_raw: &str,
     p: &Parameters,
-) -> Result<(), std::io::Error> {
-    let todo_list = parse_todo_list(todo_raw).unwrap();
-    if p.labels
+    ages: Option<&TaskAges>,
+    today: NaiveDate,
+) -> Result<bool, std::io::Error> {
+    if is_hidden(todo_raw) && !p.hidden {
+        return Ok(false);
+    }
+    if p.with_motives && !has_motive(todo_raw, p.motive_contains) {
+        return Ok(false);
+    }
+    let todo_list = match listed(todo_raw, p) {
+        Some(todo_list) => todo_list,
+        None => return Ok(false),
+    };
+    let has_labels = p
+        .labels
         .iter()
-        .all(|l| todo_list.labels.iter().any(|fl| fl == l))
-    {
+        .all(|l| todo_list.labels.iter().any(|fl| fl == l));
+    if has_labels {
         let is_done = todo_list.tasks_are_all_done();
         // so XOR is a thing: https://doc.rust-lang.org/reference/types/boolean.html#logical-xor
         if !p.all && (is_done ^ p.done) {
-            return Ok(());
+            return Ok(false);
+        }
+
+        let sections = match &p.sections {
+            Some(sections) if sections.contains(&"*") => parse_todo_list_sections(todo_raw),
+            Some(sections) => {
+                let sections = resolve_sections(&todo_list.title, todo_raw, sections);
+                if sections.is_empty() {
+                    return Ok(false);
+                }
+                sections
+            }
+            None => vec![],
+        };
+        let in_sections = parse_sections(todo_raw)
+            .into_iter()
+            .filter(|s| sections.contains(&s.name))
+            .collect::<Vec<_>>();
+
+        if p.fail_if_open {
+            let open_tasks = parse_tasks(todo_raw)
+                .into_iter()
+                .filter(|t| !t.done)
+                .filter(|t| sections.is_empty() || in_sections.iter().any(|s| s.contains(t)))
+                .collect::<Vec<_>>();
+            for task in &open_tasks {
+                match &task.section {
+                    Some(section) => {
+                        writeln!(stdout, "{} > {section}: {}", todo_list.title, task.text)?
+                    }
+                    None => writeln!(stdout, "{}: {}", todo_list.title, task.text)?,
+                }
+            }
+            return Ok(!open_tasks.is_empty());
+        }
+
+        if p.stale.is_some() {
+            let ages = match ages {
+                Some(ages) => ages,
+                None => return Ok(false),
+            };
+            let min_age = ages.only_older_than.unwrap_or(0);
+            let stale_tasks = parse_tasks(todo_raw)
+                .into_iter()
+                .filter(|t| !t.done)
+                .filter(|t| sections.is_empty() || in_sections.iter().any(|s| s.contains(t)))
+                .filter_map(|t| {
+                    let age = ages.age(&todo_list.title, &t.text)?;
+                    (age >= min_age).then_some((t, age))
+                })
+                .collect::<Vec<_>>();
+            for (task, age) in &stale_tasks {
+                match &task.section {
+                    Some(section) => writeln!(
+                        stdout,
+                        "{} > {section}: {} ({age} days old)",
+                        todo_list.title, task.text
+                    )?,
+                    None => writeln!(
+                        stdout,
+                        "{}: {} ({age} days old)",
+                        todo_list.title, task.text
+                    )?,
+                }
+            }
+            return Ok(!stale_tasks.is_empty());
+        }
+
+        let flag = |text: &str| {
+            let text = match ages {
+                Some(ages) => ages.flag(&todo_list.title, text, p.color),
+                None => text.to_string(),
+            };
+            match p.width {
+                Some(width) => wrap_tasks(text.as_str(), width),
+                None => text,
+            }
+        };
+
+        if let Some(due) = p.due {
+            let tasks = parse_todo_list_due_tasks(todo_raw, p.short, |date| due.keeps(date, today));
+            if tasks.is_empty() {
+                return Ok(false);
+            }
+            writeln!(stdout, "# {}", todo_list.title)?;
+            for task in tasks {
+                writeln!(stdout, "{}", flag(task.as_str()))?;
+            }
+            return Ok(true);
         }
 
-        let sections = p.sections.clone().unwrap_or_default();
+        if let Some(priority) = p.priority {
+            let tasks = parse_todo_list_tasks(todo_raw, false, true, p.short, None)
+                .unwrap()
+                .into_iter()
+                .filter(|task| task.get(6..).and_then(parse_priority) == Some(priority))
+                .collect::<Vec<_>>();
+            if tasks.is_empty() {
+                return Ok(false);
+            }
+            writeln!(stdout, "# {}", todo_list.title)?;
+            for task in tasks {
+                writeln!(stdout, "{}", flag(task.as_str()))?;
+            }
+            return Ok(true);
+        }
+
+        if p.sections_only {
+            writeln!(stdout, "# {}", todo_list.title)?;
+            for section in parse_sections(todo_raw) {
+                let indent = "  ".repeat(section.depth - 3);
+                match direct_tally(&section) {
+                    Some(direct) => writeln!(
+                        stdout,
+                        "{}/{}\t{indent}- {} ({direct})",
+                        section.done, section.total, section.name
+                    )?,
+                    None => writeln!(
+                        stdout,
+                        "{}/{}\t{indent}- {}",
+                        section.done, section.total, section.name
+                    )?,
+                }
+            }
+            return Ok(true);
+        }
 
         if p.completed || p.open {
             writeln!(stdout, "# {}", todo_list.title)?;
@@ -295,61 +1071,209 @@ fn print_todo(
                 let tasks =
                     parse_todo_list_tasks(todo_raw, p.completed, p.open, p.short, None).unwrap();
                 for task in tasks {
-                    // trim_end avoid cluttering the output with all whitespace the
-                    // user might have used to make his Todo list more readable or
-                    // the accidental trailing spaces he might have left
-                    writeln!(stdout, "{}", task.as_str().trim_end())?;
+                    writeln!(stdout, "{}", flag(task.as_str()))?;
                 }
             } else if !sections.is_empty() {
-                for section in sections {
-                    writeln!(stdout, "\n## {section}\n")?;
+                for section in outermost(&in_sections) {
+                    writeln!(stdout, "\n## {}\n", section.name)?;
                     let tasks = parse_todo_list_tasks(
                         todo_raw,
                         p.completed,
                         p.open,
                         p.short,
-                        Some(section),
+                        Some(section.name.as_str()),
                     )
                     .unwrap();
                     for task in tasks {
-                        // trim_end avoid cluttering the output with all whitespace the
-                        // user might have used to make his Todo list more readable or
-                        // the accidental trailing spaces he might have left
-                        writeln!(stdout, "{}", task.as_str().trim_end())?;
+                        writeln!(stdout, "{}", flag(task.as_str()))?;
                     }
                 }
             }
         } else if sections.is_empty() {
             if p.short {
+                let high_priority = parse_tasks(todo_raw)
+                    .iter()
+                    .filter(|t| !t.done && t.priority() == Some(HIGH_PRIORITY))
+                    .count();
+                let high_priority = match high_priority {
+                    0 => String::new(),
+                    n => format!(" ({n} high priority)"),
+                };
                 writeln!(
                     stdout,
-                    "{}/{}\t- {}",
+                    "{}/{}\t- {}{high_priority}",
                     todo_list.done, todo_list.total, todo_list.title
                 )?;
+                if p.with_motives {
+                    for motive in parse_todo_list_motives(todo_raw) {
+                        writeln!(stdout, "\t  because {motive}")?;
+                    }
+                }
+            } else if let Some(fields) = &p.fields {
+                let parts = parse_todo_list_parts(todo_raw);
+                let parts = [
+                    ("title", format!("# {}", parts.title)),
+                    ("description", part("Description", &parts.description)),
+                    ("tasks", part("Todo list", &flag(&parts.tasks))),
+                    ("motives", part("Motives", &parts.motives)),
+                ];
+                let shown = parts
+                    .iter()
+                    .filter(|(field, content)| fields.contains(field) && !content.is_empty())
+                    .map(|(_, content)| content.as_str())
+                    .collect::<Vec<_>>();
+                writeln!(stdout, "{}\n", shown.join("\n\n"))?;
+            } else if p.show_archive {
+                writeln!(stdout, "{}", flag(todo_raw))?;
             } else {
-                writeln!(stdout, "{}", todo_raw)?;
+                writeln!(stdout, "{}", flag(&collapse_archive(todo_raw)))?;
+            }
+        } else if p.short {
+            for section in &in_sections {
+                let direct = direct_tally(section)
+                    .map(|direct| format!(", {direct}"))
+                    .unwrap_or_default();
+                writeln!(
+                    stdout,
+                    "{}/{}\t- {} ({}{direct})",
+                    section.done, section.total, todo_list.title, section.name
+                )?;
             }
         } else {
-            for section in sections {
-                let todo_list_section = parse_todo_list_section(&todo_list, section).unwrap();
-                if p.short {
-                    writeln!(
-                        stdout,
-                        "{}/{}\t- {} ({section})",
-                        todo_list_section.done, todo_list_section.total, todo_list_section.title
-                    )?;
-                } else {
-                    writeln!(stdout, "{}", todo_raw)?;
+            writeln!(stdout, "# {}", todo_list.title)?;
+            for section in outermost(&in_sections) {
+                writeln!(
+                    stdout,
+                    "\n## {} ({}/{})\n",
+                    section.name, section.done, section.total
+                )?;
+                let tasks =
+                    parse_todo_list_tasks(todo_raw, true, true, false, Some(section.name.as_str()))
+                        .unwrap();
+                for task in tasks {
+                    writeln!(stdout, "{}", flag(task.as_str()))?;
                 }
             }
         }
     }
-    Ok(())
+    Ok(has_labels)
+}
+
+/// Returns Todo list where archived tasks are replaced by their number
+fn collapse_archive(todo_raw: &str) -> String {
+    let (heading, last_line) = match parse_archive(todo_raw) {
+        Some(archive) => archive,
+        None => return todo_raw.to_string(),
+    };
+    let archived = match parse_archived_tasks(todo_raw) {
+        0 => return todo_raw.to_string(),
+        1 => "(1 archived task)".to_string(),
+        n => format!("({n} archived tasks)"),
+    };
+    let mut lines = todo_raw.lines().collect::<Vec<_>>();
+    lines.splice(heading + 1..=last_line, ["", archived.as_str()]);
+    let mut collapsed = lines.join("\n");
+    if todo_raw.ends_with('\n') {
+        collapsed.push('\n');
+    }
+    collapsed
+}
+
+/// Returns the parsed Todo list, or the parsed notes with `--notes-too`. None when the file is not
+/// listed.
+///
+/// Files which are not Todo lists (see [`is_todo_list`](crate::parse::is_todo_list)), such as
+/// long-form notes kept next to Todo lists, are skipped by default.
+fn listed(todo_raw: &str, p: &Parameters) -> Option<ParsedTodoList> {
+    match parse_todo_list(todo_raw) {
+        Ok(todo_list) => Some(todo_list),
+        Err(_) if p.notes_too => parse_notes(todo_raw).ok(),
+        Err(_) => None,
+    }
+}
+
+/// Returns the arguments filtering Todo lists by their motives
+pub fn motive_args() -> [Arg<'static, 'static>; 2] {
+    [
+        Arg::with_name("with-motives")
+            .long("with-motives")
+            .help("Shows only Todo lists with motives (and their motives with --short)"),
+        Arg::with_name("motive-contains")
+            .long("motive-contains")
+            .value_name("TEXT")
+            .help("Shows only Todo lists with a motive containing TEXT (case insensitive)")
+            .takes_value(true),
+    ]
+}
+
+/// Returns true if the Todo list has motives, one of them containing `text` (case insensitive)
+/// when given
+pub fn has_motive(todo_raw: &str, text: Option<&str>) -> bool {
+    let text = text.unwrap_or_default().to_lowercase();
+    parse_todo_list_motives(todo_raw)
+        .iter()
+        .any(|motive| motive.to_lowercase().contains(text.as_str()))
+}
+
+/// Returns the part of a Todo list below its heading, or nothing if the part is empty
+fn part(heading: &str, content: &str) -> String {
+    if content.is_empty() {
+        String::new()
+    } else {
+        format!("## {heading}\n\n{content}")
+    }
+}
+
+/// Returns the names of the sections of the Todo list matching the requested sections
+///
+/// Sections are matched case-insensitively. When a requested section does not exist, the available
+/// sections of the Todo list are suggested.
+fn resolve_sections(title: &str, todo_raw: &str, requested: &[&str]) -> Vec<String> {
+    let available = parse_todo_list_sections(todo_raw);
+    let mut sections = vec![];
+    for section in requested {
+        match available
+            .iter()
+            .find(|s| s.to_lowercase() == section.to_lowercase())
+        {
+            Some(s) => sections.push(s.to_string()),
+            None if available.is_empty() => {
+                eprintln!("No section \"{section}\" in \"{title}\" which has no sections")
+            }
+            None => eprintln!(
+                "No section \"{section}\" in \"{title}\". Available sections: {}",
+                available.join(", ")
+            ),
+        }
+    }
+    sections
+}
+
+/// Returns the number of done and total direct tasks of a section to show next to its totals,
+/// if some of its tasks are inside subsections
+fn direct_tally(section: &Section) -> Option<String> {
+    section
+        .has_nested_tasks()
+        .then(|| format!("{}/{} direct", section.direct_done, section.direct_total))
+}
+
+/// Returns the sections which are not subsections of another one of `sections`, which show their
+/// subsections
+fn outermost(sections: &[Section]) -> Vec<&Section> {
+    sections
+        .iter()
+        .filter(|s| {
+            !sections
+                .iter()
+                .any(|o| o.line < s.line && s.last_line <= o.last_line)
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{todo_list, MemoryStorage, TempFolder};
     use lazy_static::lazy_static;
     //use simplelog::*;
 
@@ -395,7 +1319,7 @@ mod tests {
         }
 
         /// Set entries for testing purposes
-        fn entries(mut self, entries: Vec<Vec<&'a str>>) -> Parameters {
+        fn entries(mut self, entries: Vec<Vec<&'a str>>) -> Parameters<'a> {
             self.entries = Some(entries);
             self
         }
@@ -406,12 +1330,36 @@ mod tests {
             self
         }
 
+        /// Set `everywhere` parameter to true
+        fn everywhere(mut self) -> Parameters<'a> {
+            self.everywhere = true;
+            self
+        }
+
+        /// Set `fail_if_open` parameter to true
+        fn fail_if_open(mut self) -> Parameters<'a> {
+            self.fail_if_open = true;
+            self
+        }
+
         /// Set labels
-        fn labels(mut self, labels: Vec<&'a str>) -> Parameters {
+        fn labels(mut self, labels: Vec<&'a str>) -> Parameters<'a> {
             self.labels = labels;
             self
         }
 
+        /// Set labels added to printed Todo lists
+        fn label_add(mut self, labels: Vec<&'a str>) -> Parameters<'a> {
+            self.label_add = labels;
+            self
+        }
+
+        /// Set labels removed from printed Todo lists
+        fn label_remove(mut self, labels: Vec<&'a str>) -> Parameters<'a> {
+            self.label_remove = labels;
+            self
+        }
+
         /// Build a new Parameter struct.
         fn new() -> Parameters<'a> {
             Parameters {
@@ -421,11 +1369,30 @@ mod tests {
                 done: false,
                 entries: None,
                 global: false,
+                everywhere: false,
+                fail_if_open: false,
                 labels: vec![],
+                label_add: vec![],
+                label_remove: vec![],
                 open: false,
                 short: false,
                 task_lists: None,
+                positional_titles: vec![],
                 sections: None,
+                sections_only: false,
+                stale: None,
+                color: false,
+                sort: None,
+                hidden: false,
+                notes_too: false,
+                with_motives: false,
+                motive_contains: None,
+                width: None,
+                fields: None,
+                show_archive: false,
+                archived: false,
+                due: None,
+                priority: None,
             }
         }
 
@@ -435,6 +1402,12 @@ mod tests {
             self
         }
 
+        /// Set `archived` parameter to true
+        fn archived(mut self) -> Parameters<'a> {
+            self.archived = true;
+            self
+        }
+
         /// Set `short` parameter to true
         fn short(mut self) -> Parameters<'a> {
             self.short = true;
@@ -442,13 +1415,19 @@ mod tests {
         }
 
         /// Set task lists in Parameters struct:
-        fn task_lists(mut self, task_lists: Vec<&'a str>) -> Parameters {
+        fn task_lists(mut self, task_lists: Vec<&'a str>) -> Parameters<'a> {
             self.task_lists = Some(task_lists);
             self
         }
 
+        /// Set `sections_only` parameter to true
+        fn sections_only(mut self) -> Parameters<'a> {
+            self.sections_only = true;
+            self
+        }
+
         /// Set task lists in Parameters struct:
-        fn sections(mut self, sections: Vec<&'a str>) -> Parameters {
+        fn sections(mut self, sections: Vec<&'a str>) -> Parameters<'a> {
             self.sections = Some(sections);
             self
         }
@@ -463,14 +1442,17 @@ mod tests {
                     name: String::from("ctx1"),
                     timezone: String::from("CET"),
                     folder_location: String::from("fake/folder1"),
+                    ..Default::default()
                 },
                 Context {
                     ide: String::from(""),
                     name: String::from("ctx2"),
                     timezone: String::from("CET"),
                     folder_location: String::from("fake/folder2"),
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
         static ref CONFIG_TWO_CTX_2: Configuration = Configuration {
             active_ctx_name: String::from("ctx2"),
@@ -480,14 +1462,17 @@ mod tests {
                     name: String::from("ctx1"),
                     timezone: String::from("CET"),
                     folder_location: String::from("fake/folder1"),
+                    ..Default::default()
                 },
                 Context {
                     ide: String::from(""),
                     name: String::from("ctx2"),
                     timezone: String::from("CET"),
                     folder_location: String::from("fake/folder2"),
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
         static ref CONFIG_ONE_CTX: Configuration = Configuration {
             active_ctx_name: String::from("ctx1"),
@@ -496,7 +1481,9 @@ mod tests {
                 name: String::from("ctx1"),
                 timezone: String::from("CET"),
                 folder_location: String::from("fake/folder"),
+                ..Default::default()
             }],
+            ..Default::default()
         };
     }
 
@@ -515,6 +1502,7 @@ mod tests {
             .config(Configuration {
                 active_ctx_name: String::from("ctx1"),
                 ctxs: vec![],
+                ..Default::default()
             })
             .entries(entries);
         assert!(list_message(&mut stdout, &parameters).is_err());
@@ -705,11 +1693,70 @@ mod tests {
     }
 
     #[test]
-    fn valid_extension() {
-        assert!(is_valid_extension("md"));
-        assert!(is_valid_extension("txt"));
-        assert!(!is_valid_extension(""));
-        assert!(!is_valid_extension("jpg"));
+    fn list_todo_lists_everywhere() {
+        init();
+        let mut config = CONFIG_TWO_CTX_1.to_owned();
+        config.use_workspace(std::path::Path::new("repo/.todo"));
+        let entries = vec![
+            vec!["# title1\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first"],
+            vec!["# title2\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first"],
+            vec!["# title3\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first"],
+        ];
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(entries.clone())
+            .config(config.clone())
+            .short();
+        assert_eq!(list_message(&mut stdout, &parameters).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Todo lists from repo/.todo\n0/1\t- title3\n"
+        );
+
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(entries)
+            .config(config)
+            .short()
+            .everywhere();
+        assert_eq!(list_message(&mut stdout, &parameters).unwrap(), 2);
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Todo lists from fake/folder1 (ctx1)\n0/1\t- title1\nTodo lists from repo/.todo (local)\n0/1\t- title3\n"
+        );
+    }
+
+    #[test]
+    fn report_open_tasks() {
+        init();
+        let entries = vec![
+            vec![
+                "# release\n\n## Description\n\nLABEL=release-blocker\n\n## Todo list\n\n* [x] tag\n### Docs\n* [ ] changelog\n",
+                "# chores\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] dishes\n",
+                "# done\n\n## Description\n\nLABEL=release-blocker\n\n## Todo list\n\n* [x] all\n",
+            ],
+            vec![],
+        ];
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(entries.clone())
+            .config(CONFIG_TWO_CTX_1.to_owned())
+            .labels(vec!["release-blocker"])
+            .fail_if_open();
+        assert_eq!(list_message(&mut stdout, &parameters).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "release > Docs: changelog\n"
+        );
+
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(entries)
+            .config(CONFIG_TWO_CTX_1.to_owned())
+            .task_lists(vec!["done"])
+            .fail_if_open();
+        assert_eq!(list_message(&mut stdout, &parameters).unwrap(), 0);
+        assert!(stdout.is_empty());
     }
 
     #[test]
@@ -836,6 +1883,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn count_matching_todo_lists() {
+        init();
+        let entries = vec![
+            vec!["# title1\n\n## Description\n\nLABEL=l1\n\n## Todo list\n\n* [ ] open1"],
+            vec![
+                "# title3\n\n## Description\n\nLABEL=l1\n\n## Todo list\n\n* [ ] open1",
+                "# title4\n\n## Description\n\nLABEL=l2\n\n## Todo list\n\n* [x] completed1",
+            ],
+        ];
+        let count = |parameters: Parameters| {
+            let mut stdout = vec![];
+            list_message(&mut stdout, &parameters).unwrap()
+        };
+        let parameters = || {
+            Parameters::new()
+                .entries(entries.clone())
+                .config(CONFIG_TWO_CTX_2.to_owned())
+        };
+
+        assert_eq!(count(parameters()), 1);
+        assert_eq!(count(parameters().all()), 2);
+        assert_eq!(count(parameters().global()), 2);
+        assert_eq!(count(parameters().task_lists(vec!["missing-title"])), 0);
+        assert_eq!(count(parameters().labels(vec!["nosuch"])), 0);
+        assert_eq!(count(parameters().done().labels(vec!["l1"])), 0);
+    }
+
     #[test]
     fn show_many_task_lists() {
         init();
@@ -1065,4 +2140,532 @@ mod tests {
             String::from_utf8(expected.to_vec()).unwrap()
         );
     }
+
+    #[test]
+    fn task_list_patterns() {
+        let matches =
+            |pattern: &str, title: &str| title_patterns(&[pattern]).unwrap()[0].is_match(title);
+        assert!(matches("title1", "title1"));
+        assert!(!matches("title", "title1"));
+        assert!(matches("sprint-*", "sprint-12"));
+        assert!(!matches("sprint-*", "my sprint-12"));
+        assert!(matches("sprint-?", "sprint-1"));
+        assert!(!matches("sprint-?", "sprint-12"));
+        assert!(matches("C++ (v2)", "C++ (v2)"));
+        assert!(matches("/^sprint-\\d+$/", "sprint-12"));
+        assert!(!matches("/^sprint-\\d+$/", "sprint-next"));
+        assert!(matches("/groceries/", "weekly groceries"));
+        assert!(title_patterns(&["/(/"]).is_err());
+    }
+
+    #[test]
+    fn show_task_lists_matching_glob() {
+        init();
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(vec![
+                vec!["# title1\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] open1"],
+                vec![
+                    "# sprint-1\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] open1",
+                    "# backlog\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] open2",
+                    "# sprint-2\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] open3",
+                ],
+            ])
+            .config(CONFIG_TWO_CTX_2.to_owned())
+            .open()
+            .task_lists(vec!["sprint-*"]);
+
+        assert_eq!(list_message(&mut stdout, &parameters).unwrap(), 2);
+        let expected =
+            b"Todo lists from fake/folder2\n# sprint-1\n* [ ] open1\n# sprint-2\n* [ ] open3\n";
+        assert_eq!(
+            stdout,
+            expected,
+            "\ngot     : \"{}\"\nexpected: \"{}\"",
+            String::from_utf8(stdout.to_owned()).unwrap(),
+            String::from_utf8(expected.to_vec()).unwrap()
+        );
+    }
+
+    #[test]
+    fn show_sections_only() {
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(vec![
+                vec![],
+                vec![
+                    "# title3\n\n## Description\n\nLABEL=l1\n\n## Todo list\n\n\
+* [ ] open1\n\n### Section1\n\n* [ ] open2\n* [x] completed2\n\n### Section 2\n\n* [x] completed3",
+                ],
+            ])
+            .config(CONFIG_TWO_CTX_2.to_owned())
+            .sections_only();
+
+        assert!(list_message(&mut stdout, &parameters).is_ok());
+        let expected =
+            b"Todo lists from fake/folder2\n# title3\n1/2\t- Section1\n1/1\t- Section 2\n";
+        assert_eq!(
+            stdout,
+            expected,
+            "\ngot     : \"{}\"\nexpected: \"{}\"",
+            String::from_utf8(stdout.to_vec()).unwrap(),
+            String::from_utf8(expected.to_vec()).unwrap()
+        );
+    }
+
+    #[test]
+    fn show_every_section() {
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(vec![
+                vec![],
+                vec![
+                    "# title3\n\n## Description\n\nLABEL=l1\n\n## Todo list\n\n\
+* [ ] open1\n\n### Section1\n\n* [ ] open2\n* [x] completed2\n\n### Section 2\n\n* [x] completed3",
+                ],
+            ])
+            .config(CONFIG_TWO_CTX_2.to_owned())
+            .sections(vec!["*"]);
+
+        assert!(list_message(&mut stdout, &parameters).is_ok());
+        let expected = b"Todo lists from fake/folder2\n# title3\n\n## Section1 (1/2)\n\n\
+* [ ] open2\n* [x] completed2\n\n## Section 2 (1/1)\n\n* [x] completed3\n";
+        assert_eq!(
+            stdout,
+            expected,
+            "\ngot     : \"{}\"\nexpected: \"{}\"",
+            String::from_utf8(stdout.to_vec()).unwrap(),
+            String::from_utf8(expected.to_vec()).unwrap()
+        );
+
+        let mut stdout = vec![];
+        let parameters = parameters.short();
+        assert!(list_message(&mut stdout, &parameters).is_ok());
+        let expected =
+            b"Todo lists from fake/folder2\n1/2\t- title3 (Section1)\n1/1\t- title3 (Section 2)\n";
+        assert_eq!(
+            stdout,
+            expected,
+            "\ngot     : \"{}\"\nexpected: \"{}\"",
+            String::from_utf8(stdout.to_vec()).unwrap(),
+            String::from_utf8(expected.to_vec()).unwrap()
+        );
+    }
+
+    #[test]
+    fn sections_are_matched_case_insensitively() {
+        let todo_raw = "# title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n\
+### Section1\n\n* [ ] open1\n\n### Section 2\n\n* [x] completed1";
+        assert_eq!(
+            resolve_sections("title", todo_raw, &["section 2", "SECTION1"]),
+            vec!["Section 2", "Section1"]
+        );
+        assert!(resolve_sections("title", todo_raw, &["Section3"]).is_empty());
+
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(vec![vec![], vec![todo_raw]])
+            .config(CONFIG_TWO_CTX_2.to_owned())
+            .sections(vec!["section 2"])
+            .short();
+        assert_eq!(list_message(&mut stdout, &parameters).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Todo lists from fake/folder2\n1/1\t- title (Section 2)\n"
+        );
+
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(vec![vec![], vec![todo_raw]])
+            .config(CONFIG_TWO_CTX_2.to_owned())
+            .sections(vec!["Section3"])
+            .short();
+        assert_eq!(list_message(&mut stdout, &parameters).unwrap(), 0);
+    }
+
+    #[test]
+    fn lists_and_tasks_filters() {
+        let filters_of = |args: Vec<&str>| {
+            let matches = list_command().get_matches_from(args);
+            filters(&matches)
+        };
+        assert_eq!(filters_of(vec!["list"]), (false, false, false, false));
+        assert_eq!(
+            filters_of(vec!["list", "--lists", "done"]),
+            (false, true, false, false)
+        );
+        assert_eq!(
+            filters_of(vec!["list", "--done"]),
+            (false, true, false, false)
+        );
+        assert_eq!(
+            filters_of(vec!["list", "--lists", "all", "--tasks", "all"]),
+            (true, false, true, true)
+        );
+        assert_eq!(
+            filters_of(vec!["list", "-a", "-c", "-o"]),
+            (true, false, true, true)
+        );
+        assert_eq!(
+            filters_of(vec!["list", "--tasks", "open"]),
+            (false, false, false, true)
+        );
+        assert!(list_command()
+            .get_matches_from_safe(vec!["list", "--lists", "all", "--done"])
+            .is_err());
+    }
+
+    #[test]
+    fn multi_word_titles() {
+        let titles_of = |args: Vec<&str>| {
+            let matches = list_command().get_matches_from(args);
+            title_filters(&matches).map(|titles| titles.join("|"))
+        };
+        assert_eq!(titles_of(vec!["list"]), None);
+        assert_eq!(
+            titles_of(vec![
+                "list",
+                "-t",
+                "Weekly review",
+                "-t",
+                "groceries",
+                "--open"
+            ]),
+            Some("Weekly review|groceries".to_string())
+        );
+        assert_eq!(
+            titles_of(vec!["list", "-t", "Weekly review", "groceries"]),
+            Some("Weekly review|groceries".to_string())
+        );
+
+        let patterns = title_patterns(&[" Weekly  review "]).unwrap();
+        assert!(patterns[0].is_match("Weekly review"));
+        assert!(patterns[0].is_match("Weekly\treview"));
+        assert!(!patterns[0].is_match("Weekly reviews"));
+    }
+
+    #[test]
+    fn positional_titles_naming_filters_are_errors() {
+        init();
+        let entries = || {
+            vec![
+                vec![],
+                vec![
+                    "# open\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] open1",
+                    "# title1\n\n## Description\n\nLABEL=work\n\n## Todo list\n\n* [ ] open2",
+                ],
+            ]
+        };
+        let count = |task_lists: Vec<&'static str>, positional_titles: Vec<&'static str>| {
+            let mut parameters = Parameters::new()
+                .entries(entries())
+                .config(CONFIG_TWO_CTX_2.to_owned())
+                .task_lists(task_lists);
+            parameters.positional_titles = positional_titles;
+            list_message(&mut vec![], &parameters)
+        };
+        assert_eq!(count(vec!["open"], vec!["open"]).unwrap(), 1);
+        assert_eq!(count(vec!["done"], vec![]).unwrap(), 0);
+        assert_eq!(count(vec!["missing"], vec!["missing"]).unwrap(), 0);
+        for title in ["done", "work", "ctx1"] {
+            let error = count(vec!["title1", title], vec![title]).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            assert!(error
+                .to_string()
+                .contains(format!("-t \"{title}\"").as_str()));
+        }
+    }
+
+    #[test]
+    fn stamp_labels_of_shown_todo_lists() {
+        let folder = TempFolder::new("list-stamp");
+        let sprint = folder.join("sprint-1.md");
+        let notes = folder.join("notes.md");
+        std::fs::write(
+            &sprint,
+            "# sprint-1\n\n## Description\n\nLABEL=work,old\n\n## Todo list\n\n* [ ] a\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &notes,
+            "# notes\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] b\n",
+        )
+        .unwrap();
+        let config = crate::parse::parse_configuration_file(
+            None,
+            Some(
+                format!(
+                    "active_ctx_name = \"ctx\"\n\n[[ctxs]]\nname = \"ctx\"\nide = \"\"\ntimezone = \"\"\nfolder_location = \"{}\"\n",
+                    folder.display()
+                )
+                .as_str(),
+            ),
+        )
+        .unwrap();
+        let p = Parameters::new()
+            .config(config)
+            .short()
+            .task_lists(vec!["sprint-*"])
+            .label_add(vec!["urgent", "work"])
+            .label_remove(vec!["old"]);
+        assert_eq!(list_message(&mut vec![], &p).unwrap(), 1);
+        assert!(std::fs::read_to_string(&sprint)
+            .unwrap()
+            .contains("LABEL=work,urgent\n"));
+        assert!(std::fs::read_to_string(&notes)
+            .unwrap()
+            .contains("LABEL=\n"));
+    }
+
+    #[test]
+    fn listing_archived_todo_lists_keeps_task_ages() {
+        let folder = TempFolder::new("list-ages");
+        std::fs::write(
+            folder.join("release.md"),
+            todo_list("release", &[], &["* [ ] tag"]),
+        )
+        .unwrap();
+        std::fs::create_dir_all(folder.join(crate::ARCHIVE_FOLDER)).unwrap();
+        std::fs::write(
+            folder.join(crate::ARCHIVE_FOLDER).join("old.md"),
+            todo_list("old", &[], &["* [ ] forgotten"]),
+        )
+        .unwrap();
+        let config = crate::parse::parse_configuration_file(
+            None,
+            Some(
+                format!(
+                    "active_ctx_name = \"ctx\"\n\n[[ctxs]]\nname = \"ctx\"\nide = \"\"\ntimezone = \"\"\nfolder_location = \"{}\"\nstale_after_days = 30\n",
+                    folder.display()
+                )
+                .as_str(),
+            ),
+        )
+        .unwrap();
+        let ctx = config.active_ctx().clone();
+        list_message(&mut vec![], &Parameters::new().config(config.clone())).unwrap();
+        let first_seen = FirstSeen::read(&ctx).unwrap();
+        assert_ne!(first_seen, FirstSeen::default());
+
+        list_message(&mut vec![], &Parameters::new().config(config).archived()).unwrap();
+        assert_eq!(FirstSeen::read(&ctx).unwrap(), first_seen);
+    }
+
+    #[test]
+    fn context_banner_atop_todo_lists() {
+        let mut config = CONFIG_ONE_CTX.to_owned();
+        config.ctxs[0].description = Some("Day job".to_string());
+        config.ctxs[0].goal = Some("ship v2".to_string());
+        let mut stdout = vec![];
+        let parameters = Parameters::new()
+            .entries(vec![vec![
+                "# title1\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first",
+            ]])
+            .config(config)
+            .short();
+
+        assert!(list_message(&mut stdout, &parameters).is_ok());
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Day job (goal: ship v2)\nTodo lists from fake/folder\n0/1\t- title1\n"
+        );
+    }
+
+    #[test]
+    fn hidden_todo_lists_are_skipped() {
+        let entries = vec![vec![
+            "# title1\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first\n<!-- todo:hidden -->\n",
+            "# title2\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first",
+        ]];
+        let mut stdout = vec![];
+        let mut parameters = Parameters::new()
+            .entries(entries)
+            .config(CONFIG_ONE_CTX.to_owned())
+            .short();
+        assert_eq!(list_message(&mut stdout, &parameters).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Todo lists from fake/folder\n0/1\t- title2\n"
+        );
+
+        parameters.hidden = true;
+        assert_eq!(list_message(&mut vec![], &parameters).unwrap(), 2);
+    }
+
+    #[test]
+    fn snapshot_of_sections() {
+        let storage = MemoryStorage::new()
+            .context(
+                "work",
+                &[
+                    todo_list(
+                        "house",
+                        &["home"],
+                        &[
+                            "### Kitchen",
+                            "* [ ] clean",
+                            "#### Fridge",
+                            "* [x] defrost",
+                            "### Garden",
+                            "* [ ] mow",
+                        ],
+                    )
+                    .as_str(),
+                    todo_list("release", &[], &["* [x] tag", "* [ ] announce"]).as_str(),
+                ],
+            )
+            .context("home", &[]);
+        insta::assert_snapshot!("sections_only", storage.list(&["--sections-only"]));
+        insta::assert_snapshot!(
+            "short_sections",
+            storage.list(&["--section", "*", "--short"])
+        );
+        insta::assert_snapshot!("section", storage.list(&["house", "--section", "kitchen"]));
+    }
+
+    #[test]
+    fn broken_context_does_not_stop_global_listing() {
+        let root = TempFolder::new("list-broken");
+        std::fs::write(
+            root.join("release.md"),
+            todo_list("release", &[], &["* [ ] tag"]),
+        )
+        .unwrap();
+        let ctx = |name: &str, folder: &std::path::Path| Context {
+            name: name.to_string(),
+            folder_location: folder.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let config = Configuration {
+            active_ctx_name: "gone".to_string(),
+            ctxs: vec![ctx("gone", &root.join("gone")), ctx("work", &root)],
+            ..Default::default()
+        };
+
+        let mut stdout = vec![];
+        let parameters = Parameters::new().config(config.clone()).short();
+        assert!(list_message(&mut stdout, &parameters).is_err());
+        let mut stdout = vec![];
+        let parameters = Parameters::new().config(config).global().short();
+        let error = list_message(&mut stdout, &parameters).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "1 Todo context(s) could not be listed: gone"
+        );
+        assert!(String::from_utf8(stdout)
+            .unwrap()
+            .ends_with("0/1\t- release\n"));
+    }
+
+    #[test]
+    fn notes_are_skipped_unless_asked() {
+        let storage = MemoryStorage::new().context(
+            "work",
+            &[
+                todo_list("release", &[], &["* [ ] tag"]).as_str(),
+                "# Meeting notes\n\nLong-form notes.\n\n* [ ] follow up\n",
+                "no title at all\n",
+            ],
+        );
+        assert_eq!(
+            storage.list(&["--short"]),
+            "Todo lists from memory/work\n0/1\t- release\n"
+        );
+        assert_eq!(
+            storage.list(&["--short", "--notes-too"]),
+            "Todo lists from memory/work\n0/1\t- release\n0/1\t- Meeting notes\n"
+        );
+    }
+
+    #[test]
+    fn archive_is_collapsed_unless_asked() {
+        let todo_raw =
+            "# release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] announce\n\n## Archive\n\n* [x] tag\n* [x] build\n";
+        let storage = MemoryStorage::new().context("work", &[todo_raw]);
+        assert_eq!(
+            storage.list(&[]),
+            "Todo lists from memory/work\n# release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] announce\n\n## Archive\n\n(2 archived tasks)\n\n"
+        );
+        assert_eq!(
+            storage.list(&["--show-archive"]),
+            format!("Todo lists from memory/work\n{todo_raw}\n")
+        );
+        assert_eq!(
+            storage.list(&["--short"]),
+            "Todo lists from memory/work\n0/1\t- release\n"
+        );
+    }
+
+    #[test]
+    fn open_tasks_by_due_date() {
+        let today = chrono::Local::today().naive_local().format("%Y-%m-%d");
+        let storage = MemoryStorage::new().context(
+            "work",
+            &[
+                todo_list(
+                    "bills",
+                    &[],
+                    &[
+                        "* [ ] pay rent @due(2000-07-01)",
+                        "* [x] pay water @due(2000-06-01)",
+                        "* [ ] pay internet",
+                        "* [ ] renew passport @due(2999-01-01)",
+                    ],
+                )
+                .as_str(),
+                todo_list(
+                    "today",
+                    &[],
+                    &[format!("* [ ] call @due({today})").as_str()],
+                )
+                .as_str(),
+            ],
+        );
+        assert_eq!(
+            storage.list(&["--overdue"]),
+            "Todo lists from memory/work\n# bills\n* [ ] pay rent @due(2000-07-01)\n"
+        );
+        assert_eq!(
+            storage.list(&["--due-today"]),
+            format!("Todo lists from memory/work\n# today\n* [ ] call @due({today})\n")
+        );
+        assert_eq!(
+            storage.list(&["--due-before", "3000-01-01", "-t", "bills"]),
+            "Todo lists from memory/work\n# bills\n* [ ] pay rent @due(2000-07-01)\n* [ ] renew passport @due(2999-01-01)\n"
+        );
+    }
+
+    #[test]
+    fn open_tasks_by_priority() {
+        let storage = MemoryStorage::new().context(
+            "work",
+            &[
+                todo_list(
+                    "health",
+                    &[],
+                    &["* [ ] (B) book checkup", "* [ ] (A) call dentist"],
+                )
+                .as_str(),
+                todo_list(
+                    "chores",
+                    &[],
+                    &[
+                        "* [ ] (A) pay rent",
+                        "* [x] (A) pay water",
+                        "* [ ] (A) fix sink",
+                        "* [ ] vacuum",
+                    ],
+                )
+                .as_str(),
+            ],
+        );
+        assert_eq!(
+            storage.list(&["--priority", "b"]),
+            "Todo lists from memory/work\n# health\n* [ ] (B) book checkup\n"
+        );
+        assert_eq!(
+            storage.list(&["--short", "--sort", "priority"]),
+            "Todo lists from memory/work\n1/4\t- chores (2 high priority)\n0/2\t- health (1 high priority)\n"
+        );
+    }
 }
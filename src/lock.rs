@@ -0,0 +1,158 @@
+//! Lock Todo list against modification
+//!
+//! A locked Todo list holds the `<!-- todo:locked -->` annotation on its own line. The annotation is
+//! invisible once the markdown is rendered and travels with the file when it is synchronized. Every
+//! command modifying a Todo list refuses to do so while it is locked unless `--force` is given.
+use super::{path_arg, todo_path_from_args, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::fs::read_to_string;
+
+/// Annotation marking a Todo list as locked
+pub const LOCKED_ANNOTATION: &str = "<!-- todo:locked -->";
+
+/// Returns the Lock Todo command
+pub fn lock_command() -> App<'static, 'static> {
+    App::new("lock")
+        .about("Prevent any modification of todo list within Todo context")
+        .author(crate_authors!())
+        .arg(title_arg())
+        .arg(path_arg())
+}
+
+/// Returns the Unlock Todo command
+pub fn unlock_command() -> App<'static, 'static> {
+    App::new("unlock")
+        .about("Allow modifications of a locked todo list within Todo context")
+        .author(crate_authors!())
+        .arg(title_arg())
+        .arg(path_arg())
+}
+
+/// Returns title argument shared by lock and unlock commands
+fn title_arg() -> Arg<'static, 'static> {
+    Arg::with_name("title")
+        .short("t")
+        .long("title")
+        .value_name("TITLE")
+        .index(1)
+        .help("Title of todo list")
+        .takes_value(true)
+        .required_unless("path")
+}
+
+/// Locks Todo list in active Todo context
+pub fn lock_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("lock subcommand");
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    let todo_raw = read_to_string(&filepath)?;
+    if is_locked(todo_raw.as_str()) {
+        println!("Todo list \"{title}\" is already locked");
+        return Ok(());
+    }
+    std::fs::write(&filepath, lock(todo_raw.as_str()))?;
+    debug!("wrote {filepath}");
+    println!("Locked \"{title}\"");
+    Ok(())
+}
+
+/// Unlocks Todo list in active Todo context
+pub fn unlock_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("unlock subcommand");
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    let todo_raw = read_to_string(&filepath)?;
+    if !is_locked(todo_raw.as_str()) {
+        println!("Todo list \"{title}\" is not locked");
+        return Ok(());
+    }
+    std::fs::write(&filepath, unlock(todo_raw.as_str()))?;
+    debug!("wrote {filepath}");
+    println!("Unlocked \"{title}\"");
+    Ok(())
+}
+
+/// Returns true if Todo list holds the lock annotation
+pub fn is_locked(todo_raw: &str) -> bool {
+    todo_raw.lines().any(|l| l.trim() == LOCKED_ANNOTATION)
+}
+
+/// Returns an error when the Todo list at `filepath` is locked and `force` is not set
+///
+/// A missing file is not locked.
+pub fn ensure_unlocked(filepath: &str, force: bool) -> Result<(), std::io::Error> {
+    if force {
+        return Ok(());
+    }
+    match read_to_string(filepath) {
+        Ok(todo_raw) if is_locked(todo_raw.as_str()) => Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("Todo list at \"{filepath}\" is locked. Unlock it or use --force."),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns Todo list with the lock annotation appended
+fn lock(todo_raw: &str) -> String {
+    annotate(todo_raw, LOCKED_ANNOTATION)
+}
+
+/// Returns Todo list without any lock annotation
+fn unlock(todo_raw: &str) -> String {
+    remove_annotation(todo_raw, LOCKED_ANNOTATION)
+}
+
+/// Returns Todo list with `annotation` appended on its own line
+pub(crate) fn annotate(todo_raw: &str, annotation: &str) -> String {
+    let separator = if todo_raw.ends_with('\n') { "" } else { "\n" };
+    format!("{todo_raw}{separator}{annotation}\n")
+}
+
+/// Returns Todo list without any line holding `annotation`
+pub(crate) fn remove_annotation(todo_raw: &str, annotation: &str) -> String {
+    let mut removed = todo_raw
+        .lines()
+        .filter(|l| l.trim() != annotation)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if todo_raw.ends_with('\n') {
+        removed.push('\n');
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_and_unlock() {
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] release\n";
+        assert!(!is_locked(todo_raw));
+        let locked = lock(todo_raw);
+        assert!(is_locked(locked.as_str()));
+        assert_eq!(
+            locked,
+            "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] release\n<!-- todo:locked -->\n"
+        );
+        assert_eq!(unlock(locked.as_str()), todo_raw);
+
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=";
+        let locked = lock(todo_raw);
+        assert_eq!(
+            locked,
+            "# Title\n\n## Description\n\nLABEL=\n<!-- todo:locked -->\n"
+        );
+    }
+
+    #[test]
+    fn locked_todo_list_keeps_its_tasks() {
+        let todo_raw =
+            lock("# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] a\n* [ ] b");
+        let todo_list = crate::parse::parse_todo_list(todo_raw.as_str()).unwrap();
+        assert_eq!(todo_list.done, 1);
+        assert_eq!(todo_list.total, 2);
+    }
+}
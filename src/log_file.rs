@@ -0,0 +1,93 @@
+//! Write logs of the operations performed by Todo into a file as JSON lines
+//!
+//! Each line is a JSON object holding the time of the record, the milliseconds elapsed since Todo
+//! started, the level, the module which emitted the record and its message. Files read and written,
+//! the subcommand outcome and its errors are all logged, which helps debugging scripts calling Todo
+//! many times.
+use crate::json::quote;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Logger appending JSON lines to a file
+struct JsonLinesLogger {
+    file: Mutex<File>,
+    start: Instant,
+}
+
+impl Log for JsonLinesLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // dependencies logs are noise for users
+        metadata.target().starts_with("todo")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = json_line(
+            chrono::Local::now().to_rfc3339().as_str(),
+            self.start.elapsed().as_millis(),
+            record.level().as_str(),
+            record.target(),
+            record.args().to_string().as_str(),
+        );
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{line}");
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Appends every log record of Todo to the file at `log_file_path`
+pub fn init(log_file_path: &str) -> Result<(), std::io::Error> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file_path)?;
+    log::set_boxed_logger(Box::new(JsonLinesLogger {
+        file: Mutex::new(file),
+        start: Instant::now(),
+    }))
+    .map_err(std::io::Error::other)?;
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+}
+
+/// Returns log record as a JSON object on a single line
+fn json_line(ts: &str, elapsed_ms: u128, level: &str, target: &str, message: &str) -> String {
+    format!(
+        "{{\"ts\":{},\"elapsed_ms\":{elapsed_ms},\"level\":{},\"target\":{},\"message\":{}}}",
+        quote(ts),
+        quote(level),
+        quote(target),
+        quote(message)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_line_is_escaped() {
+        assert_eq!(
+            json_line(
+                "2024-05-01T10:00:00+00:00",
+                12,
+                "DEBUG",
+                "todo::lock",
+                "wrote \"a\\b.md\"\n\u{1}"
+            ),
+            "{\"ts\":\"2024-05-01T10:00:00+00:00\",\"elapsed_ms\":12,\"level\":\"DEBUG\",\
+\"target\":\"todo::lock\",\"message\":\"wrote \\\"a\\\\b.md\\\"\\n\\u0001\"}"
+        );
+    }
+}
@@ -1,13 +1,76 @@
-use clap::{crate_authors, crate_version, App, AppSettings, Arg};
-use log::{debug, warn};
+use clap::{crate_authors, crate_version, App, AppSettings, Arg, ArgMatches};
+use log::{debug, error, info, trace, warn};
+use std::path::Path;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Instant;
 //use simplelog::*;
+use todo::activity::{log_command, log_command_process};
+use todo::add::{add_command, add_command_process};
+use todo::archive::{
+    archive_command, archive_command_process, unarchive_command, unarchive_command_process,
+};
+#[cfg(feature = "scripting")]
+use todo::batch::{batch_command, read_invocations};
+#[cfg(feature = "sync")]
+use todo::caldav::{caldav_command, caldav_command_process};
+use todo::calendar::{calendar_command, calendar_command_process};
+use todo::comment::{comment_command, comment_command_process};
 use todo::config::{config_command, config_command_process};
+use todo::count::{count_command, count_command_process};
 use todo::create::{create_command, create_command_process};
+#[cfg(feature = "server")]
+use todo::daemon::{client_command, daemon_command, daemon_command_process, query};
 use todo::delete::{delete_command, delete_command_process};
+use todo::describe::{describe_command, describe_command_process};
+#[cfg(feature = "notifications")]
+use todo::digest::{digest_command, digest_command_process};
+use todo::done::{done_command, done_command_process};
 use todo::edit::{edit_command, edit_command_process};
+#[cfg(feature = "scripting")]
+use todo::external::external_command_process;
+#[cfg(feature = "git")]
+use todo::githook::{githook_command, githook_command_process};
+use todo::graph::{graph_command, graph_command_process};
+use todo::help::{help_command, help_command_process};
+use todo::hide::{hide_command, hide_command_process, unhide_command, unhide_command_process};
+use todo::import::{import_command, import_command_process};
+use todo::ingest::{ingest_command, ingest_command_process};
+use todo::lint::{lint_command, lint_command_process};
 use todo::list::{list_command, list_command_process};
-use todo::parse::{parse_active_context, parse_configuration_file};
+use todo::lock::{lock_command, lock_command_process, unlock_command, unlock_command_process};
+use todo::log_file;
+use todo::maintain::{maintain_command, maintain_command_process};
+use todo::merge_file::{merge_file_command, merge_file_command_process};
+use todo::migrate::{migrate_command, migrate_command_process};
+use todo::parse::{parse_active_configuration, parse_configuration_file};
+use todo::parts::{split_command, split_command_process};
+use todo::pick::no_input_arg;
+use todo::pin::{order_command, pin_command, pin_command_process, unpin_command};
+use todo::profile::{configuration_path, profile_arg};
+use todo::progress::progress_arg;
+use todo::quick::{quick_command, quick_command_process};
 use todo::r#move::{move_command, move_command_process};
+use todo::release::{release_checklist_command, release_checklist_command_process};
+use todo::remote;
+use todo::rename::{rename_command, rename_command_process};
+#[cfg(feature = "scripting")]
+use todo::script::{script_command, script_command_process};
+use todo::seal::{seal_command, seal_command_process, verify_command, verify_command_process};
+use todo::search::{search_command, search_command_process};
+use todo::show::{show_command, show_command_process};
+use todo::snapshot::{snapshot_command, snapshot_command_process};
+use todo::stats::{record_usage, stats_command, stats_command_process};
+use todo::statusline::{statusline_command, statusline_command_process};
+use todo::summary::{summary_command, summary_command_process};
+use todo::top::{top_command, top_command_process};
+#[cfg(feature = "tui")]
+use todo::ui::{ui_command, ui_command_process};
+use todo::version::{json_version_arg, missing_feature, version_json, wants_json_version};
+use todo::view::{
+    run_view_command_process, view_command, view_command_process, view_todo_list_process,
+};
+use todo::workspace::{find_workspace, init_command, init_command_process};
+use todo::{Configuration, Context, NO_MATCH_EXIT_CODE, OPEN_TASKS_EXIT_CODE};
 
 fn main() -> Result<(), std::io::Error> {
     // TODO comment before release
@@ -32,7 +95,10 @@ fn main() -> Result<(), std::io::Error> {
 This tool was inspired from kubectl and git. This tool hopes to save some ink from your whiteboard.")
         .about("Tool to manage todo lists from multiple contexts");
     let app = app
-        .setting(AppSettings::SubcommandRequired)
+        // guides are printed by our own help subcommand
+        .setting(AppSettings::DisableHelpSubcommand)
+        // unknown subcommands run todo-NAME executables
+        .setting(AppSettings::AllowExternalSubcommands)
         // this command is mostly for testing purposes
         .arg(
             Arg::with_name("with-config")
@@ -42,6 +108,13 @@ This tool was inspired from kubectl and git. This tool hopes to save some ink fr
                 .help("Use <CONFIG_RAW> instead of configuration file")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("log-file")
+                .long("log-file")
+                .value_name("LOG_FILE")
+                .help("Appends JSON lines logs of every operation to LOG_FILE")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("with-config-path")
                 .short("p")
@@ -50,18 +123,144 @@ This tool was inspired from kubectl and git. This tool hopes to save some ink fr
                 .help(with_config_path_help_text.as_str())
                 .takes_value(true),
         )
+        .arg(profile_arg())
+        .arg(progress_arg())
+        .arg(no_input_arg())
+        .arg(json_version_arg())
+        .subcommand(add_command())
+        .subcommand(calendar_command())
+        .subcommand(comment_command())
         .subcommand(create_command())
         .subcommand(config_command())
         .subcommand(edit_command())
         .subcommand(delete_command())
+        .subcommand(describe_command())
+        .subcommand(done_command())
+        .subcommand(graph_command())
+        .subcommand(help_command())
+        .subcommand(hide_command())
+        .subcommand(import_command())
+        .subcommand(ingest_command())
+        .subcommand(init_command())
+        .subcommand(lint_command())
         .subcommand(list_command())
-        .subcommand(move_command());
-    let matches = app.get_matches();
+        .subcommand(lock_command())
+        .subcommand(log_command())
+        .subcommand(unhide_command())
+        .subcommand(unlock_command())
+        .subcommand(unpin_command())
+        .subcommand(maintain_command())
+        .subcommand(archive_command())
+        .subcommand(unarchive_command())
+        .subcommand(split_command())
+        .subcommand(count_command())
+        .subcommand(rename_command())
+        .subcommand(merge_file_command())
+        .subcommand(migrate_command())
+        .subcommand(move_command())
+        .subcommand(order_command())
+        .subcommand(pin_command())
+        .subcommand(quick_command())
+        .subcommand(release_checklist_command())
+        .subcommand(seal_command())
+        .subcommand(search_command())
+        .subcommand(show_command())
+        .subcommand(snapshot_command())
+        .subcommand(stats_command())
+        .subcommand(statusline_command())
+        .subcommand(summary_command())
+        .subcommand(top_command())
+        .subcommand(verify_command())
+        .subcommand(view_command());
+    #[cfg(feature = "scripting")]
+    let app = app.subcommand(batch_command()).subcommand(script_command());
+    #[cfg(feature = "sync")]
+    let app = app.subcommand(caldav_command());
+    #[cfg(feature = "server")]
+    let app = app
+        .subcommand(client_command())
+        .subcommand(daemon_command());
+    #[cfg(feature = "notifications")]
+    let app = app.subcommand(digest_command());
+    #[cfg(feature = "git")]
+    let app = app.subcommand(githook_command());
+    #[cfg(feature = "tui")]
+    let app = app.subcommand(ui_command());
+    let args = std::env::args_os()
+        .map(|a| a.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    if wants_json_version(&args) {
+        // clap would print the plain version, so it parses everything else
+        let args = args
+            .iter()
+            .filter(|a| !matches!(a.as_str(), "--version" | "-V" | "--json"));
+        let matches = app.clone().get_matches_from(args);
+        let config_path = match matches.is_present("with-config") {
+            true => None,
+            false => configuration_path(&matches, home.as_str()).ok(),
+        };
+        println!("{}", version_json(config_path.as_deref()));
+        return Ok(());
+    }
+    let matches = app.clone().get_matches();
+    if let Some(args) = matches.subcommand_matches("help") {
+        return help_command_process(args, app);
+    }
+    let matches = match matches.subcommand_name() {
+        Some(_) => matches,
+        None => default_command_matches(app.clone(), &matches, home.as_str()),
+    };
+
+    if let Some(log_file_path) = matches.value_of("log-file") {
+        log_file::init(log_file_path)?;
+    }
+    let subcommand = matches.subcommand_name().unwrap_or_default();
+    info!("{subcommand} subcommand started");
+    let start = Instant::now();
+    let result = run(&matches, home.as_str(), &app);
+    let duration_ms = start.elapsed().as_millis();
+    match &result {
+        Ok(()) => info!("{subcommand} subcommand completed in {duration_ms} ms"),
+        Err(e) => error!("{subcommand} subcommand failed in {duration_ms} ms: {e}"),
+    }
+    let exit_code = EXIT_CODE.load(Ordering::Relaxed);
+    if result.is_ok() && exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    result
+}
+
+/// Returns the command line completed with the configured default command when `todo` is invoked
+/// without subcommand. A subcommand is required without default command.
+fn default_command_matches<'a>(
+    app: App<'a, '_>,
+    matches: &ArgMatches,
+    home: &str,
+) -> ArgMatches<'a> {
+    let raw_config = matches.value_of("with-config");
+    let default_command = configuration_path(matches, home)
+        .and_then(|path| configuration(path.as_str(), raw_config, false))
+        .ok()
+        .and_then(|config| {
+            config
+                .default_command(config.active_ctx())
+                .map(|c| c.to_vec())
+        });
+    match default_command {
+        Some(default_command) => {
+            debug!("default command: {default_command:?}");
+            let args = std::env::args().chain(default_command);
+            app.setting(AppSettings::SubcommandRequired)
+                .get_matches_from(args)
+        }
+        None => app.setting(AppSettings::SubcommandRequired).get_matches(),
+    }
+}
 
-    let default_todo_configuration_path = format!("{}/.todo", home.as_str());
-    let todo_configuration_path = matches
-        .value_of("with-config-path")
-        .unwrap_or_else(|| default_todo_configuration_path.as_str());
+/// Runs the subcommand of the parsed command line
+fn run(matches: &ArgMatches, home: &str, app: &App) -> Result<(), std::io::Error> {
+    let todo_configuration_path = configuration_path(matches, home)?;
+    let todo_configuration_path = todo_configuration_path.as_str();
 
     // other subcommands than config requires a working configuration file
     let raw_config = matches.value_of("with-config");
@@ -71,48 +270,526 @@ This tool was inspired from kubectl and git. This tool hopes to save some ink fr
         return config_command_process(args, todo_configuration_path, raw_config);
     }
 
-    let ctx = parse_active_context(Some(todo_configuration_path), raw_config)?;
-    let config = parse_configuration_file(Some(todo_configuration_path), raw_config)?;
+    // git runs merge drivers without any Todo configuration
+    if let Some(args) = matches.subcommand_matches("merge-file") {
+        return merge_file_command_process(args);
+    }
+
+    // git hooks lint Todo lists of a given folder
+    if let Some(args) = matches.subcommand_matches("lint") {
+        return lint_command_process(args, todo_configuration_path, raw_config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("snapshot") {
+        return snapshot_command_process(args, todo_configuration_path, raw_config);
+    }
+
+    // views are saved inside the configuration file
+    if let Some(args) = matches.subcommand_matches("view") {
+        if args.subcommand_name().is_some() {
+            return view_command_process(args, todo_configuration_path, raw_config);
+        }
+    }
+
+    // pins and manual orders are saved inside the configuration file
+    if matches!(matches.subcommand_name(), Some("order" | "pin" | "unpin")) {
+        return pin_command_process(matches, todo_configuration_path, raw_config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("init") {
+        return init_command_process(args);
+    }
+
+    // the daemon reloads the configuration by itself
+    #[cfg(feature = "server")]
+    if let Some(args) = matches.subcommand_matches("daemon") {
+        return daemon_command_process(args, todo_configuration_path, raw_config);
+    }
+
+    // shell prompts only need the active Todo context
+    if let Some(args) = matches.subcommand_matches("statusline") {
+        let config = configuration(todo_configuration_path, raw_config, true)?;
+        return statusline_command_process(args, config.active_ctx());
+    }
+
+    let mut config = configuration(todo_configuration_path, raw_config, false)?;
+    // remote Todo lists are worked on locally then uploaded
+    let every_ctx = uses_other_ctxs(matches, &config);
+    let mirrors = remote::mirror(todo_configuration_path, &mut config, every_ctx)?;
+    let ctx = config.active_ctx().clone();
+    let result = match matches.subcommand() {
+        #[cfg(feature = "scripting")]
+        ("batch", Some(args)) => run_batch(args, app, todo_configuration_path, &ctx, &config),
+        #[cfg(feature = "server")]
+        ("client", Some(args)) => run_client(args, app, todo_configuration_path, &ctx, &config),
+        _ => run_in_context(matches, app, todo_configuration_path, &ctx, &config, false),
+    };
+    match (result, remote::upload(mirrors)) {
+        (Err(e), Err(upload_error)) => {
+            eprintln!("Error: {upload_error}");
+            Err(e)
+        }
+        (result, uploaded) => result.and(uploaded),
+    }
+}
+
+/// Returns true if the subcommand may use Todo lists of other Todo contexts than the active one
+fn uses_other_ctxs(matches: &ArgMatches, config: &Configuration) -> bool {
+    match matches.subcommand() {
+        ("list", Some(args)) => args.is_present("global"),
+        ("edit", Some(args)) => args.is_present("context name"),
+        // batches, clients, views and aliases run other command lines
+        (
+            "batch" | "client" | "maintain" | "move" | "show" | "stats" | "summary" | "top" | "ui"
+            | "view",
+            _,
+        ) => true,
+        (name, _) => config.alias(name).is_some(),
+    }
+}
+
+/// Subcommands which do not run inside the Todo context of a batch, a client or an alias
+const UNBATCHED_SUBCOMMANDS: [&str; 13] = [
+    "batch",
+    "client",
+    "config",
+    "daemon",
+    "help",
+    "init",
+    "lint",
+    "merge-file",
+    "order",
+    "pin",
+    "snapshot",
+    "statusline",
+    "unpin",
+];
+
+/// Returns the parsed invocation, which must run inside the Todo context
+///
+/// * `via` - how the invocation runs, for error messages (`inside a batch`)
+fn in_context_matches<'a>(
+    app: &App<'a, '_>,
+    invocation: Vec<String>,
+    via: &str,
+) -> Result<ArgMatches<'a>, std::io::Error> {
+    let command_line = std::iter::once("todo".to_string()).chain(invocation);
+    let matches = app
+        .clone()
+        .get_matches_from_safe(command_line)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.message))?;
+    match matches.subcommand() {
+        (name, _) if UNBATCHED_SUBCOMMANDS.contains(&name) => {
+            Err(std::io::Error::other(format!("{name} cannot run {via}")))
+        }
+        ("view", Some(args)) if args.subcommand_name().is_some() => Err(std::io::Error::other(
+            format!("views cannot be managed {via}"),
+        )),
+        ("", _) => Err(std::io::Error::other("missing subcommand")),
+        _ => Ok(matches),
+    }
+}
+
+/// Runs the invocations read from stdin inside the Todo context of the batch
+///
+/// The batch stops at the first failing invocation unless `--keep-going` is given.
+#[cfg(feature = "scripting")]
+fn run_batch(
+    args: &ArgMatches,
+    app: &App,
+    todo_configuration_path: &str,
+    ctx: &Context,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    trace!("batch subcommand");
+    let keep_going = args.is_present("keep-going");
+    let mut failures = 0;
+    for (line, invocation) in read_invocations()? {
+        debug!("batch line {line}: {invocation:?}");
+        let result = in_context_matches(app, invocation, "inside a batch").and_then(|matches| {
+            run_in_context(&matches, app, todo_configuration_path, ctx, config, true)
+        });
+        if let Err(e) = result {
+            eprintln!("Error: line {line}: {e}");
+            failures += 1;
+            if !keep_going {
+                break;
+            }
+        }
+    }
+    if failures > 0 {
+        return Err(std::io::Error::other(format!(
+            "{failures} batch invocation(s) failed"
+        )));
+    }
+    Ok(())
+}
+
+/// Runs the invocation of the client, through the daemon when it answers the list invocation
+#[cfg(feature = "server")]
+fn run_client(
+    args: &ArgMatches,
+    app: &App,
+    todo_configuration_path: &str,
+    ctx: &Context,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    trace!("client subcommand");
+    let invocation = args
+        .values_of("arguments")
+        .unwrap()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>();
+    let matches = in_context_matches(app, invocation.clone(), "through the client")?;
+    if let Some(list_args) = matches.subcommand_matches("list") {
+        // global options before list are left to this process
+        if matches!(invocation[0].as_str(), "list" | "ls") {
+            if let Some((count, output)) = query(todo_configuration_path, ctx, &invocation) {
+                std::io::Write::write_all(&mut std::io::stdout(), &output)?;
+                exit_list(list_args, count, false);
+                return Ok(());
+            }
+            debug!("no daemon answered, listing without it");
+        }
+    }
+    run_in_context(&matches, app, todo_configuration_path, ctx, config, false)
+}
+
+/// Runs the arguments of the alias followed by the arguments of the invocation
+fn run_alias(
+    name: &str,
+    args: &ArgMatches,
+    app: &App,
+    todo_configuration_path: &str,
+    ctx: &Context,
+    config: &Configuration,
+    batch: bool,
+) -> Result<(), std::io::Error> {
+    trace!("alias {name}");
+    let invocation = config
+        .alias(name)
+        .unwrap_or_default()
+        .iter()
+        .cloned()
+        .chain(
+            args.values_of("")
+                .unwrap_or_default()
+                .map(|a| a.to_string()),
+        )
+        .collect::<Vec<_>>();
+    debug!("alias {name} expands to {invocation:?}");
+    let matches = in_context_matches(app, invocation, "through an alias")?;
+    let expanded = matches.subcommand_name().unwrap_or_default();
+    if config.alias(expanded).is_some() {
+        return Err(std::io::Error::other(format!(
+            "alias {name} expands to alias {expanded}, which is not expanded again"
+        )));
+    }
+    run_in_context(&matches, app, todo_configuration_path, ctx, config, batch)
+}
+
+/// Exit code of `todo` once the subcommand completed and remote Todo lists are uploaded
+static EXIT_CODE: AtomicI32 = AtomicI32::new(0);
+
+/// Sets the exit code like `todo list` does after printing `count` Todo lists
+///
+/// Nothing matching exits with NO_MATCH_EXIT_CODE, except with `--fail-if-open` where matching Todo
+/// lists have open tasks and exit with OPEN_TASKS_EXIT_CODE. Inside a batch, the outcome is only
+/// reported.
+fn exit_list(args: &ArgMatches, count: usize, batch: bool) {
+    if args.is_present("fail-if-open") {
+        if count > 0 {
+            info!("list subcommand found {count} Todo list(s) with open tasks");
+            eprintln!("{count} todo list(s) have open tasks");
+            if !batch {
+                EXIT_CODE.store(OPEN_TASKS_EXIT_CODE, Ordering::Relaxed);
+            }
+        }
+        return;
+    }
+    if count == 0 {
+        info!("list subcommand matched no Todo lists");
+        eprintln!("No matching Todo lists");
+        if !batch {
+            EXIT_CODE.store(NO_MATCH_EXIT_CODE, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Returns the configuration of this invocation
+///
+/// `.todo-context` files, then project-local workspaces, then TODO_ACTIVE_CTX, TODO_FOLDER and
+/// TODO_IDE steer this invocation only. Workspaces are usable without configuration file.
+///
+/// With `only_active_ctx`, other Todo contexts than the active one may be left out.
+fn configuration(
+    todo_configuration_path: &str,
+    raw_config: Option<&str>,
+    only_active_ctx: bool,
+) -> Result<Configuration, std::io::Error> {
+    let cwd = std::env::current_dir()?;
+    let workspace = find_workspace(&cwd);
+    let parsed = if only_active_ctx {
+        parse_only_active_ctx(todo_configuration_path, raw_config, &cwd)
+    } else {
+        parse_configuration_file(Some(todo_configuration_path), raw_config)
+    };
+    let mut config = match parsed {
+        Ok(config) => config,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && workspace.is_some() => {
+            Configuration::new()
+        }
+        Err(e) => return Err(e),
+    };
+    config.detect_active_ctx(&cwd)?;
+    if let Some(workspace) = workspace {
+        config.use_workspace(&workspace);
+    }
+    config.override_from_environment()?;
+    Ok(config)
+}
+
+/// Returns the configuration with the Todo contexts which can be active inside `cwd`, or the whole
+/// configuration when its layout is unexpected
+fn parse_only_active_ctx(
+    todo_configuration_path: &str,
+    raw_config: Option<&str>,
+    cwd: &Path,
+) -> Result<Configuration, std::io::Error> {
+    let raw = match raw_config {
+        Some(raw) => raw.to_string(),
+        None => std::fs::read_to_string(todo_configuration_path)?,
+    };
+    match parse_active_configuration(raw.as_str(), cwd)? {
+        Some(config) => Ok(config),
+        None => parse_configuration_file(Some(todo_configuration_path), raw_config),
+    }
+}
+
+/// Runs the subcommand of the parsed command line which requires a Todo context
+///
+/// Inside a batch, list commands do not exit.
+fn run_in_context(
+    matches: &ArgMatches,
+    app: &App,
+    todo_configuration_path: &str,
+    ctx: &Context,
+    config: &Configuration,
+    batch: bool,
+) -> Result<(), std::io::Error> {
+    if config.records_usage() {
+        let subcommand = matches.subcommand_name().unwrap_or_default();
+        if let Err(e) = record_usage(todo_configuration_path, subcommand, ctx.name.as_str()) {
+            warn!("Usage statistics could not be recorded: {e}");
+        }
+    }
+
+    if let Some(args) = matches.subcommand_matches("add") {
+        return add_command_process(args, ctx);
+    }
+
+    #[cfg(feature = "sync")]
+    if let Some(args) = matches.subcommand_matches("caldav") {
+        return caldav_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("calendar") {
+        return calendar_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("comment") {
+        if let Err(e) = comment_command_process(args, ctx, config) {
+            eprintln!("Error: {e}");
+            return Err(std::io::Error::other("Comment command could not complete."));
+        } else {
+            return Ok(());
+        }
+    }
 
     if let Some(args) = matches.subcommand_matches("create") {
-        return create_command_process(args, &ctx);
+        return create_command_process(args, ctx);
     }
 
     if let Some(args) = matches.subcommand_matches("delete") {
-        return delete_command_process(args, &ctx);
+        return delete_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("describe") {
+        return describe_command_process(args, ctx);
+    }
+
+    #[cfg(feature = "notifications")]
+    if let Some(args) = matches.subcommand_matches("digest") {
+        return digest_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("done") {
+        if let Err(e) = done_command_process(args, ctx) {
+            eprintln!("Error: {e}");
+            return Err(std::io::Error::other("Done command could not complete."));
+        } else {
+            return Ok(());
+        }
     }
 
     if let Some(args) = matches.subcommand_matches("edit") {
-        if let Err(e) = edit_command_process(args, &ctx, &config) {
+        if let Err(e) = edit_command_process(args, ctx, config) {
             eprintln!("Error: {e}");
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Edit command could not complete.",
-            ));
+            return Err(std::io::Error::other("Edit command could not complete."));
         } else {
             return Ok(());
         }
     }
 
+    #[cfg(feature = "git")]
+    if let Some(args) = matches.subcommand_matches("githook") {
+        return githook_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("graph") {
+        return graph_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("import") {
+        return import_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("ingest") {
+        return ingest_command_process(args, ctx);
+    }
+
     if let Some(args) = matches.subcommand_matches("list") {
-        return list_command_process(args, &config);
+        let count = list_command_process(args, config)?;
+        exit_list(args, count, batch);
+        return Ok(());
+    }
+
+    if let Some(args) = matches.subcommand_matches("hide") {
+        return hide_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("unhide") {
+        return unhide_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("lock") {
+        return lock_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("unlock") {
+        return unlock_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("log") {
+        return log_command_process(args, ctx, config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("archive") {
+        return archive_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("unarchive") {
+        return unarchive_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("count") {
+        return count_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("rename") {
+        return rename_command_process(args, ctx);
+    }
+
+    #[cfg(feature = "scripting")]
+    if let Some(args) = matches.subcommand_matches("script") {
+        return script_command_process(args, ctx);
+    }
+
+    #[cfg(feature = "tui")]
+    if let Some(args) = matches.subcommand_matches("ui") {
+        return ui_command_process(args, config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("split") {
+        return split_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("maintain") {
+        return maintain_command_process(args, config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("migrate") {
+        return migrate_command_process(args, todo_configuration_path, ctx);
     }
 
     if let Some(args) = matches.subcommand_matches("move") {
-        if let Err(e) = move_command_process(args, &config) {
+        if let Err(e) = move_command_process(args, config) {
             eprintln!("Error: {e}");
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Move command could not complete.",
-            ));
+            return Err(std::io::Error::other("Move command could not complete."));
         } else {
             return Ok(());
         }
     }
 
+    if let Some(args) = matches.subcommand_matches("quick") {
+        return quick_command_process(args, ctx, config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("release-checklist") {
+        return release_checklist_command_process(args, todo_configuration_path, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("seal") {
+        return seal_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("search") {
+        return search_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("show") {
+        return show_command_process(args, ctx, config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("stats") {
+        return stats_command_process(args, todo_configuration_path, config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("summary") {
+        return summary_command_process(args, todo_configuration_path, config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("top") {
+        return top_command_process(args, config);
+    }
+
+    if let Some(args) = matches.subcommand_matches("verify") {
+        return verify_command_process(args, ctx);
+    }
+
+    if let Some(args) = matches.subcommand_matches("view") {
+        if view_todo_list_process(args, ctx, config)? {
+            return Ok(());
+        }
+        let (list_args, count) = run_view_command_process(args, ctx, config)?;
+        exit_list(&list_args, count, batch);
+        return Ok(());
+    }
+
+    if let (name, Some(args)) = matches.subcommand() {
+        // aliases of the configuration take precedence over todo-NAME executables
+        if config.alias(name).is_some() {
+            return run_alias(name, args, app, todo_configuration_path, ctx, config, batch);
+        }
+        if let Some(feature) = missing_feature(name) {
+            return Err(std::io::Error::other(format!(
+                "todo {name} requires the \"{feature}\" feature, which this build was made without"
+            )));
+        }
+        #[cfg(feature = "scripting")]
+        return external_command_process(name, args, todo_configuration_path, ctx);
+    }
+
     warn!("Unrecognised subcommand");
-    Err(std::io::Error::new(
-        std::io::ErrorKind::Other,
-        "unrecognised subcommand",
-    ))
+    Err(std::io::Error::other("unrecognised subcommand"))
 }
@@ -0,0 +1,273 @@
+//! Apply maintenance policies of all Todo contexts
+//!
+//! This command is meant to be run periodically (with cron for instance). Todo contexts opt in the
+//! policies inside the configuration file:
+//!
+//! * `auto_archive_after_days` - fully completed Todo lists that were not modified for this many
+//!   days are moved into the archive folder of the Todo context. Locked Todo lists are left alone.
+//!
+//! Caches of every Todo context are then refreshed and written: the metadata of Todo lists (see
+//! [`crate::metadata`]) and the days open tasks were first seen (see [`crate::age`]), when ages are
+//! tracked. Removed Todo lists and tasks which are no longer open are forgotten.
+use crate::age::{tracks_ages, FirstSeen, FIRST_SEEN_FILE};
+use crate::archive::{archive_folder, move_into_archive};
+use crate::lock::is_locked;
+use crate::metadata::MetadataCache;
+use crate::parse::parse_todo_list;
+use crate::single_file::SingleFile;
+use crate::{read_todo_lists, Configuration, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Returns the Maintain command
+pub fn maintain_command() -> App<'static, 'static> {
+    App::new("maintain")
+        .about("Apply maintenance policies (auto-archive) and refresh caches of all Todo contexts")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("dry-run")
+                .short("n")
+                .long("dry-run")
+                .help("Report what would be done without modifying anything"),
+        )
+}
+
+/// Applies maintenance policies of every Todo context and reports what was done
+pub fn maintain_command_process(
+    args: &ArgMatches,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    trace!("maintain subcommand");
+    let dry_run = args.is_present("dry-run");
+    for ctx in &config.ctxs {
+        match ctx.auto_archive_after_days {
+            Some(after_days) => {
+                let archived = auto_archive(ctx, after_days, dry_run)?;
+                if archived.is_empty() {
+                    println!("Context \"{}\": nothing to archive", ctx.name);
+                } else {
+                    println!(
+                        "Context \"{}\": {} {} Todo list(s)",
+                        ctx.name,
+                        if dry_run { "would archive" } else { "archived" },
+                        archived.len()
+                    );
+                    for title in archived {
+                        println!("- {title}");
+                    }
+                }
+            }
+            None => debug!("context {} has no auto-archive policy", ctx.name),
+        }
+        for report in refresh_caches(ctx, dry_run)? {
+            println!("Context \"{}\": {report}", ctx.name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Refreshes the metadata cache of the Todo context and, when ages are tracked, the days its open
+/// tasks were first seen. Caches are written unless `dry_run`. Returns a report for each cache.
+fn refresh_caches(ctx: &Context, dry_run: bool) -> Result<Vec<String>, std::io::Error> {
+    if !Path::new(ctx.folder_location.as_str()).is_dir() {
+        debug!("context {} has no Todo folder", ctx.name);
+        return Ok(vec![]);
+    }
+    let status = |changed: bool| match (changed, dry_run) {
+        (false, _) => "up to date",
+        (true, true) => "would be updated",
+        (true, false) => "updated",
+    };
+    let mut reports = vec![];
+
+    let mut metadata = MetadataCache::read(ctx)?;
+    let changed = metadata.refresh(ctx)?;
+    if changed && !dry_run {
+        metadata.write(ctx)?;
+    }
+    reports.push(format!(
+        "metadata cache {}, {} Todo list(s)",
+        status(changed),
+        metadata.todo_lists().count()
+    ));
+
+    let first_seen_path = Path::new(ctx.folder_location.as_str()).join(FIRST_SEEN_FILE);
+    if tracks_ages(ctx, false) || first_seen_path.is_file() {
+        let todo_raws = if ctx.is_single_file() {
+            SingleFile::read(ctx)?.todo_lists
+        } else {
+            let archive_folder = archive_folder(ctx);
+            read_todo_lists(ctx)?
+                .into_iter()
+                .filter(|(filepath, _)| !Path::new(filepath).starts_with(&archive_folder))
+                .map(|(_, todo_raw)| todo_raw)
+                .collect()
+        };
+        let mut first_seen = FirstSeen::read(ctx)?;
+        let today = chrono::Local::today().naive_local();
+        let changed = first_seen.update(todo_raws.iter().map(|t| t.as_str()), today);
+        if changed && !dry_run {
+            first_seen.write(ctx)?;
+        }
+        reports.push(format!(
+            "task ages {}, {} open task(s)",
+            status(changed),
+            first_seen.open_tasks()
+        ));
+    }
+    Ok(reports)
+}
+
+/// Moves fully completed Todo lists older than `after_days` into the archive folder of the Todo
+/// context. Returns the titles of the archived Todo lists.
+fn auto_archive(
+    ctx: &Context,
+    after_days: u64,
+    dry_run: bool,
+) -> Result<Vec<String>, std::io::Error> {
+    let folder = Path::new(ctx.folder_location.as_str());
+    if !folder.is_dir() {
+        debug!("context {} has no Todo folder", ctx.name);
+        return Ok(vec![]);
+    }
+    let archive_folder = archive_folder(ctx);
+    let now = SystemTime::now();
+    let mut archived = vec![];
+    for (filepath, todo_raw) in read_todo_lists(ctx)? {
+        let path = Path::new(filepath.as_str());
+        // Todo lists archived beforehand are already moved
+        if path.starts_with(&archive_folder) || !path.is_file() {
+            continue;
+        }
+        let modified = std::fs::metadata(path)?.modified()?;
+        let age_days = now
+            .duration_since(modified)
+            .map(|d| d.as_secs() / (24 * 60 * 60))
+            .unwrap_or(0);
+        if is_locked(todo_raw.as_str()) || !should_archive(todo_raw.as_str(), age_days, after_days)
+        {
+            continue;
+        }
+
+        let title = match parse_todo_list(todo_raw.as_str()) {
+            Ok(todo_list) => todo_list.title,
+            Err(_) => filepath.to_string(),
+        };
+        if !dry_run {
+            move_into_archive(ctx, filepath.as_str())?;
+        }
+        archived.push(title);
+    }
+
+    Ok(archived)
+}
+
+/// Returns true if Todo list has all of its tasks done and was not modified for `after_days` days
+///
+/// Todo lists without any task are never archived.
+fn should_archive(todo_raw: &str, age_days: u64, after_days: u64) -> bool {
+    match parse_todo_list(todo_raw) {
+        Ok(todo_list) => {
+            todo_list.total > 0 && todo_list.tasks_are_all_done() && age_days >= after_days
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+    use crate::ARCHIVE_FOLDER;
+
+    #[test]
+    fn archive_only_old_completed_lists() {
+        let done = "# done\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] first";
+        let open = "# open\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first";
+        let empty = "# empty\n\n## Description\n\nLABEL=\n";
+        assert!(should_archive(done, 30, 30));
+        assert!(should_archive(done, 31, 30));
+        assert!(!should_archive(done, 29, 30));
+        assert!(!should_archive(open, 100, 30));
+        assert!(!should_archive(empty, 100, 30));
+        assert!(!should_archive("not a todo list", 100, 30));
+    }
+
+    #[test]
+    fn auto_archive_moves_files() {
+        let root = TempFolder::new("maintain");
+        std::fs::write(
+            root.join("done.md"),
+            "# done\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] first",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("open.md"),
+            "# open\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first",
+        )
+        .unwrap();
+        let ctx = Context {
+            name: "ctx".to_string(),
+            folder_location: root.location(),
+            ..Default::default()
+        };
+
+        assert_eq!(auto_archive(&ctx, 0, true).unwrap(), vec!["done"]);
+        assert!(root.join("done.md").exists());
+
+        assert_eq!(auto_archive(&ctx, 0, false).unwrap(), vec!["done"]);
+        assert!(!root.join("done.md").exists());
+        assert!(root.join(ARCHIVE_FOLDER).join("done.md").exists());
+        assert!(root.join("open.md").exists());
+
+        // archived Todo lists are not archived again
+        assert!(auto_archive(&ctx, 0, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn refresh_and_prune_caches() {
+        let root = TempFolder::new("maintain-caches");
+        let open = root.join("open.md");
+        std::fs::write(
+            &open,
+            "# open\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first\n* [ ] second\n",
+        )
+        .unwrap();
+        let ctx = Context {
+            name: "ctx".to_string(),
+            folder_location: root.location(),
+            stale_after_days: Some(30),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            refresh_caches(&ctx, true).unwrap(),
+            vec![
+                "metadata cache would be updated, 1 Todo list(s)",
+                "task ages would be updated, 2 open task(s)"
+            ]
+        );
+        assert!(!root.join(crate::metadata::METADATA_FILE).exists());
+        refresh_caches(&ctx, false).unwrap();
+        assert!(root.join(crate::metadata::METADATA_FILE).exists());
+        assert_eq!(
+            refresh_caches(&ctx, false).unwrap(),
+            vec![
+                "metadata cache up to date, 1 Todo list(s)",
+                "task ages up to date, 2 open task(s)"
+            ]
+        );
+
+        std::fs::remove_file(&open).unwrap();
+        assert_eq!(
+            refresh_caches(&ctx, false).unwrap(),
+            vec![
+                "metadata cache updated, 0 Todo list(s)",
+                "task ages updated, 0 open task(s)"
+            ]
+        );
+    }
+}
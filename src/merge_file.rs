@@ -0,0 +1,240 @@
+//! Merge two versions of a Todo list, meant to be used as a git merge driver
+//!
+//! Inside git-backed Todo contexts, register the driver once:
+//!
+//! ```text
+//! git config merge.todo.driver "todo merge-file %O %A %B"
+//! echo "*.md merge=todo" >> .gitattributes
+//! ```
+//!
+//! Tasks are merged semantically instead of line by line: tasks added on either side are kept, a
+//! task checked on either side is done and a task deleted on one side while untouched on the other
+//! side is deleted. Added tasks go to their section. Only conflicting changes outside of the task
+//! list make the merge fail.
+use crate::parse::{insert_task_lines, parse_tasks, Task};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::fs::read_to_string;
+
+/// Returns the Merge file command
+pub fn merge_file_command() -> App<'static, 'static> {
+    App::new("merge-file")
+        .about("Merge two versions of a todo list (git merge driver: todo merge-file %O %A %B)")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("base")
+                .value_name("BASE")
+                .index(1)
+                .help("Common ancestor version of the todo list")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("ours")
+                .value_name("OURS")
+                .index(2)
+                .help("Our version of the todo list, overwritten with the merge result")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("theirs")
+                .value_name("THEIRS")
+                .index(3)
+                .help("Their version of the todo list")
+                .takes_value(true)
+                .required(true),
+        )
+}
+
+/// Merges their version of a Todo list into ours. Returns an error when changes conflict.
+pub fn merge_file_command_process(args: &ArgMatches) -> Result<(), std::io::Error> {
+    trace!("merge-file subcommand");
+    let ours_path = args.value_of("ours").unwrap();
+    let base = read_to_string(args.value_of("base").unwrap())?;
+    let ours = read_to_string(ours_path)?;
+    let theirs = read_to_string(args.value_of("theirs").unwrap())?;
+
+    let (merged, is_clean) = merge(base.as_str(), ours.as_str(), theirs.as_str());
+    std::fs::write(ours_path, merged)?;
+    debug!("wrote {ours_path}");
+    if !is_clean {
+        return Err(std::io::Error::other(format!(
+            "Both sides changed \"{ours_path}\" outside of its task list"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the merge of both versions of a Todo list and whether the merge had no conflict
+///
+/// Content outside of the task list comes from the side which changed it. When both sides changed
+/// it differently, our side is kept and the merge is not clean.
+pub fn merge(base: &str, ours: &str, theirs: &str) -> (String, bool) {
+    let (base_skeleton, ours_skeleton, theirs_skeleton) =
+        (skeleton(base), skeleton(ours), skeleton(theirs));
+    let is_clean = ours_skeleton == base_skeleton
+        || theirs_skeleton == base_skeleton
+        || ours_skeleton == theirs_skeleton;
+    // the merged Todo list is laid out like the side whose non-task content should be kept
+    let (layout, other) = if ours_skeleton == base_skeleton && theirs_skeleton != base_skeleton {
+        (theirs, ours)
+    } else {
+        (ours, theirs)
+    };
+
+    let base_tasks = parse_tasks(base);
+    let layout_tasks = parse_tasks(layout);
+    let other_tasks = parse_tasks(other);
+    let mut lines = layout
+        .lines()
+        .map(|l| Some(l.to_string()))
+        .collect::<Vec<_>>();
+
+    for task in &layout_tasks {
+        let other_task = find(&other_tasks, task);
+        let base_task = find(&base_tasks, task);
+        match (other_task, base_task) {
+            // deleted on the other side and untouched on this side
+            (None, Some(base_task)) if base_task.done == task.done => {
+                for line in lines.iter_mut().take(task.last_line + 1).skip(task.line) {
+                    *line = None;
+                }
+            }
+            // done beats open
+            (Some(other_task), _) if other_task.done && !task.done => {
+                lines[task.line] = Some(format!("* [x] {}", task.text));
+            }
+            _ => {}
+        }
+    }
+    let mut merged = lines.into_iter().flatten().collect::<Vec<_>>().join("\n");
+    if layout.ends_with('\n') {
+        merged.push('\n');
+    }
+
+    let other_lines = other.lines().collect::<Vec<_>>();
+    for task in &other_tasks {
+        if find(&layout_tasks, task).is_some() || find(&base_tasks, task).is_some() {
+            continue;
+        }
+        let task_lines = other_lines[task.line..=task.last_line]
+            .iter()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>();
+        merged = insert_task(merged.as_str(), task, task_lines);
+    }
+
+    (merged, is_clean)
+}
+
+/// Returns the task with the same text within the same section
+fn find<'a>(tasks: &'a [Task], task: &Task) -> Option<&'a Task> {
+    tasks
+        .iter()
+        .find(|t| t.text == task.text && t.section == task.section)
+}
+
+/// Returns Todo list content without its tasks (comments included)
+fn skeleton(todo_raw: &str) -> Vec<String> {
+    let tasks = parse_tasks(todo_raw);
+    todo_raw
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| !tasks.iter().any(|t| t.line <= *i && *i <= t.last_line))
+        .map(|(_, l)| l.trim_end().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Returns Todo list with the lines of the task inserted after the last task of its section. The
+/// section is created at the end of the task list if it does not exist.
+pub(crate) fn insert_task(todo_raw: &str, task: &Task, task_lines: Vec<String>) -> String {
+    insert_task_lines(todo_raw, task.section.as_deref(), &task_lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "\
+# Groceries
+
+## Description
+
+LABEL=
+
+## Todo list
+
+* [ ] milk
+* [ ] bread
+* [ ] eggs
+
+### Hardware
+
+* [ ] nails
+";
+
+    #[test]
+    fn merge_tasks_semantically() {
+        let ours = BASE
+            .replace("* [ ] milk", "* [x] milk")
+            .replace("* [ ] eggs\n", "* [ ] eggs\n* [ ] butter\n");
+        let theirs = BASE
+            .replace("* [ ] bread\n", "")
+            .replace("* [ ] eggs", "* [x] eggs")
+            .replace("* [ ] nails\n", "* [ ] nails\n* [ ] screws\n");
+
+        let (merged, is_clean) = merge(BASE, ours.as_str(), theirs.as_str());
+        assert!(is_clean);
+        assert_eq!(
+            merged,
+            "\
+# Groceries
+
+## Description
+
+LABEL=
+
+## Todo list
+
+* [x] milk
+* [x] eggs
+* [ ] butter
+
+### Hardware
+
+* [ ] nails
+* [ ] screws
+"
+        );
+    }
+
+    #[test]
+    fn keep_description_changed_on_one_side() {
+        let ours = BASE.replace("* [ ] milk", "* [x] milk");
+        let theirs = BASE.replace("LABEL=", "LABEL=home").replace(
+            "* [ ] nails\n",
+            "* [ ] nails\n\n### Garden\n\n* [ ] seeds\n",
+        );
+        let (merged, is_clean) = merge(BASE, ours.as_str(), theirs.as_str());
+        assert!(is_clean);
+        assert!(merged.contains("LABEL=home"));
+        assert!(merged.contains("* [x] milk"));
+        assert!(merged.contains("### Garden\n\n* [ ] seeds\n"));
+
+        let ours = BASE.replace("LABEL=", "LABEL=work");
+        let (merged, is_clean) = merge(BASE, ours.as_str(), theirs.as_str());
+        assert!(!is_clean);
+        assert!(merged.contains("LABEL=work"));
+    }
+
+    #[test]
+    fn task_added_to_missing_section() {
+        let ours = BASE.replace("\n### Hardware\n\n* [ ] nails\n", "");
+        let theirs = BASE.replace("* [ ] nails\n", "* [ ] nails\n* [ ] screws\n");
+        let (merged, is_clean) = merge(BASE, ours.as_str(), theirs.as_str());
+        assert!(is_clean);
+        assert!(merged.ends_with("* [ ] eggs\n\n### Hardware\n\n* [ ] screws\n"));
+    }
+}
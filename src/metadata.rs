@@ -0,0 +1,200 @@
+//! Cache of the metadata of Todo lists
+//!
+//! Reading and parsing every Todo list of a big Todo folder takes time, which shell prompts calling
+//! `todo count` cannot afford. The metadata of each Todo list (title, labels and number of tasks)
+//! is kept inside the `.todo-metadata.toml` file of the Todo folder with the modification time of
+//! its file. Only Todo list files modified since are read again. The archive folder is left out.
+use crate::archive::archive_folder;
+use crate::parse::parse_todo_list;
+use crate::parts::{is_part, part_paths, Parts};
+use crate::single_file::{single_file_path, SingleFile};
+use crate::Context;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+/// File of the Todo folder caching the metadata of Todo lists
+pub const METADATA_FILE: &str = ".todo-metadata.toml";
+
+/// Metadata of a Todo list
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Metadata {
+    pub title: String,
+    pub labels: Vec<String>,
+    /// Number of done tasks
+    pub done: usize,
+    /// Number of tasks
+    pub total: usize,
+}
+
+/// Metadata of the Todo lists of a file
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct CachedFile {
+    /// Modification time of the file (and its parts) when it was read
+    stamp: String,
+    /// Todo lists of the file. Files which are not Todo lists have none.
+    todo_lists: Vec<Metadata>,
+}
+
+/// Metadata of the Todo lists of a Todo context, by file
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct MetadataCache {
+    #[serde(default)]
+    files: BTreeMap<String, CachedFile>,
+}
+
+impl MetadataCache {
+    /// Returns the cache of the Todo context. Without file, or with a file which cannot be
+    /// understood, nothing is cached.
+    pub fn read(ctx: &Context) -> Result<MetadataCache, std::io::Error> {
+        match std::fs::read_to_string(metadata_path(ctx)) {
+            Ok(raw) => Ok(toml::from_str(raw.as_str()).unwrap_or_default()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(MetadataCache::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads again the Todo list files of the Todo context modified since they were cached and
+    /// forgets removed files. Returns true if anything changed.
+    pub fn refresh(&mut self, ctx: &Context) -> Result<bool, std::io::Error> {
+        let mut files = BTreeMap::new();
+        let mut changed = false;
+        for filepath in todo_list_files(ctx)? {
+            let stamp = match stamp(filepath.as_str()) {
+                Some(stamp) => stamp,
+                None => continue,
+            };
+            match self.files.remove(&filepath) {
+                Some(cached) if cached.stamp == stamp => {
+                    files.insert(filepath, cached);
+                }
+                _ => {
+                    let todo_lists = if ctx.is_single_file() {
+                        SingleFile::read(ctx)?.todo_lists
+                    } else {
+                        vec![Parts::read(filepath.as_str())?.joined()]
+                    };
+                    let todo_lists = todo_lists
+                        .iter()
+                        .filter_map(|todo_raw| parse_todo_list(todo_raw).ok())
+                        .map(|todo_list| Metadata {
+                            title: todo_list.title,
+                            labels: todo_list.labels,
+                            done: todo_list.done,
+                            total: todo_list.total,
+                        })
+                        .collect();
+                    debug!("cached metadata of {filepath}");
+                    files.insert(filepath, CachedFile { stamp, todo_lists });
+                    changed = true;
+                }
+            }
+        }
+        changed |= !self.files.is_empty();
+        self.files = files;
+        Ok(changed)
+    }
+
+    /// Returns the metadata of every cached Todo list
+    pub fn todo_lists(&self) -> impl Iterator<Item = &Metadata> {
+        self.files.values().flat_map(|file| file.todo_lists.iter())
+    }
+
+    /// Writes the cache inside the Todo folder of the Todo context
+    pub fn write(&self, ctx: &Context) -> Result<(), std::io::Error> {
+        let path = metadata_path(ctx);
+        std::fs::write(&path, toml::to_string(self).map_err(std::io::Error::other)?)?;
+        debug!("wrote {}", path.display());
+        Ok(())
+    }
+}
+
+/// Returns the path of the metadata cache of the Todo context
+fn metadata_path(ctx: &Context) -> PathBuf {
+    Path::new(ctx.folder_location.as_str()).join(METADATA_FILE)
+}
+
+/// Returns the Todo list files of the Todo context, outside its archive folder and parts aside
+fn todo_list_files(ctx: &Context) -> Result<Vec<String>, std::io::Error> {
+    if ctx.is_single_file() {
+        return Ok(vec![single_file_path(ctx)]);
+    }
+    let archive_folder = archive_folder(ctx);
+    let mut filepaths = vec![];
+    for entry in WalkDir::new(ctx.folder_location.as_str())
+        .into_iter()
+        .filter_entry(|e| e.path() != archive_folder)
+    {
+        let entry = entry.map_err(std::io::Error::other)?;
+        if entry.file_type().is_file()
+            && ctx.is_todo_list_file(entry.path())
+            && !is_part(entry.path())
+        {
+            filepaths.push(entry.path().to_string_lossy().to_string());
+        }
+    }
+    Ok(filepaths)
+}
+
+/// Returns the latest modification time of the file and of its parts, with the number of parts, or
+/// None when the file cannot be read
+fn stamp(filepath: &str) -> Option<String> {
+    let parts = part_paths(filepath);
+    let modified = std::iter::once(filepath)
+        .chain(parts.iter().map(|p| p.as_str()))
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .max()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Some(format!(
+        "{}.{:09}+{}",
+        modified.as_secs(),
+        modified.subsec_nanos(),
+        parts.len()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn cache_only_reads_modified_files() {
+        let folder = TempFolder::new("metadata");
+        let groceries = folder.join("groceries.md");
+        std::fs::write(
+            &groceries,
+            "# groceries\n\n## Description\n\nLABEL=home\n\n## Todo list\n\n* [x] milk\n* [ ] eggs\n",
+        )
+        .unwrap();
+        let ctx = Context {
+            folder_location: folder.location(),
+            ..Default::default()
+        };
+
+        let mut cache = MetadataCache::read(&ctx).unwrap();
+        assert!(cache.refresh(&ctx).unwrap());
+        cache.write(&ctx).unwrap();
+        let mut cache = MetadataCache::read(&ctx).unwrap();
+        assert!(!cache.refresh(&ctx).unwrap());
+        assert_eq!(
+            cache.todo_lists().collect::<Vec<_>>(),
+            vec![&Metadata {
+                title: "groceries".to_string(),
+                labels: vec!["home".to_string()],
+                done: 1,
+                total: 2,
+            }]
+        );
+
+        std::fs::remove_file(&groceries).unwrap();
+        assert!(cache.refresh(&ctx).unwrap());
+        assert_eq!(cache.todo_lists().count(), 0);
+    }
+}
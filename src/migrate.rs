@@ -0,0 +1,325 @@
+//! Reorganise the Todo list files of a Todo context
+//!
+//! Layouts are:
+//!
+//! * `flat`: every Todo list at the root of the Todo folder
+//! * `per-label`: Todo lists inside a subfolder named after their first label (root without label).
+//!   Labels which cannot name a folder of their own (`..`, `a/b`, `archive`...) are rejected.
+//! * `per-year`: Todo lists inside a subfolder named after the year they were last modified
+//!
+//! Only files move, parts (see [`crate::parts`]) along with their Todo list. A shell script undoing the migration is written next to the configuration file
+//! inside `.todo_migrations/`.
+use crate::confirm::{confirm, Operation};
+use crate::parse::parse_todo_list;
+use crate::parts::{is_part, part_path, part_paths};
+use crate::progress::Progress;
+use crate::{read_todo_lists, Context, ARCHIVE_FOLDER};
+use chrono::{DateTime, Datelike, Local};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::path::{Component, Path, PathBuf};
+
+/// Organisation of Todo list files inside a Todo folder
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Layout {
+    Flat,
+    PerLabel,
+    PerYear,
+}
+
+impl Layout {
+    fn parse(layout: &str) -> Layout {
+        match layout {
+            "per-label" => Layout::PerLabel,
+            "per-year" => Layout::PerYear,
+            _ => Layout::Flat,
+        }
+    }
+
+    /// Returns true if a Todo list inside `subfolder` (None at the root) follows this layout
+    fn accepts(&self, subfolder: Option<&str>) -> bool {
+        match (self, subfolder) {
+            (_, None) => *self != Layout::PerYear,
+            (Layout::Flat, Some(_)) => false,
+            (Layout::PerLabel, Some(_)) => true,
+            (Layout::PerYear, Some(folder)) => {
+                folder.len() == 4 && folder.chars().all(|c| c.is_ascii_digit())
+            }
+        }
+    }
+
+    /// Returns the subfolder of a Todo list with `labels` last modified in `year`
+    fn subfolder(&self, labels: &[String], year: i32) -> Option<String> {
+        match self {
+            Layout::Flat => None,
+            Layout::PerLabel => labels.first().cloned(),
+            Layout::PerYear => Some(year.to_string()),
+        }
+    }
+}
+
+/// Returns the Migrate command
+pub fn migrate_command() -> App<'static, 'static> {
+    let layouts = &["flat", "per-label", "per-year"];
+    App::new("migrate")
+        .about("Move Todo list files of active Todo context into another folder layout")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("from")
+                .long("from")
+                .value_name("LAYOUT")
+                .help("Current layout of the Todo folder")
+                .possible_values(layouts)
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("to")
+                .long("to")
+                .value_name("LAYOUT")
+                .help("Layout to migrate to")
+                .possible_values(layouts)
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Print moves without moving any file"),
+        )
+}
+
+/// Moves Todo lists of active Todo context into the new layout
+pub fn migrate_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    ctx: &Context,
+) -> Result<(), std::io::Error> {
+    trace!("migrate subcommand");
+    let from = Layout::parse(args.value_of("from").unwrap());
+    let to = Layout::parse(args.value_of("to").unwrap());
+    let folder = Path::new(ctx.folder_location.as_str());
+
+    let mut todo_lists = vec![];
+    for (filepath, todo_raw) in read_todo_lists(ctx)? {
+        let labels = parse_todo_list(todo_raw.as_str())
+            .map(|t| t.labels)
+            .unwrap_or_default();
+        let modified: DateTime<Local> = std::fs::metadata(&filepath)?.modified()?.into();
+        todo_lists.push((PathBuf::from(filepath), labels, modified.year()));
+    }
+    let moves = plan_moves(folder, &todo_lists, from, to)?;
+
+    if moves.is_empty() {
+        println!("Nothing to migrate");
+        return Ok(());
+    }
+    for (old_path, new_path) in &moves {
+        println!("{} -> {}", old_path.display(), new_path.display());
+    }
+    if args.is_present("dry-run") {
+        return Ok(());
+    }
+    let moved = moves
+        .iter()
+        .filter(|(old_path, _)| !is_part(old_path))
+        .count();
+    let prompt = format!("Move {moved} todo list(s)?");
+    if !confirm(ctx, Operation::Migrate, prompt.as_str())? {
+        return Ok(());
+    }
+
+    let undo_folder = Path::new(todo_configuration_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(".todo_migrations");
+    std::fs::create_dir_all(&undo_folder)?;
+    let timestamp = Local::now().format("%Y%m%d%H%M%S");
+    let undo_path = undo_folder.join(format!("{}-{timestamp}-undo.sh", ctx.name));
+    std::fs::write(&undo_path, undo_script(&moves))?;
+    debug!("wrote {}", undo_path.display());
+
+    let mut progress = Progress::start(args, "migrate", moves.len());
+    for (old_path, new_path) in &moves {
+        if let Some(parent) = new_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(old_path, new_path)?;
+        debug!("moved {} to {}", old_path.display(), new_path.display());
+        progress.advance(new_path.to_string_lossy().as_ref());
+    }
+    progress.finish();
+    println!(
+        "Moved {moved} todo list(s). Undo with: sh \"{}\"",
+        undo_path.display()
+    );
+    Ok(())
+}
+
+/// Returns the old and new path of the Todo lists to move
+///
+/// * `todo_lists` - path, labels and year of last modification of Todo lists
+fn plan_moves(
+    folder: &Path,
+    todo_lists: &[(PathBuf, Vec<String>, i32)],
+    from: Layout,
+    to: Layout,
+) -> Result<Vec<(PathBuf, PathBuf)>, std::io::Error> {
+    let mut moves = vec![];
+    for (filepath, labels, year) in todo_lists {
+        let relative = filepath.strip_prefix(folder).unwrap_or(filepath);
+        let mut components = relative.iter().map(|c| c.to_string_lossy().to_string());
+        let subfolder = match relative.iter().count() {
+            1 => None,
+            2 => components.next(),
+            _ => {
+                debug!("skipping nested {}", filepath.display());
+                continue;
+            }
+        };
+        if subfolder.as_deref() == Some(ARCHIVE_FOLDER) {
+            continue;
+        }
+        if !from.accepts(subfolder.as_deref()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{} does not follow the {} layout",
+                    filepath.display(),
+                    format!("{from:?}").to_lowercase()
+                ),
+            ));
+        }
+        let file_name = filepath.file_name().unwrap();
+        let new_path = match to.subfolder(labels, *year) {
+            Some(subfolder) if !is_folder_name(subfolder.as_str()) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} cannot move into a folder named after its label \"{subfolder}\"",
+                        filepath.display()
+                    ),
+                ));
+            }
+            Some(subfolder) => folder.join(subfolder).join(file_name),
+            None => folder.join(file_name),
+        };
+        if new_path != *filepath {
+            // parts follow their Todo list
+            let parts = part_paths(filepath.to_string_lossy().as_ref());
+            let new_filepath = new_path.to_string_lossy().to_string();
+            moves.push((filepath.clone(), new_path));
+            for (i, part) in parts.into_iter().enumerate() {
+                moves.push((PathBuf::from(part), part_path(new_filepath.as_str(), i + 1)));
+            }
+        }
+    }
+    for (i, (_, new_path)) in moves.iter().enumerate() {
+        let taken = new_path.exists() || moves[..i].iter().any(|(_, other)| other == new_path);
+        if taken {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} would be overwritten", new_path.display()),
+            ));
+        }
+    }
+    Ok(moves)
+}
+
+/// Returns true if `name` names a subfolder of the Todo folder, other than its archive folder
+fn is_folder_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) && !name.contains(['/', '\\'])
+        && name != ARCHIVE_FOLDER
+}
+
+/// Returns `s` quoted for POSIX shells
+pub(crate) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Returns a shell script moving Todo lists back to their old path
+fn undo_script(moves: &[(PathBuf, PathBuf)]) -> String {
+    let quote = |path: &Path| shell_quote(path.display().to_string().as_str());
+    let mut script = "#!/bin/sh\nset -e\n".to_string();
+    for (old_path, new_path) in moves.iter().rev() {
+        script.push_str(format!("mv -- {} {}\n", quote(new_path), quote(old_path)).as_str());
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_migrations() {
+        let folder = Path::new("/todo");
+        let todo_lists = vec![
+            (
+                PathBuf::from("/todo/release.md"),
+                vec!["work".to_string(), "urgent".to_string()],
+                2024,
+            ),
+            (PathBuf::from("/todo/groceries.md"), vec![], 2023),
+            (
+                PathBuf::from("/todo/archive/old.md"),
+                vec!["work".to_string()],
+                2020,
+            ),
+        ];
+        let moves = plan_moves(folder, &todo_lists, Layout::Flat, Layout::PerLabel).unwrap();
+        assert_eq!(
+            moves,
+            vec![(
+                PathBuf::from("/todo/release.md"),
+                PathBuf::from("/todo/work/release.md")
+            )]
+        );
+        let moves = plan_moves(folder, &todo_lists, Layout::Flat, Layout::PerYear).unwrap();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[1].1, PathBuf::from("/todo/2023/groceries.md"));
+
+        let per_label = vec![(
+            PathBuf::from("/todo/work/release.md"),
+            vec!["work".to_string()],
+            2024,
+        )];
+        assert!(plan_moves(folder, &per_label, Layout::Flat, Layout::PerYear).is_err());
+        let moves = plan_moves(folder, &per_label, Layout::PerLabel, Layout::Flat).unwrap();
+        for label in ["..", "a/b", "/etc", ".", "archive", ""] {
+            let todo_lists = vec![(
+                PathBuf::from("/todo/release.md"),
+                vec![label.to_string()],
+                2024,
+            )];
+            assert!(plan_moves(folder, &todo_lists, Layout::Flat, Layout::PerLabel).is_err());
+        }
+        assert_eq!(
+            undo_script(&moves),
+            "#!/bin/sh\nset -e\nmv -- '/todo/release.md' '/todo/work/release.md'\n"
+        );
+    }
+
+    #[test]
+    fn parts_follow_their_todo_list() {
+        let folder = crate::test_support::TempFolder::new("migrate");
+        std::fs::write(folder.join("release.md"), "# Release\n").unwrap();
+        std::fs::write(folder.join("release.1.md"), "* [ ] ship\n").unwrap();
+        let todo_lists = vec![(folder.join("release.md"), vec!["work".to_string()], 2024)];
+        let moves = plan_moves(&folder, &todo_lists, Layout::Flat, Layout::PerLabel).unwrap();
+        assert_eq!(
+            moves,
+            vec![
+                (folder.join("release.md"), folder.join("work/release.md")),
+                (
+                    folder.join("release.1.md"),
+                    folder.join("work/release.1.md")
+                ),
+            ]
+        );
+    }
+}
@@ -1,10 +1,25 @@
 //! Move Todo list in specified Todo context
+//!
+//! `todo move --copy` leaves the Todo list in place and writes a copy in the other Todo context.
+//! `--as NEW_TITLE` rewrites the title of the moved or copied Todo list. When a different Todo
+//! list is already at destination, `--on-collision` tells whether to prompt, overwrite, abort or
+//! write beside it with a numbered title (see [`crate::conflict`]). Parts of the Todo list (see
+//! [`crate::parts`]) are moved along with it.
 use core::fmt;
 
-use crate::{prompt_for_todo_folder_if_not_exists, todo_path};
+use crate::conflict::{on_collision_arg, resolve_collision, OnCollision, Placement};
+use crate::lock::ensure_unlocked;
+use crate::parts::{part_path, part_paths, Parts};
+use crate::pick::pick_ctx;
+use crate::single_file::{retitle, title_of, SingleFile};
+use crate::{
+    closest_names, did_you_mean, path_arg, prompt_for_todo_folder_if_not_exists, resolve_todo_path,
+    todo_path,
+};
 
-use super::Configuration;
+use super::{Configuration, Context};
 use clap::{crate_authors, App, Arg, ArgMatches};
+use log::debug;
 
 /// Errors for move command
 #[derive(Debug)]
@@ -22,6 +37,14 @@ pub enum Error {
     // First argument is the name of the file to move
     // Second argument is the path to the file to move
     NothingToMove(String, String),
+    /// The Todo list is locked against modification. Argument is the Todo list path.
+    Locked(String),
+    /// The path of the Todo list could not be resolved from its title
+    Resolution(std::io::Error),
+    /// The conflict with the Todo list at the new path could not be resolved
+    Conflict(std::io::Error),
+    /// No Todo context to move to was given nor picked
+    NoContext(std::io::Error),
 }
 
 impl fmt::Display for Error {
@@ -33,7 +56,12 @@ impl fmt::Display for Error {
                 } else {
                     writeln!(f, "New path is unknown!")?;
                 }
-                writeln!(f, "\"{ctx_name}\" does not match any available context.")?;
+                let closest = closest_names(ctx_name, ctxs_available_names.iter().map(|n| n.as_str()));
+                writeln!(
+                    f,
+                    "\"{ctx_name}\" does not match any available context{}.",
+                    did_you_mean(&closest)
+                )?;
                 writeln!(f, "Please select a name among:")?;
                 for ctx_name in ctxs_available_names {
                     writeln!(f, "- {ctx_name}")?;
@@ -48,6 +76,12 @@ impl fmt::Display for Error {
             Error::NothingToMove(file, filepath) => {
                 writeln!(f, "File \"{file}\" could not be moved because there is nothing at \"{filepath}\"")?
             }
+            Error::Locked(filepath) => {
+                writeln!(f, "Todo list at \"{filepath}\" is locked. Unlock it or use --force.")?
+            }
+            Error::Resolution(e) => writeln!(f, "{e}")?,
+            Error::Conflict(e) => writeln!(f, "Conflict could not be resolved: {e}")?,
+            Error::NoContext(e) => writeln!(f, "{e}")?,
         }
 
         Ok(())
@@ -57,7 +91,8 @@ impl fmt::Display for Error {
 /// Returns the Edit Todo command
 pub fn move_command() -> App<'static, 'static> {
     App::new("move")
-        .about("Move todo list into other Todo context")
+        .visible_alias("mv")
+        .about("Move or copy todo list into other Todo context")
         .author(crate_authors!())
         .arg(
             Arg::with_name("title")
@@ -67,26 +102,76 @@ pub fn move_command() -> App<'static, 'static> {
                 .index(1)
                 .help("Title of Todo list to move")
                 .takes_value(true)
-                .required(true),
+                .required_unless("path"),
         )
+        .arg(path_arg())
         .arg(
             Arg::with_name("context name")
                 .short("c")
                 .long("ctx")
                 .value_name("CONTEXT_NAME")
                 .index(2)
-                .help("Name of todo context to move to")
-                .takes_value(true)
-                .required(true),
+                .help("Name of todo context to move to (picked interactively when omitted)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("copy")
+                .long("copy")
+                .help("Copies todo list instead of moving it"),
+        )
+        .arg(
+            Arg::with_name("new title")
+                .long("as")
+                .value_name("NEW_TITLE")
+                .help("Title of todo list once moved or copied")
+                .takes_value(true),
+        )
+        .arg(on_collision_arg())
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Move todo list even if it is locked"),
         )
 }
 
-/// Move Todo list from active Todo to specified context
+/// Move Todo list from active Todo to specified context. With `--copy`, the Todo list is copied
+/// instead.
 pub fn move_command_process(args: &ArgMatches, config: &Configuration) -> Result<(), Error> {
-    let title = args.value_of("title").unwrap();
-    let ctx_name = args.value_of("context name").unwrap();
+    let title = args.value_of("title");
+    let copy = args.is_present("copy");
+    let picked;
+    let ctx_name = match args.value_of("context name") {
+        Some(ctx_name) => ctx_name,
+        None => {
+            picked = pick_ctx(
+                config,
+                if copy {
+                    "Copy to context"
+                } else {
+                    "Move to context"
+                },
+                (!copy).then_some(config.active_ctx_name.as_str()),
+                args.is_present("no-input"),
+            )
+            .map_err(Error::NoContext)?;
+            picked.as_str()
+        }
+    };
+
+    if let Some(new_ctx) = config.ctxs.iter().find(|&ctx| ctx.name == ctx_name) {
+        let ctx = config.active_ctx();
+        if ctx.is_single_file() || new_ctx.is_single_file() {
+            return move_with_single_file(args, ctx, new_ctx);
+        }
+    }
 
-    let (old_path, new_path) = match paths_for_moving_todo_list(title, ctx_name, config) {
+    let paths = match args.value_of("path") {
+        Some(filepath) => new_path_for_moving_file(filepath, ctx_name, config)
+            .map(|new_path| (filepath.to_string(), new_path)),
+        None => paths_for_moving_todo_list(title.unwrap(), ctx_name, config),
+    };
+    let (old_path, new_path) = match paths {
         Ok(vs) => (vs.0, vs.1),
         Err(e) => {
             eprintln!("{e}");
@@ -108,12 +193,23 @@ pub fn move_command_process(args: &ArgMatches, config: &Configuration) -> Result
             return Err(e);
         }
     };
+    let new_path = match args.value_of("new title") {
+        Some(new_title) => todo_path(new_ctx, new_title),
+        None => new_path,
+    };
 
     // Note: std::fs::rename does not indicate why the renaming fails. However
     // we can assume rename will fail if there is no file to copy from hence why
     // we test if filepath leads to a file.
     if !std::path::Path::new(&old_path).is_file() {
-        return Err(Error::NothingToMove(title.to_string(), old_path));
+        return Err(Error::NothingToMove(
+            title.unwrap_or(old_path.as_str()).to_string(),
+            old_path,
+        ));
+    }
+
+    if !copy && ensure_unlocked(old_path.as_str(), args.is_present("force")).is_err() {
+        return Err(Error::Locked(old_path));
     }
 
     if let Err(e) = prompt_for_todo_folder_if_not_exists(new_ctx) {
@@ -121,26 +217,253 @@ pub fn move_command_process(args: &ArgMatches, config: &Configuration) -> Result
         return Err(Error::PromptingUserForContextFolderCreation);
     }
 
-    if std::fs::rename(&old_path, &new_path).is_err() {
-        eprintln!("Error: file could not be moved from {old_path} to {new_path}.");
-        return Err(Error::Renaming);
+    let parts = Parts::read(old_path.as_str()).map_err(|_| Error::Renaming)?;
+    let todo_raw = parts.joined();
+    let incoming = match args.value_of("new title") {
+        Some(new_title) => retitle(todo_raw.as_str(), new_title),
+        None => todo_raw.clone(),
+    };
+    let onto_itself = old_path == new_path;
+    if onto_itself && !copy && incoming == todo_raw {
+        debug!("{old_path} is already in place");
+        return Ok(());
+    }
+    let local = Parts::read(new_path.as_str())
+        .ok()
+        .map(|local| local.joined())
+        .filter(|local| *local != incoming || onto_itself);
+    let placement = place(
+        args,
+        new_path.as_str(),
+        local.as_deref(),
+        incoming.as_str(),
+        onto_itself,
+        |t| std::path::Path::new(todo_path(new_ctx, t).as_str()).exists(),
+    )?;
+    let (new_path, todo_list) = match placement {
+        Placement::Replace(todo_list) => (new_path, todo_list),
+        Placement::Beside(new_title, todo_list) => {
+            (todo_path(new_ctx, new_title.as_str()), todo_list)
+        }
+    };
+
+    let written = match write_parts(&parts, todo_list.as_str(), new_path.as_str()) {
+        Ok(written) => written,
+        Err(e) => {
+            eprintln!("Error: Todo list could not be written to {new_path}: {e}");
+            return Err(Error::Renaming);
+        }
+    };
+    if copy {
+        debug!("copied {old_path} to {new_path}");
+        return Ok(());
+    }
+
+    for path in parts.paths.iter().filter(|path| !written.contains(path)) {
+        if std::fs::remove_file(path).is_err() {
+            eprintln!("Error: {path} could not be removed after moving it to {new_path}.");
+            return Err(Error::Renaming);
+        }
     }
+    debug!("moved {old_path} to {new_path}");
 
     Ok(())
 }
 
+/// Writes `todo_list` at `new_path`, spread over as many parts as `parts` (the Todo list it comes
+/// from) when its lines still fit them, then removes the parts left over at `new_path`. Returns the
+/// written paths.
+fn write_parts(
+    parts: &Parts,
+    todo_list: &str,
+    new_path: &str,
+) -> Result<Vec<String>, std::io::Error> {
+    let mut moved = parts.clone();
+    // tasks merged from the replaced Todo list do not fit the parts anymore
+    if moved.set(todo_list).is_err() {
+        moved.raws = vec![todo_list.to_string()];
+    }
+    moved.paths = (0..moved.raws.len())
+        .map(|i| part_path(new_path, i).to_string_lossy().to_string())
+        .collect();
+    let left_over = part_paths(new_path)
+        .into_iter()
+        .filter(|path| !moved.paths.contains(path))
+        .collect::<Vec<_>>();
+    moved.write()?;
+    for path in left_over {
+        std::fs::remove_file(&path)?;
+        debug!("removed {path}");
+    }
+    Ok(moved.paths)
+}
+
+/// Moves (or copies) Todo list when either Todo context stores its Todo lists inside a single file
+fn move_with_single_file(args: &ArgMatches, ctx: &Context, new_ctx: &Context) -> Result<(), Error> {
+    let copy = args.is_present("copy");
+    let force = args.is_present("force") || copy;
+    let single_file_error = |e: std::io::Error, title: &str, path: &str| match e.kind() {
+        std::io::ErrorKind::NotFound => Error::NothingToMove(title.to_string(), path.to_string()),
+        std::io::ErrorKind::PermissionDenied => Error::Locked(format!("{path} ({title})")),
+        _ => Error::Resolution(e),
+    };
+
+    let (todo_raw, old_path) = match args.value_of("path") {
+        Some(filepath) => (None, filepath.to_string()),
+        None if ctx.is_single_file() => {
+            let title = args.value_of("title").unwrap();
+            let single_file = SingleFile::read(ctx).map_err(Error::Resolution)?;
+            let i = single_file
+                .position_of_unlocked(title, force)
+                .map_err(|e| single_file_error(e, title, single_file.path.as_str()))?;
+            (Some(single_file.todo_lists[i].clone()), single_file.path)
+        }
+        None => {
+            let title = args.value_of("title").unwrap();
+            let filepath = resolve_todo_path(ctx, title).map_err(Error::Resolution)?;
+            (None, filepath)
+        }
+    };
+    let todo_raw = match todo_raw {
+        Some(todo_raw) => todo_raw,
+        None => {
+            let todo_raw = Parts::read(old_path.as_str())
+                .map(|parts| parts.joined())
+                .map_err(|_| {
+                    let title = args.value_of("title").unwrap_or(old_path.as_str());
+                    Error::NothingToMove(title.to_string(), old_path.clone())
+                })?;
+            if ensure_unlocked(old_path.as_str(), force).is_err() {
+                return Err(Error::Locked(old_path));
+            }
+            todo_raw
+        }
+    };
+    let title = title_of(todo_raw.as_str()).to_string();
+    let incoming = match args.value_of("new title") {
+        Some(new_title) => retitle(todo_raw.as_str(), new_title),
+        None => todo_raw.clone(),
+    };
+    let new_title = title_of(incoming.as_str()).to_string();
+
+    if let Err(e) = prompt_for_todo_folder_if_not_exists(new_ctx) {
+        eprintln!("Error: {e}");
+        return Err(Error::PromptingUserForContextFolderCreation);
+    }
+    let new_path = if new_ctx.is_single_file() {
+        let mut single_file = SingleFile::read(new_ctx).map_err(Error::Resolution)?;
+        let onto_itself = single_file.path == old_path && new_title == title;
+        if onto_itself && !copy {
+            debug!("\"{title}\" is already in place");
+            return Ok(());
+        }
+        let position = single_file
+            .position(new_title.as_str())
+            .map_err(Error::Resolution)?;
+        let local = position
+            .map(|i| single_file.todo_lists[i].as_str())
+            .filter(|local| *local != incoming || onto_itself);
+        let placement = place(
+            args,
+            single_file.path.as_str(),
+            local,
+            incoming.as_str(),
+            onto_itself,
+            |t| single_file.position(t).is_ok_and(|i| i.is_some()),
+        )?;
+        match (placement, position) {
+            (Placement::Replace(todo_list), Some(i)) => single_file.todo_lists[i] = todo_list,
+            (Placement::Replace(todo_list), None) | (Placement::Beside(_, todo_list), _) => {
+                single_file.todo_lists.push(todo_list)
+            }
+        }
+        single_file.write().map_err(|_| Error::Renaming)?;
+        single_file.path
+    } else {
+        let new_path = todo_path(new_ctx, new_title.as_str());
+        let local = Parts::read(new_path.as_str())
+            .ok()
+            .map(|local| local.joined())
+            .filter(|local| *local != incoming);
+        let placement = place(
+            args,
+            new_path.as_str(),
+            local.as_deref(),
+            incoming.as_str(),
+            false,
+            |t| std::path::Path::new(todo_path(new_ctx, t).as_str()).exists(),
+        )?;
+        let (new_path, todo_list) = match placement {
+            Placement::Replace(todo_list) => (new_path, todo_list),
+            Placement::Beside(t, todo_list) => (todo_path(new_ctx, t.as_str()), todo_list),
+        };
+        let parts = Parts {
+            paths: vec![old_path.clone()],
+            raws: vec![incoming.clone()],
+        };
+        write_parts(&parts, todo_list.as_str(), new_path.as_str()).map_err(|_| Error::Renaming)?;
+        new_path
+    };
+    if copy {
+        debug!("copied \"{title}\" from {old_path} to {new_path}");
+        return Ok(());
+    }
+
+    if args.value_of("path").is_none() && ctx.is_single_file() {
+        let mut single_file = SingleFile::read(ctx).map_err(Error::Resolution)?;
+        let i = single_file
+            .position_of_unlocked(title.as_str(), true)
+            .map_err(|e| single_file_error(e, title.as_str(), old_path.as_str()))?;
+        single_file.todo_lists.remove(i);
+        single_file.write().map_err(|_| Error::Renaming)?;
+    } else {
+        for path in std::iter::once(old_path.clone()).chain(part_paths(old_path.as_str())) {
+            std::fs::remove_file(&path).map_err(|_| Error::Renaming)?;
+        }
+    }
+    debug!("moved \"{title}\" from {old_path} to {new_path}");
+    Ok(())
+}
+
+/// Returns where and what to write as the Todo list brought at `new_path`, which may already hold
+/// the `local` Todo list. See `--on-collision`.
+///
+/// A Todo list copied `onto_itself` must be written beside itself with a numbered title.
+fn place(
+    args: &ArgMatches,
+    new_path: &str,
+    local: Option<&str>,
+    incoming: &str,
+    onto_itself: bool,
+    is_taken: impl Fn(&str) -> bool,
+) -> Result<Placement, Error> {
+    let on_collision = OnCollision::from_args(args);
+    if onto_itself && on_collision != OnCollision::Suffix {
+        return Err(Error::Conflict(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "the Todo list would be copied onto itself. Use --as or --on-collision suffix",
+        )));
+    }
+    let placement = resolve_collision(new_path, local, incoming, on_collision, is_taken)
+        .map_err(Error::Conflict)?;
+    if let Placement::Beside(new_title, _) = &placement {
+        println!("A Todo list already exists at \"{new_path}\", wrote \"{new_title}\" instead");
+    }
+    Ok(placement)
+}
+
 /// Returns the path of the Todo list and the new path to move the Todo list
 fn paths_for_moving_todo_list(
     title: &str,
     ctx_name: &str,
     config: &Configuration,
 ) -> Result<(String, String), Error> {
-    let current_folder_location_of_todo_list = match config
+    let current_ctx = match config
         .ctxs
         .iter()
         .find(|&ctx| ctx.name == config.active_ctx_name)
     {
-        Some(ctx) => ctx.folder_location.as_str(),
+        Some(ctx) => ctx,
         None => {
             eprintln!("Error: No matching context was found for {ctx_name}");
             return Err(Error::UnknownContext(
@@ -150,30 +473,52 @@ fn paths_for_moving_todo_list(
             ));
         }
     };
-    let new_folder_location_of_todo_list =
-        match config.ctxs.iter().find(|&ctx| ctx.name == ctx_name) {
-            Some(ctx) => ctx.folder_location.as_str(),
-            None => {
-                eprintln!("Error: No matching context was found for {ctx_name}");
-                return Err(Error::UnknownContext(
-                    false,
-                    ctx_name.to_string(),
-                    config.ctxs.iter().map(|ctx| ctx.name.to_string()).collect(),
-                ));
-            }
-        };
+    let new_ctx = match config.ctxs.iter().find(|&ctx| ctx.name == ctx_name) {
+        Some(ctx) => ctx,
+        None => {
+            eprintln!("Error: No matching context was found for {ctx_name}");
+            return Err(Error::UnknownContext(
+                false,
+                ctx_name.to_string(),
+                config.ctxs.iter().map(|ctx| ctx.name.to_string()).collect(),
+            ));
+        }
+    };
 
-    let old_path = todo_path(current_folder_location_of_todo_list, title);
-    let new_path = todo_path(new_folder_location_of_todo_list, title);
+    let old_path = resolve_todo_path(current_ctx, title).map_err(Error::Resolution)?;
+    let new_path = todo_path(new_ctx, title);
 
     Ok((old_path, new_path))
 }
 
+/// Returns the new path to move the Todo list file into, keeping its file name
+fn new_path_for_moving_file(
+    filepath: &str,
+    ctx_name: &str,
+    config: &Configuration,
+) -> Result<String, Error> {
+    let new_ctx = match config.ctxs.iter().find(|&ctx| ctx.name == ctx_name) {
+        Some(ctx) => ctx,
+        None => {
+            return Err(Error::UnknownContext(
+                false,
+                ctx_name.to_string(),
+                config.ctxs.iter().map(|ctx| ctx.name.to_string()).collect(),
+            ));
+        }
+    };
+    let file_name = std::path::Path::new(filepath)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    Ok(format!("{}/{file_name}", new_ctx.folder_location))
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use crate::Context;
     // use simplelog::*;
 
     // // TODO wait for before/after_test macro
@@ -197,14 +542,17 @@ mod tests {
                     name: "ctx1".to_string(),
                     timezone: "".to_string(),
                     folder_location: "/path/to/folder1".to_string(),
+                    ..Default::default()
                 },
                 Context {
                     ide: "".to_string(),
                     name: "ctx2".to_string(),
                     timezone: "".to_string(),
                     folder_location: "/path/to/folder2".to_string(),
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
         let (old_path, new_path) = paths_for_moving_todo_list("file", "ctx2", &config).unwrap();
         // Note: abstract the file extension to not make the test brittle
@@ -214,6 +562,47 @@ mod tests {
         assert!(new_path.starts_with(expected_new_path));
     }
 
+    #[test]
+    fn move_file_keeps_its_name() {
+        let config = Configuration {
+            active_ctx_name: "ctx1".to_string(),
+            ctxs: vec![
+                Context {
+                    name: "ctx1".to_string(),
+                    folder_location: "/path/to/folder1".to_string(),
+                    ..Default::default()
+                },
+                Context {
+                    name: "ctx2".to_string(),
+                    folder_location: "/path/to/folder2".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let new_path =
+            new_path_for_moving_file("/somewhere/else/notes.txt", "ctx2", &config).unwrap();
+        assert_eq!(new_path, "/path/to/folder2/notes.txt");
+        assert!(new_path_for_moving_file("notes.txt", "unknown", &config).is_err());
+    }
+
+    #[test]
+    fn promote_workspace_todo_list() {
+        let mut config = Configuration {
+            active_ctx_name: "ctx1".to_string(),
+            ctxs: vec![Context {
+                name: "ctx1".to_string(),
+                folder_location: "/path/to/folder1".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        config.use_workspace(std::path::Path::new("/repo/.todo"));
+        let (old_path, new_path) = paths_for_moving_todo_list("file", "ctx1", &config).unwrap();
+        assert!(old_path.starts_with("/repo/.todo/file."));
+        assert!(new_path.starts_with("/path/to/folder1/file."));
+    }
+
     #[test]
     fn unknown_context_throws_error() {
         let config = Configuration {
@@ -224,14 +613,17 @@ mod tests {
                     name: "ctx1".to_string(),
                     timezone: "".to_string(),
                     folder_location: "/path/to/folder1".to_string(),
+                    ..Default::default()
                 },
                 Context {
                     ide: "".to_string(),
                     name: "ctx2".to_string(),
                     timezone: "".to_string(),
                     folder_location: "/path/to/folder2".to_string(),
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
         let paths = paths_for_moving_todo_list("file", "unknown", &config);
         assert!(paths.is_err());
@@ -242,7 +634,35 @@ mod tests {
                 assert_eq!(available_ctxs, vec!["ctx1".to_string(), "ctx2".to_string()])
             }
 
-            _ => assert!(false),
+            _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn move_todo_list_with_its_parts() {
+        let root = crate::test_support::TempFolder::new("move");
+        let (folder1, folder2) = (root.join("ctx1"), root.join("ctx2"));
+        std::fs::create_dir_all(&folder1).unwrap();
+        std::fs::create_dir_all(&folder2).unwrap();
+        let ctx = |name: &str, folder: &std::path::Path| Context {
+            name: name.to_string(),
+            folder_location: folder.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let config = Configuration {
+            active_ctx_name: "ctx1".to_string(),
+            ctxs: vec![ctx("ctx1", &folder1), ctx("ctx2", &folder2)],
+            ..Default::default()
+        };
+        let raw = "# live\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n";
+        std::fs::write(folder1.join("live.md"), raw).unwrap();
+        std::fs::write(folder1.join("live.1.md"), "* [ ] b\n").unwrap();
+
+        let args = move_command().get_matches_from(vec!["move", "live", "ctx2", "--as", "Live"]);
+        move_command_process(&args, &config).unwrap();
+        assert!(std::fs::read_dir(&folder1).unwrap().next().is_none());
+        let moved = Parts::read(folder2.join("Live.md").to_str().unwrap()).unwrap();
+        assert_eq!(moved.raws.len(), 2);
+        assert_eq!(moved.raws[1], "* [ ] b\n");
+    }
 }
@@ -4,33 +4,28 @@
 //! serialize a Todo list with a crate and expect it to be managed by a human. This module parses also
 //! the configuration file.
 use super::{Configuration, Context};
+use crate::config_schema::unknown_keys;
 use lazy_static::lazy_static;
 use log::{debug, trace};
 use regex::Regex;
 use std::io::Read;
-
-/// Represents a parsed Todo list.
-///
-/// A parsed Todo list has many relevant informations such as its name and its task list status.
-pub struct ParsedTodoList {
-    pub raw: String,
-    pub title: String,
-    pub labels: Vec<String>,
-    pub done: usize,
-    pub total: usize,
-}
-
-impl ParsedTodoList {
-    /// Returns true if all items from task list are checked
-    pub fn tasks_are_all_done(&self) -> bool {
-        self.done == self.total
-    }
-}
+use std::sync::Once;
+use todo_core::task::section_heading;
+pub use todo_core::task::{
+    parse_due_date, parse_priority, parse_sections, parse_tasks, Section, Task, ARCHIVE_HEADING,
+};
+use todo_core::todo_list::{
+    normalize_task, parse_task_list, parse_todo_list_tasks_status, parse_todo_list_title,
+};
+pub use todo_core::todo_list::{
+    parse_todo_list_motives, parse_todo_list_parts, ParsedTodoList, TodoListParts,
+};
 
 // Regexes which are used at several places
 lazy_static! {
-    static ref TODO_LIST_RE: Regex =
-        Regex::new("\n## Todo list\n\n(?sm)(?P<list>.*?)(?-m:$|\n## .*)").unwrap();
+    static ref TASK_ID_RE: Regex = Regex::new(r"(?:^|\s)\^(?P<id>[0-9A-Za-z-]+)$").unwrap();
+    static ref TASK_REFERENCE_RE: Regex = Regex::new(r"@see\(\^(?P<id>[0-9A-Za-z-]+)\)").unwrap();
+    static ref WIKILINK_RE: Regex = Regex::new(r"\[\[(?P<title>[^\[\]]+)\]\]").unwrap();
 }
 
 /// Returns configuration of all Todo contexts and the name of the active context
@@ -64,14 +59,20 @@ pub fn parse_configuration_file(
         }
     };
 
+    // configuration is parsed more than once per invocation
+    static UNKNOWN_KEYS_WARNING: Once = Once::new();
+    UNKNOWN_KEYS_WARNING.call_once(|| {
+        for warning in unknown_keys(content) {
+            eprintln!("Warning: {warning}");
+        }
+    });
     let configuration: Configuration = toml::from_str(content)?;
     if !configuration
         .ctxs
         .iter()
         .any(|c| c.name == configuration.active_ctx_name)
     {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
+        return Err(std::io::Error::other(
             "Invalid configuration because no contexts correspond to active context",
         ));
     }
@@ -79,6 +80,97 @@ pub fn parse_configuration_file(
     Ok(configuration)
 }
 
+/// Returns the configuration with only the Todo contexts which can be active inside `dir`: the
+/// configured active context, the one named by `TODO_ACTIVE_CTX` and the one named by the closest
+/// context marker
+///
+/// Other Todo contexts and tables are not parsed, which keeps commands needing only the active Todo
+/// context (`todo statusline`) fast. Returns None when the configuration is not laid out as written
+/// by `todo config` (one `[[ctxs]]` table per Todo context), in which case the whole configuration
+/// should be parsed with `parse_configuration_file`.
+pub fn parse_active_configuration(
+    raw_configuration: &str,
+    dir: &std::path::Path,
+) -> Result<Option<Configuration>, std::io::Error> {
+    lazy_static! {
+        static ref CTX_NAME_RE: Regex = Regex::new(r#"^\s*name\s*=\s*"([^"\\]*)"\s*$"#).unwrap();
+    }
+    let mut top_level = String::new();
+    let mut ctxs: Vec<(Option<String>, String)> = vec![];
+    // true inside tables other than Todo contexts
+    let mut skipped = false;
+    for line in raw_configuration.lines() {
+        let header = line.trim();
+        if header == "[[ctxs]]" {
+            ctxs.push((None, String::new()));
+            skipped = false;
+        } else if header.starts_with("[ctxs.") {
+            // subtable of the last Todo context
+            if ctxs.is_empty() {
+                return Ok(None);
+            }
+            skipped = false;
+        } else if header.starts_with('[') {
+            skipped = true;
+        }
+        if skipped {
+            continue;
+        }
+        match ctxs.last_mut() {
+            Some((name, table)) => {
+                if name.is_none() {
+                    *name = CTX_NAME_RE.captures(line).map(|caps| caps[1].to_string());
+                }
+                table.push_str(line);
+                table.push('\n');
+            }
+            None => {
+                top_level.push_str(line);
+                top_level.push('\n');
+            }
+        }
+    }
+
+    let values = top_level.parse::<toml::Value>()?;
+    let mut names = vec![];
+    match values.get("active_ctx_name").and_then(|n| n.as_str()) {
+        Some(name) => names.push(name.to_string()),
+        None => return Ok(None),
+    }
+    if let Some(name) = std::env::var(crate::ACTIVE_CTX_VARIABLE)
+        .ok()
+        .filter(|n| !n.is_empty())
+    {
+        names.push(name);
+    }
+    if values.get("detect_context").and_then(|d| d.as_bool()) == Some(true) {
+        if let Some((_, name)) = crate::context_marker(dir)? {
+            names.push(name);
+        }
+    }
+
+    let mut content = top_level;
+    for (name, table) in ctxs {
+        match name {
+            Some(name) if names.contains(&name) => content.push_str(table.as_str()),
+            Some(_) => {}
+            None => return Ok(None),
+        }
+    }
+    let configuration: Configuration = toml::from_str(content.as_str())?;
+    if !configuration
+        .ctxs
+        .iter()
+        .any(|c| c.name == configuration.active_ctx_name)
+    {
+        return Err(std::io::Error::other(
+            "Invalid configuration because no contexts correspond to active context",
+        ));
+    }
+    debug!("parsed contexts {names:?} of configuration");
+    Ok(Some(configuration))
+}
+
 /// Returns active Todo context of configuration
 ///
 /// Uses `raw configuration` when supplied instead of `todo_configuration_path`.
@@ -96,33 +188,34 @@ pub fn parse_active_context(
     Ok(conf.clone())
 }
 
-/// Returns parsed Todo list
-///
-/// The motivation for this function is that instead of saving all the content through serializing
-/// with a crate like Serde, the user can open the file and find it editable (think editing a json
-/// vs xml file).
+/// Returns parsed Todo list (see [`todo_core::parse_todo_list`])
 pub fn parse_todo_list(todo_raw: &str) -> Result<ParsedTodoList, std::io::Error> {
-    let title = parse_todo_list_title(todo_raw);
-    if title.is_none() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Todo list does not have a title",
-        ));
-    }
-    let labels = parse_todo_list_labels(todo_raw).unwrap();
+    todo_core::parse_todo_list(todo_raw).map_err(|e| std::io::Error::other(e.to_string()))
+}
+
+/// Returns true if the file content is a Todo list rather than notes: it has a title and labels
+/// below `## Description`, like Todo lists written by `todo create`
+pub fn is_todo_list(todo_raw: &str) -> bool {
+    parse_todo_list(todo_raw).is_ok()
+}
+
+/// Returns parsed notes, a markdown file with a title which is not a Todo list (see
+/// [`is_todo_list`]), as a Todo list without labels
+pub fn parse_notes(todo_raw: &str) -> Result<ParsedTodoList, std::io::Error> {
+    let title = parse_todo_list_title(todo_raw)
+        .ok_or_else(|| std::io::Error::other("Notes do not have a title"))?;
     let (done, total) = parse_todo_list_tasks_status(todo_raw);
-    let todo = ParsedTodoList {
+    Ok(ParsedTodoList {
         raw: todo_raw.to_string(),
-        title: title.unwrap(),
-        labels,
+        title,
+        labels: vec![],
         done,
         total,
-    };
-
-    Ok(todo)
+    })
 }
 
-/// Returns parsed Todo list
+/// Returns parsed section of Todo list, whose task counts include its subsections (see
+/// [`Section`] for direct task counts)
 ///
 /// The motivation for this function is that instead of saving all the content through serializing
 /// with a crate like Serde, the user can open the file and find it editable (think editing a json
@@ -131,12 +224,17 @@ pub fn parse_todo_list_section(
     parsed_todo_list: &ParsedTodoList,
     section: &str,
 ) -> Result<ParsedTodoList, std::io::Error> {
-    let section_re =
-        Regex::new(format!("\n### {}\n\n(?sm)(?P<section>.*?)(?-m:$|\n### .*)", section).as_str())
-            .unwrap();
-    let todo_list_section = match section_re.captures(parsed_todo_list.raw.as_str()) {
-        Some(cap) => cap.name("section").unwrap().as_str().to_string(),
-        None => return Err(std::io::Error::new(std::io::ErrorKind::Other, "Oh no")),
+    let todo_list_section = parse_section_content(
+        parse_task_list(parsed_todo_list.raw.as_str()).as_str(),
+        section,
+    );
+    let todo_list_section = match todo_list_section {
+        Some(content) => content,
+        None => {
+            return Err(std::io::Error::other(format!(
+                "Todo list has no section \"{section}\""
+            )))
+        }
     };
     let todo_list_section = format!("\n## Todo list\n\n{}", todo_list_section);
     let (done, total) = parse_todo_list_tasks_status(todo_list_section.as_str());
@@ -162,24 +260,16 @@ pub fn parse_todo_list_tasks(
     section: Option<&str>,
 ) -> Result<Vec<String>, std::io::Error> {
     if !completed && !open {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
+        return Err(std::io::Error::other(
             "complete and open parameters are not mutually exclusive",
         ));
     }
     let mut tasks = vec![];
-    let todo_list = match TODO_LIST_RE.captures(todo_raw) {
-        Some(cap) => cap,
-        None => return Ok(tasks),
-    };
-    let mut todo_list = todo_list.name("list").unwrap().as_str().to_string();
+    let mut todo_list = parse_task_list(todo_raw);
     let mut todo_section = "".to_string();
     if let Some(s) = section {
-        let section_re: Regex =
-            Regex::new(format!("\n### {}\n\n(?sm)(?P<section>.*?)(?-m:$|\n### .*)", s).as_str())
-                .unwrap();
-        todo_section = match section_re.captures(todo_list.as_str()) {
-            Some(cap) => cap.name("section").unwrap().as_str().to_string(),
+        todo_section = match parse_section_content(todo_list.as_str(), s) {
+            Some(content) => content,
             None => return Ok(tasks),
         };
     }
@@ -227,8 +317,7 @@ pub fn parse_todo_list_tasks(
         // need to copy from them
         for caps in re.captures_iter(todo_list.as_str()) {
             trace!("CAP");
-            let task = caps["summary"].to_string();
-            tasks.push(task);
+            tasks.push(normalize_task(&caps["summary"]));
         }
     } else {
         let fre = match (completed, open) {
@@ -240,78 +329,249 @@ pub fn parse_todo_list_tasks(
         // You cannot return static items in a match, hence the
         // need to copy from them
         for caps in fre.captures_iter(todo_list.as_str()) {
-            let task = caps.unwrap()["summary"].to_string();
-            tasks.push(task);
+            tasks.push(normalize_task(&caps.unwrap()["summary"]));
         }
     }
 
     Ok(tasks)
 }
 
-/// Returns title from Todo list
-fn parse_todo_list_title(todo_raw: &str) -> Option<String> {
-    lazy_static! {
-        static ref TITLE_RE: Regex = Regex::new(r"^# (.+)\n").unwrap();
-    }
-    match TITLE_RE.captures(todo_raw) {
-        None => None,
-        Some(caps) => {
-            if caps.len() == 1 {
-                None
-            } else {
-                Some(String::from(&caps[1]))
+/// Returns open tasks of Todo list due (`@due(YYYY-MM-DD)`) at a date `is_due` accepts, like
+/// [`parse_todo_list_tasks`] returns them
+pub fn parse_todo_list_due_tasks(
+    todo_raw: &str,
+    short: bool,
+    is_due: impl Fn(chrono::NaiveDate) -> bool,
+) -> Vec<String> {
+    parse_todo_list_tasks(todo_raw, false, true, short, None)
+        .unwrap()
+        .into_iter()
+        .filter(|task| {
+            task.lines()
+                .next()
+                .and_then(parse_due_date)
+                .is_some_and(&is_due)
+        })
+        .collect()
+}
+
+/// Returns the ID of a task (`^a3f2` at the end of its text)
+pub fn parse_task_id(text: &str) -> Option<&str> {
+    TASK_ID_RE
+        .captures(text.trim_end())
+        .map(|caps| caps.name("id").unwrap().as_str())
+}
+
+/// Returns the IDs of the tasks referenced with `@see(^a3f2)`
+pub fn parse_task_references(text: &str) -> Vec<&str> {
+    TASK_REFERENCE_RE
+        .captures_iter(text)
+        .map(|caps| caps.name("id").unwrap().as_str())
+        .collect()
+}
+
+/// Returns the titles of the Todo lists linked with `[[title]]`
+pub fn parse_wikilinks(text: &str) -> Vec<&str> {
+    WIKILINK_RE
+        .captures_iter(text)
+        .map(|caps| caps.name("title").unwrap().as_str().trim())
+        .collect()
+}
+
+/// Returns the content of a section (`### Section`) of a task list without its heading, its
+/// subsections included
+///
+/// The section name is compared as is, so it may contain any character.
+fn parse_section_content(task_list: &str, section: &str) -> Option<String> {
+    let mut lines = task_list
+        .lines()
+        .skip_while(|l| section_heading(l).map(|(_, name)| name) != Some(section));
+    let (depth, _) = section_heading(lines.next()?)?;
+    let content = lines
+        .skip_while(|l| l.trim().is_empty())
+        .take_while(|l| !l.starts_with("## ") && section_heading(l).is_none_or(|(d, _)| d > depth))
+        .collect::<Vec<_>>();
+    Some(content.join("\n"))
+}
+
+/// Returns the names of the sections (`### Section`) and subsections of the task list of given
+/// Todo list, in order
+pub fn parse_todo_list_sections(todo_raw: &str) -> Vec<String> {
+    parse_task_list(todo_raw)
+        .lines()
+        .filter_map(section_heading)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Returns the tasks whose text contains `pattern` (case insensitive)
+pub fn find_tasks<'a>(tasks: &'a [Task], pattern: &str) -> Vec<&'a Task> {
+    let pattern = pattern.to_lowercase();
+    tasks
+        .iter()
+        .filter(|t| t.text.to_lowercase().contains(pattern.as_str()))
+        .collect()
+}
+
+/// Returns Todo list with `lines` inserted before the line at index `at`
+///
+/// Lines are appended when `at` is past the last line. The trailing newline of the Todo list is
+/// preserved.
+pub fn insert_lines(todo_raw: &str, at: usize, lines: &[String]) -> String {
+    let mut raw_lines = todo_raw.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+    let at = at.min(raw_lines.len());
+    raw_lines.splice(at..at, lines.iter().cloned());
+    let mut new_raw = raw_lines.join("\n");
+    if todo_raw.ends_with('\n') || at == todo_raw.lines().count() {
+        new_raw.push('\n');
+    }
+    new_raw
+}
+
+/// Returns Todo list with `task_lines` inserted after the last task of `section` (the task list
+/// itself when None), or below its heading when it has no tasks yet. A missing section is added at
+/// the end of the task list, before any part (`## Motives`, `## Archive`...) following it.
+pub fn insert_task_lines(todo_raw: &str, section: Option<&str>, task_lines: &[String]) -> String {
+    let tasks = parse_tasks(todo_raw);
+    let mut lines = todo_raw.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+    let at = match tasks.iter().rev().find(|t| t.section.as_deref() == section) {
+        Some(last) => last.last_line + 1,
+        None => {
+            let heading = match section {
+                Some(section) => format!("### {section}"),
+                None => "## Todo list".to_string(),
+            };
+            let task_list = lines.iter().position(|l| l.trim_end() == "## Todo list");
+            let existing = match section {
+                Some(_) => parse_sections(todo_raw)
+                    .into_iter()
+                    .find(|s| Some(s.name.as_str()) == section)
+                    .map(|s| s.line),
+                None => task_list,
+            };
+            match existing {
+                Some(i) => {
+                    let mut at = i + 1;
+                    while at < lines.len() && lines[at].trim().is_empty() {
+                        at += 1;
+                    }
+                    if at == i + 1 {
+                        lines.insert(at, String::new());
+                        at += 1;
+                    }
+                    at
+                }
+                None => {
+                    // end of the task list, or else of the Todo list but its archive
+                    let end = match task_list {
+                        Some(i) => lines[i + 1..]
+                            .iter()
+                            .position(|l| l.starts_with("## ") || l.starts_with("# "))
+                            .map_or(lines.len(), |j| i + 1 + j),
+                        None => parse_archive(todo_raw).map_or(lines.len(), |(h, _)| h),
+                    };
+                    let mut at = end;
+                    while at > 0 && lines[at - 1].trim().is_empty() {
+                        at -= 1;
+                    }
+                    lines.drain(at..end);
+                    if at < lines.len() {
+                        // blank line before the part following the task list
+                        lines.insert(at, String::new());
+                    }
+                    let mut added = vec![];
+                    if at > 0 {
+                        added.push(String::new());
+                    }
+                    added.extend([heading, String::new()]);
+                    let below = at + added.len();
+                    lines.splice(at..at, added);
+                    below
+                }
             }
         }
+    };
+    lines.splice(at..at, task_lines.iter().cloned());
+    let mut inserted = lines.join("\n");
+    inserted.push('\n');
+    inserted
+}
+
+/// Returns the indices of the `## Archive` heading of Todo list and of the last line of its
+/// archive, not counting trailing blank lines
+pub fn parse_archive(todo_raw: &str) -> Option<(usize, usize)> {
+    let lines = todo_raw.lines().collect::<Vec<_>>();
+    let heading = lines.iter().position(|l| l.trim_end() == ARCHIVE_HEADING)?;
+    let end = lines[heading + 1..]
+        .iter()
+        .position(|l| l.starts_with("## ") || l.starts_with("# "))
+        .map_or(lines.len(), |i| heading + 1 + i);
+    let last_line = (heading..end)
+        .rev()
+        .find(|i| !lines[*i].trim().is_empty())
+        .unwrap_or(heading);
+    Some((heading, last_line))
+}
+
+/// Returns the number of tasks inside the archive of Todo list
+pub fn parse_archived_tasks(todo_raw: &str) -> usize {
+    match parse_archive(todo_raw) {
+        Some((heading, last_line)) => todo_raw
+            .lines()
+            .skip(heading + 1)
+            .take(last_line - heading)
+            .filter(|l| l.starts_with("* [ ] ") || l.starts_with("* [x] "))
+            .count(),
+        None => 0,
     }
 }
 
-/// Returns the detailed informations about the task list of given Todo list. Tasks can be spread throughout the
-/// file.
-fn parse_todo_list_tasks_status(todo_raw: &str) -> (usize, usize) {
-    let todo_list = match TODO_LIST_RE.captures(todo_raw) {
-        Some(cap) => cap,
-        None => return (0, 0),
-    };
-    let todo_list = todo_list.name("list").unwrap();
-    lazy_static! {
-        static ref DONE_RE: Regex = Regex::new(r"(?m)^\* \[(.{1})\] .+$").unwrap();
-    }
-    let mut done = 0;
-    let matches = DONE_RE.find_iter(todo_list.as_str());
-    let total = matches.count();
-    for mat in DONE_RE.find_iter(todo_list.as_str()) {
-        if mat.as_str().get(0..6).unwrap().eq("* [x] ") {
-            done += 1;
+/// Returns Todo list where `tasks` (comments included) are moved to the end of its archive. The
+/// archive is added at the end of the Todo list if missing.
+pub fn archive_tasks(todo_raw: &str, tasks: &[Task]) -> String {
+    let mut lines = todo_raw.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+    let mut tasks = tasks.to_vec();
+    tasks.sort_by_key(|t| t.line);
+    let archived = tasks
+        .iter()
+        .flat_map(|t| lines[t.line..=t.last_line].to_vec())
+        .collect::<Vec<_>>();
+    for task in tasks.iter().rev() {
+        lines.drain(task.line..=task.last_line);
+    }
+    let mut kept = lines.join("\n");
+    if todo_raw.ends_with('\n') {
+        kept.push('\n');
+    }
+
+    match parse_archive(kept.as_str()) {
+        Some((heading, last_line)) => {
+            let mut new_lines = archived;
+            if last_line == heading {
+                new_lines.insert(0, String::new());
+            }
+            insert_lines(kept.as_str(), last_line + 1, &new_lines)
+        }
+        None => {
+            let mut archive = kept.trim_end().to_string();
+            archive.push_str(format!("\n\n{ARCHIVE_HEADING}\n\n").as_str());
+            archive.push_str(archived.join("\n").as_str());
+            archive.push('\n');
+            archive
         }
     }
-    (done, total)
 }
 
-/// Returns labels of Todo list
-fn parse_todo_list_labels(todo_raw: &str) -> Result<Vec<String>, std::io::Error> {
+/// Returns Todo list with its labels replaced by `labels`
+pub fn set_todo_list_labels(todo_raw: &str, labels: &[String]) -> String {
     lazy_static! {
-        static ref LABEL_RE: Regex = Regex::new(r"## Description\n\nLABEL=(.*)").unwrap();
-    }
-    let label_matches = LABEL_RE.captures(todo_raw).unwrap();
-    if label_matches.len() == 1 {
-        eprintln!("Error while parsing labels");
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Error while parsing labels",
-        ));
+        static ref LABEL_LINE_RE: Regex = Regex::new(r"(## Description\n\nLABEL=).*").unwrap();
     }
-
-    // does collect to vec[""] but empty label is not a valid label
-    let file_labels = label_matches
-        .get(1)
-        .unwrap()
-        .as_str()
-        .split(',')
-        .map(|s| s.to_string())
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<String>>();
-
-    Ok(file_labels)
+    LABEL_LINE_RE
+        .replace(todo_raw, |caps: &regex::Captures| {
+            format!("{}{}", &caps[1], labels.join(","))
+        })
+        .to_string()
 }
 
 #[cfg(test)]
@@ -330,7 +590,7 @@ mod tests {
         );
     }
 
-    const SAMPLE_CONFIG: &'static str = "active_ctx_name = \"config1\"
+    const SAMPLE_CONFIG: &str = "active_ctx_name = \"config1\"
 
 [[config]]
 name = \"config1\"
@@ -379,14 +639,17 @@ todo_folder = \"/path/to/config2/folder\"";
                     name: String::from("config1"),
                     timezone: String::from(""),
                     folder_location: String::from(""),
+                    ..Default::default()
                 },
                 Context {
                     ide: String::from(""),
                     name: String::from("config2"),
                     timezone: String::from(""),
                     folder_location: String::from(""),
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
         assert!(config.update_active_ctx("missing_config").is_err());
     }
@@ -634,27 +897,27 @@ Confusing description
         let completed = true;
         let open = true;
         let short = true;
-        assert!(parse_todo_list_tasks(&todo_raw, completed, open, short, None).is_ok());
+        assert!(parse_todo_list_tasks(todo_raw, completed, open, short, None).is_ok());
         let completed = true;
         let open = true;
         let short = false; // testing if short modifies this behavior
-        assert!(parse_todo_list_tasks(&todo_raw, completed, open, short, None).is_ok());
+        assert!(parse_todo_list_tasks(todo_raw, completed, open, short, None).is_ok());
         let completed = false;
         let open = true;
         let short = false;
-        assert!(parse_todo_list_tasks(&todo_raw, completed, open, short, None).is_ok());
+        assert!(parse_todo_list_tasks(todo_raw, completed, open, short, None).is_ok());
         let completed = true;
         let open = false;
         let short = false;
-        assert!(parse_todo_list_tasks(&todo_raw, completed, open, short, None).is_ok());
+        assert!(parse_todo_list_tasks(todo_raw, completed, open, short, None).is_ok());
         let completed = false;
         let open = false;
         let short = false;
-        assert!(parse_todo_list_tasks(&todo_raw, completed, open, short, None).is_err());
+        assert!(parse_todo_list_tasks(todo_raw, completed, open, short, None).is_err());
         let completed = false;
         let open = false;
         let short = true;
-        assert!(parse_todo_list_tasks(&todo_raw, completed, open, short, None).is_err());
+        assert!(parse_todo_list_tasks(todo_raw, completed, open, short, None).is_err());
     }
 
     #[test]
@@ -771,7 +1034,7 @@ this line should also be caught
             String::from("* [x] completed1"),
             String::from("* [x] completed2"),
             String::from("* [x] completed3 long description\nthis line should be caught"),
-            String::from("* [x] completed4 long description\nthis line should also be caught\n\n"),
+            String::from("* [x] completed4 long description\nthis line should also be caught"),
         ];
         assert_eq!(tasks, expected);
     }
@@ -810,8 +1073,554 @@ this line should also be caught
         let expected: Vec<String> = vec![
             String::from("* [ ] open1"),
             String::from("* [ ] open2 long description\nthis line should be caught"),
-            String::from("* [ ] open3 long description\nthis line should also be caught\n\n"),
+            String::from("* [ ] open3 long description\nthis line should also be caught"),
         ];
         assert_eq!(tasks, expected);
     }
+
+    #[test]
+    fn tasks_are_stable_across_whitespace_perturbations() {
+        init();
+        let tasks = [
+            "* [ ] open1",
+            "* [x] completed1 long description\nmore",
+            "* [ ] open2",
+        ];
+        for separator in ["\n", "\n\n", "\n \n\t\n"] {
+            for trailing_spaces in ["", " ", "\t  "] {
+                for end_of_file in ["", "\n", "\n\n\n"] {
+                    let task_list = tasks
+                        .iter()
+                        .map(|t| t.replace('\n', format!("{trailing_spaces}\n").as_str()))
+                        .map(|t| format!("{t}{trailing_spaces}"))
+                        .collect::<Vec<_>>()
+                        .join(separator);
+                    let todo_raw = format!(
+                        "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n{task_list}{end_of_file}"
+                    );
+                    let case = format!("{todo_raw:?}");
+                    assert_eq!(
+                        parse_todo_list_tasks(todo_raw.as_str(), true, true, false, None).unwrap(),
+                        tasks,
+                        "{case}"
+                    );
+                    assert_eq!(
+                        parse_todo_list_tasks(todo_raw.as_str(), true, true, true, None).unwrap(),
+                        [
+                            "* [ ] open1",
+                            "* [x] completed1 long description",
+                            "* [ ] open2"
+                        ],
+                        "{case}"
+                    );
+                    assert_eq!(
+                        parse_todo_list_tasks(todo_raw.as_str(), false, true, false, None).unwrap(),
+                        ["* [ ] open1", "* [ ] open2"],
+                        "{case}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_tasks_with_sections_and_comments() {
+        init();
+        let todo_raw = "\
+# Title
+
+## Description
+
+LABEL=
+* [ ] not a task
+
+## Todo list
+
+* [ ] call plumber
+  > 2024-05-01 10:00 waiting on Bob
+  > left a message
+* [x] buy paint
+
+### Kitchen
+
+* [ ] fix sink
+> not indented so not a comment
+
+## Motives
+
+* [ ] not a task either
+";
+        let tasks = parse_tasks(todo_raw);
+        assert_eq!(tasks.len(), 3);
+        let mut call_plumber = Task::new("call plumber", false);
+        call_plumber.comments = vec![
+            String::from("2024-05-01 10:00 waiting on Bob"),
+            String::from("left a message"),
+        ];
+        call_plumber.line = 9;
+        call_plumber.last_line = 11;
+        assert_eq!(tasks[0], call_plumber);
+        assert!(tasks[1].done);
+        assert!(tasks[1].comments.is_empty());
+        assert_eq!(tasks[2].text, "fix sink");
+        assert_eq!(tasks[2].section, Some(String::from("Kitchen")));
+        assert!(tasks[2].comments.is_empty());
+    }
+
+    #[test]
+    fn set_labels() {
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=a,b\nsome description\n";
+        assert_eq!(
+            set_todo_list_labels(todo_raw, &["b".to_string(), "urgent".to_string()]),
+            "# Title\n\n## Description\n\nLABEL=b,urgent\nsome description\n"
+        );
+        assert_eq!(
+            set_todo_list_labels(todo_raw, &[]),
+            "# Title\n\n## Description\n\nLABEL=\nsome description\n"
+        );
+    }
+
+    #[test]
+    fn parse_task_ids_and_references() {
+        assert_eq!(parse_task_id("write readme ^a3f2"), Some("a3f2"));
+        assert_eq!(parse_task_id("write readme^a3f2"), None);
+        assert_eq!(parse_task_id("raise 2^10"), None);
+        assert_eq!(
+            parse_task_references("publish @see(^a3f2) after @see(^b-1)"),
+            vec!["a3f2", "b-1"]
+        );
+        assert_eq!(
+            parse_wikilinks("wait for [[release]] and [[ C++ (v2) ]], not [link]"),
+            vec!["release", "C++ (v2)"]
+        );
+    }
+
+    #[test]
+    fn parse_due_dates() {
+        assert_eq!(
+            parse_due_date("pay rent @due(2024-07-01) @every(1m)"),
+            chrono::NaiveDate::from_ymd_opt(2024, 7, 1)
+        );
+        assert_eq!(parse_due_date("pay rent @due(2024-13-01)"), None);
+        assert_eq!(parse_due_date("pay rent"), None);
+    }
+
+    #[test]
+    fn parse_priorities() {
+        assert_eq!(parse_priority("(A) call dentist"), Some('A'));
+        assert_eq!(parse_priority("(a) call dentist"), None);
+        assert_eq!(parse_priority("call dentist (A) today"), None);
+        assert_eq!(parse_priority("(AB) call dentist"), None);
+        let tasks = parse_tasks("# t\n\n## Todo list\n\n* [ ] (B) call dentist\n* [x] buy milk\n");
+        assert_eq!(
+            tasks.iter().map(Task::priority).collect::<Vec<_>>(),
+            vec![Some('B'), None]
+        );
+    }
+
+    #[test]
+    fn parse_only_active_contexts() {
+        let raw = "active_ctx_name = \"home\"\n\n[[ctxs]]\nname = \"work\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_location = \"/work\"\n\n[ctxs.caldav]\nurl = \"https://example.com/\"\n\n[[ctxs]]\nname = \"home\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_location = \"/home\"\n\n[ctxs.caldav]\nurl = \"https://example.org/\"\n\n[views]\nmorning = [\"--open\"]\n";
+        let dir = std::path::Path::new("/");
+        let config = parse_active_configuration(raw, dir).unwrap().unwrap();
+        assert_eq!(config.ctxs.len(), 1);
+        assert_eq!(config.active_ctx().folder_location, "/home");
+        assert_eq!(
+            config.active_ctx().caldav.as_ref().unwrap().url,
+            "https://example.org/"
+        );
+
+        let unnamed = raw.replace("name = \"work\"", "\"name\" = 'work'");
+        assert!(parse_active_configuration(unnamed.as_str(), dir)
+            .unwrap()
+            .is_none());
+        let unknown = raw.replace("active_ctx_name = \"home\"", "active_ctx_name = \"gone\"");
+        assert!(parse_active_configuration(unknown.as_str(), dir).is_err());
+    }
+
+    #[test]
+    fn parse_sections() {
+        init();
+        let todo_raw = "# Title\n\n## Description\n\n### Not a section\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n\n### Kitchen\n\n* [ ] b\n\n### C++ (v2)\n\n* [x] c\n\n## Notes\n\n### Not either";
+        assert_eq!(
+            parse_todo_list_sections(todo_raw),
+            vec![String::from("Kitchen"), String::from("C++ (v2)")]
+        );
+        assert_eq!(
+            parse_todo_list_tasks(todo_raw, true, true, false, Some("C++ (v2)")).unwrap(),
+            vec![String::from("* [x] c")]
+        );
+        assert!(parse_todo_list_sections("# Title\n\n## Todo list\n\n* [ ] a").is_empty());
+    }
+
+    #[test]
+    fn parse_section_with_special_characters() {
+        init();
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n\n\
+### C++ (v2)\n\n* [ ] b\n* [x] c\n\n### .*\n\n* [x] d\n\n### [wip] $HOME\n* [ ] e";
+        let todo_list = parse_todo_list(todo_raw).unwrap();
+        for (section, done, total) in [("C++ (v2)", 1, 2), (".*", 1, 1), ("[wip] $HOME", 0, 1)] {
+            let todo_list_section = parse_todo_list_section(&todo_list, section).unwrap();
+            assert_eq!(
+                (todo_list_section.done, todo_list_section.total),
+                (done, total),
+                "section {section}"
+            );
+        }
+        assert!(parse_todo_list_section(&todo_list, "C++").is_err());
+        assert_eq!(
+            parse_todo_list_tasks(todo_raw, false, true, false, Some("[wip] $HOME")).unwrap(),
+            vec![String::from("* [ ] e")]
+        );
+        assert!(
+            parse_todo_list_tasks(todo_raw, true, true, false, Some("(v2)"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn todo_list_parts() {
+        let todo_raw = "# Release\n\n## Description\n\nLABEL=work\n\nShip it.\n\n## Todo list\n\n* [ ] tag\n\n### Docs\n\n* [x] publish\n\n## Motives\n\n1. users\n";
+        assert_eq!(
+            parse_todo_list_parts(todo_raw),
+            TodoListParts {
+                title: "Release".to_string(),
+                description: "Ship it.".to_string(),
+                tasks: "* [ ] tag\n\n### Docs\n\n* [x] publish".to_string(),
+                motives: "1. users".to_string(),
+            }
+        );
+        assert_eq!(parse_todo_list_motives(todo_raw), vec!["users".to_string()]);
+        let parts = parse_todo_list_parts("# Empty\n\n## Description\n\nLABEL=\n");
+        assert_eq!(
+            parse_todo_list_parts(
+                "# Spread\n\n## Description\n\nLABEL=\n\n## Monday\n\n* [ ] call\n"
+            )
+            .tasks,
+            "* [ ] call"
+        );
+        assert_eq!(
+            (parts.description, parts.tasks, parts.motives),
+            (String::new(), String::new(), String::new())
+        );
+    }
+
+    #[test]
+    fn task_lines_are_inserted_into_sections() {
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n  > note\n\n### Shop\n\n* [ ] b\n\n## Motives\n\n* rest\n";
+        let line = |text: &str| vec![format!("* [ ] {text}")];
+        assert_eq!(
+            insert_task_lines(todo_raw, None, &line("c")),
+            "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n  > note\n* [ ] c\n\n### Shop\n\n* [ ] b\n\n## Motives\n\n* rest\n"
+        );
+        assert_eq!(
+            insert_task_lines(todo_raw, Some("Shop"), &line("c")),
+            "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n  > note\n\n### Shop\n\n* [ ] b\n* [ ] c\n\n## Motives\n\n* rest\n"
+        );
+        assert_eq!(
+            insert_task_lines(todo_raw, Some("Garden"), &line("c")),
+            "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n  > note\n\n### Shop\n\n* [ ] b\n\n### Garden\n\n* [ ] c\n\n## Motives\n\n* rest\n"
+        );
+        assert_eq!(
+            insert_task_lines("# Title\n\n## Description\n\nLABEL=\n", None, &line("c")),
+            "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] c\n"
+        );
+    }
+
+    #[test]
+    fn archived_tasks() {
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] a\n  > done early\n* [ ] b\n* [x] c\n";
+        let tasks = parse_tasks(todo_raw);
+        let archived = archive_tasks(todo_raw, &[tasks[2].clone(), tasks[0].clone()]);
+        assert_eq!(
+            archived,
+            "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] b\n\n## Archive\n\n* [x] a\n  > done early\n* [x] c\n"
+        );
+        assert_eq!(parse_archived_tasks(archived.as_str()), 2);
+        assert_eq!(parse_todo_list_tasks_status(archived.as_str()), (0, 1));
+        let tasks = parse_tasks(archived.as_str());
+        assert_eq!(
+            archive_tasks(archived.as_str(), &tasks),
+            "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n\n## Archive\n\n* [x] a\n  > done early\n* [x] c\n* [ ] b\n"
+        );
+        // tasks spread throughout the Todo list stop at the archive
+        let spread = "# Title\n\n## Monday\n\n* [ ] call\n\n## Archive\n\n* [x] write\n";
+        assert_eq!(parse_tasks(spread).len(), 1);
+        assert_eq!(parse_todo_list_tasks_status(spread), (0, 1));
+    }
+
+    #[test]
+    fn nested_section_task_counts() {
+        init();
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n### House\n\n* [ ] a\n\n\
+#### Kitchen\n\n* [x] b\n\n##### Fridge\n\n* [ ] c\n\n#### Garden\n\n* [x] d\n\n### Car\n\n* [ ] e\n";
+        let counts = super::parse_sections(todo_raw)
+            .into_iter()
+            .map(|s| {
+                (
+                    s.name,
+                    s.parent,
+                    (s.direct_done, s.direct_total),
+                    (s.done, s.total),
+                )
+            })
+            .collect::<Vec<_>>();
+        let name = |n: &str| n.to_string();
+        assert_eq!(
+            counts,
+            vec![
+                (name("House"), None, (0, 1), (2, 4)),
+                (name("Kitchen"), Some(name("House")), (1, 1), (1, 2)),
+                (name("Fridge"), Some(name("Kitchen")), (0, 1), (0, 1)),
+                (name("Garden"), Some(name("House")), (1, 1), (1, 1)),
+                (name("Car"), None, (0, 1), (0, 1)),
+            ]
+        );
+        let todo_list = parse_todo_list(todo_raw).unwrap();
+        for (section, done, total) in [("House", 2, 4), ("Kitchen", 1, 2), ("Car", 0, 1)] {
+            let todo_list_section = parse_todo_list_section(&todo_list, section).unwrap();
+            assert_eq!(
+                (todo_list_section.done, todo_list_section.total),
+                (done, total),
+                "section {section}"
+            );
+        }
+        let sections = parse_tasks(todo_raw)
+            .into_iter()
+            .map(|t| t.section.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(sections, ["House", "Kitchen", "Fridge", "Garden", "Car"]);
+    }
+
+    #[test]
+    fn count_tasks_without_todo_list_heading() {
+        init();
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Groceries\n\n* [x] milk\n* [ ] bread\n\n## Chores\n\n### Kitchen\n\n* [ ] dishes\n";
+        let todo_list = parse_todo_list(todo_raw).unwrap();
+        assert_eq!((todo_list.done, todo_list.total), (1, 3));
+        assert!(!todo_list.tasks_are_all_done());
+        assert_eq!(
+            parse_todo_list_sections(todo_raw),
+            vec![String::from("Kitchen")]
+        );
+        let tasks = parse_tasks(todo_raw);
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[1].section, None);
+        assert_eq!(tasks[2].section, Some(String::from("Kitchen")));
+
+        // tasks outside the `## Todo list` section are ignored when it exists
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] milk\n\n## Notes\n\n* [ ] not a task";
+        let todo_list = parse_todo_list(todo_raw).unwrap();
+        assert_eq!((todo_list.done, todo_list.total), (1, 1));
+        assert_eq!(parse_tasks(todo_raw).len(), 1);
+
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Groceries\n\n* [x] milk\n* [ ] bread\n\n## Chores\n\n* [ ] dishes\n";
+        assert_eq!(
+            parse_todo_list_tasks(todo_raw, false, true, false, None)
+                .unwrap()
+                .iter()
+                .map(|t| t.trim_end())
+                .collect::<Vec<_>>(),
+            vec!["* [ ] bread", "* [ ] dishes"]
+        );
+    }
+
+    #[test]
+    fn find_tasks_by_substring() {
+        init();
+        let todo_raw = "# Title\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] Buy milk\n* [ ] buy bread\n* [ ] call mom";
+        let tasks = parse_tasks(todo_raw);
+        assert_eq!(find_tasks(&tasks, "BUY").len(), 2);
+        assert_eq!(find_tasks(&tasks, "mom")[0].text, "call mom");
+        assert!(find_tasks(&tasks, "dentist").is_empty());
+    }
+
+    #[test]
+    fn insert_lines_in_todo_list() {
+        init();
+        let lines = vec![String::from("new")];
+        assert_eq!(insert_lines("a\nb\n", 1, &lines), "a\nnew\nb\n");
+        assert_eq!(insert_lines("a\nb", 1, &lines), "a\nnew\nb");
+        assert_eq!(insert_lines("a\nb", 2, &lines), "a\nb\nnew\n");
+        assert_eq!(insert_lines("a\nb\n", 10, &lines), "a\nb\nnew\n");
+    }
+}
+
+/// Property tests: random Todo lists are parsed without panicking and the regex based parsers agree
+/// with a line by line reference implementation. Failures print the smallest failing Todo list.
+#[cfg(test)]
+mod properties {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+
+    const WORDS: &[&str] = &[
+        "a",
+        "pay rent",
+        "C++ (v2)",
+        ".*",
+        "[wip]",
+        "$HOME",
+        "été",
+        "日本語",
+        "🚀 launch",
+        "[😀]",
+        "^a3f2",
+        "@see(^b)",
+        "@due(2026-10-15)",
+        "[[Release]]",
+        "*",
+        "[ ]",
+        "#",
+        "\\",
+    ];
+
+    /// Returns a line of the task list, not always a task
+    fn task_list_line() -> impl Strategy<Value = String> {
+        let word = || select(WORDS);
+        prop_oneof![
+            3 => word().prop_map(|word| format!("* [ ] {word}")),
+            2 => word().prop_map(|word| format!("* [x] {word}")),
+            1 => (select(&["😀", "€", "-"][..]), word())
+                .prop_map(|(mark, word)| format!("* [{mark}] {word}")),
+            1 => (3..6usize, word()).prop_map(|(n, word)| format!("{} {word}", "#".repeat(n))),
+            1 => word().prop_map(|word| format!("  > {word}")),
+            1 => Just(String::new()),
+            1 => (select(&["* []", "*[x]", "- [ ]", "text", " * [ ]"][..]), word())
+                .prop_map(|(prefix, word)| format!("{prefix} {word}")),
+            1 => (word(), select(&[" ", "\t", ""][..]))
+                .prop_map(|(word, trailing)| format!("{word}{trailing}")),
+        ]
+    }
+
+    /// Returns a random Todo list whose task list lines are not always tasks
+    fn random_todo_list() -> impl Strategy<Value = String> {
+        (
+            select(WORDS),
+            select(&["", "work", "work,home"][..]),
+            vec(select(&["* [ ] not a task", "text", ""][..]), 0..3),
+            vec(task_list_line(), 0..30),
+            any::<bool>(),
+            any::<bool>(),
+        )
+            .prop_map(|(title, labels, description, lines, notes, trimmed)| {
+                let mut todo_raw = format!("# {title}\n\n## Description\n\nLABEL={labels}\n\n");
+                for line in description {
+                    todo_raw.push_str(format!("{line}\n").as_str());
+                }
+                todo_raw.push_str("\n## Todo list\n\n");
+                for line in lines {
+                    todo_raw.push_str(format!("{line}\n").as_str());
+                }
+                if notes {
+                    todo_raw.push_str("\n## Notes\n\n* [ ] not a task either\n");
+                }
+                if trimmed {
+                    todo_raw.pop();
+                }
+                todo_raw
+            })
+    }
+
+    /// Returns done and total tasks of the Todo list, counted line by line
+    fn reference_counts(todo_raw: &str) -> (usize, usize) {
+        let task_list = todo_raw
+            .lines()
+            .skip_while(|l| *l != "## Todo list")
+            .skip(1)
+            .take_while(|l| !l.starts_with("## "));
+        let (mut done, mut total) = (0, 0);
+        for line in task_list {
+            if line.starts_with("* [ ] ") || line.starts_with("* [x] ") {
+                total += 1;
+                done += usize::from(line.starts_with("* [x] "));
+            }
+        }
+        (done, total)
+    }
+
+    const PIECES: &[&str] = &[
+        "# ",
+        "## ",
+        "## Todo list",
+        "### ",
+        "* [ ] ",
+        "* [x] ",
+        "* [",
+        "]",
+        "\n",
+        "\n\n",
+        "  > ",
+        "LABEL=",
+        "é",
+        "€",
+        "😀",
+        "* [😀] ",
+        "* [€] ",
+        "\r\n",
+        "\t",
+        "^",
+        "@see(^",
+        "@due(",
+        ")",
+        "[[",
+        "]]",
+    ];
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(500))]
+
+        #[test]
+        fn parsers_agree_on_random_todo_lists(todo_raw in random_todo_list()) {
+            let (done, total) = reference_counts(todo_raw.as_str());
+
+            let todo_list = parse_todo_list(todo_raw.as_str()).unwrap();
+            prop_assert_eq!((todo_list.done, todo_list.total), (done, total));
+
+            let tasks = parse_tasks(todo_raw.as_str());
+            prop_assert_eq!(tasks.len(), total);
+            prop_assert_eq!(tasks.iter().filter(|t| t.done).count(), done);
+
+            for short in [true, false] {
+                let listed =
+                    parse_todo_list_tasks(todo_raw.as_str(), true, true, short, None).unwrap();
+                prop_assert_eq!(listed.len(), total);
+                let open =
+                    parse_todo_list_tasks(todo_raw.as_str(), false, true, short, None).unwrap();
+                prop_assert_eq!(open.len(), total - done);
+            }
+
+            let sections = parse_sections(todo_raw.as_str());
+            let direct_total = sections.iter().map(|s| s.direct_total).sum::<usize>();
+            let without_section = tasks.iter().filter(|t| t.section.is_none()).count();
+            prop_assert_eq!(direct_total + without_section, total);
+            for section in &sections {
+                prop_assert!(section.direct_total <= section.total);
+                prop_assert!(section.done <= section.total);
+                let tally = tasks.iter().filter(|t| section.contains(t)).count();
+                prop_assert_eq!(section.total, tally);
+            }
+        }
+
+        #[test]
+        fn parsers_do_not_panic_on_random_text(pieces in vec(select(PIECES), 0..40)) {
+            let text = pieces.concat();
+            let _ = parse_todo_list(text.as_str());
+            let _ = parse_todo_list_tasks(text.as_str(), true, true, false, None);
+            let _ = parse_todo_list_tasks(text.as_str(), false, true, true, None);
+            let _ = parse_todo_list_sections(text.as_str());
+            for section in parse_sections(text.as_str()) {
+                let _ =
+                    parse_todo_list_tasks(text.as_str(), true, true, false, Some(&section.name));
+            }
+            for task in parse_tasks(text.as_str()) {
+                let _ = parse_task_id(task.text.as_str());
+                let _ = parse_task_references(task.text.as_str());
+                let _ = parse_due_date(task.text.as_str());
+            }
+            let _ = parse_wikilinks(text.as_str());
+        }
+    }
 }
@@ -0,0 +1,345 @@
+//! Todo lists spanning many files (parts)
+//!
+//! A long Todo list may be spread over parts: `project.md` holds the beginning of the Todo list and
+//! `project.1.md`, `project.2.md`... hold the lines which follow, in order. Parts are concatenated
+//! when read, so `todo list`, `todo show` and `todo done` see one Todo list.
+//!
+//! `todo split TITLE` spreads a Todo list over parts no bigger than a size threshold (when possible)
+//! and `todo split --auto` does so for every Todo list of the active Todo context with a file above
+//! the threshold. Parts only start at a task or a heading so a task keeps its comments.
+use crate::lock::is_locked;
+use crate::parse::{archive_tasks, parse_todo_list, Task};
+use crate::{read_todo_lists, resolve_todo_path, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::path::{Path, PathBuf};
+
+/// Size of parts by default, in bytes
+const DEFAULT_MAX_BYTES: &str = "16384";
+
+/// Todo list read from its file and its parts
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parts {
+    /// Path of the Todo list file followed by the paths of its parts
+    pub paths: Vec<String>,
+    /// Raw content of each file
+    pub raws: Vec<String>,
+}
+
+impl Parts {
+    /// Reads the Todo list at `filepath` and its parts
+    pub fn read(filepath: &str) -> Result<Parts, std::io::Error> {
+        let mut paths = vec![filepath.to_string()];
+        paths.extend(part_paths(filepath));
+        let raws = paths
+            .iter()
+            .map(std::fs::read_to_string)
+            .collect::<Result<Vec<_>, _>>()?;
+        debug!("read {}", paths.join(", "));
+        Ok(Parts { paths, raws })
+    }
+
+    /// Returns the Todo list with its parts concatenated
+    pub fn joined(&self) -> String {
+        let mut joined = String::new();
+        for (i, raw) in self.raws.iter().enumerate() {
+            joined.push_str(raw);
+            if i + 1 < self.raws.len() && !raw.is_empty() && !raw.ends_with('\n') {
+                joined.push('\n');
+            }
+        }
+        joined
+    }
+
+    /// Returns the index of the part holding `line` of the joined Todo list, and the index of that
+    /// line inside the part
+    pub fn locate(&self, line: usize) -> (usize, usize) {
+        let mut first_line = 0;
+        for (i, raw) in self.raws.iter().enumerate() {
+            let lines = raw.lines().count();
+            if line < first_line + lines || i + 1 == self.raws.len() {
+                return (i, line - first_line);
+            }
+            first_line += lines;
+        }
+        (0, line)
+    }
+
+    /// Replaces the Todo list by `todo_raw`. With parts, lines are put back into the part they were
+    /// read from, which requires `todo_raw` to have as many lines as the joined Todo list.
+    pub fn set(&mut self, todo_raw: &str) -> Result<(), std::io::Error> {
+        if self.raws.len() == 1 {
+            self.raws[0] = todo_raw.to_string();
+            return Ok(());
+        }
+        if todo_raw.lines().count() != self.joined().lines().count() {
+            return Err(std::io::Error::other(format!(
+                "Todo list at {} spans {} files and cannot change its number of lines",
+                self.paths[0],
+                self.paths.len()
+            )));
+        }
+        let mut lines = todo_raw.lines();
+        for raw in self.raws.iter_mut() {
+            let mut part = lines
+                .by_ref()
+                .take(raw.lines().count())
+                .collect::<Vec<_>>()
+                .join("\n");
+            if raw.ends_with('\n') {
+                part.push('\n');
+            }
+            *raw = part;
+        }
+        Ok(())
+    }
+
+    /// Moves the task of the joined Todo list (comments included) to the end of the archive of the
+    /// Todo list, inside the last part
+    pub fn archive_task(&mut self, task: &Task) {
+        let (i, line) = self.locate(task.line);
+        let last_line = line + task.last_line - task.line;
+        let last = self.raws.len() - 1;
+        if i == last {
+            let mut local = task.clone();
+            local.line = line;
+            local.last_line = last_line;
+            self.raws[last] = archive_tasks(self.raws[last].as_str(), &[local]);
+            return;
+        }
+
+        let mut lines = self.raws[i].lines().collect::<Vec<_>>();
+        let block = lines.drain(line..=last_line).collect::<Vec<_>>().join("\n");
+        let mut kept = lines.join("\n");
+        if self.raws[i].ends_with('\n') {
+            kept.push('\n');
+        }
+        self.raws[i] = kept;
+
+        // the task is appended to the last part, then archived there
+        let mut with_task = self.raws[last].clone();
+        if !with_task.is_empty() && !with_task.ends_with('\n') {
+            with_task.push('\n');
+        }
+        let line = with_task.lines().count();
+        with_task.push_str(block.as_str());
+        with_task.push('\n');
+        let mut local = task.clone();
+        local.line = line;
+        local.last_line = line + task.last_line - task.line;
+        self.raws[last] = archive_tasks(with_task.as_str(), &[local]);
+    }
+
+    /// Writes the Todo list file and its parts
+    pub fn write(&self) -> Result<(), std::io::Error> {
+        for (path, raw) in self.paths.iter().zip(self.raws.iter()) {
+            std::fs::write(path, raw)?;
+            debug!("wrote {path}");
+        }
+        Ok(())
+    }
+}
+
+/// Returns the Split command
+pub fn split_command() -> App<'static, 'static> {
+    App::new("split")
+        .about("Spread long todo lists over many files (parts)")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .index(1)
+                .help("Title of todo list")
+                .takes_value(true)
+                .required_unless("auto"),
+        )
+        .arg(
+            Arg::with_name("auto")
+                .long("auto")
+                .help("Splits every todo list with a file bigger than --max-bytes")
+                .conflicts_with("title"),
+        )
+        .arg(
+            Arg::with_name("max-bytes")
+                .long("max-bytes")
+                .value_name("BYTES")
+                .help("Size of parts, in bytes")
+                .default_value(DEFAULT_MAX_BYTES)
+                .validator(|n| match n.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(format!("\"{n}\" is not a number of bytes")),
+                })
+                .takes_value(true),
+        )
+}
+
+/// Splits the Todo list of active Todo context, or every Todo list bigger than the threshold
+pub fn split_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("split subcommand");
+    if ctx.is_single_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!(
+                "Todo context \"{}\" stores its Todo lists inside a single file which cannot be split",
+                ctx.name
+            ),
+        ));
+    }
+    let max_bytes = args
+        .value_of("max-bytes")
+        .unwrap()
+        .parse::<usize>()
+        .unwrap();
+    let filepaths = match args.value_of("title") {
+        Some(title) => vec![resolve_todo_path(ctx, title)?],
+        None => read_todo_lists(ctx)?
+            .into_iter()
+            .filter(|(filepath, todo_raw)| {
+                parse_todo_list(todo_raw).is_ok()
+                    && file_sizes(filepath)
+                        .into_iter()
+                        .any(|size| size > max_bytes)
+            })
+            .map(|(filepath, _)| filepath)
+            .collect(),
+    };
+    if filepaths.is_empty() {
+        println!("No todo list is bigger than {max_bytes} bytes");
+    }
+    for filepath in filepaths {
+        let parts = Parts::read(filepath.as_str())?;
+        let todo_raw = parts.joined();
+        if is_locked(todo_raw.as_str()) {
+            eprintln!("Warning: todo list at {filepath} is locked and was not split");
+            continue;
+        }
+        let chunks = split(todo_raw.as_str(), max_bytes);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let path = part_path(filepath.as_str(), i);
+            std::fs::write(&path, chunk)?;
+            debug!("wrote {}", path.display());
+        }
+        for old_part in parts.paths.iter().skip(chunks.len()) {
+            std::fs::remove_file(old_part)?;
+            debug!("removed {old_part}");
+        }
+        println!("Split {filepath} into {} file(s)", chunks.len());
+    }
+    Ok(())
+}
+
+/// Returns the existing parts of the Todo list file at `filepath`, in order
+pub fn part_paths(filepath: &str) -> Vec<String> {
+    (1..)
+        .map(|i| part_path(filepath, i))
+        .take_while(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
+
+/// Returns true if the file at `path` is a part of another Todo list file (`project.1.md` of
+/// `project.md`)
+pub fn is_part(path: &Path) -> bool {
+    let (stem, extension) = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) => (stem.to_string_lossy(), extension.to_string_lossy()),
+        _ => return false,
+    };
+    match stem.rsplit_once('.') {
+        Some((base, n)) if !base.is_empty() && n.parse::<usize>().is_ok_and(|n| n > 0) => {
+            path.with_file_name(format!("{base}.{extension}")).is_file()
+        }
+        _ => false,
+    }
+}
+
+/// Returns the path of part `i` of the Todo list file at `filepath`. Part 0 is the file itself.
+pub(crate) fn part_path(filepath: &str, i: usize) -> PathBuf {
+    let path = Path::new(filepath);
+    if i == 0 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(extension) => {
+            path.with_file_name(format!("{stem}.{i}.{}", extension.to_string_lossy()))
+        }
+        None => path.with_file_name(format!("{stem}.{i}")),
+    }
+}
+
+/// Returns the sizes of the Todo list file at `filepath` and of its parts
+fn file_sizes(filepath: &str) -> Vec<usize> {
+    std::iter::once(filepath.to_string())
+        .chain(part_paths(filepath))
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len() as usize)
+        .collect()
+}
+
+/// Returns the Todo list cut into parts of at most `max_bytes` bytes. A part starts at a task or a
+/// heading only, so it can be bigger when no such line comes sooner.
+fn split(todo_raw: &str, max_bytes: usize) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    for line in todo_raw.split_inclusive('\n') {
+        let part = parts.last_mut().unwrap();
+        let starts_part = line.starts_with("* [") || line.starts_with('#');
+        if starts_part && !part.is_empty() && part.len() + line.len() > max_bytes {
+            parts.push(String::new());
+        }
+        parts.last_mut().unwrap().push_str(line);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn todo_list_parts() {
+        let todo_raw = "# project\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] first\n  > why\n* [ ] second\n* [ ] third\n";
+        let chunks = split(todo_raw, 40);
+        assert_eq!(
+            chunks,
+            vec![
+                "# project\n\n## Description\n\nLABEL=\n\n",
+                "## Todo list\n\n* [ ] first\n  > why\n",
+                "* [ ] second\n* [ ] third\n"
+            ]
+        );
+        assert_eq!(chunks.concat(), todo_raw);
+
+        let folder = TempFolder::new("parts");
+        let filepath = folder.join("project.md");
+        let filepath = filepath.to_str().unwrap();
+        for (i, chunk) in chunks.iter().enumerate() {
+            std::fs::write(part_path(filepath, i), chunk).unwrap();
+        }
+        assert!(is_part(&folder.join("project.2.md")));
+        assert!(!is_part(Path::new(filepath)));
+
+        let mut parts = Parts::read(filepath).unwrap();
+        assert_eq!(parts.paths.len(), 3);
+        assert_eq!(parts.joined(), todo_raw);
+        assert_eq!(parts.locate(8), (1, 2));
+        assert_eq!(parts.locate(11), (2, 1));
+
+        parts
+            .set(todo_raw.replace("[ ] first", "[x] first").as_str())
+            .unwrap();
+        let task = crate::parse::parse_tasks(parts.joined().as_str()).remove(0);
+        parts.archive_task(&task);
+        parts.write().unwrap();
+        let parts = Parts::read(filepath).unwrap();
+        assert_eq!(
+            parts.raws[1..],
+            [
+                "## Todo list\n\n",
+                "* [ ] second\n* [ ] third\n\n## Archive\n\n* [x] first\n  > why\n"
+            ]
+        );
+    }
+}
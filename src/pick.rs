@@ -0,0 +1,93 @@
+//! Pick a Todo context interactively when its name is omitted
+//!
+//! `todo config set-context` and `todo move TITLE` without a context name show a fuzzy picker of
+//! the available Todo contexts: typing narrows the list down. Without a terminal, or with
+//! `--no-input`, the command fails with the available contexts instead of waiting for an answer.
+use crate::Configuration;
+use clap::Arg;
+use dialoguer::FuzzySelect;
+use std::io::IsTerminal;
+
+/// Returns the argument forbidding interactive prompts
+pub fn no_input_arg() -> Arg<'static, 'static> {
+    Arg::with_name("no-input")
+        .long("no-input")
+        .help("Never prompts: commands needing an answer (such as a Todo context to pick) fail instead")
+        .global(true)
+}
+
+/// Returns the name of the Todo context picked among Todo contexts of the configuration but
+/// `except`. The active Todo context is selected first.
+pub fn pick_ctx(
+    config: &Configuration,
+    prompt: &str,
+    except: Option<&str>,
+    no_input: bool,
+) -> Result<String, std::io::Error> {
+    let names = config
+        .ctxs
+        .iter()
+        .map(|ctx| ctx.name.as_str())
+        .filter(|name| Some(*name) != except)
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No other Todo context to pick. Create one with `todo config create-context`",
+        ));
+    }
+    // prompts would wait forever inside scripts
+    if no_input || !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("No context given. Available contexts: {}", names.join(", ")),
+        ));
+    }
+    let active = names
+        .iter()
+        .position(|name| *name == config.active_ctx_name)
+        .unwrap_or(0);
+    let picked = FuzzySelect::new()
+        .with_prompt(prompt)
+        .items(&names)
+        .default(active)
+        .interact()
+        .map_err(std::io::Error::other)?;
+    Ok(names[picked].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Context;
+
+    #[test]
+    fn no_input_lists_contexts_to_pick_from() {
+        let ctx = |name: &str| Context {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let config = Configuration {
+            active_ctx_name: "work".to_string(),
+            ctxs: vec![ctx("work"), ctx("home")],
+            ..Default::default()
+        };
+        assert_eq!(
+            pick_ctx(&config, "Pick", None, true)
+                .unwrap_err()
+                .to_string(),
+            "No context given. Available contexts: work, home"
+        );
+        assert_eq!(
+            pick_ctx(&config, "Pick", Some("work"), true)
+                .unwrap_err()
+                .to_string(),
+            "No context given. Available contexts: home"
+        );
+        let single = Configuration {
+            ctxs: vec![ctx("work")],
+            ..config
+        };
+        assert!(pick_ctx(&single, "Pick", Some("work"), true).is_err());
+    }
+}
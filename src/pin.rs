@@ -0,0 +1,246 @@
+//! Pin Todo lists and order Todo lists manually
+//!
+//! `todo list` prints the pinned Todo lists of a Todo context first, then the Todo lists of its
+//! manual order, then the other Todo lists in the order chosen with `--sort`. Pins (`pinned`) and the
+//! manual order (`order`) are titles stored with the Todo context inside the configuration file.
+use crate::config_file::write_configuration;
+use crate::parse::{parse_configuration_file, parse_tasks};
+use crate::single_file::{title_of, SingleFile};
+use crate::{read_todo_lists, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::trace;
+use std::path::Path;
+
+/// Returns the Pin command
+pub fn pin_command() -> App<'static, 'static> {
+    App::new("pin")
+        .about("Pin todo lists so they are listed first within Todo context")
+        .author(crate_authors!())
+        .arg(titles_arg())
+}
+
+/// Returns the Unpin command
+pub fn unpin_command() -> App<'static, 'static> {
+    App::new("unpin")
+        .about("Unpin todo lists within Todo context")
+        .author(crate_authors!())
+        .arg(titles_arg())
+}
+
+/// Returns the Order command
+pub fn order_command() -> App<'static, 'static> {
+    App::new("order")
+        .about("Set the order of todo lists listed after pinned ones within Todo context, or show it")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("titles")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .help("Todo lists in the order they are listed, repeat for more (-t release -t groceries)")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("clear")
+                .long("clear")
+                .help("Remove the manual order of Todo context")
+                .conflicts_with("titles"),
+        )
+}
+
+/// Returns title argument shared by pin and unpin commands
+fn titles_arg() -> Arg<'static, 'static> {
+    Arg::with_name("titles")
+        .short("t")
+        .long("title")
+        .value_name("TITLE")
+        .help("Title of todo list, repeat for more")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .required(true)
+}
+
+/// Executes pin, unpin and order commands, which change the active Todo context of the
+/// configuration file
+pub fn pin_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    raw_config: Option<&str>,
+) -> Result<(), std::io::Error> {
+    let mut config = parse_configuration_file(Some(todo_configuration_path), raw_config)?;
+    let active_ctx_name = config.active_ctx_name.clone();
+    let ctx = match config
+        .ctxs
+        .iter_mut()
+        .find(|ctx| ctx.name == active_ctx_name)
+    {
+        Some(ctx) => ctx,
+        None => {
+            return Err(std::io::Error::other(format!(
+                "Unknown context \"{active_ctx_name}\""
+            )))
+        }
+    };
+
+    if let Some(args) = args.subcommand_matches("pin") {
+        trace!("pin subcommand");
+        for title in args.values_of("titles").unwrap() {
+            warn_unknown_title(ctx, title)?;
+            if ctx.pinned.iter().any(|p| same_title(p, title)) {
+                println!("\"{title}\" is already pinned");
+                continue;
+            }
+            ctx.pinned.push(title.to_string());
+            println!("Pinned \"{title}\"");
+        }
+    } else if let Some(args) = args.subcommand_matches("unpin") {
+        trace!("unpin subcommand");
+        for title in args.values_of("titles").unwrap() {
+            let pins = ctx.pinned.len();
+            ctx.pinned.retain(|p| !same_title(p, title));
+            if ctx.pinned.len() == pins {
+                println!("\"{title}\" is not pinned");
+            } else {
+                println!("Unpinned \"{title}\"");
+            }
+        }
+    } else if let Some(args) = args.subcommand_matches("order") {
+        trace!("order subcommand");
+        if args.is_present("clear") {
+            ctx.order.clear();
+            println!("\"{active_ctx_name}\" has no manual order");
+        } else if let Some(titles) = args.values_of("titles") {
+            ctx.order = titles.map(|t| t.to_string()).collect();
+            for title in ctx.order.iter() {
+                warn_unknown_title(ctx, title)?;
+            }
+            println!("Ordered {} todo list(s)", ctx.order.len());
+        } else {
+            for title in &ctx.pinned {
+                println!("{title} (pinned)");
+            }
+            for title in &ctx.order {
+                println!("{title}");
+            }
+            return Ok(());
+        }
+    }
+    write_configuration(todo_configuration_path, &config)
+}
+
+/// Returns true if both titles name the same Todo list
+pub(crate) fn same_title(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// Warns when no Todo list of the Todo context is titled `title`, since it could be misspelled
+fn warn_unknown_title(ctx: &Context, title: &str) -> Result<(), std::io::Error> {
+    let todo_raws = if ctx.is_single_file() {
+        SingleFile::read(ctx)?.todo_lists
+    } else if Path::new(ctx.folder_location.as_str()).is_dir() {
+        read_todo_lists(ctx)?
+            .into_iter()
+            .map(|(_, todo_raw)| todo_raw)
+            .collect()
+    } else {
+        vec![]
+    };
+    if !todo_raws.iter().any(|t| same_title(title_of(t), title)) {
+        eprintln!(
+            "Warning: no Todo list titled \"{title}\" in context {}",
+            ctx.name
+        );
+    }
+    Ok(())
+}
+
+/// Returns indices of `todo_raws` in listing order: pinned Todo lists of the Todo context first,
+/// then Todo lists of its manual order, then the others sorted by `sort` (`title`, `open` for most
+/// open tasks first or `priority` for the highest priority open tasks first, then most such tasks)
+/// or else left in place
+pub(crate) fn listing_order(todo_raws: &[&str], ctx: &Context, sort: Option<&str>) -> Vec<usize> {
+    let mut indices = (0..todo_raws.len()).collect::<Vec<_>>();
+    indices.sort_by_cached_key(|i| {
+        let title = title_of(todo_raws[*i]);
+        let position = |titles: &[String]| titles.iter().position(|t| same_title(t, title));
+        let (group, position) = match (position(&ctx.pinned), position(&ctx.order)) {
+            (Some(position), _) => (0, position),
+            (None, Some(position)) => (1, position),
+            (None, None) => (2, 0),
+        };
+        let (priority, open, title) = match sort {
+            Some("open") => (
+                None,
+                parse_tasks(todo_raws[*i])
+                    .iter()
+                    .filter(|t| !t.done)
+                    .count(),
+                String::new(),
+            ),
+            Some("priority") => {
+                let priorities = parse_tasks(todo_raws[*i])
+                    .iter()
+                    .filter(|t| !t.done)
+                    .filter_map(|t| t.priority())
+                    .collect::<Vec<_>>();
+                let highest = priorities.iter().min().copied();
+                let count = priorities.iter().filter(|p| Some(**p) == highest).count();
+                (highest, count, String::new())
+            }
+            Some("title") => (None, 0, title.to_lowercase()),
+            _ => (None, 0, String::new()),
+        };
+        // Todo lists without priorities come last
+        let priority = priority.unwrap_or(char::MAX);
+        (group, position, priority, std::cmp::Reverse(open), title)
+    });
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_then_ordered_then_sorted() {
+        let todo_raw = |title: &str, tasks: &str| {
+            format!("# {title}\n\n## Description\n\nLABEL=\n\n## Todo list\n\n{tasks}")
+        };
+        let todo_raws = [
+            todo_raw("zebra", "* [ ] a\n"),
+            todo_raw("groceries", "* [ ] a\n* [ ] b\n"),
+            todo_raw("Release", "* [x] a\n"),
+            todo_raw("apple", "* [x] a\n"),
+        ];
+        let todo_raws = todo_raws.iter().map(|t| t.as_str()).collect::<Vec<_>>();
+        let ctx = Context {
+            pinned: vec!["release".to_string()],
+            order: vec!["zebra".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(listing_order(&todo_raws, &ctx, None), vec![2, 0, 1, 3]);
+        assert_eq!(
+            listing_order(&todo_raws, &ctx, Some("title")),
+            vec![2, 0, 3, 1]
+        );
+        assert_eq!(
+            listing_order(&todo_raws, &Context::default(), Some("open")),
+            vec![1, 0, 2, 3]
+        );
+
+        let todo_raws = [
+            todo_raw("none", "* [ ] a\n"),
+            todo_raw("one A", "* [ ] (B) a\n* [ ] (A) b\n"),
+            todo_raw("done A", "* [x] (A) a\n* [ ] (C) b\n"),
+            todo_raw("two A", "* [ ] (A) a\n* [ ] (A) b\n"),
+        ];
+        let todo_raws = todo_raws.iter().map(|t| t.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            listing_order(&todo_raws, &Context::default(), Some("priority")),
+            vec![3, 1, 2, 0]
+        );
+    }
+}
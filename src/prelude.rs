@@ -0,0 +1,29 @@
+//! Stable API for crates depending on `todo`
+//!
+//! ```no_run
+//! use todo::prelude::*;
+//!
+//! let config = Configuration::from_file("/home/me/.config/todo/todo.toml")?;
+//! let store = TodoStore::new(config.active_ctx().clone());
+//! for todo_list in store.todo_lists()? {
+//!     let open = todo_list.tasks().iter().filter(|t| !t.done).count();
+//!     println!("{}: {open} open tasks", todo_list.title());
+//! }
+//! # Ok::<(), Error>(())
+//! ```
+//!
+//! Items re-exported here follow semantic versioning: while `todo` is `0.x`, a breaking change of
+//! them (removed or renamed item, changed signature, removed public field) only comes with a new
+//! minor version, so `todo = "0.1"` keeps compiling. Adding fields is not breaking: structs with
+//! public fields ([`Context`], [`Task`], [`Section`]) are `#[non_exhaustive]`, so they are built
+//! with their constructor (such as [`Context::new`]) then their fields are set.
+//!
+//! Everything else is plumbing of the `todo` binary and may change in any release, notably the
+//! subcommand modules (`*_command` and `*_command_process` functions) and their errors.
+//!
+//! Fallible functions return [`Error`], which is [`std::io::Error`]. Its kind is part of the stable
+//! API: for instance, a missing Todo list is [`std::io::ErrorKind::NotFound`].
+pub use crate::store::TodoStore;
+pub use crate::{Configuration, Context, TodoList};
+pub use std::io::Error;
+pub use todo_core::{Section, Task};
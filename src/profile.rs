@@ -0,0 +1,146 @@
+//! Select among configuration files with `todo --profile NAME`
+//!
+//! A profile is a configuration file of its own inside `~/.config/todo/profiles/` (or
+//! `$XDG_CONFIG_HOME/todo/profiles/`): `todo --profile personal` uses
+//! `~/.config/todo/profiles/personal.toml`. Todo contexts, the active context, the default command,
+//! views and aliases of a profile are therefore isolated from other profiles and from `~/.todo`,
+//! which is used without `--profile`.
+use clap::{Arg, ArgMatches};
+use log::debug;
+use std::path::{Path, PathBuf};
+
+/// Returns the profile argument
+pub fn profile_arg() -> Arg<'static, 'static> {
+    Arg::with_name("profile")
+        .long("profile")
+        .value_name("PROFILE")
+        .help(
+            "Uses configuration file of PROFILE inside ~/.config/todo/profiles/ instead of default",
+        )
+        .conflicts_with("with-config-path")
+        .takes_value(true)
+}
+
+/// Returns the path of the configuration file: given with `--with-config-path`, of the profile
+/// given with `--profile` or else `~/.todo`
+///
+/// A profile must exist, unless a configuration command creates it.
+pub fn configuration_path(matches: &ArgMatches, home: &str) -> Result<String, std::io::Error> {
+    configuration_path_in(matches, home, profiles_folder(home))
+}
+
+/// Returns the path of the configuration file, where profiles are inside `folder`
+fn configuration_path_in(
+    matches: &ArgMatches,
+    home: &str,
+    folder: PathBuf,
+) -> Result<String, std::io::Error> {
+    if let Some(path) = matches.value_of("with-config-path") {
+        return Ok(path.to_string());
+    }
+    let name = match matches.value_of("profile") {
+        Some(name) => name,
+        None => return Ok(format!("{home}/.todo")),
+    };
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("\"{name}\" is not a valid profile name"),
+        ));
+    }
+    let path = folder.join(format!("{name}.toml"));
+    if !path.exists() {
+        if matches.subcommand_name() != Some("config") {
+            let available = profiles(folder.as_path());
+            let available = if available.is_empty() {
+                String::from("No profiles exist yet")
+            } else {
+                format!("Available profiles: {}", available.join(", "))
+            };
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "Unknown profile \"{name}\". {available}. Create it with `todo --profile {name} config create-context`"
+                ),
+            ));
+        }
+        std::fs::create_dir_all(&folder)?;
+    }
+    debug!("profile {name}: {}", path.display());
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Returns the folder of the configuration files of profiles
+fn profiles_folder(home: &str) -> PathBuf {
+    let config_home = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(config_home) if !config_home.is_empty() => PathBuf::from(config_home),
+        _ => Path::new(home).join(".config"),
+    };
+    config_home.join("todo").join("profiles")
+}
+
+/// Returns the names of the profiles inside the profiles folder, sorted
+fn profiles(folder: &Path) -> Vec<String> {
+    let mut profiles = std::fs::read_dir(folder)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let path = e.path();
+                    match path.extension() {
+                        Some(extension) if extension == "toml" => {
+                            Some(path.file_stem()?.to_string_lossy().to_string())
+                        }
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    profiles.sort();
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+    use clap::App;
+
+    #[test]
+    fn profile_configuration_paths() {
+        let home = TempFolder::new("profiles");
+        let home = home.to_str().unwrap();
+        let folder = Path::new(home).join("profiles");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("work.toml"), "").unwrap();
+        let app = App::new("todo")
+            .arg(profile_arg())
+            .arg(
+                Arg::with_name("with-config-path")
+                    .short("p")
+                    .takes_value(true),
+            )
+            .subcommand(App::new("config"))
+            .subcommand(App::new("list"));
+        let path = |args: &[&str]| {
+            let matches = app.clone().get_matches_from(args);
+            configuration_path_in(&matches, home, folder.clone())
+                .map(|p| p.replace(folder.to_str().unwrap(), "P"))
+        };
+
+        assert_eq!(path(&["todo", "list"]).unwrap(), format!("{home}/.todo"));
+        assert_eq!(path(&["todo", "-p", "/a", "list"]).unwrap(), "/a");
+        assert_eq!(
+            path(&["todo", "--profile", "work", "list"]).unwrap(),
+            "P/work.toml"
+        );
+        let unknown = path(&["todo", "--profile", "home", "list"]).unwrap_err();
+        assert!(unknown.to_string().contains("Available profiles: work."));
+        assert_eq!(
+            path(&["todo", "--profile", "home", "config"]).unwrap(),
+            "P/home.toml"
+        );
+        assert!(path(&["todo", "--profile", "../etc", "list"]).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! Report the progress of long operations on stderr
+//!
+//! `--progress` selects how:
+//!
+//! * `auto` (default): a progress bar when stderr is a terminal, nothing otherwise
+//! * `bar`: a progress bar
+//! * `json`: one JSON event per line, for wrappers
+//! * `none`: nothing
+//!
+//! JSON events are `start`, one `progress` per processed item and `finish`:
+//!
+//! ```text
+//! {"event":"start","operation":"import","done":0,"total":2}
+//! {"event":"progress","operation":"import","done":1,"total":2,"item":"pay rent"}
+//! {"event":"finish","operation":"import","done":2,"total":2}
+//! ```
+use crate::json::quote;
+use clap::{Arg, ArgMatches};
+use std::io::{IsTerminal, Write};
+
+/// Width of the progress bar, in characters
+const BAR_WIDTH: usize = 24;
+
+/// Returns the global argument selecting how progress is reported
+pub fn progress_arg() -> Arg<'static, 'static> {
+    Arg::with_name("progress")
+        .long("progress")
+        .value_name("auto|bar|json|none")
+        .help("Reports progress of long operations (caldav, import, ingest, migrate) on stderr")
+        .possible_values(&["auto", "bar", "json", "none"])
+        .default_value("auto")
+        .takes_value(true)
+        .global(true)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Bar,
+    Json,
+    Silent,
+}
+
+/// Progress of an operation over `total` items
+pub struct Progress {
+    mode: Mode,
+    operation: &'static str,
+    done: usize,
+    total: usize,
+}
+
+impl Progress {
+    /// Starts reporting the progress of `operation` as selected by `--progress`
+    pub fn start(args: &ArgMatches, operation: &'static str, total: usize) -> Progress {
+        let mode = match args.value_of("progress").unwrap_or("auto") {
+            "bar" => Mode::Bar,
+            "json" => Mode::Json,
+            "none" => Mode::Silent,
+            _ if std::io::stderr().is_terminal() => Mode::Bar,
+            _ => Mode::Silent,
+        };
+        let progress = Progress {
+            mode,
+            operation,
+            done: 0,
+            total,
+        };
+        progress.report("start", None);
+        progress
+    }
+
+    /// Reports that `item` was processed
+    pub fn advance(&mut self, item: &str) {
+        self.done += 1;
+        self.report("progress", Some(item));
+    }
+
+    /// Prints `line` on stdout without mixing it with the progress bar
+    pub fn println(&self, line: &str) {
+        if self.mode == Mode::Bar {
+            eprint!("\r\x1b[2K");
+        }
+        println!("{line}");
+        self.report("redraw", None);
+    }
+
+    /// Reports the end of the operation
+    pub fn finish(self) {
+        self.report("finish", None);
+    }
+
+    fn report(&self, event: &str, item: Option<&str>) {
+        match self.mode {
+            Mode::Silent => {}
+            Mode::Json if event == "redraw" => {}
+            Mode::Json => {
+                let item = item
+                    .map(|item| format!(",\"item\":{}", quote(item)))
+                    .unwrap_or_default();
+                eprintln!(
+                    "{{\"event\":\"{event}\",\"operation\":\"{}\",\"done\":{},\"total\":{}{item}}}",
+                    self.operation, self.done, self.total
+                );
+            }
+            Mode::Bar if event == "finish" => eprint!("\r\x1b[2K"),
+            Mode::Bar => {
+                eprint!(
+                    "\r\x1b[2K{}",
+                    bar(self.operation, self.done, self.total, item)
+                );
+                let _ = std::io::stderr().flush();
+            }
+        }
+    }
+}
+
+/// Returns the progress bar line of `done` items out of `total`
+fn bar(operation: &str, done: usize, total: usize, item: Option<&str>) -> String {
+    let filled = (done * BAR_WIDTH).checked_div(total).unwrap_or(BAR_WIDTH);
+    let item = item
+        .map(|item| {
+            let mut shortened = item.chars().take(40).collect::<String>();
+            if item.chars().count() > 40 {
+                shortened.push('…');
+            }
+            format!(" {shortened}")
+        })
+        .unwrap_or_default();
+    format!(
+        "{operation} [{}{}] {done}/{total}{item}",
+        "#".repeat(filled.min(BAR_WIDTH)),
+        " ".repeat(BAR_WIDTH - filled.min(BAR_WIDTH))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_progress_bar() {
+        assert_eq!(
+            bar("import", 6, 24, Some("pay rent")),
+            "import [######                  ] 6/24 pay rent"
+        );
+        assert_eq!(
+            bar("migrate", 0, 0, None),
+            "migrate [########################] 0/0"
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! Add a task written in natural language
+//!
+//! `todo quick pay rent every 1st of month @home !high` adds
+//! `* [ ] (A) pay rent @due(2024-07-01) @every(1m) @label(home)` to the inbox Todo list. Understood
+//! words are:
+//!
+//! * due dates: `today`, `tomorrow`, `[on|by|due] WEEKDAY|YYYY-MM-DD`, `in N days|weeks|months`,
+//!   `next week|month|WEEKDAY`
+//! * recurrence: `daily`, `weekly`, `monthly`, `yearly`, `every [N] day|week|month|year`,
+//!   `every WEEKDAY`, `every Nth of [the] month`
+//! * labels: `@label`
+//! * priority: `!high`, `!medium`, `!low` (or `!a`, `!b`, `!c`)
+//!
+//! Any other word is part of the task.
+use crate::confirm::{confirm, Operation};
+use crate::lock::ensure_unlocked;
+use crate::merge_file::insert_task;
+use crate::parse::Task;
+use crate::{
+    prompt_for_todo_folder_if_not_exists, read_todo_list_or_new, resolve_todo_path, Configuration,
+    Context,
+};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+
+/// Task with the annotations understood from natural language
+#[derive(Debug, Default, PartialEq)]
+struct QuickTask {
+    text: String,
+    due: Option<NaiveDate>,
+    /// Recurrence period (e.g. `1w`)
+    every: Option<String>,
+    labels: Vec<String>,
+    priority: Option<char>,
+    /// Understood words with their meaning
+    interpretations: Vec<(String, String)>,
+}
+
+impl QuickTask {
+    /// Returns the task line with its annotations
+    fn line(&self) -> String {
+        let mut line = String::from("* [ ] ");
+        if let Some(priority) = self.priority {
+            line.push_str(format!("({priority}) ").as_str());
+        }
+        line.push_str(self.text.as_str());
+        if let Some(due) = self.due {
+            line.push_str(format!(" @due({})", due.format("%Y-%m-%d")).as_str());
+        }
+        if let Some(every) = &self.every {
+            line.push_str(format!(" @every({every})").as_str());
+        }
+        for label in &self.labels {
+            line.push_str(format!(" @label({label})").as_str());
+        }
+        line
+    }
+}
+
+/// Returns the Quick command
+pub fn quick_command() -> App<'static, 'static> {
+    App::new("quick")
+        .about("Add a task written in natural language (dates, recurrence, @labels, !priority)")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("text")
+                .value_name("TEXT")
+                .index(1)
+                .multiple(true)
+                .help("Task, e.g. \"pay rent every 1st of month @home !high\"")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("list")
+                .long("list")
+                .value_name("TITLE")
+                .help("Todo list receiving the task")
+                .default_value("Inbox")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("explain")
+                .long("explain")
+                .help("Show how the text was understood and confirm before adding the task"),
+        )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Add task even if todo list is locked"),
+        )
+}
+
+/// Adds task to inbox Todo list of active Todo context
+pub fn quick_command_process(
+    args: &ArgMatches,
+    ctx: &Context,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    trace!("quick subcommand");
+    let input = args
+        .values_of("text")
+        .unwrap()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let title = args.value_of("list").unwrap();
+    let task = parse_quick(
+        input.as_str(),
+        chrono::Local::today().naive_local(),
+        config.date_format(ctx).as_str(),
+    );
+
+    if args.is_present("explain") {
+        for (words, meaning) in &task.interpretations {
+            println!("\"{words}\" -> {meaning}");
+        }
+        println!("task: {}", task.line());
+        let prompt = format!("Add task to \"{title}\"?");
+        if !confirm(ctx, Operation::Quick, prompt.as_str())? {
+            return Ok(());
+        }
+    }
+
+    if let Err(e) = prompt_for_todo_folder_if_not_exists(ctx) {
+        return Err(std::io::Error::other(e.to_string()));
+    }
+    let filepath = resolve_todo_path(ctx, title)?;
+    ensure_unlocked(filepath.as_str(), args.is_present("force"))?;
+    let todo_raw = read_todo_list_or_new(filepath.as_str(), title)?;
+    let section_less = Task::new(task.text.as_str(), false);
+    std::fs::write(
+        &filepath,
+        insert_task(todo_raw.as_str(), &section_less, vec![task.line()]),
+    )?;
+    debug!("wrote {filepath}");
+    println!("Added \"{}\" to \"{title}\"", task.text);
+    Ok(())
+}
+
+/// Returns the task understood from `input`. Dates of interpretations are printed with
+/// `date_format`.
+fn parse_quick(input: &str, today: NaiveDate, date_format: &str) -> QuickTask {
+    let words = input.split_whitespace().collect::<Vec<_>>();
+    let lower = words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>();
+    let word = |i: usize| lower.get(i).map(|w| w.as_str()).unwrap_or("");
+    let mut task = QuickTask::default();
+    let mut text = vec![];
+    let mut i = 0;
+    while i < words.len() {
+        // number of understood words and their meaning
+        let mut understood: Option<(usize, String)> = None;
+        let w = word(i);
+
+        if let Some(label) = words[i].strip_prefix('@').filter(|l| !l.is_empty()) {
+            task.labels.push(label.to_string());
+            understood = Some((1, format!("label {label}")));
+        } else if let Some(priority) = w.strip_prefix('!').and_then(priority) {
+            task.priority = Some(priority);
+            understood = Some((1, format!("priority {priority}")));
+        } else if let Some((count, every, first)) = recurrence(&lower[i..], today) {
+            task.due = Some(first);
+            understood = Some((
+                count,
+                format!(
+                    "repeats every {every} (@every({every})), first due {}",
+                    first.format(date_format)
+                ),
+            ));
+            task.every = Some(every);
+        } else if let Some((count, due)) = due_date(&lower[i..], today) {
+            task.due = Some(due);
+            understood = Some((count, format!("due {}", due.format(date_format))));
+        }
+
+        match understood {
+            Some((count, meaning)) => {
+                task.interpretations
+                    .push((words[i..i + count].join(" "), meaning));
+                i += count;
+            }
+            None => {
+                text.push(words[i]);
+                i += 1;
+            }
+        }
+    }
+    task.text = text.join(" ");
+    task
+}
+
+/// Returns priority of `!word`
+fn priority(word: &str) -> Option<char> {
+    match word {
+        "high" | "h" | "a" => Some('A'),
+        "medium" | "med" | "m" | "b" => Some('B'),
+        "low" | "l" | "c" => Some('C'),
+        _ => None,
+    }
+}
+
+/// Returns the number of words, the period and the first due date of the recurrence starting the
+/// words
+fn recurrence(words: &[String], today: NaiveDate) -> Option<(usize, String, NaiveDate)> {
+    let word = |i: usize| words.get(i).map(|w| w.as_str()).unwrap_or("");
+    let period = match word(0) {
+        "daily" => Some('d'),
+        "weekly" => Some('w'),
+        "monthly" => Some('m'),
+        "yearly" => Some('y'),
+        _ => None,
+    };
+    if let Some(period) = period {
+        return Some((1, format!("1{period}"), today));
+    }
+    if word(0) != "every" {
+        return None;
+    }
+    if let Some(weekday) = weekday(word(1)) {
+        return Some((2, "1w".to_string(), next_weekday(today, weekday, true)));
+    }
+    if let Some(unit) = unit(word(1)) {
+        return Some((2, format!("1{unit}"), today));
+    }
+    if let (Ok(n), Some(unit)) = (word(1).parse::<u32>(), unit(word(2))) {
+        if n > 0 {
+            return Some((3, format!("{n}{unit}"), today));
+        }
+    }
+    if let (Some(day), "of") = (ordinal(word(1)), word(2)) {
+        let count = if word(3) == "the" { 5 } else { 4 };
+        if word(count - 1) == "month" {
+            return next_day_of_month(today, day).map(|first| (count, "1m".to_string(), first));
+        }
+    }
+    None
+}
+
+/// Returns the number of words and the due date starting the words
+fn due_date(words: &[String], today: NaiveDate) -> Option<(usize, NaiveDate)> {
+    let word = |i: usize| words.get(i).map(|w| w.as_str()).unwrap_or("");
+    let date = |w: &str| match w {
+        "today" => Some(today),
+        "tomorrow" => Some(today + Duration::days(1)),
+        w => weekday(w)
+            .map(|weekday| next_weekday(today, weekday, false))
+            .or_else(|| NaiveDate::parse_from_str(w, "%Y-%m-%d").ok()),
+    };
+    match word(0) {
+        "today" | "tomorrow" => date(word(0)).map(|d| (1, d)),
+        "on" | "by" | "due" => date(word(1)).map(|d| (2, d)),
+        "in" => {
+            let n = word(1).parse::<u32>().ok()?;
+            // dates out of range are left as text
+            unit(word(2))
+                .and_then(|unit| add(today, n, unit))
+                .map(|due| (3, due))
+        }
+        "next" => match word(1) {
+            "week" => Some((2, next_weekday(today, Weekday::Mon, false))),
+            "month" => {
+                let next_month = add(today, 1, 'm')?;
+                with_day(next_month.year(), next_month.month(), 1).map(|due| (2, due))
+            }
+            w => weekday(w).map(|weekday| (2, next_weekday(today, weekday, false))),
+        },
+        w => NaiveDate::parse_from_str(w, "%Y-%m-%d")
+            .ok()
+            .map(|d| (1, d)),
+    }
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn unit(word: &str) -> Option<char> {
+    match word.trim_end_matches('s') {
+        "day" => Some('d'),
+        "week" => Some('w'),
+        "month" => Some('m'),
+        "year" => Some('y'),
+        _ => None,
+    }
+}
+
+/// Returns day of month of `1st`, `2nd`, `3rd`, `15th`...
+fn ordinal(word: &str) -> Option<u32> {
+    let day = word
+        .strip_suffix("st")
+        .or_else(|| word.strip_suffix("nd"))
+        .or_else(|| word.strip_suffix("rd"))
+        .or_else(|| word.strip_suffix("th"))?
+        .parse::<u32>()
+        .ok()?;
+    (1..=31).contains(&day).then_some(day)
+}
+
+/// Returns the next `weekday` after `today` (or `today` itself when `including_today`)
+fn next_weekday(today: NaiveDate, weekday: Weekday, including_today: bool) -> NaiveDate {
+    let mut days = (7 + weekday.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64)
+        % 7;
+    if days == 0 && !including_today {
+        days = 7;
+    }
+    today + Duration::days(days)
+}
+
+/// Returns the next date falling on `day` of month from `today` (included). Days past the end of a
+/// month fall on its last day.
+fn next_day_of_month(today: NaiveDate, day: u32) -> Option<NaiveDate> {
+    let this_month = with_day(today.year(), today.month(), day)?;
+    if this_month >= today {
+        return Some(this_month);
+    }
+    let next_month = add(today, 1, 'm')?;
+    with_day(next_month.year(), next_month.month(), day)
+}
+
+/// Returns `day` of month, or the last day of the month when the month is shorter. Returns `None`
+/// when the year is out of range.
+fn with_day(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    (1..=day)
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+}
+
+/// Returns `date` moved `n` days, weeks, months or years later, or `None` when the date is out of
+/// range
+fn add(date: NaiveDate, n: u32, unit: char) -> Option<NaiveDate> {
+    let months = match unit {
+        'd' => return date.checked_add_signed(Duration::days(n as i64)),
+        'w' => return date.checked_add_signed(Duration::weeks(n as i64)),
+        'm' => n as i64,
+        _ => 12 * n as i64,
+    };
+    let month0 = date.month0() as i64 + months;
+    with_day(
+        i32::try_from(date.year() as i64 + month0.div_euclid(12)).ok()?,
+        month0.rem_euclid(12) as u32 + 1,
+        date.day(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_DATE_FORMAT;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parse_recurrence_labels_and_priority() {
+        // a Wednesday
+        let today = date("2024-06-12");
+        let task = parse_quick(
+            "pay rent every 1st of month @home !high",
+            today,
+            DEFAULT_DATE_FORMAT,
+        );
+        assert_eq!(task.text, "pay rent");
+        assert_eq!(
+            task.line(),
+            "* [ ] (A) pay rent @due(2024-07-01) @every(1m) @label(home)"
+        );
+        assert_eq!(
+            task.interpretations,
+            vec![
+                (
+                    "every 1st of month".to_string(),
+                    "repeats every 1m (@every(1m)), first due 2024-07-01".to_string()
+                ),
+                ("@home".to_string(), "label home".to_string()),
+                ("!high".to_string(), "priority A".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_quick("call mom tomorrow", today, "%d.%m.%Y").interpretations,
+            vec![("tomorrow".to_string(), "due 13.06.2024".to_string())]
+        );
+
+        assert_eq!(
+            parse_quick("water plants every 3 days", today, DEFAULT_DATE_FORMAT).line(),
+            "* [ ] water plants @due(2024-06-12) @every(3d)"
+        );
+        assert_eq!(
+            parse_quick("standup every Wednesday", today, DEFAULT_DATE_FORMAT).due,
+            Some(today)
+        );
+    }
+
+    #[test]
+    fn parse_due_dates() {
+        let today = date("2024-06-12");
+        let due = |input: &str| parse_quick(input, today, DEFAULT_DATE_FORMAT).due;
+        assert_eq!(due("call bank tomorrow"), Some(date("2024-06-13")));
+        assert_eq!(due("call bank on friday"), Some(date("2024-06-14")));
+        assert_eq!(due("call bank by wed"), Some(date("2024-06-19")));
+        assert_eq!(due("call bank in 2 weeks"), Some(date("2024-06-26")));
+        assert_eq!(due("call bank next week"), Some(date("2024-06-17")));
+        assert_eq!(due("call bank next month"), Some(date("2024-07-01")));
+        assert_eq!(due("call bank 2024-08-01"), Some(date("2024-08-01")));
+        assert_eq!(due("read friday notes"), None);
+        assert_eq!(
+            parse_quick("read friday notes", today, DEFAULT_DATE_FORMAT).text,
+            "read friday notes"
+        );
+        assert_eq!(add(date("2024-01-31"), 1, 'm'), Some(date("2024-02-29")));
+        assert_eq!(
+            next_day_of_month(date("2024-02-10"), 31),
+            Some(date("2024-02-29"))
+        );
+        // out of range dates are text
+        for input in [
+            "x in 999999 years",
+            "y in 4294967295 days",
+            "z in 4294967295 weeks",
+        ] {
+            let task = parse_quick(input, today, DEFAULT_DATE_FORMAT);
+            assert_eq!(task.text, input);
+            assert_eq!(task.due, None);
+        }
+    }
+}
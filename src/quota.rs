@@ -0,0 +1,115 @@
+//! Soft limits of Todo contexts
+//!
+//! `max_open_tasks` and `max_lists` of a Todo context are soft limits: nothing is refused, but
+//! `todo list` warns when they are exceeded and `todo stats quotas` reports them. Archived Todo
+//! lists do not count.
+use crate::parse::parse_tasks;
+use crate::single_file::SingleFile;
+use crate::{read_todo_lists, Context, ARCHIVE_FOLDER};
+use std::path::Path;
+
+/// Open tasks and Todo lists of a Todo context
+#[derive(Debug, Default, PartialEq)]
+pub struct Load {
+    pub open_tasks: usize,
+    pub lists: usize,
+}
+
+impl Load {
+    fn of<'a>(todo_raws: impl Iterator<Item = &'a str>) -> Load {
+        let mut load = Load::default();
+        for todo_raw in todo_raws {
+            load.lists += 1;
+            load.open_tasks += parse_tasks(todo_raw).iter().filter(|t| !t.done).count();
+        }
+        load
+    }
+}
+
+/// Returns true if the Todo context declares any soft limit
+pub fn has_quota(ctx: &Context) -> bool {
+    ctx.max_open_tasks.is_some() || ctx.max_lists.is_some()
+}
+
+/// Returns the open tasks and Todo lists of the Todo context, outside its archive
+pub fn load(ctx: &Context) -> Result<Load, std::io::Error> {
+    if ctx.is_single_file() {
+        let single_file = SingleFile::read(ctx)?;
+        return Ok(Load::of(single_file.todo_lists.iter().map(|t| t.as_str())));
+    }
+    let folder = Path::new(ctx.folder_location.as_str());
+    if !folder.is_dir() {
+        return Ok(Load::default());
+    }
+    let archive_folder = folder.join(ARCHIVE_FOLDER);
+    let todo_lists = read_todo_lists(ctx)?;
+    Ok(Load::of(
+        todo_lists
+            .iter()
+            .filter(|(filepath, _)| !Path::new(filepath).starts_with(&archive_folder))
+            .map(|(_, todo_raw)| todo_raw.as_str()),
+    ))
+}
+
+/// Returns a message for each soft limit of the Todo context exceeded by `load`
+pub fn exceeded(ctx: &Context, load: &Load) -> Vec<String> {
+    let mut messages = vec![];
+    if let Some(max) = ctx.max_open_tasks.filter(|max| load.open_tasks > *max) {
+        messages.push(format!(
+            "{} context has {} open tasks, over your limit of {max}",
+            ctx.name, load.open_tasks
+        ));
+    }
+    if let Some(max) = ctx.max_lists.filter(|max| load.lists > *max) {
+        messages.push(format!(
+            "{} context has {} Todo lists, over your limit of {max}",
+            ctx.name, load.lists
+        ));
+    }
+    messages
+}
+
+/// Warns about soft limits of the Todo context which are exceeded
+pub fn warn_exceeded(ctx: &Context) -> Result<(), std::io::Error> {
+    if !has_quota(ctx) {
+        return Ok(());
+    }
+    for message in exceeded(ctx, &load(ctx)?) {
+        eprintln!("Warning: {message}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exceeded_quotas() {
+        let load = Load::of(
+            [
+                "# a\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] one\n* [x] two\n* [ ] three\n",
+                "# b\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] four\n",
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            load,
+            Load {
+                open_tasks: 3,
+                lists: 2
+            }
+        );
+        let ctx = Context {
+            name: "work".to_string(),
+            max_open_tasks: Some(2),
+            max_lists: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            exceeded(&ctx, &load),
+            vec!["work context has 3 open tasks, over your limit of 2"]
+        );
+        assert!(exceeded(&Context::default(), &load).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+//! Create the release checklist of a version from a template
+//!
+//! Templates are Todo lists stored next to the configuration file inside `.todo_templates/`
+//! (`.todo_templates/release.md` for `--template release`). Every `{{version}}` of the template is
+//! replaced by the released version. A built-in checklist is used when the `release` template does
+//! not exist.
+use crate::parse::parse_todo_list;
+use crate::{prompt_for_todo_folder_if_not_exists, todo_path, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::path::{Path, PathBuf};
+
+/// Placeholder replaced by the version inside templates
+const VERSION_PLACEHOLDER: &str = "{{version}}";
+
+/// Checklist used when no `release` template exists
+const DEFAULT_RELEASE_TEMPLATE: &str = "# Release {{version}}
+
+## Description
+
+LABEL=release
+
+## Todo list
+
+* [ ] bump version to {{version}}
+* [ ] update changelog for {{version}}
+* [ ] run the test suite
+* [ ] tag v{{version}}
+* [ ] publish {{version}}
+* [ ] announce {{version}}
+";
+
+/// Returns the Release checklist command
+pub fn release_checklist_command() -> App<'static, 'static> {
+    App::new("release-checklist")
+        .about("Create the release checklist of a version from a template")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("version")
+                .long("version")
+                .value_name("VERSION")
+                .help("Released version replacing {{version}} inside the template")
+                .takes_value(true)
+                .empty_values(false)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("template")
+                .long("template")
+                .value_name("TEMPLATE")
+                .help("Template inside .todo_templates/ next to the configuration file")
+                .default_value("release")
+                .takes_value(true),
+        )
+}
+
+/// Writes release checklist inside active Todo context and prints its path
+pub fn release_checklist_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    ctx: &Context,
+) -> Result<(), std::io::Error> {
+    trace!("release-checklist subcommand");
+    let version = args.value_of("version").unwrap();
+    let template_name = args.value_of("template").unwrap();
+    let template_path =
+        templates_folder(todo_configuration_path).join(format!("{template_name}.md"));
+    let template = match std::fs::read_to_string(&template_path) {
+        Ok(template) => template,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && template_name == "release" => {
+            DEFAULT_RELEASE_TEMPLATE.to_string()
+        }
+        Err(e) => {
+            return Err(std::io::Error::new(
+                e.kind(),
+                format!(
+                    "Template {} could not be read: {e}",
+                    template_path.display()
+                ),
+            ))
+        }
+    };
+
+    let checklist = instantiate(template.as_str(), version);
+    let title = parse_todo_list(checklist.as_str())?.title;
+    if let Err(e) = prompt_for_todo_folder_if_not_exists(ctx) {
+        return Err(std::io::Error::other(e.to_string()));
+    }
+    let filepath = todo_path(ctx, title.as_str());
+    if Path::new(filepath.as_str()).exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Release checklist \"{title}\" already exists ({filepath})"),
+        ));
+    }
+    std::fs::write(&filepath, checklist)?;
+    debug!("wrote {filepath}");
+    println!("{filepath}");
+    Ok(())
+}
+
+/// Returns the folder where templates are stored, next to the configuration file
+pub(crate) fn templates_folder(todo_configuration_path: &str) -> PathBuf {
+    Path::new(todo_configuration_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(".todo_templates")
+}
+
+/// Returns template with its version placeholders replaced. The version is appended to the title
+/// when the title has no placeholder so checklists of different versions do not collide.
+fn instantiate(template: &str, version: &str) -> String {
+    let template = match template.split_once('\n') {
+        Some((title, rest)) if title.starts_with("# ") && !title.contains(VERSION_PLACEHOLDER) => {
+            format!("{title} {VERSION_PLACEHOLDER}\n{rest}")
+        }
+        _ => template.to_string(),
+    };
+    template.replace(VERSION_PLACEHOLDER, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instantiate_templates() {
+        let checklist = instantiate(DEFAULT_RELEASE_TEMPLATE, "1.4.0");
+        assert!(checklist.starts_with("# Release 1.4.0\n"));
+        assert!(checklist.contains("* [ ] tag v1.4.0\n"));
+        assert!(!checklist.contains(VERSION_PLACEHOLDER));
+
+        assert_eq!(
+            instantiate(
+                "# Deploy\n\n## Todo list\n\n* [ ] ship {{version}}\n",
+                "2.0"
+            ),
+            "# Deploy 2.0\n\n## Todo list\n\n* [ ] ship 2.0\n"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Todo contexts stored on a remote host over SSH
+//!
+//! Every Todo context whose folder location is `ssh://[user@]host[:port]/path` is mirrored next to
+//! the configuration file (inside the `.todo_remote` folder) before running a command. Todo lists
+//! changed by the command are uploaded afterwards and deleted ones are removed from the remote
+//! host. SSH connections are shared between invocations for a few minutes (OpenSSH
+//! `ControlMaster`).
+//!
+//! Remote Todo contexts require building with the `ssh` feature and an OpenSSH client.
+use crate::Configuration;
+#[cfg(feature = "ssh")]
+use log::debug;
+#[cfg(feature = "ssh")]
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+#[cfg(feature = "ssh")]
+use std::process::{Command, Stdio};
+
+/// Location of a remote Todo folder
+#[derive(Debug, PartialEq)]
+pub struct RemoteLocation {
+    /// `[user@]host` as understood by ssh
+    destination: String,
+    port: Option<u16>,
+    /// Absolute path of the Todo folder on the remote host
+    path: String,
+}
+
+/// Returns the remote location of `folder_location` if it is an `ssh://` URL
+pub fn parse_remote_location(folder_location: &str) -> Option<RemoteLocation> {
+    let rest = folder_location.strip_prefix("ssh://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "."),
+    };
+    let (destination, port) = match authority.rsplit_once(':') {
+        Some((destination, port)) => (destination, Some(port.parse::<u16>().ok()?)),
+        None => (authority, None),
+    };
+    if destination.is_empty() {
+        return None;
+    }
+    Some(RemoteLocation {
+        destination: destination.to_string(),
+        port,
+        path: path.to_string(),
+    })
+}
+
+/// Local copy of a remote Todo folder
+pub struct Mirror {
+    #[cfg(feature = "ssh")]
+    remote: RemoteLocation,
+    local: PathBuf,
+    #[cfg(feature = "ssh")]
+    downloaded: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+/// Downloads the Todo folder of the active Todo context when it is remote and points it (inside
+/// `config`) to the local copy. With `every_ctx`, for commands reaching into other Todo contexts
+/// (`list --global`, `move`...), other remote Todo contexts are downloaded too: those which cannot
+/// be downloaded are left out of `config` with a warning. Returns the local copies to upload once
+/// the command ran.
+pub fn mirror(
+    todo_configuration_path: &str,
+    config: &mut Configuration,
+    every_ctx: bool,
+) -> Result<Vec<Mirror>, std::io::Error> {
+    let mut mirrors = vec![];
+    let mut unavailable = vec![];
+    for ctx in config.ctxs.iter_mut() {
+        let is_active = ctx.name == config.active_ctx_name;
+        if !is_active && !every_ctx {
+            continue;
+        }
+        let remote = match parse_remote_location(ctx.folder_location.as_str()) {
+            Some(remote) => remote,
+            None => continue,
+        };
+        let local = std::path::Path::new(todo_configuration_path)
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."))
+            .join(".todo_remote")
+            .join(ctx.name.as_str());
+        match download(remote, local) {
+            Ok(mirror) => {
+                ctx.folder_location = mirror.local.to_string_lossy().to_string();
+                mirrors.push(mirror);
+            }
+            Err(e) if !is_active => {
+                eprintln!("Warning: Todo context {} is skipped: {e}", ctx.name);
+                unavailable.push(ctx.name.clone());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    config.ctxs.retain(|ctx| !unavailable.contains(&ctx.name));
+    Ok(mirrors)
+}
+
+/// Uploads every mirror, even after one fails. Returns the first failure, others are printed.
+pub fn upload(mirrors: Vec<Mirror>) -> Result<(), std::io::Error> {
+    let mut result = Ok(());
+    for mirror in mirrors {
+        if let Err(e) = mirror.upload() {
+            if result.is_ok() {
+                result = Err(e);
+            } else {
+                eprintln!("Error: {e}");
+            }
+        }
+    }
+    result
+}
+
+#[cfg(not(feature = "ssh"))]
+fn download(remote: RemoteLocation, _local: PathBuf) -> Result<Mirror, std::io::Error> {
+    Err(std::io::Error::other(format!(
+        "Todo folder \"{}\" is remote but todo was built without the \"ssh\" feature",
+        remote.path
+    )))
+}
+
+#[cfg(not(feature = "ssh"))]
+impl Mirror {
+    /// Uploads Todo lists changed since the download
+    pub fn upload(self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ssh")]
+fn download(remote: RemoteLocation, local: PathBuf) -> Result<Mirror, std::io::Error> {
+    if local.exists() {
+        std::fs::remove_dir_all(&local)?;
+    }
+    std::fs::create_dir_all(&local)?;
+    let output = ssh(&remote)
+        .arg(format!("tar cf - -C {} .", quote(remote.path.as_str())))
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "Todo folder could not be downloaded from \"{}\"",
+            remote.destination
+        )));
+    }
+    tar::Archive::new(output.stdout.as_slice()).unpack(&local)?;
+    debug!("downloaded {} into {}", remote.path, local.display());
+    let downloaded = files(&local)?;
+    Ok(Mirror {
+        remote,
+        local,
+        downloaded,
+    })
+}
+
+#[cfg(feature = "ssh")]
+impl Mirror {
+    /// Uploads Todo lists changed since the download and removes deleted ones from the remote host
+    pub fn upload(self) -> Result<(), std::io::Error> {
+        use std::io::Write;
+
+        let current = files(&self.local)?;
+        for (relative, content) in &current {
+            if self.downloaded.get(relative) == Some(content) {
+                continue;
+            }
+            let remote_path = format!("{}/{}", self.remote.path, relative.display());
+            let parent = format!(
+                "{}/{}",
+                self.remote.path,
+                relative.parent().unwrap_or(relative).display()
+            );
+            let mut child = ssh(&self.remote)
+                .arg(format!(
+                    "mkdir -p {} && cat > {}",
+                    quote(parent.as_str()),
+                    quote(remote_path.as_str())
+                ))
+                .stdin(Stdio::piped())
+                .spawn()?;
+            child.stdin.take().unwrap().write_all(content)?;
+            if !child.wait()?.success() {
+                return Err(std::io::Error::other(format!(
+                    "\"{remote_path}\" could not be uploaded"
+                )));
+            }
+            debug!("uploaded {remote_path}");
+        }
+        for relative in self.downloaded.keys() {
+            if current.contains_key(relative) {
+                continue;
+            }
+            let remote_path = format!("{}/{}", self.remote.path, relative.display());
+            let status = ssh(&self.remote)
+                .arg(format!("rm -f {}", quote(remote_path.as_str())))
+                .status()?;
+            if !status.success() {
+                return Err(std::io::Error::other(format!(
+                    "\"{remote_path}\" could not be removed"
+                )));
+            }
+            debug!("removed {remote_path}");
+        }
+        Ok(())
+    }
+}
+
+/// Returns ssh command to `remote` sharing its connection with other invocations
+#[cfg(feature = "ssh")]
+fn ssh(remote: &RemoteLocation) -> Command {
+    let control_path = std::env::temp_dir().join("todo-ssh-%r@%h:%p");
+    let mut command = Command::new("ssh");
+    command
+        .arg("-o")
+        .arg("ControlMaster=auto")
+        .arg("-o")
+        .arg(format!("ControlPath={}", control_path.display()))
+        .arg("-o")
+        .arg("ControlPersist=5m");
+    if let Some(port) = remote.port {
+        command.arg("-p").arg(port.to_string());
+    }
+    command.arg(remote.destination.as_str());
+    command
+}
+
+/// Returns `s` quoted for the remote shell
+#[cfg(feature = "ssh")]
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Returns the content of every file inside `folder` by path relative to `folder`
+#[cfg(feature = "ssh")]
+fn files(folder: &std::path::Path) -> Result<BTreeMap<PathBuf, Vec<u8>>, std::io::Error> {
+    let mut files = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(folder) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(folder).unwrap().to_path_buf();
+            files.insert(relative, std::fs::read(entry.path())?);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_locations() {
+        assert_eq!(parse_remote_location("/home/me/todo"), None);
+        assert_eq!(
+            parse_remote_location("ssh://me@server/home/me/todo"),
+            Some(RemoteLocation {
+                destination: "me@server".to_string(),
+                port: None,
+                path: "/home/me/todo".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_remote_location("ssh://server:2222/todo"),
+            Some(RemoteLocation {
+                destination: "server".to_string(),
+                port: Some(2222),
+                path: "/todo".to_string(),
+            })
+        );
+        assert_eq!(parse_remote_location("ssh://server:port/todo"), None);
+        assert_eq!(parse_remote_location("ssh:///todo"), None);
+    }
+
+    #[cfg(not(feature = "ssh"))]
+    #[test]
+    fn unavailable_remote_contexts_are_skipped_unless_active() {
+        let mut config = Configuration {
+            active_ctx_name: "local".to_string(),
+            ctxs: vec![
+                crate::Context::new("local", "todo"),
+                crate::Context::new("server", "ssh://me@server/todo"),
+            ],
+            ..Default::default()
+        };
+        assert!(mirror("todo.toml", &mut config, false).unwrap().is_empty());
+        assert_eq!(config.ctxs().len(), 2);
+
+        // not taken for a local folder named "ssh://..."
+        assert!(mirror("todo.toml", &mut config, true).unwrap().is_empty());
+        let names = config
+            .ctxs()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["local"]);
+
+        config.active_ctx_name = "server".to_string();
+        config
+            .ctxs
+            .push(crate::Context::new("server", "ssh://me@server/todo"));
+        assert!(mirror("todo.toml", &mut config, false).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Rename a Todo list of active Todo context with `todo rename TITLE NEW_TITLE`
+//!
+//! Both the `# Title` heading and the file are renamed, so the Todo list file stays named after its
+//! title (see [`crate::todo_path`]). Parts of the Todo list (see [`crate::parts`]) are renamed
+//! along. An existing Todo list at the destination is only overwritten with `--force`.
+use crate::lock::ensure_unlocked;
+use crate::parts::{part_path, part_paths, Parts};
+use crate::single_file::{retitle, title_of, SingleFile};
+use crate::{resolve_todo_path, todo_path, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::path::Path;
+
+/// Returns the Rename command
+pub fn rename_command() -> App<'static, 'static> {
+    App::new("rename")
+        .about("Rename todo list within Todo context, both its title and its file")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .value_name("TITLE")
+                .index(1)
+                .help("Title of todo list")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("new title")
+                .value_name("NEW_TITLE")
+                .index(2)
+                .help("New title of todo list")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("force")
+                .short("f")
+                .long("force")
+                .help("Overwrite the todo list already titled NEW_TITLE, rename even if locked"),
+        )
+}
+
+/// Renames the Todo list of active Todo context
+pub fn rename_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("rename subcommand");
+    let title = args.value_of("title").unwrap();
+    let new_title = args.value_of("new title").unwrap().trim();
+    let force = args.is_present("force");
+    if new_title.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The new title of a Todo list cannot be empty",
+        ));
+    }
+
+    if ctx.is_single_file() {
+        let mut single_file = SingleFile::read(ctx)?;
+        let mut i = single_file.position_of_unlocked(title, force)?;
+        if let Some(j) = single_file.position(new_title)?.filter(|j| *j != i) {
+            if !force {
+                return Err(already_exists(new_title, single_file.path.as_str()));
+            }
+            single_file.todo_lists.remove(j);
+            if j < i {
+                i -= 1;
+            }
+        }
+        single_file.todo_lists[i] = retitle(single_file.todo_lists[i].as_str(), new_title);
+        single_file.write()?;
+    } else {
+        let filepath = resolve_todo_path(ctx, title)?;
+        if !Path::new(filepath.as_str()).is_file() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No Todo list is titled \"{title}\""),
+            ));
+        }
+        ensure_unlocked(filepath.as_str(), force)?;
+        rename(ctx, filepath.as_str(), new_title, force)?;
+    }
+    println!("Renamed \"{title}\" to \"{new_title}\"");
+    Ok(())
+}
+
+/// Retitles the Todo list at `filepath` and moves it (with its parts) to the path of its new title.
+/// Returns the new path of the Todo list.
+///
+/// An existing Todo list titled `new_title` or at the new path (and its parts) is replaced if
+/// `force` is true.
+fn rename(
+    ctx: &Context,
+    filepath: &str,
+    new_title: &str,
+    force: bool,
+) -> Result<String, std::io::Error> {
+    let mut parts = Parts::read(filepath)?;
+    let new_path = todo_path(ctx, new_title);
+    let moved = !same_file(filepath, new_path.as_str());
+    let new_paths = (0..parts.paths.len())
+        .map(|i| {
+            part_path(new_path.as_str(), i)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    if let Some(part) = parts.paths[1..]
+        .iter()
+        .find(|part| new_paths.iter().any(|path| same_file(part, path)))
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Todo list titled \"{new_title}\" would overwrite {part}, a part of the Todo list"
+            ),
+        ));
+    }
+    let existing = [resolve_todo_path(ctx, new_title)?, new_path.clone()]
+        .into_iter()
+        .filter(|path| Path::new(path).is_file() && !same_file(filepath, path))
+        .collect::<Vec<_>>();
+    if let Some(path) = existing.first() {
+        if !force {
+            return Err(already_exists(new_title, path));
+        }
+    }
+    for existing in existing {
+        if !Path::new(existing.as_str()).is_file() {
+            continue;
+        }
+        for path in std::iter::once(existing.clone()).chain(part_paths(existing.as_str())) {
+            std::fs::remove_file(&path)?;
+            debug!("removed {path}");
+        }
+    }
+
+    let title = title_of(parts.raws[0].as_str()).to_string();
+    parts.raws[0] = retitle(parts.raws[0].as_str(), new_title);
+    if !moved {
+        parts.write()?;
+        return Ok(new_path);
+    }
+    for (path, raw) in new_paths.iter().zip(parts.raws.iter()) {
+        std::fs::write(path, raw)?;
+        debug!("wrote {path}");
+    }
+    for path in parts.paths.iter().filter(|path| !new_paths.contains(path)) {
+        std::fs::remove_file(path)?;
+        debug!("removed {path}");
+    }
+    debug!("renamed \"{title}\" from {filepath} to {new_path}");
+    Ok(new_path)
+}
+
+/// Returns true if both paths are the same file
+fn same_file(a: &str, b: &str) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(a) == Path::new(b),
+    }
+}
+
+/// Returns the error of a Todo list renamed over another one
+fn already_exists(new_title: &str, path: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!("A Todo list titled \"{new_title}\" already exists at {path}. Use --force to overwrite it"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn rename_file_and_parts() {
+        let root = TempFolder::new("rename");
+        let project = root.join("project.md");
+        std::fs::write(&project, "# project\n\n## Description\n\nLABEL=\n").unwrap();
+        std::fs::write(root.join("project.1.md"), "\n## Todo list\n\n* [ ] a\n").unwrap();
+        std::fs::write(
+            root.join("other.md"),
+            "# taken\n\n## Description\n\nLABEL=\n",
+        )
+        .unwrap();
+        let ctx = Context {
+            folder_location: root.location(),
+            ..Default::default()
+        };
+        let filepath = project.to_string_lossy().to_string();
+
+        assert_eq!(
+            rename(&ctx, filepath.as_str(), "taken", false)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(
+            rename(&ctx, filepath.as_str(), "project.1", true)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert!(project.exists() && root.join("project.1.md").exists());
+        let renamed = rename(&ctx, filepath.as_str(), "launch", false).unwrap();
+        assert_eq!(renamed, todo_path(&ctx, "launch"));
+        assert!(!project.exists() && !root.join("project.1.md").exists());
+        assert_eq!(
+            Parts::read(renamed.as_str()).unwrap().joined(),
+            "# launch\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] a\n"
+        );
+
+        let renamed = rename(&ctx, renamed.as_str(), "taken", true).unwrap();
+        assert_eq!(part_paths(renamed.as_str()).len(), 1);
+        assert!(!root.join("launch.md").exists() && !root.join("other.md").exists());
+    }
+}
@@ -0,0 +1,230 @@
+//! Render Todo lists for reading in the terminal
+//!
+//! `todo view TITLE` prints the Todo list rendered rather than as raw markdown: headings are
+//! emphasized, checkboxes become glyphs (☐ open, ☑ done), labels are spelled out, task comments are
+//! set apart, annotations (`<!-- todo:locked -->`) are hidden and text is wrapped to the width of the
+//! terminal with hanging indents.
+//!
+//! `todo list` wraps long tasks the same way. The width is detected from the terminal (or the
+//! `COLUMNS` environment variable) and given with `--width N` when printing to a file.
+use clap::{Arg, ArgMatches};
+use std::io::IsTerminal;
+
+const BOLD: &str = "\x1b[1m";
+const UNDERLINE: &str = "\x1b[4m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Width used when the width of the terminal is unknown
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Returns the width argument
+pub fn width_arg() -> Arg<'static, 'static> {
+    Arg::with_name("width")
+        .long("width")
+        .value_name("N")
+        .help("Wraps text at N columns (width of the terminal by default, 0 does not wrap)")
+        .validator(|width| {
+            width
+                .parse::<usize>()
+                .map(|_| ())
+                .map_err(|_| format!("\"{width}\" is not a number of columns"))
+        })
+        .takes_value(true)
+}
+
+/// Returns the width text is wrapped at: given with `--width`, or else the width of the terminal.
+/// None when text is not wrapped.
+pub fn output_width(args: &ArgMatches) -> Option<usize> {
+    match args.value_of("width").and_then(|w| w.parse::<usize>().ok()) {
+        Some(0) => None,
+        Some(width) => Some(width),
+        None => terminal_width(),
+    }
+}
+
+/// Returns the width of the terminal printed to, or else from the `COLUMNS` environment variable
+fn terminal_width() -> Option<usize> {
+    if std::io::stdout().is_terminal() {
+        if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+            return Some(width as usize);
+        }
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse::<usize>().ok())
+        .filter(|columns| *columns > 0)
+}
+
+/// Returns true if printed text may hold colors and styles
+pub fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Returns the Todo list rendered for reading, with lines of at most `width` characters where
+/// possible, styled when `color` is set
+pub fn render(todo_raw: &str, width: usize, color: bool) -> String {
+    let style = |styles: &[&str], text: &str| {
+        if color {
+            format!("{}{text}{RESET}", styles.concat())
+        } else {
+            text.to_string()
+        }
+    };
+    let mut rendered = vec![];
+    let mut in_code = false;
+    for line in todo_raw.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            rendered.push(style(&[CYAN], format!("    {line}").as_str()));
+            continue;
+        }
+        let trimmed = line.trim();
+        if trimmed.starts_with("<!--") && trimmed.ends_with("-->") {
+            continue;
+        }
+        if let Some(title) = line.strip_prefix("# ") {
+            rendered.push(style(&[BOLD, UNDERLINE], title.trim()));
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            rendered.push(style(&[BOLD], heading.trim().to_uppercase().as_str()));
+        } else if line.starts_with("###") {
+            rendered.push(style(&[BOLD], line.trim_start_matches('#').trim()));
+        } else if let Some(labels) = line.strip_prefix("LABEL=") {
+            let labels = labels
+                .split(',')
+                .filter(|l| !l.is_empty())
+                .collect::<Vec<_>>();
+            if !labels.is_empty() {
+                rendered.push(style(
+                    &[DIM],
+                    format!("Labels: {}", labels.join(", ")).as_str(),
+                ));
+            }
+        } else if let Some(task) = line.strip_prefix("* [ ] ") {
+            rendered.extend(wrap(task, width, "☐ ", "  "));
+        } else if let Some(task) = line.strip_prefix("* [x] ") {
+            for wrapped in wrap(task, width, "☑ ", "  ") {
+                rendered.push(style(&[DIM], wrapped.as_str()));
+            }
+        } else if let Some(comment) = trimmed
+            .strip_prefix('>')
+            .filter(|_| line.starts_with([' ', '\t']))
+        {
+            for wrapped in wrap(comment.trim(), width, "  │ ", "  │ ") {
+                rendered.push(style(&[DIM], wrapped.as_str()));
+            }
+        } else if let Some(item) = line.strip_prefix("* ").or(line.strip_prefix("- ")) {
+            rendered.extend(wrap(item, width, "• ", "  "));
+        } else if trimmed.is_empty() {
+            // consecutive blank lines are collapsed
+            if rendered.last().is_some_and(|l: &String| !l.is_empty()) {
+                rendered.push(String::new());
+            }
+        } else {
+            rendered.extend(wrap(trimmed, width, "", ""));
+        }
+    }
+    while rendered.last().is_some_and(|l| l.is_empty()) {
+        rendered.pop();
+    }
+    rendered.push(String::new());
+    rendered.join("\n")
+}
+
+/// Returns `text` (Todo list or tasks) where lines of tasks longer than `width` are wrapped, with
+/// continuation lines indented below the task text. Colored tasks keep their color.
+pub fn wrap_tasks(text: &str, width: usize) -> String {
+    let mut wrapped = vec![];
+    for line in text.split('\n') {
+        // colors are excluded from the width
+        let (color, content, reset) = match line.strip_prefix("\x1b[") {
+            Some(styled) => match (styled.split_once('m'), line.ends_with(RESET)) {
+                (Some((code, content)), true) => (
+                    format!("\x1b[{code}m"),
+                    &content[..content.len() - RESET.len()],
+                    RESET,
+                ),
+                _ => (String::new(), line, ""),
+            },
+            None => (String::new(), line, ""),
+        };
+        let checkbox = content
+            .get(0..6)
+            .filter(|c| *c == "* [ ] " || *c == "* [x] ");
+        match checkbox {
+            Some(checkbox) if content.chars().count() > width => {
+                let lines = wrap(&content[6..], width, checkbox, "      ");
+                wrapped.push(format!("{color}{}{reset}", lines.join("\n")));
+            }
+            _ => wrapped.push(line.to_string()),
+        }
+    }
+    wrapped.join("\n")
+}
+
+/// Returns `text` wrapped into lines of at most `width` characters where possible. The first line
+/// starts with `first`, the others with `rest`. Words longer than a line are not split.
+pub fn wrap(text: &str, width: usize, first: &str, rest: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = first.to_string();
+    let mut empty = true;
+    for word in text.split_whitespace() {
+        if !empty && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(line);
+            line = rest.to_string();
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+        }
+        line.push_str(word);
+        empty = false;
+    }
+    lines.push(line);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_todo_list() {
+        let todo_raw = "# Release\n\n## Description\n\nLABEL=work,v2\n\nShip the second version of the tool to everyone.\n\n<!-- todo:locked -->\n\n## Todo list\n\n* [ ] write the changelog from the merged pull requests\n  > ask for review\n* [x] tag\n\n### Docs\n\n* [ ] publish\n\n\n";
+        assert_eq!(
+            render(todo_raw, 30, false),
+            "Release
+
+DESCRIPTION
+
+Labels: work, v2
+
+Ship the second version of the
+tool to everyone.
+
+TODO LIST
+
+☐ write the changelog from the
+  merged pull requests
+  │ ask for review
+☑ tag
+
+Docs
+
+☐ publish
+"
+        );
+        assert_eq!(
+            wrap("a bb ccc", 4, "- ", "  "),
+            vec!["- a", "  bb", "  ccc"]
+        );
+        assert_eq!(
+            wrap_tasks("# Title\n\n* [ ] pay the rent before the end\n\x1b[33m* [x] call the plumber\x1b[0m\n* [ ] short", 16),
+            "# Title\n\n* [ ] pay the\n      rent\n      before the\n      end\n\x1b[33m* [x] call the\n      plumber\x1b[0m\n* [ ] short"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Async runtime of network transfers
+//!
+//! Network features (`sync`, `notifications` and `server`) run their transfers on a tokio runtime,
+//! so a slow server does not hold up the others and many transfers are in flight at once. The
+//! runtime stays behind [`block_on`]: subcommands remain synchronous functions, and purely local
+//! subcommands never start a runtime nor depend on tokio when built without network features.
+use std::future::Future;
+use std::process::{Output, Stdio};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Number of transfers in flight at once
+pub const CONCURRENT_TRANSFERS: usize = 4;
+
+/// Runs `future` to completion on a new runtime, from synchronous code
+pub fn block_on<T>(
+    future: impl Future<Output = Result<T, std::io::Error>>,
+) -> Result<T, std::io::Error> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(future)
+}
+
+/// Runs `transfers`, at most [`CONCURRENT_TRANSFERS`] at once, and hands each result to `done` as
+/// soon as its transfer completes. The first failing transfer cancels the others.
+pub async fn concurrently<T, F>(
+    transfers: impl IntoIterator<Item = F>,
+    mut done: impl FnMut(T),
+) -> Result<(), std::io::Error>
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, std::io::Error>> + Send + 'static,
+{
+    let permits = Arc::new(Semaphore::new(CONCURRENT_TRANSFERS));
+    let mut transfers_left = JoinSet::new();
+    for transfer in transfers {
+        let permits = Arc::clone(&permits);
+        transfers_left.spawn(async move {
+            let _permit = permits
+                .acquire_owned()
+                .await
+                .map_err(std::io::Error::other)?;
+            transfer.await
+        });
+    }
+    while let Some(result) = transfers_left.join_next().await {
+        done(result.map_err(std::io::Error::other)??);
+    }
+    Ok(())
+}
+
+/// Runs curl with `args`, writing `body` to its standard input, and returns its output
+pub async fn curl(args: &[&str], body: Option<&str>) -> Result<Output, std::io::Error> {
+    let mut child = Command::new("curl")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    if let Some(body) = body {
+        stdin.write_all(body.as_bytes()).await?;
+    }
+    drop(stdin);
+    child.wait_with_output().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn transfers_run_concurrently_up_to_the_limit() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most_in_flight = Arc::new(AtomicUsize::new(0));
+        let transfers = (0..10).map(|i| {
+            let in_flight = Arc::clone(&in_flight);
+            let most_in_flight = Arc::clone(&most_in_flight);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                most_in_flight.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(i)
+            }
+        });
+        let mut done = vec![];
+        block_on(concurrently(transfers, |i| done.push(i))).unwrap();
+        done.sort();
+        assert_eq!(done, (0..10).collect::<Vec<_>>());
+        assert_eq!(most_in_flight.load(Ordering::SeqCst), CONCURRENT_TRANSFERS);
+
+        let failing = (0..3).map(|i| async move {
+            match i {
+                1 => Err(std::io::Error::other("unreachable server")),
+                _ => Ok(i),
+            }
+        });
+        assert!(block_on(concurrently(failing, |_| {})).is_err());
+    }
+}
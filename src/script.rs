@@ -0,0 +1,252 @@
+//! Run Rhai scripts over the Todo lists of active Todo context
+//!
+//! `todo script run FILE [ARGS...]` runs a [Rhai](https://rhai.rs) script, for custom filters,
+//! formatters or batch edits. Scripts cannot read files, run processes or reach the network: they
+//! only see the Todo lists of the active Todo context through these functions:
+//!
+//! * `todo_lists()`: array of Todo lists, each a map with `title`, `description`, `labels` and
+//!   `tasks`. Each task is a map with `done`, `text`, `section` and `priority` (`()` when missing).
+//! * `add(title, text)`: adds an open task to the Todo list titled `title` (like `todo add`)
+//! * `done(title, pattern)`: checks off the open task matching `pattern` (like `todo done`)
+//!
+//! Arguments following the script are the `ARGS` array of strings and `print` writes to stdout.
+//! Locked Todo lists are only modified with `--force`. For instance, this script prints the number
+//! of open tasks of each Todo list:
+//!
+//! ```text
+//! for todo_list in todo_lists() {
+//!     let open = todo_list.tasks.filter(|task| !task.done);
+//!     print(`${todo_list.title}: ${open.len()}`);
+//! }
+//! ```
+use crate::add::add_task;
+use crate::done::check_off;
+use crate::lock::ensure_unlocked;
+use crate::parts::Parts;
+use crate::store::TodoStore;
+use crate::{resolve_todo_path, Context, TodoList};
+use clap::{crate_authors, App, AppSettings, Arg, ArgMatches};
+use log::{debug, trace};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope};
+
+/// Operations a script may run, so that a runaway loop does not hang `todo`
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+/// Returns the Script command
+pub fn script_command() -> App<'static, 'static> {
+    App::new("script")
+        .about("Run Rhai scripts over todo lists within Todo context")
+        .author(crate_authors!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("run")
+                .about("Run a Rhai script")
+                .arg(
+                    Arg::with_name("file")
+                        .value_name("FILE")
+                        .index(1)
+                        .help("Rhai script")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("args")
+                        .value_name("ARGS")
+                        .index(2)
+                        .help("Arguments of the script (ARGS array)")
+                        .takes_value(true)
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .short("f")
+                        .long("force")
+                        .help("Let the script modify locked todo lists"),
+                ),
+        )
+}
+
+/// Runs a script over the Todo lists of active Todo context
+pub fn script_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("script subcommand");
+    match args.subcommand() {
+        ("run", Some(args)) => {
+            let file = args.value_of("file").unwrap();
+            let script = std::fs::read_to_string(file)?;
+            debug!("read {file}");
+            let script_args = args
+                .values_of("args")
+                .unwrap_or_default()
+                .map(str::to_string)
+                .collect();
+            run(ctx, script.as_str(), script_args, args.is_present("force"))
+                .map_err(|e| std::io::Error::new(e.kind(), format!("{file}: {e}")))
+        }
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+/// Runs `script` with `args` as its `ARGS` array
+fn run(ctx: &Context, script: &str, args: Vec<String>, force: bool) -> Result<(), std::io::Error> {
+    let mut scope = Scope::new();
+    scope.push_constant(
+        "ARGS",
+        args.into_iter().map(Dynamic::from).collect::<Array>(),
+    );
+    engine(ctx, force)
+        .run_with_scope(&mut scope, script)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Returns the script engine with the functions over the Todo lists of the Todo context
+fn engine(ctx: &Context, force: bool) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let store = TodoStore::new(ctx.clone());
+    engine.register_fn(
+        "todo_lists",
+        move || -> Result<Array, Box<EvalAltResult>> {
+            Ok(store
+                .todo_lists()
+                .map_err(script_error)?
+                .iter()
+                .map(|todo_list| Dynamic::from_map(todo_list_map(todo_list)))
+                .collect())
+        },
+    );
+    let add_ctx = ctx.clone();
+    engine.register_fn("add", move |title: &str, text: &str| {
+        add(&add_ctx, title, text, force).map_err(script_error)
+    });
+    let done_ctx = ctx.clone();
+    engine.register_fn("done", move |title: &str, pattern: &str| {
+        done(&done_ctx, title, pattern, force).map_err(script_error)
+    });
+    engine
+}
+
+fn script_error(e: std::io::Error) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+/// Returns the Todo list as seen by scripts
+fn todo_list_map(todo_list: &TodoList) -> Map {
+    let optional = |value: Option<String>| value.map(Dynamic::from).unwrap_or(Dynamic::UNIT);
+    let tasks = todo_list
+        .tasks()
+        .iter()
+        .map(|task| {
+            let mut map = Map::new();
+            map.insert("done".into(), task.done.into());
+            map.insert("text".into(), task.text.clone().into());
+            map.insert("section".into(), optional(task.section.clone()));
+            map.insert(
+                "priority".into(),
+                optional(task.priority().map(String::from)),
+            );
+            Dynamic::from_map(map)
+        })
+        .collect::<Array>();
+    let labels = todo_list
+        .labels()
+        .iter()
+        .map(|label| Dynamic::from(label.clone()))
+        .collect::<Array>();
+
+    let mut map = Map::new();
+    map.insert("title".into(), todo_list.title().into());
+    map.insert("description".into(), todo_list.description().into());
+    map.insert("labels".into(), labels.into());
+    map.insert("tasks".into(), tasks.into());
+    map
+}
+
+/// Returns the file of the Todo list titled `title`, which must not be locked unless forced
+fn writable_path(ctx: &Context, title: &str, force: bool) -> Result<String, std::io::Error> {
+    if ctx.is_single_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Scripts only modify Todo lists stored one per file",
+        ));
+    }
+    let filepath = resolve_todo_path(ctx, title)?;
+    ensure_unlocked(filepath.as_str(), force)?;
+    Ok(filepath)
+}
+
+/// Adds an open task to the Todo list titled `title`
+fn add(ctx: &Context, title: &str, text: &str, force: bool) -> Result<(), std::io::Error> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Task is empty",
+        ));
+    }
+    let filepath = writable_path(ctx, title, force)?;
+    let todo_raw = std::fs::read_to_string(&filepath)?;
+    std::fs::write(&filepath, add_task(todo_raw.as_str(), text, None)?)?;
+    debug!("wrote {filepath}");
+    Ok(())
+}
+
+/// Checks off the open task matching `pattern` in the Todo list titled `title`
+fn done(ctx: &Context, title: &str, pattern: &str, force: bool) -> Result<(), std::io::Error> {
+    let filepath = writable_path(ctx, title, force)?;
+    let mut parts = Parts::read(filepath.as_str())?;
+    let (todo_raw, _) = check_off(parts.joined().as_str(), pattern).map_err(|e| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string().trim_end())
+    })?;
+    parts.set(todo_raw.as_str())?;
+    parts.write()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::{annotate, LOCKED_ANNOTATION};
+    use crate::test_support::{todo_list, TempFolder};
+
+    #[test]
+    fn scripts_read_and_modify_todo_lists() {
+        let folder = TempFolder::new("script");
+        let project = folder.join("project.md");
+        std::fs::write(
+            &project,
+            todo_list(
+                "Project",
+                &["work"],
+                &["* [ ] (A) ship", "* [ ] write docs"],
+            ),
+        )
+        .unwrap();
+        let ctx = Context::new("ctx", folder.location().as_str());
+
+        let script = r#"
+            let open = todo_lists()[0].tasks.filter(|task| !task.done && task.priority == "A");
+            done(ARGS[0], "ship");
+            add(ARGS[0], `${open.len()} urgent task`);
+        "#;
+        run(&ctx, script, vec!["project".to_string()], false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&project).unwrap(),
+            todo_list(
+                "Project",
+                &["work"],
+                &["* [x] (A) ship", "* [ ] write docs", "* [ ] 1 urgent task"]
+            )
+        );
+
+        let error = run(&ctx, r#"done("project", "dentist")"#, vec![], false).unwrap_err();
+        assert!(error.to_string().contains("No open task matches"));
+        assert!(run(&ctx, r#"open("/etc/passwd")"#, vec![], false).is_err());
+
+        std::fs::write(
+            &project,
+            annotate(todo_list("Project", &[], &[]).as_str(), LOCKED_ANNOTATION),
+        )
+        .unwrap();
+        assert!(run(&ctx, r#"add("project", "x")"#, vec![], false).is_err());
+    }
+}
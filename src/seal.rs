@@ -0,0 +1,220 @@
+//! Seal finalized Todo lists and verify they were not tampered with afterwards
+//!
+//! A sealed Todo list ends with the `<!-- todo:sealed sha256=HASH -->` annotation where `HASH` is
+//! the SHA-256 digest of the Todo list content without the annotation. Optionally, the file can
+//! also be signed with minisign which leaves a `.minisig` file next to the Todo list.
+use crate::{path_arg, read_todo_lists, resolve_todo_path, todo_path_from_args, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use lazy_static::lazy_static;
+use log::{debug, trace};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::fs::read_to_string;
+use std::process::Command;
+
+lazy_static! {
+    static ref SEAL_RE: Regex =
+        Regex::new(r"(?m)^<!-- todo:sealed sha256=(?P<hash>[0-9a-f]{64}) -->\n?").unwrap();
+}
+
+/// State of the seal of a Todo list
+#[derive(Debug, PartialEq)]
+pub enum Seal {
+    /// Todo list was never sealed
+    Missing,
+    /// Todo list content matches its seal
+    Intact,
+    /// Todo list content was modified after sealing
+    Broken,
+}
+
+/// Returns the Seal Todo command
+pub fn seal_command() -> App<'static, 'static> {
+    App::new("seal")
+        .about("Seal todo list with a content hash to detect later modifications")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .index(1)
+                .help("Title of todo list")
+                .takes_value(true)
+                .required_unless("path"),
+        )
+        .arg(path_arg())
+        .arg(
+            Arg::with_name("minisign")
+                .short("s")
+                .long("minisign")
+                .value_name("SECRET_KEY")
+                .help("Also sign sealed todo list with minisign using SECRET_KEY")
+                .takes_value(true),
+        )
+}
+
+/// Returns the Verify Todo command
+pub fn verify_command() -> App<'static, 'static> {
+    App::new("verify")
+        .about("Verify sealed todo lists were not modified")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .index(1)
+                .help("Title of todo list (default verifies all sealed todo lists)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("minisign")
+                .short("p")
+                .long("minisign")
+                .value_name("PUBLIC_KEY")
+                .help("Also verify minisign signature using PUBLIC_KEY")
+                .takes_value(true),
+        )
+}
+
+/// Seals Todo list in active Todo context
+pub fn seal_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("seal subcommand");
+    let filepath = todo_path_from_args(args, ctx)?;
+    let title = args.value_of("title").unwrap_or(filepath.as_str());
+    let todo_raw = read_to_string(&filepath)?;
+    std::fs::write(&filepath, seal(todo_raw.as_str()))?;
+    debug!("wrote {filepath}");
+    println!("Sealed \"{title}\"");
+
+    if let Some(secret_key) = args.value_of("minisign") {
+        let status = Command::new("minisign")
+            .args(["-S", "-s", secret_key, "-m", filepath.as_str()])
+            .status()?;
+        if !status.success() {
+            return Err(std::io::Error::other("minisign could not sign todo list"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies seals of Todo lists in active Todo context
+pub fn verify_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("verify subcommand");
+    let todo_lists = match args.value_of("title") {
+        Some(title) => {
+            let filepath = resolve_todo_path(ctx, title)?;
+            let todo_raw = read_to_string(&filepath)?;
+            vec![(filepath, todo_raw)]
+        }
+        None => read_todo_lists(ctx)?,
+    };
+
+    let mut tampered = 0;
+    for (filepath, todo_raw) in todo_lists {
+        let status = match verify(todo_raw.as_str()) {
+            Seal::Missing => {
+                debug!("{filepath} is not sealed");
+                if args.value_of("title").is_some() {
+                    println!("NOT SEALED\t{filepath}");
+                    tampered += 1;
+                }
+                continue;
+            }
+            Seal::Intact => "OK",
+            Seal::Broken => {
+                tampered += 1;
+                "TAMPERED"
+            }
+        };
+        println!("{status}\t{filepath}");
+
+        if let Some(public_key) = args.value_of("minisign") {
+            let signature_ok = Command::new("minisign")
+                .args(["-V", "-q", "-p", public_key, "-m", filepath.as_str()])
+                .status()?
+                .success();
+            if !signature_ok {
+                println!("BAD SIGNATURE\t{filepath}");
+                tampered += 1;
+            }
+        }
+    }
+
+    if tampered > 0 {
+        return Err(std::io::Error::other(format!(
+            "{tampered} todo list(s) failed verification"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns Todo list content with its seal replaced by a seal of the current content
+fn seal(todo_raw: &str) -> String {
+    let mut content = SEAL_RE.replace_all(todo_raw, "").to_string();
+    if !content.ends_with('\n') && !content.is_empty() {
+        content.push('\n');
+    }
+    format!(
+        "{content}<!-- todo:sealed sha256={} -->\n",
+        digest(content.as_str())
+    )
+}
+
+/// Returns the state of the seal of Todo list
+pub fn verify(todo_raw: &str) -> Seal {
+    let hash = match SEAL_RE.captures(todo_raw) {
+        Some(caps) => caps["hash"].to_string(),
+        None => return Seal::Missing,
+    };
+    let content = SEAL_RE.replace_all(todo_raw, "").to_string();
+    if digest(content.as_str()) == hash {
+        Seal::Intact
+    } else {
+        Seal::Broken
+    }
+}
+
+/// Returns hexadecimal SHA-256 digest of content
+fn digest(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TODO_RAW: &str =
+        "# Release 1.0\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [x] tag\n";
+
+    #[test]
+    fn sealed_todo_list_is_intact() {
+        assert_eq!(verify(TODO_RAW), Seal::Missing);
+        let sealed = seal(TODO_RAW);
+        assert!(sealed.starts_with(TODO_RAW));
+        assert_eq!(verify(sealed.as_str()), Seal::Intact);
+        // sealing again does not stack seals
+        assert_eq!(seal(sealed.as_str()), sealed);
+    }
+
+    #[test]
+    fn tampering_breaks_seal() {
+        let sealed = seal(TODO_RAW);
+        let tampered = sealed.replace("* [x] tag", "* [ ] tag");
+        assert_eq!(verify(tampered.as_str()), Seal::Broken);
+        let tampered = format!("{sealed}* [x] sneaky\n");
+        assert_eq!(verify(tampered.as_str()), Seal::Broken);
+    }
+
+    #[test]
+    fn seal_without_trailing_newline() {
+        let sealed = seal("# Title\n\n## Description\n\nLABEL=");
+        assert!(sealed.starts_with("# Title\n\n## Description\n\nLABEL=\n<!-- todo:sealed sha256="));
+        assert_eq!(verify(sealed.as_str()), Seal::Intact);
+    }
+}
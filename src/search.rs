@@ -0,0 +1,306 @@
+//! Search Todo lists of the active Todo context
+//!
+//! Titles, sections and tasks of Todo lists are read into an in-memory index of their words. Every
+//! query word must match a word of a hit, either exactly, as a prefix or, for query words of at
+//! least 4 characters, with one typo (two from 8 characters). Swapped neighbouring characters count
+//! as one typo. Title hits rank above section hits, which rank above task hits, which rank above
+//! motive hits. Closer matches rank first otherwise.
+//!
+//! `--with-motives` and `--motive-contains TEXT` search only Todo lists with (matching) motives, as
+//! `todo list` does.
+use crate::json::quote;
+use crate::list::{has_motive, motive_args};
+use crate::parse::{parse_sections, parse_tasks, parse_todo_list_motives};
+use crate::single_file::{title_of, SingleFile};
+use crate::{read_todo_lists, Context};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::trace;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Part of a Todo list where a hit was found, from lowest to highest rank
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Motive,
+    Task,
+    Section,
+    Title,
+}
+
+/// Searchable text of a Todo list
+#[derive(Debug, PartialEq)]
+struct Document {
+    kind: Kind,
+    /// Title of the Todo list
+    title: String,
+    /// Text of the title, section, task or motive
+    text: String,
+    path: String,
+    /// Line of the text inside the Todo list, from 1
+    line: usize,
+}
+
+/// Documents by words they contain
+#[derive(Default)]
+struct Index {
+    documents: Vec<Document>,
+    words: BTreeMap<String, Vec<usize>>,
+}
+
+impl Index {
+    /// Adds title, sections, tasks and motives of the Todo list
+    fn add(&mut self, path: &str, todo_raw: &str) {
+        let title = title_of(todo_raw).to_string();
+        let line_of = |prefix: &str, text: &str| {
+            todo_raw
+                .lines()
+                .position(|l| l.strip_prefix(prefix).map(str::trim) == Some(text))
+                .map(|i| i + 1)
+                .unwrap_or(1)
+        };
+        let mut documents = vec![(Kind::Title, title.clone(), line_of("# ", title.as_str()))];
+        for section in parse_sections(todo_raw) {
+            documents.push((Kind::Section, section.name, section.line + 1));
+        }
+        for task in parse_tasks(todo_raw) {
+            documents.push((Kind::Task, task.text, task.line + 1));
+        }
+        for motive in parse_todo_list_motives(todo_raw) {
+            let line = todo_raw
+                .lines()
+                .position(|l| l.contains(motive.as_str()))
+                .map(|i| i + 1)
+                .unwrap_or(1);
+            documents.push((Kind::Motive, motive, line));
+        }
+
+        for (kind, text, line) in documents {
+            let id = self.documents.len();
+            for word in words(text.as_str()) {
+                let ids = self.words.entry(word).or_default();
+                if ids.last() != Some(&id) {
+                    ids.push(id);
+                }
+            }
+            self.documents.push(Document {
+                kind,
+                title: title.clone(),
+                text,
+                path: path.to_string(),
+                line,
+            });
+        }
+    }
+
+    /// Returns how closely documents match `term`: 3 for a word, 2 for a prefix, 1 for a typo
+    fn matches(&self, term: &str) -> BTreeMap<usize, u8> {
+        let mut matches = BTreeMap::new();
+        let mut found = |ids: &[usize], quality: u8| {
+            for id in ids {
+                let best = matches.entry(*id).or_insert(quality);
+                *best = (*best).max(quality);
+            }
+        };
+        for (word, ids) in self
+            .words
+            .range(term.to_string()..)
+            .take_while(|(word, _)| word.starts_with(term))
+        {
+            found(ids, if word == term { 3 } else { 2 });
+        }
+        let typos = match term.chars().count() {
+            0..=3 => 0,
+            4..=7 => 1,
+            _ => 2,
+        };
+        if typos > 0 {
+            for (word, ids) in &self.words {
+                if strsim::osa_distance(word, term) <= typos {
+                    found(ids, 1);
+                }
+            }
+        }
+        matches
+    }
+
+    /// Returns documents matching every word of `query` with their score, best first
+    fn search(&self, query: &str) -> Vec<(&Document, usize)> {
+        let mut scores: Option<BTreeMap<usize, usize>> = None;
+        for term in words(query) {
+            let matches = self.matches(term.as_str());
+            scores = Some(match scores {
+                None => matches
+                    .into_iter()
+                    .map(|(id, q)| (id, q as usize))
+                    .collect(),
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(id, s)| matches.get(&id).map(|q| (id, s + *q as usize)))
+                    .collect(),
+            });
+        }
+        let mut hits = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, score)| (&self.documents[id], score))
+            .collect::<Vec<_>>();
+        hits.sort_by(|(a, a_score), (b, b_score)| {
+            b.kind
+                .cmp(&a.kind)
+                .then(b_score.cmp(a_score))
+                .then(a.title.cmp(&b.title))
+                .then(a.line.cmp(&b.line))
+        });
+        hits
+    }
+}
+
+/// Returns the lowercase words of `text`
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Returns the Search command
+pub fn search_command() -> App<'static, 'static> {
+    App::new("search")
+        .about("Search titles, sections, tasks and motives of Todo lists within Todo context")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("query")
+                .value_name("QUERY")
+                .help("Words to search, matched by prefix and with typos")
+                .multiple(true)
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("limit")
+                .short("n")
+                .long("limit")
+                .value_name("N")
+                .help("Maximum number of hits")
+                .default_value("20")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Print hits as a JSON array (kind, title, text, path, line, score)"),
+        )
+        .args(&motive_args())
+}
+
+/// Prints hits of the query inside Todo lists of the Todo context, best first
+pub fn search_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("search subcommand");
+    let limit = args
+        .value_of("limit")
+        .unwrap()
+        .parse::<usize>()
+        .map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("--limit: {e}"))
+        })?;
+    let query = args
+        .values_of("query")
+        .unwrap()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let with_motives = args.is_present("with-motives") || args.is_present("motive-contains");
+    let searched =
+        |todo_raw: &str| !with_motives || has_motive(todo_raw, args.value_of("motive-contains"));
+    let mut index = Index::default();
+    if ctx.is_single_file() {
+        let single_file = SingleFile::read(ctx)?;
+        for todo_raw in single_file.todo_lists.iter().filter(|t| searched(t)) {
+            index.add(single_file.path.as_str(), todo_raw);
+        }
+    } else if Path::new(ctx.folder_location.as_str()).is_dir() {
+        for (filepath, todo_raw) in read_todo_lists(ctx)? {
+            if searched(todo_raw.as_str()) {
+                index.add(filepath.as_str(), todo_raw.as_str());
+            }
+        }
+    }
+    let hits = index.search(query.as_str());
+    let hits = &hits[..hits.len().min(limit)];
+
+    if args.is_present("json") {
+        let hits = hits
+            .iter()
+            .map(|(d, score)| {
+                format!(
+                    "{{\"kind\":\"{}\",\"title\":{},\"text\":{},\"path\":{},\"line\":{},\"score\":{score}}}",
+                    format!("{:?}", d.kind).to_lowercase(),
+                    quote(d.title.as_str()),
+                    quote(d.text.as_str()),
+                    quote(d.path.as_str()),
+                    d.line
+                )
+            })
+            .collect::<Vec<_>>();
+        println!("[{}]", hits.join(","));
+        return Ok(());
+    }
+    if hits.is_empty() {
+        eprintln!("No matches for \"{query}\"");
+    }
+    for (d, _) in hits {
+        match d.kind {
+            Kind::Title => println!("{}", d.title),
+            Kind::Section => println!("{} > {}", d.title, d.text),
+            Kind::Task => println!("{}: {}", d.title, d.text),
+            Kind::Motive => println!("{} (motive): {}", d.title, d.text),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rank_hits() {
+        let mut index = Index::default();
+        index.add(
+            "release.md",
+            "# Release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n### Changelog\n\n* [ ] write release notes\n* [ ] bump version\n",
+        );
+        index.add(
+            "groceries.md",
+            "# Groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] milk\n* [ ] release the parking brake\n\n## Motives\n\n1. stay healthy\n",
+        );
+
+        let hits = index.search("release");
+        let found = hits
+            .iter()
+            .map(|(d, _)| (d.kind, d.title.as_str(), d.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (Kind::Title, "Release", 1),
+                (Kind::Task, "Groceries", 10),
+                (Kind::Task, "Release", 11),
+            ]
+        );
+
+        assert_eq!(index.search("chan")[0].0.kind, Kind::Section);
+        assert_eq!(
+            index.search("relaese notes")[0].0.text,
+            "write release notes"
+        );
+        assert_eq!(index.search("verion").len(), 1);
+        assert!(index.search("milk notes").is_empty());
+        assert!(index.search("mlk").is_empty());
+        let hits = index.search("health");
+        assert_eq!(
+            (hits[0].0.kind, hits[0].0.title.as_str(), hits[0].0.line),
+            (Kind::Motive, "Groceries", 14)
+        );
+    }
+}
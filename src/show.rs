@@ -0,0 +1,377 @@
+//! Show Todo list from active Todo context
+use crate::parse::{parse_task_id, parse_tasks, parse_todo_list, Task};
+use crate::parts::Parts;
+use crate::{path_arg, read_todo_lists, todo_path_from_args, Configuration, Context};
+use chrono::NaiveDate;
+use clap::{crate_authors, App, Arg, ArgMatches};
+use lazy_static::lazy_static;
+use log::{debug, trace};
+use regex::Regex;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// A reference from a Todo list to another Todo list
+#[derive(Debug, PartialEq)]
+pub struct Backlink {
+    /// Title of the Todo list which references the other Todo list
+    pub title: String,
+    /// Tasks of the Todo list which reference the other Todo list. When empty, the reference was
+    /// found outside of the task list.
+    pub tasks: Vec<String>,
+}
+
+/// Returns the Show Todo command
+pub fn show_command() -> App<'static, 'static> {
+    App::new("show")
+        .about("Show todo list within Todo context")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("title")
+                .short("t")
+                .long("title")
+                .value_name("TITLE")
+                .index(1)
+                .help("Title of todo list")
+                .takes_value(true)
+                .required_unless_one(&["path", "task"]),
+        )
+        .arg(path_arg())
+        .arg(
+            Arg::with_name("task")
+                .long("task")
+                .value_name("ID")
+                .help("Shows the task with ID (e.g. ^a3f2) with its todo list and section")
+                .takes_value(true)
+                .conflicts_with_all(&["title", "path", "backlinks"]),
+        )
+        .arg(
+            Arg::with_name("on")
+                .long("on")
+                .value_name("YYYY-MM-DD")
+                .help("Shows the dated todo list (e.g. \"Plan 2024-05-12\") of TITLE on this date")
+                .takes_value(true)
+                .conflicts_with_all(&["path", "backlinks"]),
+        )
+        .arg(
+            Arg::with_name("latest")
+                .long("latest")
+                .help("Shows the dated todo list of TITLE with the latest date")
+                .conflicts_with_all(&["on", "path", "backlinks"]),
+        )
+        .arg(
+            Arg::with_name("backlinks")
+                .short("b")
+                .long("backlinks")
+                .help("Shows Todo lists and tasks which reference this Todo list"),
+        )
+}
+
+/// Shows Todo list of active Todo context, the Todo lists referencing it or a task by its ID
+pub fn show_command_process(
+    args: &ArgMatches,
+    ctx: &Context,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    trace!("show subcommand");
+    if let Some(id) = args.value_of("task") {
+        let id = id.trim_start_matches('^');
+        // tasks keep their ID when their Todo list moves to another Todo context
+        let ctxs = std::iter::once(ctx).chain(config.ctxs.iter().filter(|c| c.name != ctx.name));
+        for c in ctxs {
+            if !Path::new(c.folder_location.as_str()).is_dir() {
+                continue;
+            }
+            let todo_lists = read_todo_lists(c)?;
+            let todo_raws = todo_lists
+                .iter()
+                .map(|(_, raw)| raw.as_str())
+                .collect::<Vec<_>>();
+            if let Some((todo_raw, task)) = find_task(id, &todo_raws) {
+                let other_ctx = (c.name != ctx.name).then_some(c.name.as_str());
+                return show_task(&mut std::io::stdout(), todo_raw, &task, other_ctx);
+            }
+        }
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No task has ID ^{id}"),
+        ));
+    }
+
+    if args.is_present("on") || args.is_present("latest") {
+        let title = args.value_of("title").unwrap();
+        let on = match args.value_of("on") {
+            Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                Ok(date) => Some(date),
+                Err(e) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Invalid date \"{date}\" (YYYY-MM-DD): {e}"),
+                    ))
+                }
+            },
+            None => None,
+        };
+        let todo_lists = read_todo_lists(ctx)?;
+        return match dated_todo_list(&todo_lists, title, on) {
+            Some((filepath, todo_raw)) => {
+                debug!("dated todo list {filepath}");
+                println!("{todo_raw}");
+                Ok(())
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                match on {
+                    Some(on) => format!("No \"{title}\" todo list is dated {on}"),
+                    None => format!("No \"{title}\" todo list is dated"),
+                },
+            )),
+        };
+    }
+
+    if !args.is_present("backlinks") {
+        let todo_raw = Parts::read(todo_path_from_args(args, ctx)?.as_str())?.joined();
+        println!("{todo_raw}");
+        return Ok(());
+    }
+
+    let title = match (args.value_of("title"), args.value_of("path")) {
+        (Some(title), _) => title.to_string(),
+        (None, Some(filepath)) => parse_todo_list(read_to_string(filepath)?.as_str())?.title,
+        (None, None) => unreachable!("title or path is required"),
+    };
+
+    let todo_lists = read_todo_lists(ctx)?;
+    let todo_raws = todo_lists
+        .iter()
+        .map(|(_, raw)| raw.as_str())
+        .collect::<Vec<_>>();
+    show_backlinks(&mut std::io::stdout(), title.as_str(), &todo_raws)
+}
+
+/// Returns the path and content of the Todo list titled `name` with a date (e.g. `Plan 2024-05-12`),
+/// dated `on` or else with the latest date
+fn dated_todo_list<'a>(
+    todo_lists: &'a [(String, String)],
+    name: &str,
+    on: Option<NaiveDate>,
+) -> Option<&'a (String, String)> {
+    lazy_static! {
+        static ref DATE_RE: Regex = Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap();
+    }
+    let separators: &[char] = &[' ', '-', '_', ':', '/'];
+    todo_lists
+        .iter()
+        .filter_map(|todo_list| {
+            let title = parse_todo_list(todo_list.1.as_str()).ok()?.title;
+            let date = DATE_RE.find(title.as_str())?;
+            let date_value = NaiveDate::parse_from_str(date.as_str(), "%Y-%m-%d").ok()?;
+            let undated = format!("{}{}", &title[..date.start()], &title[date.end()..]);
+            let undated = undated.trim_matches(separators);
+            undated
+                .eq_ignore_ascii_case(name)
+                .then_some((date_value, todo_list))
+        })
+        .filter(|(date, _)| on.is_none_or(|on| *date == on))
+        .max_by_key(|(date, _)| *date)
+        .map(|(_, todo_list)| todo_list)
+}
+
+/// Returns the Todo list containing the task with given ID and the task
+fn find_task<'a>(id: &str, todo_raws: &[&'a str]) -> Option<(&'a str, Task)> {
+    todo_raws.iter().find_map(|todo_raw| {
+        parse_tasks(todo_raw)
+            .into_iter()
+            .find(|t| parse_task_id(t.text.as_str()) == Some(id))
+            .map(|t| (*todo_raw, t))
+    })
+}
+
+/// Prints task with its comments below the title and section of its Todo list
+fn show_task(
+    stdout: &mut dyn std::io::Write,
+    todo_raw: &str,
+    task: &Task,
+    other_ctx: Option<&str>,
+) -> Result<(), std::io::Error> {
+    let title = todo_raw
+        .lines()
+        .next()
+        .and_then(|l| l.strip_prefix("# "))
+        .unwrap_or("");
+    write!(stdout, "{title}")?;
+    if let Some(section) = &task.section {
+        write!(stdout, " > {section}")?;
+    }
+    if let Some(ctx_name) = other_ctx {
+        write!(stdout, " (context \"{ctx_name}\")")?;
+    }
+    writeln!(stdout)?;
+    for line in todo_raw
+        .lines()
+        .skip(task.line)
+        .take(task.last_line - task.line + 1)
+    {
+        writeln!(stdout, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Prints Todo lists and tasks which reference the Todo list with given title
+fn show_backlinks(
+    stdout: &mut dyn std::io::Write,
+    title: &str,
+    todo_raws: &[&str],
+) -> Result<(), std::io::Error> {
+    writeln!(stdout, "Backlinks to \"{title}\"")?;
+    for backlink in backlinks(title, todo_raws) {
+        writeln!(stdout, "# {}", backlink.title)?;
+        for task in backlink.tasks {
+            writeln!(stdout, "{task}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns all Todo lists referencing the Todo list with given title
+///
+/// A reference is either a wikilink (`[[title]]`) or a plain mention of the title. The Todo list
+/// itself is never part of its backlinks.
+pub fn backlinks(title: &str, todo_raws: &[&str]) -> Vec<Backlink> {
+    let mention_re =
+        Regex::new(format!(r"(?m)(^|\W){}(\W|$)", regex::escape(title)).as_str()).unwrap();
+    let mut backlinks = vec![];
+    for todo_raw in todo_raws {
+        let todo_list = match parse_todo_list(todo_raw) {
+            Ok(todo_list) => todo_list,
+            Err(e) => {
+                debug!("skipping unparsable Todo list: {e}");
+                continue;
+            }
+        };
+        if todo_list.title == title {
+            continue;
+        }
+
+        // the title line of the Todo list could mention the title too
+        let body = todo_raw.split_once('\n').map(|(_, b)| b).unwrap_or("");
+        if !mention_re.is_match(body) {
+            continue;
+        }
+
+        let tasks = body
+            .lines()
+            .filter(|l| l.starts_with("* [ ] ") || l.starts_with("* [x] "))
+            .filter(|l| mention_re.is_match(l))
+            .map(|l| l.trim_end().to_string())
+            .collect();
+        backlinks.push(Backlink {
+            title: todo_list.title,
+            tasks,
+        });
+    }
+
+    backlinks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backlinks_from_tasks_and_description() {
+        let todo_raws = vec![
+            "# release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] ship it",
+            "# docs\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] wait for [[release]]\n* [x] unrelated\n* [x] announce release",
+            "# blog\n\n## Description\n\nLABEL=\nWritten after the release\n",
+            "# releases-archive\n\n## Description\n\nLABEL=\n",
+        ];
+        let expected = vec![
+            Backlink {
+                title: "docs".to_string(),
+                tasks: vec![
+                    "* [ ] wait for [[release]]".to_string(),
+                    "* [x] announce release".to_string(),
+                ],
+            },
+            Backlink {
+                title: "blog".to_string(),
+                tasks: vec![],
+            },
+        ];
+        assert_eq!(backlinks("release", &todo_raws), expected);
+    }
+
+    #[test]
+    fn titles_with_special_characters() {
+        let todo_raws = vec![
+            "# C++ (v2)\n\n## Description\n\nLABEL=\n",
+            "# port\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] finish [[C++ (v2)]]",
+        ];
+        let backlinks = backlinks("C++ (v2)", &todo_raws);
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].title, "port");
+    }
+
+    #[test]
+    fn show_task_by_id() {
+        let todo_raws = vec![
+            "# docs\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] publish @see(^a3f2)\n",
+            "# release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n### Docs\n\n* [ ] readme ^a3f2\n  > in english\n* [ ] faq\n",
+        ];
+        let (todo_raw, task) = find_task("a3f2", &todo_raws).unwrap();
+        assert!(find_task("b000", &todo_raws).is_none());
+        let mut stdout = vec![];
+        show_task(&mut stdout, todo_raw, &task, Some("work")).unwrap();
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "release > Docs (context \"work\")\n* [ ] readme ^a3f2\n  > in english\n"
+        );
+    }
+
+    #[test]
+    fn show_backlinks_output() {
+        let mut stdout = vec![];
+        let todo_raws = vec![
+            "# release\n\n## Description\n\nLABEL=\n",
+            "# docs\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] wait for [[release]]",
+        ];
+        assert!(show_backlinks(&mut stdout, "release", &todo_raws).is_ok());
+        let expected = b"Backlinks to \"release\"\n# docs\n* [ ] wait for [[release]]\n";
+        assert_eq!(
+            stdout,
+            expected,
+            "\ngot     : \"{}\"\nexpected: \"{}\"",
+            String::from_utf8(stdout.to_owned()).unwrap(),
+            String::from_utf8(expected.to_vec()).unwrap()
+        );
+    }
+
+    #[test]
+    fn find_dated_todo_lists() {
+        let todo_lists = [
+            "Plan 2024-05-12",
+            "2024-05-13 plan",
+            "plan-2024-05-11",
+            "Planning 2024-06-01",
+            "plan",
+        ]
+        .iter()
+        .map(|title| {
+            (
+                format!("{title}.md"),
+                format!("# {title}\n\n## Description\n\nLABEL=\n"),
+            )
+        })
+        .collect::<Vec<_>>();
+        let date = |d| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        assert_eq!(
+            dated_todo_list(&todo_lists, "plan", Some(date("2024-05-12"))).map(|l| l.0.as_str()),
+            Some("Plan 2024-05-12.md")
+        );
+        assert_eq!(
+            dated_todo_list(&todo_lists, "plan", None).map(|l| l.0.as_str()),
+            Some("2024-05-13 plan.md")
+        );
+        assert!(dated_todo_list(&todo_lists, "plan", Some(date("2024-06-01"))).is_none());
+    }
+}
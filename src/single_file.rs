@@ -0,0 +1,219 @@
+//! Store all Todo lists of a Todo context inside a single markdown file
+//!
+//! With `storage = "single-file"`, Todo lists of the Todo context are kept one after another inside
+//! `todo.md` of the Todo folder. Each Todo list starts at its `# Title` heading and ends before the
+//! next one. Text before the first heading is kept as is.
+use crate::lock::is_locked;
+use crate::Context;
+use log::debug;
+use std::path::Path;
+use std::process::Command;
+
+/// Value of `storage` for Todo contexts stored inside a single file
+pub const SINGLE_FILE_STORAGE: &str = "single-file";
+
+/// Name of the file holding all Todo lists of a single-file Todo context
+pub const SINGLE_FILE_NAME: &str = "todo.md";
+
+/// Todo lists of a single-file Todo context
+#[derive(Debug, PartialEq)]
+pub struct SingleFile {
+    /// Path of the file
+    pub path: String,
+    /// Text before the first Todo list
+    preamble: String,
+    /// Raw Todo lists, in file order
+    pub todo_lists: Vec<String>,
+}
+
+impl SingleFile {
+    /// Reads the single file of the Todo context. A missing file holds no Todo list.
+    pub fn read(ctx: &Context) -> Result<SingleFile, std::io::Error> {
+        let path = single_file_path(ctx);
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        debug!("read {path}");
+        Ok(SingleFile::parse(path, raw.as_str()))
+    }
+
+    /// Splits `raw` at each `# Title` heading outside code blocks
+    pub(crate) fn parse(path: String, raw: &str) -> SingleFile {
+        let mut chunks = vec![String::new()];
+        let mut in_code_block = false;
+        for line in raw.lines() {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            if !in_code_block && line.starts_with("# ") {
+                chunks.push(String::new());
+            }
+            let chunk = chunks.last_mut().unwrap();
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+        let normalize = |chunk: String| format!("{}\n", chunk.trim_end());
+        let preamble = chunks.remove(0);
+        SingleFile {
+            path,
+            preamble: if preamble.trim().is_empty() {
+                String::new()
+            } else {
+                normalize(preamble)
+            },
+            todo_lists: chunks.into_iter().map(normalize).collect(),
+        }
+    }
+
+    /// Returns the index of the Todo list titled `title`
+    ///
+    /// Many Todo lists sharing the same title is an error since title-based commands are ambiguous.
+    pub fn position(&self, title: &str) -> Result<Option<usize>, std::io::Error> {
+        let positions = self
+            .todo_lists
+            .iter()
+            .enumerate()
+            .filter(|(_, todo_raw)| title_of(todo_raw) == title)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if positions.len() > 1 {
+            return Err(std::io::Error::other(format!(
+                "{} Todo lists are titled \"{title}\" inside {}. Rename all but one.",
+                positions.len(),
+                self.path
+            )));
+        }
+        Ok(positions.first().copied())
+    }
+
+    /// Returns the index of the Todo list titled `title`, which must exist and be unlocked unless
+    /// `force` is set
+    pub fn position_of_unlocked(&self, title: &str, force: bool) -> Result<usize, std::io::Error> {
+        let i = match self.position(title)? {
+            Some(i) => i,
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No Todo list titled \"{title}\" inside {}", self.path),
+                ))
+            }
+        };
+        if !force && is_locked(self.todo_lists[i].as_str()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("Todo list \"{title}\" is locked. Unlock it or use --force."),
+            ));
+        }
+        Ok(i)
+    }
+
+    /// Overwrites the file with its Todo lists
+    pub fn write(&self) -> Result<(), std::io::Error> {
+        std::fs::write(&self.path, self.to_string())?;
+        debug!("wrote {}", self.path);
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for SingleFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let chunks = std::iter::once(&self.preamble)
+            .filter(|p| !p.is_empty())
+            .chain(self.todo_lists.iter())
+            .map(|chunk| chunk.as_str())
+            .collect::<Vec<_>>();
+        write!(f, "{}", chunks.join("\n"))
+    }
+}
+
+/// Edits the Todo list titled `title` with the IDE of the Todo context, through a temporary file
+/// holding only this Todo list. Emptying the temporary file deletes the Todo list.
+pub fn edit_todo_list(ctx: &Context, title: &str, force: bool) -> Result<(), std::io::Error> {
+    let single_file = SingleFile::read(ctx)?;
+    let i = single_file.position_of_unlocked(title, force)?;
+    let tmp = std::env::temp_dir().join(format!("todo-{}-{SINGLE_FILE_NAME}", std::process::id()));
+    std::fs::write(&tmp, single_file.todo_lists[i].as_str())?;
+    debug!("wrote {}", tmp.display());
+    let status = Command::new(ctx.ide.as_str()).arg(&tmp).status();
+    let edited = std::fs::read_to_string(&tmp);
+    std::fs::remove_file(&tmp)?;
+    if !status?.success() {
+        return Err(std::io::Error::other(format!(
+            "{} exited with an error, \"{title}\" was left untouched",
+            ctx.ide
+        )));
+    }
+    let edited = edited?;
+
+    // the file may have changed while editing
+    let mut single_file = SingleFile::read(ctx)?;
+    let i = single_file.position_of_unlocked(title, true)?;
+    if edited.trim().is_empty() {
+        single_file.todo_lists.remove(i);
+    } else {
+        single_file.todo_lists[i] = format!("{}\n", edited.trim_end());
+    }
+    single_file.write()
+}
+
+/// Returns the path of the file holding all Todo lists of the Todo context
+pub fn single_file_path(ctx: &Context) -> String {
+    Path::new(ctx.folder_location.as_str())
+        .join(SINGLE_FILE_NAME)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Returns the title of a Todo list from its `# Title` heading
+pub(crate) fn title_of(todo_raw: &str) -> &str {
+    todo_raw
+        .lines()
+        .next()
+        .and_then(|l| l.strip_prefix("# "))
+        .unwrap_or("")
+        .trim()
+}
+
+/// Returns the Todo list with its `# Title` heading set to `title`
+pub(crate) fn retitle(todo_raw: &str, title: &str) -> String {
+    match todo_raw.split_once('\n') {
+        Some((heading, rest)) if heading.starts_with("# ") => format!("# {title}\n{rest}"),
+        None if todo_raw.starts_with("# ") => format!("# {title}"),
+        _ => format!("# {title}\n\n{todo_raw}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_and_join_todo_lists() {
+        let raw = "Notes about this file\n\n# Groceries\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] milk\n\n\n# Release\n\n## Description\n\nLABEL=work\n\n```sh\n# not a title\n```\n";
+        let mut single_file = SingleFile::parse("todo.md".to_string(), raw);
+        assert_eq!(single_file.preamble, "Notes about this file\n");
+        assert_eq!(single_file.todo_lists.len(), 2);
+        assert!(single_file.todo_lists[1].ends_with("# not a title\n```\n"));
+        assert_eq!(single_file.position("Release").unwrap(), Some(1));
+        assert_eq!(single_file.position("Missing").unwrap(), None);
+
+        single_file.todo_lists.remove(0);
+        assert_eq!(
+            single_file.to_string(),
+            "Notes about this file\n\n# Release\n\n## Description\n\nLABEL=work\n\n```sh\n# not a title\n```\n"
+        );
+
+        single_file
+            .todo_lists
+            .push("# Release\n\n<!-- todo:locked -->\n".to_string());
+        assert!(single_file.position("Release").is_err());
+        single_file.todo_lists.remove(0);
+        assert!(single_file.position_of_unlocked("Release", false).is_err());
+        assert_eq!(
+            single_file.position_of_unlocked("Release", true).unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,502 @@
+//! Snapshot a Todo context and restore it later on
+//!
+//! A snapshot is a tar archive containing the configuration entry of the Todo context
+//! (`context.toml`) and every file of the Todo folder (under `todo/`). Snapshots are stored next to
+//! the configuration file inside the `.todo_snapshots` folder. Restoring replaces the Todo lists of
+//! the configured Todo context of the same name, wherever its folder now is.
+use crate::config_file::write_configuration;
+use crate::confirm::{confirm, Operation};
+use crate::parse::parse_configuration_file;
+use crate::{Configuration, Context};
+use clap::{crate_authors, App, AppSettings, Arg, ArgMatches};
+use log::{debug, trace, warn};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the configuration entry inside a snapshot
+const CONTEXT_ENTRY: &str = "context.toml";
+/// Folder holding the Todo lists inside a snapshot
+const TODO_FOLDER_ENTRY: &str = "todo";
+
+/// Returns snapshot command which is comprised of multiple subcommands
+pub fn snapshot_command() -> App<'static, 'static> {
+    App::new("snapshot")
+        .about("Save and restore whole Todo contexts")
+        .author(crate_authors!())
+        .setting(AppSettings::SubcommandRequired)
+        .subcommand(
+            App::new("create")
+                .about("Archive Todo context folder and configuration entry")
+                .author(crate_authors!())
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .index(1)
+                        .help("Name appended to the timestamped snapshot")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("context name")
+                        .short("c")
+                        .long("ctx")
+                        .value_name("CONTEXT")
+                        .help("Context to snapshot (default is active context)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("restore")
+                .about("Roll back Todo context to the state of a snapshot")
+                .author(crate_authors!())
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .index(1)
+                        .help("Snapshot to restore (latest snapshot ending with NAME is used)")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            App::new("list")
+                .about("List available snapshots")
+                .author(crate_authors!()),
+        )
+}
+
+/// Executes snapshot command
+pub fn snapshot_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    raw_config: Option<&str>,
+) -> Result<(), std::io::Error> {
+    let snapshots_folder = snapshots_folder(todo_configuration_path);
+    debug!("snapshots folder: {}", snapshots_folder.display());
+
+    if let Some(args) = args.subcommand_matches("create") {
+        trace!("snapshot create");
+        let config = parse_configuration_file(Some(todo_configuration_path), raw_config)?;
+        let ctx_name = args
+            .value_of("context name")
+            .unwrap_or(config.active_ctx_name.as_str());
+        let ctx = config.ctx(ctx_name)?;
+        let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S").to_string();
+        let name = snapshot_name(ctx_name, timestamp.as_str(), args.value_of("name"));
+
+        std::fs::create_dir_all(&snapshots_folder)?;
+        let archive_path = snapshots_folder.join(format!("{name}.tar"));
+        create_snapshot(ctx, &archive_path)?;
+        println!("Saved snapshot \"{name}\" ({})", archive_path.display());
+        return Ok(());
+    }
+
+    if let Some(args) = args.subcommand_matches("restore") {
+        trace!("snapshot restore");
+        let name = args.value_of("name").unwrap();
+        let snapshot_names = list_snapshots(&snapshots_folder)?;
+        let snapshot = match find_snapshot(&snapshot_names, name) {
+            Some(s) => s,
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No snapshot matches \"{name}\""),
+                ))
+            }
+        };
+        let archive_path = snapshots_folder.join(format!("{snapshot}.tar"));
+
+        // the archived context is only known once restored, the active one sets the policy
+        let mut config = parse_configuration_file(Some(todo_configuration_path), raw_config)?;
+        let prompt = format!(
+            "Restoring \"{snapshot}\" replaces all Todo lists of the archived context. Continue?"
+        );
+        if !confirm(config.active_ctx(), Operation::Restore, prompt.as_str())? {
+            return Ok(());
+        }
+
+        let restored_ctx = restore_snapshot(&archive_path, &config)?;
+
+        match config
+            .ctxs
+            .iter_mut()
+            .find(|ctx| ctx.name == restored_ctx.name)
+        {
+            Some(ctx) => *ctx = restored_ctx.clone(),
+            None => config.ctxs.push(restored_ctx.clone()),
+        }
+        write_configuration(todo_configuration_path, &config)?;
+
+        println!(
+            "Restored context \"{}\" from snapshot \"{snapshot}\"",
+            restored_ctx.name
+        );
+        return Ok(());
+    }
+
+    if args.subcommand_matches("list").is_some() {
+        trace!("snapshot list");
+        for snapshot in list_snapshots(&snapshots_folder)? {
+            println!("{snapshot}");
+        }
+        return Ok(());
+    }
+
+    warn!("unrecognised command");
+    Err(std::io::Error::other("Unrecognised command"))
+}
+
+/// Returns the folder where snapshots are stored, next to the configuration file
+fn snapshots_folder(todo_configuration_path: &str) -> PathBuf {
+    Path::new(todo_configuration_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(".todo_snapshots")
+}
+
+/// Returns the name of a new snapshot
+fn snapshot_name(ctx_name: &str, timestamp: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{ctx_name}-{timestamp}-{name}"),
+        None => format!("{ctx_name}-{timestamp}"),
+    }
+}
+
+/// Returns the sorted names of all snapshots inside the snapshots folder
+fn list_snapshots(snapshots_folder: &Path) -> Result<Vec<String>, std::io::Error> {
+    if !snapshots_folder.exists() {
+        return Ok(vec![]);
+    }
+    let mut snapshots = vec![];
+    for entry in std::fs::read_dir(snapshots_folder)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("tar") {
+            continue;
+        }
+        if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+            snapshots.push(stem.to_string());
+        }
+    }
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// Returns the snapshot named `name` or else the latest snapshot ending with `-name`
+fn find_snapshot<'a>(snapshots: &'a [String], name: &str) -> Option<&'a str> {
+    if let Some(s) = snapshots.iter().find(|s| *s == name) {
+        return Some(s.as_str());
+    }
+    let suffix = format!("-{name}");
+    snapshots
+        .iter()
+        .filter(|s| s.ends_with(suffix.as_str()))
+        .max_by_key(|s| {
+            // timestamp is right after the context name
+            s[..s.len() - suffix.len()]
+                .rsplit('-')
+                .next()
+                .unwrap_or("")
+                .to_string()
+        })
+        .map(|s| s.as_str())
+}
+
+/// Archives Todo folder and configuration entry of Todo context at `archive_path`
+fn create_snapshot(ctx: &Context, archive_path: &Path) -> Result<(), std::io::Error> {
+    let mut builder = tar::Builder::new(File::create(archive_path)?);
+
+    let raw_ctx = toml::to_string(ctx).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(raw_ctx.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, CONTEXT_ENTRY, raw_ctx.as_bytes())?;
+
+    if Path::new(ctx.folder_location.as_str()).is_dir() {
+        builder.append_dir_all(TODO_FOLDER_ENTRY, ctx.folder_location.as_str())?;
+    }
+    builder.finish()?;
+    debug!("wrote {}", archive_path.display());
+    Ok(())
+}
+
+/// Replaces Todo folder of archived Todo context with the archived Todo lists. Returns the
+/// archived Todo context.
+///
+/// The Todo lists are restored inside the folder of the Todo context of the same name in
+/// `config`, whose folder may have moved since the snapshot. A Todo context missing from `config`
+/// is restored inside its archived folder, which must be empty.
+///
+/// The Todo lists are written inside a sibling of the Todo folder first, which then takes its
+/// place: a snapshot which cannot be restored leaves the Todo folder as it was.
+fn restore_snapshot(
+    archive_path: &Path,
+    config: &Configuration,
+) -> Result<Context, std::io::Error> {
+    let mut archive = tar::Archive::new(File::open(archive_path)?);
+    let mut raw_ctx = String::new();
+    let mut todo_entries = vec![];
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+        if path == Path::new(CONTEXT_ENTRY) {
+            entry.read_to_string(&mut raw_ctx)?;
+        } else if let Ok(relative) = path.strip_prefix(TODO_FOLDER_ENTRY) {
+            // `todo/../x` would be written outside of the Todo folder
+            if !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "Snapshot is not restored because of its entry \"{}\" outside of the Todo folder",
+                        path.display()
+                    ),
+                ));
+            }
+            if entry.header().entry_type().is_file() {
+                let mut content = vec![];
+                entry.read_to_end(&mut content)?;
+                todo_entries.push((relative.to_path_buf(), content));
+            }
+        }
+    }
+    if raw_ctx.is_empty() {
+        return Err(std::io::Error::other(
+            "Snapshot does not contain any context configuration",
+        ));
+    }
+    let mut ctx: Context = toml::from_str(raw_ctx.as_str())?;
+
+    match config.ctxs.iter().find(|c| c.name == ctx.name) {
+        Some(configured) => ctx.folder_location = configured.folder_location.clone(),
+        None => {
+            let folder = Path::new(ctx.folder_location.as_str());
+            if folder.is_dir() && folder.read_dir()?.next().is_some() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!(
+                        "Context \"{}\" is not restored inside {} which is not empty",
+                        ctx.name, ctx.folder_location
+                    ),
+                ));
+            }
+        }
+    }
+    let folder = Path::new(ctx.folder_location.as_str());
+    let staging = sibling(folder, "restoring")?;
+    if let Err(e) = write_entries(&staging, todo_entries) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    if !folder.exists() {
+        std::fs::rename(&staging, folder)?;
+        debug!("moved {} to {}", staging.display(), folder.display());
+        return Ok(ctx);
+    }
+    let replaced = sibling(folder, "replaced")?;
+    if let Err(e) = std::fs::rename(folder, &replaced) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    if let Err(e) = std::fs::rename(&staging, folder) {
+        std::fs::rename(&replaced, folder)?;
+        return Err(e);
+    }
+    debug!("moved {} to {}", staging.display(), folder.display());
+    std::fs::remove_dir_all(&replaced)?;
+    debug!("removed {}", replaced.display());
+
+    Ok(ctx)
+}
+
+/// Returns an unused path beside `folder`, named after it and `purpose`. Missing parent folders
+/// are created.
+fn sibling(folder: &Path, purpose: &str) -> Result<PathBuf, std::io::Error> {
+    let name = folder.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} cannot be replaced", folder.display()),
+        )
+    })?;
+    let sibling = folder.with_file_name(format!(
+        ".{}.{purpose}-{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    if let Some(parent) = sibling.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    // left behind by an interrupted restore
+    if sibling.exists() {
+        std::fs::remove_dir_all(&sibling)?;
+    }
+    Ok(sibling)
+}
+
+/// Writes Todo folder entries of a snapshot inside `folder`
+fn write_entries(
+    folder: &Path,
+    todo_entries: Vec<(PathBuf, Vec<u8>)>,
+) -> Result<(), std::io::Error> {
+    std::fs::create_dir_all(folder)?;
+    for (relative, content) in todo_entries {
+        let path = folder.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+        debug!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn names() {
+        assert_eq!(snapshot_name("work", "20240512", None), "work-20240512");
+        assert_eq!(
+            snapshot_name("work", "20240512", Some("before-cleanup")),
+            "work-20240512-before-cleanup"
+        );
+    }
+
+    #[test]
+    fn find_snapshot_by_name() {
+        let snapshots = vec![
+            "work-20240510-cleanup".to_string(),
+            "work-20240512".to_string(),
+            "work-20240513-cleanup".to_string(),
+        ];
+        assert_eq!(
+            find_snapshot(&snapshots, "work-20240512"),
+            Some("work-20240512")
+        );
+        assert_eq!(
+            find_snapshot(&snapshots, "cleanup"),
+            Some("work-20240513-cleanup")
+        );
+        assert_eq!(find_snapshot(&snapshots, "missing"), None);
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let root = TempFolder::new("snapshot");
+        let folder = root.join("ctx");
+        std::fs::create_dir_all(folder.join("nested")).unwrap();
+        std::fs::write(folder.join("a.md"), "# a\n").unwrap();
+        std::fs::write(folder.join("nested/b.md"), "# b\n").unwrap();
+        let ctx = Context {
+            ide: "vim".to_string(),
+            name: "ctx".to_string(),
+            timezone: "CET".to_string(),
+            folder_location: folder.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let archive_path = root.join("ctx.tar");
+        create_snapshot(&ctx, &archive_path).unwrap();
+
+        std::fs::write(folder.join("a.md"), "# a changed\n").unwrap();
+        std::fs::write(folder.join("c.md"), "# c\n").unwrap();
+        // the Todo folder moved since the snapshot
+        let moved = root.join("moved");
+        std::fs::rename(&folder, &moved).unwrap();
+        let mut config = Configuration::new();
+        config
+            .ctxs
+            .push(Context::new("ctx", moved.to_str().unwrap()));
+
+        let restored = restore_snapshot(&archive_path, &config).unwrap();
+        assert_eq!(restored.name, "ctx");
+        assert_eq!(restored.ide, "vim");
+        assert_eq!(restored.folder_location, moved.to_string_lossy());
+        assert_eq!(
+            std::fs::read_to_string(moved.join("a.md")).unwrap(),
+            "# a\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(moved.join("nested/b.md")).unwrap(),
+            "# b\n"
+        );
+        assert!(!moved.join("c.md").exists());
+        assert!(!folder.exists());
+
+        // unknown Todo contexts are restored inside their empty archived folder
+        let restored = restore_snapshot(&archive_path, &Configuration::new()).unwrap();
+        assert_eq!(restored.folder_location, folder.to_string_lossy());
+        assert!(folder.join("a.md").exists());
+        assert!(restore_snapshot(&archive_path, &Configuration::new()).is_err());
+    }
+
+    #[test]
+    fn entries_outside_of_todo_folder_are_rejected() {
+        let root = TempFolder::new("snapshot-escape");
+        let folder = root.join("ctx");
+        let archive_path = root.join("crafted.tar");
+        let mut builder = tar::Builder::new(File::create(&archive_path).unwrap());
+        let raw_ctx = toml::to_string(&Context::new("ctx", folder.to_str().unwrap())).unwrap();
+        for (path, content) in [
+            (CONTEXT_ENTRY, raw_ctx.as_str()),
+            ("todo/../escaped.md", "# escaped\n"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            // set_path refuses `..`
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, content.as_bytes()).unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+
+        let error = restore_snapshot(&archive_path, &Configuration::new()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(!root.join("escaped.md").exists());
+        assert!(!folder.exists());
+    }
+
+    #[test]
+    fn failed_restore_keeps_todo_folder() {
+        let root = TempFolder::new("snapshot-failed");
+        let folder = root.join("ctx");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("keep.md"), "# keep\n").unwrap();
+        let archive_path = root.join("broken.tar");
+        let mut builder = tar::Builder::new(File::create(&archive_path).unwrap());
+        let raw_ctx = toml::to_string(&Context::new("ctx", folder.to_str().unwrap())).unwrap();
+        // `todo/a` cannot be both a file and a folder
+        for (path, content) in [
+            (CONTEXT_ENTRY, raw_ctx.as_str()),
+            ("todo/a", "# a\n"),
+            ("todo/a/b.md", "# b\n"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_path(path).unwrap();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, content.as_bytes()).unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+        let mut config = Configuration::new();
+        config
+            .ctxs
+            .push(Context::new("ctx", folder.to_str().unwrap()));
+
+        assert!(restore_snapshot(&archive_path, &config).is_err());
+        assert!(folder.join("keep.md").exists());
+        let mut names = std::fs::read_dir(&*root)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["broken.tar", "ctx"]);
+    }
+}
@@ -0,0 +1,384 @@
+//! Statistics about Todo usage
+//!
+//! Usage statistics are opt-in with `usage_stats = true` inside the configuration file. Every
+//! invocation then increments the counters of its subcommand and of the active Todo context inside
+//! the `.todo_usage` file next to the configuration file. Statistics never leave the machine: they
+//! help spotting dead Todo contexts to prune.
+//!
+//! `todo stats quotas` compares each Todo context with its soft limits (see [`crate::quota`]).
+//!
+//! `todo stats files` tells how many files of each Todo context folder are Todo lists and how many
+//! are skipped, and why: another extension than the ones of the Todo context, unreadable or
+//! without title, or notes rather than a Todo list (see `todo list --notes-too`).
+use crate::health::check;
+use crate::parse::{is_todo_list, parse_notes};
+use crate::quota::load;
+use crate::single_file::SingleFile;
+use crate::{Configuration, Context};
+use chrono::NaiveDate;
+use clap::{crate_authors, App, AppSettings, Arg, ArgMatches};
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Usage counters stored inside the usage file
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+struct Usage {
+    /// Number of invocations of each subcommand
+    #[serde(default)]
+    subcommands: BTreeMap<String, u64>,
+    /// Number of invocations within each Todo context
+    #[serde(default)]
+    contexts: BTreeMap<String, u64>,
+    /// Date of the last invocation within each Todo context
+    #[serde(default)]
+    last_used: BTreeMap<String, String>,
+}
+
+impl Usage {
+    /// Counts one invocation of `subcommand` within Todo context `ctx_name`
+    fn record(&mut self, subcommand: &str, ctx_name: &str, date: &str) {
+        *self.subcommands.entry(subcommand.to_string()).or_default() += 1;
+        *self.contexts.entry(ctx_name.to_string()).or_default() += 1;
+        self.last_used
+            .insert(ctx_name.to_string(), date.to_string());
+    }
+}
+
+/// Returns stats command which is comprised of multiple subcommands
+pub fn stats_command() -> App<'static, 'static> {
+    App::new("stats")
+        .about("Show statistics about Todo")
+        .author(crate_authors!())
+        .setting(AppSettings::SubcommandRequired)
+        .subcommand(
+            App::new("usage")
+                .about(
+                    "Show how often each subcommand and Todo context is used (opt-in, local only)",
+                )
+                .author(crate_authors!()),
+        )
+        .subcommand(
+            App::new("quotas")
+                .about(
+                    "Show open tasks and Todo lists of each Todo context against its soft limits",
+                )
+                .author(crate_authors!()),
+        )
+        .subcommand(
+            App::new("files")
+                .about("Show how many files of each Todo context are Todo lists and how many are skipped")
+                .author(crate_authors!())
+                .arg(
+                    Arg::with_name("verbose")
+                        .short("v")
+                        .long("verbose")
+                        .help("Lists skipped files with the reason they are skipped"),
+                ),
+        )
+}
+
+/// Why a file of a Todo context folder is not read as a Todo list
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Skipped {
+    /// The extension is not one of the Todo context
+    Extension,
+    /// The file cannot be read as text or has no title
+    Unparsable,
+    /// The file has a title but no labels: notes rather than a Todo list
+    Notes,
+}
+
+impl Skipped {
+    fn reason(&self) -> &'static str {
+        match self {
+            Skipped::Extension => "other extension",
+            Skipped::Unparsable => "unparsable",
+            Skipped::Notes => "not a Todo list",
+        }
+    }
+}
+
+/// Files of a Todo context folder
+#[derive(Debug, Default, PartialEq)]
+pub struct Files {
+    /// Number of Todo lists
+    pub todo_lists: usize,
+    /// Skipped files with the reason they are skipped
+    pub skipped: Vec<(String, Skipped)>,
+}
+
+impl Files {
+    /// Returns the number of files skipped for `reason`
+    pub fn count(&self, reason: Skipped) -> usize {
+        self.skipped.iter().filter(|(_, r)| *r == reason).count()
+    }
+}
+
+/// Returns the files of the Todo context. Hidden files and folders (`.git`) are not counted.
+pub fn files(ctx: &Context) -> Result<Files, std::io::Error> {
+    let mut files = Files::default();
+    if ctx.is_single_file() {
+        files.todo_lists = SingleFile::read(ctx)?.todo_lists.len();
+        return Ok(files);
+    }
+    let entries = WalkDir::new(ctx.folder_location.as_str())
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'));
+    for entry in entries {
+        let entry = entry.map_err(std::io::Error::other)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let filepath = entry.path().to_string_lossy().to_string();
+        if !ctx.is_todo_list_file(entry.path()) {
+            files.skipped.push((filepath, Skipped::Extension));
+            continue;
+        }
+        match std::fs::read_to_string(entry.path()) {
+            Ok(todo_raw) if is_todo_list(todo_raw.as_str()) => files.todo_lists += 1,
+            Ok(todo_raw) if parse_notes(todo_raw.as_str()).is_ok() => {
+                files.skipped.push((filepath, Skipped::Notes))
+            }
+            _ => files.skipped.push((filepath, Skipped::Unparsable)),
+        }
+    }
+    Ok(files)
+}
+
+/// Executes stats command
+pub fn stats_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    if args.subcommand_matches("usage").is_some() {
+        trace!("stats usage");
+        if !config.records_usage() {
+            println!("Usage statistics are disabled. Set `usage_stats = true` in the configuration file to record them.");
+        }
+        let usage = read_usage(&usage_path(todo_configuration_path))?;
+        print_usage(&mut std::io::stdout(), &usage, config)?;
+        return Ok(());
+    }
+    if args.subcommand_matches("quotas").is_some() {
+        trace!("stats quotas");
+        print_quotas(&mut std::io::stdout(), config)?;
+        return Ok(());
+    }
+    if let Some(args) = args.subcommand_matches("files") {
+        trace!("stats files");
+        for ctx in &config.ctxs {
+            let health = check(ctx);
+            if !health.is_ok() {
+                println!("{}: {health} ({})", ctx.name, ctx.folder_location);
+                continue;
+            }
+            print_files(
+                &mut std::io::stdout(),
+                ctx,
+                &files(ctx)?,
+                args.is_present("verbose"),
+            )?;
+        }
+        return Ok(());
+    }
+
+    Err(std::io::Error::other("unrecognised stats subcommand"))
+}
+
+/// Counts one invocation of `subcommand` within Todo context `ctx_name` in the usage file
+pub fn record_usage(
+    todo_configuration_path: &str,
+    subcommand: &str,
+    ctx_name: &str,
+) -> Result<(), std::io::Error> {
+    let path = usage_path(todo_configuration_path);
+    let mut usage = read_usage(&path)?;
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    usage.record(subcommand, ctx_name, date.as_str());
+    std::fs::write(&path, toml::to_string(&usage).unwrap())?;
+    debug!("wrote {}", path.display());
+    Ok(())
+}
+
+/// Returns the path of the usage file next to the configuration file
+fn usage_path(todo_configuration_path: &str) -> PathBuf {
+    Path::new(todo_configuration_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(".todo_usage")
+}
+
+/// Returns usage counters from usage file. A missing file has no usage.
+fn read_usage(path: &Path) -> Result<Usage, std::io::Error> {
+    match std::fs::read_to_string(path) {
+        Ok(raw) => Ok(toml::from_str(raw.as_str())?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Usage::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Prints usage counters from the most used to the least used. Todo contexts of configuration
+/// which were never used are listed last.
+fn print_usage(
+    stdout: &mut dyn std::io::Write,
+    usage: &Usage,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    writeln!(stdout, "Subcommands:")?;
+    let mut subcommands = usage.subcommands.iter().collect::<Vec<_>>();
+    subcommands.sort_by_key(|(_, count)| Reverse(**count));
+    for (subcommand, count) in subcommands {
+        writeln!(stdout, "{count}\t- {subcommand}")?;
+    }
+
+    writeln!(stdout, "Contexts:")?;
+    let mut ctxs = config
+        .ctxs
+        .iter()
+        .map(|ctx| (ctx, usage.contexts.get(&ctx.name).copied().unwrap_or(0)))
+        .collect::<Vec<_>>();
+    ctxs.sort_by_key(|(_, count)| Reverse(*count));
+    for (ctx, count) in ctxs {
+        let ctx_name = ctx.name.as_str();
+        match usage.last_used.get(ctx_name) {
+            Some(date) => {
+                // dates are recorded in ISO 8601
+                let date = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                    Ok(d) => d.format(config.date_format(ctx).as_str()).to_string(),
+                    Err(_) => date.clone(),
+                };
+                writeln!(stdout, "{count}\t- {ctx_name} (last used {date})")?
+            }
+            None => writeln!(stdout, "{count}\t- {ctx_name} (never used)")?,
+        }
+    }
+    Ok(())
+}
+
+/// Prints open tasks and Todo lists of each Todo context against its soft limits
+fn print_quotas(
+    stdout: &mut dyn std::io::Write,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    for ctx in &config.ctxs {
+        let load = load(ctx)?;
+        writeln!(
+            stdout,
+            "{}: {}, {}",
+            ctx.name,
+            against(load.open_tasks, ctx.max_open_tasks, "open tasks"),
+            against(load.lists, ctx.max_lists, "Todo lists")
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints how many files of the Todo context are Todo lists and how many are skipped, listing
+/// skipped files when `verbose`
+fn print_files(
+    stdout: &mut dyn std::io::Write,
+    ctx: &Context,
+    files: &Files,
+    verbose: bool,
+) -> Result<(), std::io::Error> {
+    writeln!(
+        stdout,
+        "{}: {} Todo lists, {} skipped ({} not Todo lists, {} unparsable, {} other extension)",
+        ctx.name,
+        files.todo_lists,
+        files.skipped.len(),
+        files.count(Skipped::Notes),
+        files.count(Skipped::Unparsable),
+        files.count(Skipped::Extension),
+    )?;
+    if verbose {
+        for (filepath, reason) in &files.skipped {
+            writeln!(stdout, "\t{filepath} ({})", reason.reason())?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns `count` `what` against soft limit `max`, flagging an exceeded limit
+fn against(count: usize, max: Option<usize>, what: &str) -> String {
+    match max {
+        Some(max) if count > max => format!("{count}/{max} {what} (over limit)"),
+        Some(max) => format!("{count}/{max} {what}"),
+        None => format!("{count} {what}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_configuration_file;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn skipped_files_are_counted() {
+        let folder = TempFolder::new("stats-files");
+        std::fs::create_dir_all(folder.join(".git")).unwrap();
+        std::fs::write(
+            folder.join("release.md"),
+            "# Release\n\n## Description\n\nLABEL=\n\n## Todo list\n\n* [ ] tag\n",
+        )
+        .unwrap();
+        std::fs::write(folder.join("notes.md"), "# Notes\n\nLong text\n").unwrap();
+        std::fs::write(folder.join("draft.txt"), "no title\n").unwrap();
+        std::fs::write(folder.join("photo.jpg"), [0xff, 0xd8]).unwrap();
+        std::fs::write(folder.join(".git").join("HEAD"), "ref\n").unwrap();
+        let ctx = Context {
+            name: "work".to_string(),
+            folder_location: folder.location(),
+            ..Default::default()
+        };
+
+        let mut files = files(&ctx).unwrap();
+        files.skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut stdout = vec![];
+        print_files(&mut stdout, &ctx, &files, true).unwrap();
+        let folder = folder.location();
+        assert_eq!(
+            String::from_utf8(stdout)
+                .unwrap()
+                .replace(folder.as_str(), "F"),
+            "work: 1 Todo lists, 3 skipped (1 not Todo lists, 1 unparsable, 1 other extension)
+\tF/draft.txt (unparsable)
+\tF/notes.md (not a Todo list)
+\tF/photo.jpg (other extension)
+"
+        );
+    }
+
+    #[test]
+    fn usage_counts_subcommands_and_contexts() {
+        let mut usage = Usage::default();
+        usage.record("list", "work", "2024-05-01");
+        usage.record("list", "work", "2024-05-02");
+        usage.record("edit", "work", "2024-05-03");
+        let raw = toml::to_string(&usage).unwrap();
+        assert_eq!(toml::from_str::<Usage>(raw.as_str()).unwrap(), usage);
+
+        let config = parse_configuration_file(
+            None,
+            Some(
+                "active_ctx_name = \"work\"\n\n\
+[[ctxs]]\nname = \"work\"\nide = \"\"\ntimezone = \"\"\nfolder_location = \"\"\ndate_format = \"%d.%m.%Y\"\n\n\
+[[ctxs]]\nname = \"old\"\nide = \"\"\ntimezone = \"\"\nfolder_location = \"\"\n",
+            ),
+        )
+        .unwrap();
+        assert!(!config.records_usage());
+        let mut stdout = vec![];
+        print_usage(&mut stdout, &usage, &config).unwrap();
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Subcommands:\n2\t- list\n1\t- edit\nContexts:\n3\t- work (last used 03.05.2024)\n0\t- old (never used)\n"
+        );
+    }
+}
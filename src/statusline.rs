@@ -0,0 +1,35 @@
+//! Print the active Todo context for shell prompts
+//!
+//! Only the Todo contexts which can be active are parsed from the configuration file so the command
+//! is cheap enough to run for every prompt.
+use crate::Context;
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::trace;
+
+/// Returns the Statusline command
+pub fn statusline_command() -> App<'static, 'static> {
+    App::new("statusline")
+        .about("Print the active Todo context, for shell prompts")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Printed text where {name} and {folder} are replaced by the active context's")
+                .default_value("{name}")
+                .takes_value(true),
+        )
+}
+
+/// Prints the active Todo context
+pub fn statusline_command_process(args: &ArgMatches, ctx: &Context) -> Result<(), std::io::Error> {
+    trace!("statusline subcommand");
+    println!("{}", statusline(args.value_of("format").unwrap(), ctx));
+    Ok(())
+}
+
+fn statusline(format: &str, ctx: &Context) -> String {
+    format
+        .replace("{name}", ctx.name.as_str())
+        .replace("{folder}", ctx.folder_location.as_str())
+}
@@ -0,0 +1,94 @@
+//! Read the Todo lists of a Todo context, whatever its storage
+//!
+//! [`TodoStore`] is meant for programs built on top of this crate (dashboards, widgets...) which
+//! read Todo lists without going through the subcommands.
+use crate::pin::same_title;
+use crate::single_file::SingleFile;
+use crate::{read_todo_lists, Context, TodoList};
+use std::path::Path;
+
+/// Todo lists of a Todo context, stored as one file per Todo list or inside a single file
+#[derive(Clone, Debug)]
+pub struct TodoStore {
+    ctx: Context,
+}
+
+impl TodoStore {
+    pub fn new(ctx: Context) -> TodoStore {
+        TodoStore { ctx }
+    }
+
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+
+    /// Returns all Todo lists of the Todo context. A missing Todo folder holds no Todo list.
+    ///
+    /// Files without a title are skipped.
+    pub fn todo_lists(&self) -> Result<Vec<TodoList>, std::io::Error> {
+        let todo_raws = if self.ctx.is_single_file() {
+            SingleFile::read(&self.ctx)?.todo_lists
+        } else if Path::new(self.ctx.folder_location.as_str()).is_dir() {
+            read_todo_lists(&self.ctx)?
+                .into_iter()
+                .map(|(_, todo_raw)| todo_raw)
+                .collect()
+        } else {
+            vec![]
+        };
+        Ok(todo_raws
+            .iter()
+            .filter_map(|todo_raw| TodoList::parse(todo_raw).ok())
+            .collect())
+    }
+
+    /// Returns the Todo list titled `title` (case insensitive)
+    pub fn todo_list(&self, title: &str) -> Result<TodoList, std::io::Error> {
+        self.todo_lists()?
+            .into_iter()
+            .find(|todo_list| same_title(todo_list.title(), title))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No Todo list titled \"{title}\""),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn read_todo_lists_of_folder() {
+        let root = TempFolder::new("store");
+        std::fs::write(
+            root.join("project.md"),
+            "# Project\n\n## Description\n\nLABEL=work\nShip it\n\n## Todo list\n\n* [x] a\n* [ ] b\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("notes.txt"), "no title\n").unwrap();
+        let store = TodoStore::new(Context {
+            folder_location: root.location(),
+            ..Default::default()
+        });
+
+        let todo_list = store.todo_list("project").unwrap();
+        assert_eq!(todo_list.title(), "Project");
+        assert_eq!(todo_list.description(), "Ship it");
+        assert_eq!(todo_list.labels(), ["work"]);
+        let tasks = todo_list
+            .tasks()
+            .iter()
+            .map(|t| (t.done, t.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(tasks, [(true, "a"), (false, "b")]);
+        assert_eq!(store.todo_lists().unwrap().len(), 1);
+        assert_eq!(
+            store.todo_list("unknown").unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Summarise the last week or day of every Todo context as a report for team updates
+//!
+//! The report is rendered from a markdown template stored next to the configuration file inside
+//! `.todo_templates/` (`.todo_templates/summary.md` for `--template summary`), or from a built-in
+//! template when the `summary` template does not exist. Placeholders of the template:
+//!
+//! * `{{title}}`: "Weekly summary" or "Daily summary"
+//! * `{{period}}`: first and last day of the period
+//! * `{{completed}}`: tasks checked during the period, by Todo context (from git history of
+//!   git-backed Todo contexts)
+//! * `{{new_lists}}`: Todo lists created during the period (from git history)
+//! * `{{overdue}}`: open tasks due (`@due(YYYY-MM-DD)`) before today
+//!
+//! `--html` converts the rendered report into HTML.
+use crate::activity::{checked_tasks, created_todo_lists};
+use crate::parse::{parse_due_date, parse_tasks};
+use crate::release::templates_folder;
+use crate::single_file::{title_of, SingleFile};
+use crate::{read_todo_lists, Configuration, Context};
+use chrono::{Duration, NaiveDate};
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{trace, warn};
+use std::path::Path;
+
+/// Template used when no `summary` template exists
+const DEFAULT_SUMMARY_TEMPLATE: &str = "# {{title}}
+
+{{period}}
+
+## Completed
+
+{{completed}}
+
+## New Todo lists
+
+{{new_lists}}
+
+## Overdue
+
+{{overdue}}
+";
+
+/// What happened within a Todo context during the period
+#[derive(Debug, Default)]
+struct ContextSummary {
+    name: String,
+    /// Title and text of checked tasks, None without git history
+    completed: Option<Vec<(String, String)>>,
+    /// Titles of Todo lists created during the period
+    new_lists: Vec<String>,
+    /// Title, text and due date of open tasks past due
+    overdue: Vec<(String, String, NaiveDate)>,
+}
+
+/// Returns the Summary command
+pub fn summary_command() -> App<'static, 'static> {
+    App::new("summary")
+        .about("Summarise completed tasks, new Todo lists and overdue tasks of every Todo context as markdown")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("week")
+                .long("week")
+                .help("Summarise the last week (default)"),
+        )
+        .arg(
+            Arg::with_name("day")
+                .long("day")
+                .help("Summarise the last day")
+                .conflicts_with("week"),
+        )
+        .arg(
+            Arg::with_name("html")
+                .long("html")
+                .help("Print the summary as HTML instead of markdown"),
+        )
+        .arg(
+            Arg::with_name("template")
+                .long("template")
+                .value_name("TEMPLATE")
+                .help("Template inside .todo_templates/ next to the configuration file")
+                .default_value("summary")
+                .takes_value(true),
+        )
+}
+
+/// Prints the summary of every Todo context of the configuration
+pub fn summary_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    trace!("summary subcommand");
+    let template_name = args.value_of("template").unwrap();
+    let template_path =
+        templates_folder(todo_configuration_path).join(format!("{template_name}.md"));
+    let template = match std::fs::read_to_string(&template_path) {
+        Ok(template) => template,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && template_name == "summary" => {
+            DEFAULT_SUMMARY_TEMPLATE.to_string()
+        }
+        Err(e) => {
+            return Err(std::io::Error::new(
+                e.kind(),
+                format!(
+                    "Template {} could not be read: {e}",
+                    template_path.display()
+                ),
+            ))
+        }
+    };
+
+    let weekly = !args.is_present("day");
+    let today = chrono::Local::today().naive_local();
+    let mut summaries = vec![];
+    for ctx in &config.ctxs {
+        if !Path::new(ctx.folder_location.as_str()).is_dir() {
+            continue;
+        }
+        summaries.push(summarise(ctx, weekly, today)?);
+    }
+    let date_format = config.date_format(config.active_ctx());
+    let summary = render(
+        template.as_str(),
+        weekly,
+        today,
+        date_format.as_str(),
+        &summaries,
+    );
+    if args.is_present("html") {
+        print!("{}", to_html(summary.as_str()));
+    } else {
+        print!("{summary}");
+    }
+    Ok(())
+}
+
+/// Returns what happened within the Todo context during the last week or day
+fn summarise(
+    ctx: &Context,
+    weekly: bool,
+    today: NaiveDate,
+) -> Result<ContextSummary, std::io::Error> {
+    let folder = ctx.folder_location.as_str();
+    let since = if weekly { "1w" } else { "1d" };
+    let todo_raws = if ctx.is_single_file() {
+        SingleFile::read(ctx)?.todo_lists
+    } else {
+        read_todo_lists(ctx)?
+            .into_iter()
+            .map(|(_, todo_raw)| todo_raw)
+            .collect()
+    };
+    let mut summary = ContextSummary {
+        name: ctx.name.clone(),
+        ..Default::default()
+    };
+    match checked_tasks(ctx, since) {
+        Ok(checked) => summary.completed = Some(checked),
+        Err(e) => warn!("no git history for {folder}: {e}"),
+    }
+    // a single file holds every Todo list, so git history cannot tell which one was created
+    if !ctx.is_single_file() {
+        let titles = todo_raws.iter().map(|t| title_of(t)).collect::<Vec<_>>();
+        for title in created_todo_lists(ctx, since).unwrap_or_default() {
+            // lists created then deleted during the period are not notable
+            if titles.contains(&title.as_str()) && !summary.new_lists.contains(&title) {
+                summary.new_lists.push(title);
+            }
+        }
+    }
+    for todo_raw in &todo_raws {
+        for task in parse_tasks(todo_raw).into_iter().filter(|t| !t.done) {
+            if let Some(due) = parse_due_date(task.text.as_str()).filter(|due| *due < today) {
+                summary
+                    .overdue
+                    .push((title_of(todo_raw).to_string(), task.text, due));
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Returns the markdown summary of Todo contexts rendered from `template`
+fn render(
+    template: &str,
+    weekly: bool,
+    today: NaiveDate,
+    date_format: &str,
+    summaries: &[ContextSummary],
+) -> String {
+    let first_day = today - Duration::days(if weekly { 7 } else { 1 });
+    let mut completed = String::new();
+    for summary in summaries {
+        let checked = match &summary.completed {
+            Some(checked) if !checked.is_empty() => checked,
+            _ => continue,
+        };
+        completed.push_str(format!("### {}\n\n", summary.name).as_str());
+        for (title, task) in checked {
+            completed.push_str(format!("- {title}: {task}\n").as_str());
+        }
+        completed.push('\n');
+    }
+    if completed.is_empty() {
+        completed.push_str("Nothing completed.\n");
+    }
+    let without_history = summaries
+        .iter()
+        .filter(|s| s.completed.is_none())
+        .map(|s| s.name.as_str())
+        .collect::<Vec<_>>();
+    if !without_history.is_empty() {
+        completed.push_str(
+            format!(
+                "{}No git history for: {}\n",
+                if completed.ends_with("\n\n") {
+                    ""
+                } else {
+                    "\n"
+                },
+                without_history.join(", ")
+            )
+            .as_str(),
+        );
+    }
+
+    let mut new_lists = summaries
+        .iter()
+        .flat_map(|s| {
+            s.new_lists
+                .iter()
+                .map(move |t| format!("- {t} ({})\n", s.name))
+        })
+        .collect::<String>();
+    if new_lists.is_empty() {
+        new_lists.push_str("None.\n");
+    }
+
+    let mut overdue = summaries
+        .iter()
+        .flat_map(|s| s.overdue.iter().map(move |o| (o, s.name.as_str())))
+        .collect::<Vec<_>>();
+    overdue.sort_by_key(|((_, _, due), _)| *due);
+    let mut overdue = overdue
+        .into_iter()
+        .map(|((title, task, due), name)| format!("- {title}: {task} (due {due}, {name})\n"))
+        .collect::<String>();
+    if overdue.is_empty() {
+        overdue.push_str("None.\n");
+    }
+
+    template
+        .replace(
+            "{{title}}",
+            if weekly {
+                "Weekly summary"
+            } else {
+                "Daily summary"
+            },
+        )
+        .replace(
+            "{{period}}",
+            format!(
+                "{} to {}",
+                first_day.format(date_format),
+                today.format(date_format)
+            )
+            .as_str(),
+        )
+        .replace("{{completed}}", completed.trim_end())
+        .replace("{{new_lists}}", new_lists.trim_end())
+        .replace("{{overdue}}", overdue.trim_end())
+}
+
+/// Returns HTML of the markdown summary. Only headings, bullet points and paragraphs are
+/// converted.
+fn to_html(markdown: &str) -> String {
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    let mut html = String::new();
+    let mut in_list = false;
+    for line in markdown.lines() {
+        let item = line.strip_prefix("- ");
+        if in_list && item.is_none() {
+            html.push_str("</ul>\n");
+            in_list = false;
+        }
+        if let Some(item) = item {
+            if !in_list {
+                html.push_str("<ul>\n");
+                in_list = true;
+            }
+            html.push_str(format!("<li>{}</li>\n", escape(item)).as_str());
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            html.push_str(format!("<h3>{}</h3>\n", escape(heading)).as_str());
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            html.push_str(format!("<h2>{}</h2>\n", escape(heading)).as_str());
+        } else if let Some(heading) = line.strip_prefix("# ") {
+            html.push_str(format!("<h1>{}</h1>\n", escape(heading)).as_str());
+        } else if !line.trim().is_empty() {
+            html.push_str(format!("<p>{}</p>\n", escape(line)).as_str());
+        }
+    }
+    if in_list {
+        html.push_str("</ul>\n");
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_summary() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let summaries = vec![
+            ContextSummary {
+                name: "work".to_string(),
+                completed: Some(vec![("Release".to_string(), "tag".to_string())]),
+                new_lists: vec!["Release".to_string()],
+                overdue: vec![(
+                    "Release".to_string(),
+                    "announce @due(2026-10-01)".to_string(),
+                    day("2026-10-01"),
+                )],
+            },
+            ContextSummary {
+                name: "home".to_string(),
+                ..Default::default()
+            },
+        ];
+        let summary = render(
+            DEFAULT_SUMMARY_TEMPLATE,
+            true,
+            day("2026-10-15"),
+            "%Y-%m-%d",
+            &summaries,
+        );
+        assert_eq!(
+            summary,
+            "# Weekly summary
+
+2026-10-08 to 2026-10-15
+
+## Completed
+
+### work
+
+- Release: tag
+
+No git history for: home
+
+## New Todo lists
+
+- Release (work)
+
+## Overdue
+
+- Release: announce @due(2026-10-01) (due 2026-10-01, work)
+"
+        );
+        assert_eq!(
+            to_html("# Weekly summary\n\n## Overdue\n\n- a <b>\n- c\n\nNone.\n"),
+            "<h1>Weekly summary</h1>\n<h2>Overdue</h2>\n<ul>\n<li>a &lt;b&gt;</li>\n<li>c</li>\n</ul>\n<p>None.</p>\n"
+        );
+        assert_eq!(
+            render("{{period}}", false, day("2026-10-15"), "%d.%m.%Y", &[]),
+            "14.10.2026 to 15.10.2026"
+        );
+    }
+}
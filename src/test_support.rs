@@ -0,0 +1,123 @@
+//! Test support: Todo contexts held in memory and commands run against them
+//!
+//! Commands which accept Todo lists already read (such as `todo list` through
+//! [`crate::list::list_entries_process`]) are run against an in-memory storage instead of Todo
+//! folders, and their output is returned as text for snapshot tests (see `insta`). Tests which
+//! need actual files use a [`TempFolder`].
+use crate::list::{list_command, list_entries_process};
+use crate::{Configuration, Context};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Todo contexts with their Todo lists, held in memory
+#[derive(Debug, Default)]
+pub(crate) struct MemoryStorage {
+    ctxs: Vec<(Context, Vec<String>)>,
+}
+
+impl MemoryStorage {
+    pub(crate) fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+
+    /// Adds Todo context `name` holding `todo_lists`. The first Todo context added is active.
+    pub(crate) fn context(mut self, name: &str, todo_lists: &[&str]) -> MemoryStorage {
+        let ctx = Context {
+            name: name.to_string(),
+            folder_location: format!("memory/{name}"),
+            ..Default::default()
+        };
+        self.ctxs
+            .push((ctx, todo_lists.iter().map(|t| t.to_string()).collect()));
+        self
+    }
+
+    /// Returns the configuration of the Todo contexts
+    pub(crate) fn configuration(&self) -> Configuration {
+        Configuration {
+            active_ctx_name: self
+                .ctxs
+                .first()
+                .map(|(ctx, _)| ctx.name.clone())
+                .unwrap_or_default(),
+            ctxs: self.ctxs.iter().map(|(ctx, _)| ctx.clone()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the Todo lists of each Todo context, in the order of the configuration
+    pub(crate) fn entries(&self) -> Vec<Vec<&str>> {
+        self.ctxs
+            .iter()
+            .map(|(_, todo_lists)| todo_lists.iter().map(|t| t.as_str()).collect())
+            .collect()
+    }
+
+    /// Returns the output of `todo list` with `args`
+    pub(crate) fn list(&self, args: &[&str]) -> String {
+        let args =
+            list_command().get_matches_from(std::iter::once("list").chain(args.iter().copied()));
+        let mut stdout = vec![];
+        list_entries_process(&args, &self.configuration(), self.entries(), &mut stdout)
+            .expect("todo list failed");
+        String::from_utf8(stdout).expect("output is not UTF-8")
+    }
+}
+
+/// Returns a Todo list titled `title` with `labels` and `tasks` (one line each)
+pub(crate) fn todo_list(title: &str, labels: &[&str], tasks: &[&str]) -> String {
+    format!(
+        "# {title}\n\n## Description\n\nLABEL={}\n\n## Todo list\n\n{}\n",
+        labels.join(","),
+        tasks.join("\n")
+    )
+}
+
+/// Empty folder inside the temporary directory, removed with its content when dropped (even when
+/// the test fails)
+#[derive(Debug)]
+pub(crate) struct TempFolder {
+    path: PathBuf,
+}
+
+impl TempFolder {
+    /// Creates an empty folder whose name starts with `todo-{name}`, unique to this test run
+    pub(crate) fn new(name: &str) -> TempFolder {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "todo-{name}-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        // left over by a test run which was killed
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("temporary folder was not created");
+        TempFolder { path }
+    }
+
+    /// Returns the path of the folder as a string, such as the `folder_location` of a Todo context
+    pub(crate) fn location(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+}
+
+impl Deref for TempFolder {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempFolder {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFolder {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
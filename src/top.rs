@@ -0,0 +1,226 @@
+//! Tell where the mess is with `todo top`
+//!
+//! `todo top` lists the Todo lists with the most open tasks, the most overdue tasks (open tasks
+//! due before today, see `@due(YYYY-MM-DD)`) and the ones left untouched for the longest time.
+//! Todo lists of the active Todo context are ranked unless `--ctx` or `--global` selects others.
+//! Archived Todo lists and Todo contexts whose folder is not healthy (see [`crate::health`]) are
+//! left out.
+use crate::health::{check, Health};
+use crate::parse::{parse_due_date, parse_tasks, parse_todo_list};
+use crate::single_file::SingleFile;
+use crate::{read_todo_lists, Configuration, Context, ARCHIVE_FOLDER};
+use chrono::NaiveDate;
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::trace;
+use std::cmp::Reverse;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Number of Todo lists of each ranking by default
+const DEFAULT_NUMBER: &str = "5";
+
+/// Todo list ranked by `todo top`
+#[derive(Debug, PartialEq)]
+struct Ranked {
+    title: String,
+    ctx_name: String,
+    open: usize,
+    overdue: usize,
+    /// Days since the Todo list file was last modified
+    untouched_days: u64,
+}
+
+/// Returns the Top command
+pub fn top_command() -> App<'static, 'static> {
+    App::new("top")
+        .about("Show Todo lists with the most open tasks, the most overdue tasks and untouched for the longest time")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("number")
+                .short("n")
+                .long("number")
+                .value_name("N")
+                .help("Number of Todo lists of each ranking")
+                .default_value(DEFAULT_NUMBER)
+                .validator(|n| {
+                    n.parse::<usize>()
+                        .map(|_| ())
+                        .map_err(|_| format!("\"{n}\" is not a number of Todo lists"))
+                })
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("context name")
+                .short("c")
+                .long("ctx")
+                .value_name("CONTEXT")
+                .help("Ranks Todo lists of this context, repeat for more (active context by default)")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("global")
+                .short("g")
+                .long("global")
+                .help("Ranks Todo lists of all contexts")
+                .conflicts_with("context name"),
+        )
+}
+
+/// Prints the Todo lists of selected Todo contexts which need attention the most
+pub fn top_command_process(
+    args: &ArgMatches,
+    config: &Configuration,
+) -> Result<(), std::io::Error> {
+    trace!("top subcommand");
+    let number = args.value_of("number").unwrap().parse::<usize>().unwrap();
+    let names = match args.values_of("context name") {
+        Some(names) => names.collect::<Vec<_>>(),
+        None if args.is_present("global") => config.ctxs.iter().map(|c| c.name.as_str()).collect(),
+        None => vec![config.active_ctx_name.as_str()],
+    };
+    let today = chrono::Local::today().naive_local();
+    let mut ranked = vec![];
+    for name in names {
+        let ctx = config.ctx(name)?;
+        match check(ctx) {
+            Health::Ok => ranked.extend(rank(ctx, today)?),
+            health => eprintln!("Warning: Todo context {} is skipped: {health}", ctx.name),
+        }
+    }
+    print_top(&mut std::io::stdout(), &mut ranked, number)
+}
+
+/// Returns the Todo lists of the Todo context, outside its archive, with their open tasks, overdue
+/// tasks and age
+fn rank(ctx: &Context, today: NaiveDate) -> Result<Vec<Ranked>, std::io::Error> {
+    let todo_lists = if ctx.is_single_file() {
+        let single_file = SingleFile::read(ctx)?;
+        single_file
+            .todo_lists
+            .into_iter()
+            .map(|todo_raw| (single_file.path.clone(), todo_raw))
+            .collect()
+    } else {
+        let archive_folder = Path::new(ctx.folder_location.as_str()).join(ARCHIVE_FOLDER);
+        read_todo_lists(ctx)?
+            .into_iter()
+            .filter(|(filepath, _)| !Path::new(filepath).starts_with(&archive_folder))
+            .collect::<Vec<_>>()
+    };
+    let now = SystemTime::now();
+    let mut ranked = vec![];
+    for (filepath, todo_raw) in todo_lists {
+        let todo_list = match parse_todo_list(todo_raw.as_str()) {
+            Ok(todo_list) => todo_list,
+            Err(_) => continue,
+        };
+        let open_tasks = parse_tasks(todo_raw.as_str())
+            .into_iter()
+            .filter(|t| !t.done)
+            .collect::<Vec<_>>();
+        let modified = std::fs::metadata(&filepath)?.modified()?;
+        ranked.push(Ranked {
+            title: todo_list.title,
+            ctx_name: ctx.name.clone(),
+            open: open_tasks.len(),
+            overdue: open_tasks
+                .iter()
+                .filter(|t| parse_due_date(t.text.as_str()).is_some_and(|due| due < today))
+                .count(),
+            untouched_days: now
+                .duration_since(modified)
+                .map_or(0, |age| age.as_secs() / 86400),
+        });
+    }
+    Ok(ranked)
+}
+
+/// Prints the `number` Todo lists with the most open tasks, the most overdue tasks and untouched
+/// for the longest time. Todo lists without open or overdue tasks are not ranked for them.
+fn print_top(
+    stdout: &mut dyn std::io::Write,
+    ranked: &mut [Ranked],
+    number: usize,
+) -> Result<(), std::io::Error> {
+    // ties are broken by title
+    ranked.sort_by(|a, b| a.title.cmp(&b.title));
+
+    writeln!(stdout, "Most open tasks:")?;
+    ranked.sort_by_key(|r| Reverse(r.open));
+    print_ranking(stdout, ranked, number, |r| {
+        (r.open > 0).then(|| format!("{} open", r.open))
+    })?;
+
+    writeln!(stdout, "Most overdue tasks:")?;
+    ranked.sort_by_key(|r| Reverse(r.overdue));
+    print_ranking(stdout, ranked, number, |r| {
+        (r.overdue > 0).then(|| format!("{} overdue", r.overdue))
+    })?;
+
+    writeln!(stdout, "Untouched for the longest time:")?;
+    ranked.sort_by_key(|r| Reverse(r.untouched_days));
+    print_ranking(stdout, ranked, number, |r| {
+        Some(format!("{} days", r.untouched_days))
+    })
+}
+
+/// Prints the first `number` Todo lists having a measure
+fn print_ranking(
+    stdout: &mut dyn std::io::Write,
+    ranked: &[Ranked],
+    number: usize,
+    measure: impl Fn(&Ranked) -> Option<String>,
+) -> Result<(), std::io::Error> {
+    let mut printed = 0;
+    for (r, measure) in ranked
+        .iter()
+        .filter_map(|r| measure(r).map(|m| (r, m)))
+        .take(number)
+    {
+        writeln!(stdout, "{measure}\t- {} ({})", r.title, r.ctx_name)?;
+        printed += 1;
+    }
+    if printed == 0 {
+        writeln!(stdout, "None.")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rank_todo_lists() {
+        let ranked = |title: &str, open, overdue, untouched_days| Ranked {
+            title: title.to_string(),
+            ctx_name: "work".to_string(),
+            open,
+            overdue,
+            untouched_days,
+        };
+        let mut todo_lists = vec![
+            ranked("release", 4, 0, 2),
+            ranked("groceries", 1, 1, 30),
+            ranked("taxes", 4, 2, 90),
+            ranked("done", 0, 0, 400),
+        ];
+        let mut stdout = vec![];
+        print_top(&mut stdout, &mut todo_lists, 2).unwrap();
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Most open tasks:
+4 open\t- release (work)
+4 open\t- taxes (work)
+Most overdue tasks:
+2 overdue\t- taxes (work)
+1 overdue\t- groceries (work)
+Untouched for the longest time:
+400 days\t- done (work)
+90 days\t- taxes (work)
+"
+        );
+    }
+}
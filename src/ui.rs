@@ -0,0 +1,491 @@
+//! Browse Todo contexts and check off tasks from a terminal UI with `todo ui`
+//!
+//! Todo contexts are shown in a sidebar, the Todo lists of the selected Todo context in a panel and
+//! the tasks of the selected Todo list next to it. Arrow keys (or `hjkl`) move around, space (or
+//! `x`) checks or unchecks the selected task and `q` quits. Each edit is saved right away: only the
+//! checkbox of the task changes in the Todo list file (see [`crate::document`]).
+//!
+//! Locked Todo lists are shown but cannot be edited. Archived Todo lists are left out.
+use crate::archive::archive_folder;
+use crate::document::{Document, Kind};
+use crate::health::{check, Health};
+use crate::lock::is_locked;
+use crate::parts::Parts;
+use crate::single_file::SingleFile;
+use crate::{read_todo_lists, Configuration, Context};
+use clap::{crate_authors, App, ArgMatches};
+use console::{pad_str, style, Alignment, Key, Term};
+use log::{debug, trace};
+use std::io::IsTerminal;
+use std::path::Path;
+
+/// Width of the sidebar of Todo contexts
+const CONTEXTS_WIDTH: usize = 18;
+/// Width of the panel of Todo lists
+const TODO_LISTS_WIDTH: usize = 28;
+/// Keys shown at the bottom of the screen
+const HELP: &str = "←→ switch pane  ↑↓ select  space check/uncheck  q quit";
+
+/// Pane which has the keyboard focus
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Pane {
+    Contexts,
+    TodoLists,
+    Tasks,
+}
+
+/// Where a shown Todo list is stored
+#[derive(Debug, PartialEq)]
+enum Location {
+    /// Todo list file (and its parts)
+    File(String),
+    /// Position of the Todo list inside the single file of the Todo context
+    SingleFile(usize),
+}
+
+/// Todo list shown by the UI
+#[derive(Debug)]
+struct Shown {
+    location: Location,
+    /// Todo list as it was read, to notice changes made meanwhile
+    raw: String,
+    document: Document,
+}
+
+impl Shown {
+    fn new(location: Location, raw: String) -> Shown {
+        Shown {
+            location,
+            document: Document::parse(raw.as_str()),
+            raw,
+        }
+    }
+}
+
+/// State of the terminal UI
+struct Ui<'a> {
+    config: &'a Configuration,
+    focus: Pane,
+    /// Index of the selected Todo context
+    ctx: usize,
+    /// Todo lists of the selected Todo context
+    todo_lists: Vec<Shown>,
+    /// Index of the selected Todo list
+    todo_list: usize,
+    /// Index of the selected task of the selected Todo list
+    task: usize,
+    /// Message shown above the keys
+    status: String,
+}
+
+/// Returns the Ui command
+pub fn ui_command() -> App<'static, 'static> {
+    App::new("ui")
+        .about("Browse Todo contexts and check off tasks from a terminal UI")
+        .author(crate_authors!())
+}
+
+/// Runs the terminal UI until the user quits
+pub fn ui_command_process(args: &ArgMatches, config: &Configuration) -> Result<(), std::io::Error> {
+    trace!("ui subcommand");
+    if args.is_present("no-input") || !std::io::stdin().is_terminal() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "todo ui needs a terminal to read keys from",
+        ));
+    }
+    let term = Term::stdout();
+    if !term.is_term() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "todo ui needs a terminal to draw on",
+        ));
+    }
+    let mut ui = Ui::new(config);
+    term.hide_cursor()?;
+    let outcome = run(&term, &mut ui);
+    // the terminal is restored even when drawing or reading keys failed
+    term.clear_screen()?;
+    term.show_cursor()?;
+    outcome
+}
+
+/// Draws the UI and handles keys until the user quits
+fn run(term: &Term, ui: &mut Ui) -> Result<(), std::io::Error> {
+    loop {
+        let (height, width) = term.size();
+        term.clear_screen()?;
+        term.write_str(
+            ui.render(usize::from(width), usize::from(height))
+                .join("\n")
+                .as_str(),
+        )?;
+        let key = term.read_key()?;
+        if !ui.handle(key) {
+            return Ok(());
+        }
+    }
+}
+
+impl<'a> Ui<'a> {
+    /// Returns the UI showing the active Todo context
+    fn new(config: &'a Configuration) -> Ui<'a> {
+        let mut ui = Ui {
+            config,
+            focus: Pane::TodoLists,
+            ctx: config
+                .ctxs
+                .iter()
+                .position(|c| c.name == config.active_ctx_name)
+                .unwrap_or(0),
+            todo_lists: vec![],
+            todo_list: 0,
+            task: 0,
+            status: String::new(),
+        };
+        ui.load();
+        ui
+    }
+
+    fn selected_ctx(&self) -> Option<&'a Context> {
+        self.config.ctxs.get(self.ctx)
+    }
+
+    /// Reads the Todo lists of the selected Todo context
+    fn load(&mut self) {
+        self.todo_lists.clear();
+        self.todo_list = 0;
+        self.task = 0;
+        self.status.clear();
+        let ctx = match self.selected_ctx() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        match read_shown(ctx) {
+            Ok(todo_lists) => self.todo_lists = todo_lists,
+            Err(e) => self.status = format!("Todo lists of {} cannot be read: {e}", ctx.name),
+        }
+    }
+
+    /// Handles the key. Returns false when the user quits.
+    fn handle(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char('q') | Key::Escape | Key::CtrlC => return false,
+            Key::ArrowUp | Key::Char('k') => self.select(-1),
+            Key::ArrowDown | Key::Char('j') => self.select(1),
+            Key::ArrowLeft | Key::Char('h') | Key::BackTab => {
+                self.focus = match self.focus {
+                    Pane::Tasks => Pane::TodoLists,
+                    _ => Pane::Contexts,
+                }
+            }
+            Key::ArrowRight | Key::Char('l') | Key::Tab | Key::Enter => {
+                self.focus = match self.focus {
+                    Pane::Contexts => Pane::TodoLists,
+                    _ => Pane::Tasks,
+                }
+            }
+            Key::Char(' ') | Key::Char('x') if self.focus == Pane::Tasks => {
+                self.status = match self.toggle() {
+                    Ok(status) => status,
+                    Err(e) => format!("Error: {e}"),
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Moves the selection of the focused pane by `delta`
+    fn select(&mut self, delta: isize) {
+        let moved =
+            |i: usize, len: usize| i.saturating_add_signed(delta).min(len.saturating_sub(1));
+        match self.focus {
+            Pane::Contexts => {
+                let ctx = moved(self.ctx, self.config.ctxs.len());
+                if ctx != self.ctx {
+                    self.ctx = ctx;
+                    self.load();
+                }
+            }
+            Pane::TodoLists => {
+                let todo_list = moved(self.todo_list, self.todo_lists.len());
+                if todo_list != self.todo_list {
+                    self.todo_list = todo_list;
+                    self.task = 0;
+                }
+            }
+            Pane::Tasks => {
+                let tasks = self
+                    .todo_lists
+                    .get(self.todo_list)
+                    .map_or(0, |s| s.document.tasks().len());
+                self.task = moved(self.task, tasks);
+            }
+        }
+    }
+
+    /// Checks or unchecks the selected task and saves the Todo list. Returns what was done.
+    fn toggle(&mut self) -> Result<String, std::io::Error> {
+        let ctx = match self.selected_ctx() {
+            Some(ctx) => ctx,
+            None => return Ok(String::new()),
+        };
+        let shown = match self.todo_lists.get_mut(self.todo_list) {
+            Some(shown) => shown,
+            None => return Ok(String::new()),
+        };
+        let line = match shown.document.tasks().get(self.task) {
+            Some(line) => *line,
+            None => return Ok(String::new()),
+        };
+        if is_locked(shown.raw.as_str()) {
+            return Ok("Todo list is locked, unlock it to edit it".to_string());
+        }
+        let done = matches!(shown.document.lines[line].kind, Kind::Task { done: false });
+        let mut document = shown.document.clone();
+        document.set_done(line, done);
+        let edited = document.to_string();
+        let changed_meanwhile = || {
+            Ok(
+                "Todo list was changed by something else meanwhile and is reloaded, try again"
+                    .to_string(),
+            )
+        };
+        match shown.location {
+            Location::File(ref path) => {
+                let mut parts = Parts::read(path.as_str())?;
+                if parts.joined() != shown.raw {
+                    self.load();
+                    return changed_meanwhile();
+                }
+                parts.set(edited.as_str())?;
+                parts.write()?;
+            }
+            Location::SingleFile(i) => {
+                let mut single_file = SingleFile::read(ctx)?;
+                if single_file.todo_lists.get(i) != Some(&shown.raw) {
+                    self.load();
+                    return changed_meanwhile();
+                }
+                single_file.todo_lists[i] = edited.clone();
+                single_file.write()?;
+            }
+        }
+        debug!("saved {:?}", shown.location);
+        let text = document.lines[line]
+            .task_text()
+            .unwrap_or_default()
+            .to_string();
+        shown.raw = edited;
+        shown.document = document;
+        Ok(format!(
+            "{} \"{text}\"",
+            if done { "Checked off" } else { "Unchecked" }
+        ))
+    }
+
+    /// Returns the lines of the screen
+    fn render(&self, width: usize, height: usize) -> Vec<String> {
+        let tasks_width = width.saturating_sub(CONTEXTS_WIDTH + TODO_LISTS_WIDTH + 2);
+        let rows = height.saturating_sub(4);
+        let ctxs = self
+            .config
+            .ctxs
+            .iter()
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>();
+        let titles = self
+            .todo_lists
+            .iter()
+            .map(|s| s.document.title().unwrap_or_default().to_string())
+            .collect::<Vec<_>>();
+        let tasks = match self.todo_lists.get(self.todo_list) {
+            Some(shown) => shown
+                .document
+                .tasks()
+                .into_iter()
+                .map(|i| {
+                    let line = &shown.document.lines[i];
+                    let checkbox = match line.kind {
+                        Kind::Task { done: true } => "[x]",
+                        _ => "[ ]",
+                    };
+                    format!("{checkbox} {}", line.task_text().unwrap_or_default())
+                })
+                .collect(),
+            None => vec![],
+        };
+        let ctxs = self.column(&ctxs, self.ctx, Pane::Contexts, CONTEXTS_WIDTH, rows);
+        let titles = self.column(
+            &titles,
+            self.todo_list,
+            Pane::TodoLists,
+            TODO_LISTS_WIDTH,
+            rows,
+        );
+        let tasks = self.column(&tasks, self.task, Pane::Tasks, tasks_width, rows);
+
+        let mut screen = vec![format!(
+            "{}│{}│{}",
+            pad_str("Contexts", CONTEXTS_WIDTH, Alignment::Left, None),
+            pad_str("Todo lists", TODO_LISTS_WIDTH, Alignment::Left, None),
+            "Tasks"
+        )];
+        screen.push(format!(
+            "{}┼{}┼{}",
+            "─".repeat(CONTEXTS_WIDTH),
+            "─".repeat(TODO_LISTS_WIDTH),
+            "─".repeat(tasks_width)
+        ));
+        for row in 0..rows {
+            screen.push(
+                format!("{}│{}│{}", ctxs[row], titles[row], tasks[row])
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+        screen.push(
+            pad_str(self.status.as_str(), width, Alignment::Left, Some("…"))
+                .trim_end()
+                .to_string(),
+        );
+        screen.push(
+            pad_str(HELP, width, Alignment::Left, Some("…"))
+                .trim_end()
+                .to_string(),
+        );
+        screen
+    }
+
+    /// Returns `rows` cells of `width` characters showing `items`, scrolled so the `selected` one
+    /// is visible. The selection stands out when `pane` has the focus.
+    fn column(
+        &self,
+        items: &[String],
+        selected: usize,
+        pane: Pane,
+        width: usize,
+        rows: usize,
+    ) -> Vec<String> {
+        let offset = (selected + 1).saturating_sub(rows);
+        (offset..offset + rows)
+            .map(|i| match items.get(i) {
+                Some(item) if i == selected => {
+                    let cell = pad_str(
+                        format!("> {item}").as_str(),
+                        width,
+                        Alignment::Left,
+                        Some("…"),
+                    )
+                    .to_string();
+                    if pane == self.focus {
+                        style(cell).reverse().to_string()
+                    } else {
+                        cell
+                    }
+                }
+                Some(item) => pad_str(
+                    format!("  {item}").as_str(),
+                    width,
+                    Alignment::Left,
+                    Some("…"),
+                )
+                .to_string(),
+                None => " ".repeat(width),
+            })
+            .collect()
+    }
+}
+
+/// Returns the Todo lists of the Todo context, outside its archive folder, by title
+fn read_shown(ctx: &Context) -> Result<Vec<Shown>, std::io::Error> {
+    let mut todo_lists = if ctx.is_single_file() {
+        SingleFile::read(ctx)?
+            .todo_lists
+            .into_iter()
+            .enumerate()
+            .map(|(i, raw)| Shown::new(Location::SingleFile(i), raw))
+            .collect::<Vec<_>>()
+    } else {
+        match check(ctx) {
+            Health::Ok => {}
+            health => return Err(std::io::Error::other(health.to_string())),
+        }
+        let archive_folder = archive_folder(ctx);
+        let mut todo_lists = vec![];
+        for (filepath, raw) in read_todo_lists(ctx)? {
+            if Path::new(filepath.as_str()).starts_with(&archive_folder) {
+                continue;
+            }
+            todo_lists.push(Shown::new(Location::File(filepath), raw));
+        }
+        todo_lists
+    };
+    todo_lists.retain(|s| s.document.title().is_some());
+    todo_lists.sort_by_key(|s| s.document.title().unwrap_or_default().to_lowercase());
+    Ok(todo_lists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_panes() {
+        let config = Configuration {
+            active_ctx_name: "work".to_string(),
+            ctxs: vec![
+                Context {
+                    name: "home".to_string(),
+                    ..Default::default()
+                },
+                Context {
+                    name: "work".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let mut ui = Ui {
+            config: &config,
+            focus: Pane::Contexts,
+            ctx: 1,
+            todo_lists: vec![
+                Shown::new(
+                    Location::File("release.md".to_string()),
+                    "# release\n\n## Todo list\n\n* [x] tag\n* [ ] announce\n".to_string(),
+                ),
+                Shown::new(
+                    Location::File("taxes.md".to_string()),
+                    "# taxes\n\n## Todo list\n\n* [ ] file\n".to_string(),
+                ),
+            ],
+            todo_list: 0,
+            task: 0,
+            status: String::new(),
+        };
+        assert!(ui.handle(Key::ArrowRight));
+        assert!(ui.handle(Key::ArrowRight));
+        assert!(ui.handle(Key::ArrowDown));
+        assert_eq!(ui.task, 1);
+        // the selection stays on the last task
+        assert!(ui.handle(Key::ArrowDown));
+        assert_eq!(ui.task, 1);
+
+        let screen = ui.render(70, 7);
+        assert_eq!(
+            screen,
+            vec![
+                "Contexts          │Todo lists                  │Tasks",
+                "──────────────────┼────────────────────────────┼──────────────────────",
+                "  home            │> release                   │  [x] tag",
+                "> work            │  taxes                     │> [ ] announce",
+                "                  │                            │",
+                "",
+                "←→ switch pane  ↑↓ select  space check/uncheck  q quit",
+            ]
+        );
+        assert!(!ui.handle(Key::Char('q')));
+    }
+}
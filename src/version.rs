@@ -0,0 +1,116 @@
+//! Build information for bug reports with `todo --version --json`
+//!
+//! Distribution packages may be built from any commit with any features. `todo --version --json`
+//! prints the crate version, the git commit and date of the build, the enabled features and the
+//! configuration file in use so bug reports tell which build they are about.
+//!
+//! Subcommands of features left out of the build (see the minimal profile in `README.md`) are
+//! reported as such instead of being run as `todo-NAME` executables.
+use crate::json::quote;
+use clap::Arg;
+
+/// Optional features of the build, see `Cargo.toml`
+const FEATURES: [(&str, bool); 7] = [
+    ("tui", cfg!(feature = "tui")),
+    ("sync", cfg!(feature = "sync")),
+    ("notifications", cfg!(feature = "notifications")),
+    ("scripting", cfg!(feature = "scripting")),
+    ("server", cfg!(feature = "server")),
+    ("git", cfg!(feature = "git")),
+    ("ssh", cfg!(feature = "ssh")),
+];
+
+/// Subcommands of optional features, with their feature
+const FEATURE_SUBCOMMANDS: [(&str, &str); 8] = [
+    ("batch", "scripting"),
+    ("caldav", "sync"),
+    ("client", "server"),
+    ("daemon", "server"),
+    ("digest", "notifications"),
+    ("githook", "git"),
+    ("script", "scripting"),
+    ("ui", "tui"),
+];
+
+/// Returns the argument asking for JSON output of `--version`
+pub fn json_version_arg() -> Arg<'static, 'static> {
+    Arg::with_name("json")
+        .long("json")
+        .help("With --version, prints build information as JSON")
+}
+
+/// Returns true if the command line asks for the version as JSON, which clap cannot tell since it
+/// prints the version as soon as it reads `--version`
+pub fn wants_json_version(args: &[String]) -> bool {
+    let args = args.iter().skip(1).take_while(|a| *a != "--");
+    let (mut version, mut json) = (false, false);
+    for arg in args {
+        version |= arg == "--version" || arg == "-V";
+        json |= arg == "--json";
+    }
+    version && json
+}
+
+/// Returns the feature `subcommand` belongs to when the build left it out
+pub fn missing_feature(subcommand: &str) -> Option<&'static str> {
+    let (_, feature) = FEATURE_SUBCOMMANDS
+        .iter()
+        .find(|(name, _)| *name == subcommand)?;
+    let (_, enabled) = FEATURES.iter().find(|(name, _)| name == feature)?;
+    (!enabled).then_some(*feature)
+}
+
+/// Returns the build information as a JSON object. `config_path` is the configuration file in use.
+pub fn version_json(config_path: Option<&str>) -> String {
+    let optional = |value: &str| {
+        if value.is_empty() {
+            "null".to_string()
+        } else {
+            quote(value)
+        }
+    };
+    let features = FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| quote(name))
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"version\":{},\"git_commit\":{},\"build_date\":{},\"features\":[{}],\"config_path\":{}}}",
+        quote(env!("CARGO_PKG_VERSION")),
+        optional(env!("TODO_GIT_COMMIT")),
+        optional(env!("TODO_BUILD_DATE")),
+        features.join(","),
+        optional(config_path.unwrap_or_default()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_as_json() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert!(wants_json_version(&args(&["todo", "--version", "--json"])));
+        assert!(wants_json_version(&args(&[
+            "todo", "--json", "-p", "x", "-V"
+        ])));
+        assert!(!wants_json_version(&args(&["todo", "--version"])));
+        assert!(!wants_json_version(&args(&[
+            "todo", "search", "--json", "--", "-V"
+        ])));
+
+        let json = version_json(Some("/home/me/.todo"));
+        assert!(
+            json.starts_with(format!("{{\"version\":\"{}\",", env!("CARGO_PKG_VERSION")).as_str())
+        );
+        assert!(json.ends_with(",\"config_path\":\"/home/me/.todo\"}"));
+        assert!(version_json(None).ends_with(",\"config_path\":null}"));
+
+        assert_eq!(
+            missing_feature("ui"),
+            (!cfg!(feature = "tui")).then_some("tui")
+        );
+        assert_eq!(missing_feature("list"), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! Save combinations of list arguments as named views
+//!
+//! Views are stored inside the configuration file (`[views]` table) and are shared by all Todo
+//! contexts. Each Todo context can set the view run by a bare `todo view` with `default_view`.
+//!
+//! `todo view TITLE` renders the Todo list titled TITLE for reading (see [`crate::render`]) when no
+//! view is named TITLE.
+use crate::config_file::write_configuration;
+use crate::list::{list_command, list_command_process};
+use crate::parse::parse_configuration_file;
+use crate::pin::same_title;
+use crate::render::{output_width, render, use_color, width_arg, DEFAULT_WIDTH};
+use crate::single_file::{title_of, SingleFile};
+use crate::{read_todo_lists, Configuration, Context};
+use clap::{crate_authors, App, AppSettings, Arg, ArgMatches};
+use log::{debug, trace, warn};
+use std::path::Path;
+
+/// Returns view command which runs a saved view or manages views with its subcommands
+pub fn view_command() -> App<'static, 'static> {
+    App::new("view")
+        .about("Run and manage saved list views, or read a todo list rendered")
+        .author(crate_authors!())
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(
+            Arg::with_name("name")
+                .value_name("NAME")
+                .index(1)
+                .help("View to run (default view of active Todo context when omitted), or title of todo list to read")
+                .takes_value(true),
+        )
+        .arg(width_arg())
+        .subcommand(
+            App::new("save")
+                .about("Save (or replace) a view: todo view save NAME -- LIST_ARGS...")
+                .author(crate_authors!())
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("list arguments")
+                        .value_name("LIST_ARGS")
+                        .help("Arguments of the list command")
+                        .index(2)
+                        .multiple(true)
+                        .last(true),
+                ),
+        )
+        .subcommand(
+            App::new("delete")
+                .about("Delete a view")
+                .author(crate_authors!())
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            App::new("list")
+                .about("List saved views")
+                .author(crate_authors!()),
+        )
+        .subcommand(
+            App::new("default")
+                .about("Set the view run by `todo view` inside active Todo context")
+                .author(crate_authors!())
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .index(1)
+                        .takes_value(true)
+                        .required_unless("unset"),
+                )
+                .arg(
+                    Arg::with_name("unset")
+                        .long("unset")
+                        .help("Remove the default view of active Todo context")
+                        .conflicts_with("name"),
+                ),
+        )
+}
+
+/// Executes subcommands of view command, which manage views of the configuration file
+pub fn view_command_process(
+    args: &ArgMatches,
+    todo_configuration_path: &str,
+    raw_config: Option<&str>,
+) -> Result<(), std::io::Error> {
+    let mut config = parse_configuration_file(Some(todo_configuration_path), raw_config)?;
+
+    if let Some(args) = args.subcommand_matches("save") {
+        trace!("view save");
+        let name = args.value_of("name").unwrap();
+        let list_args = args
+            .values_of("list arguments")
+            .unwrap_or_default()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>();
+        // reject arguments the list command would reject when running the view
+        if let Err(e) = list_matches(&list_args) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                e.message,
+            ));
+        }
+        let replaced = config.views.insert(name.to_string(), list_args).is_some();
+        write_configuration(todo_configuration_path, &config)?;
+        if replaced {
+            println!("Updated view \"{name}\"");
+        } else {
+            println!("Saved view \"{name}\"");
+        }
+        return Ok(());
+    }
+
+    if let Some(args) = args.subcommand_matches("delete") {
+        trace!("view delete");
+        let name = args.value_of("name").unwrap();
+        if config.views.remove(name).is_none() {
+            return Err(unknown_view(name));
+        }
+        for ctx in config.ctxs.iter_mut() {
+            if ctx.default_view.as_deref() == Some(name) {
+                warn!("removing default view of context \"{}\"", ctx.name);
+                ctx.default_view = None;
+            }
+        }
+        write_configuration(todo_configuration_path, &config)?;
+        println!("Deleted view \"{name}\"");
+        return Ok(());
+    }
+
+    if args.subcommand_matches("list").is_some() {
+        trace!("view list");
+        let default_view = config
+            .ctxs
+            .iter()
+            .find(|ctx| ctx.name == config.active_ctx_name)
+            .and_then(|ctx| ctx.default_view.as_deref());
+        for (name, list_args) in config.views.iter() {
+            let marker = if default_view == Some(name.as_str()) {
+                " (default)"
+            } else {
+                ""
+            };
+            println!("{name}{marker}: {}", list_args.join(" "));
+        }
+        return Ok(());
+    }
+
+    if let Some(args) = args.subcommand_matches("default") {
+        trace!("view default");
+        let name = args.value_of("name");
+        if let Some(name) = name {
+            if !config.views.contains_key(name) {
+                return Err(unknown_view(name));
+            }
+        }
+        let active_ctx_name = config.active_ctx_name.clone();
+        let ctx = match config
+            .ctxs
+            .iter_mut()
+            .find(|ctx| ctx.name == active_ctx_name)
+        {
+            Some(ctx) => ctx,
+            None => {
+                return Err(std::io::Error::other(format!(
+                    "Unknown context \"{active_ctx_name}\""
+                )))
+            }
+        };
+        ctx.default_view = name.map(|n| n.to_string());
+        write_configuration(todo_configuration_path, &config)?;
+        match name {
+            Some(name) => println!("Default view of \"{active_ctx_name}\" is \"{name}\""),
+            None => println!("\"{active_ctx_name}\" has no default view"),
+        }
+        return Ok(());
+    }
+
+    warn!("unrecognised command");
+    Err(std::io::Error::other("Unrecognised command"))
+}
+
+/// Prints the Todo list of the Todo context titled like the view given as argument, rendered for
+/// reading, unless a view has that name
+///
+/// Returns false if nothing was printed because the argument is not the title of a Todo list.
+pub fn view_todo_list_process(
+    args: &ArgMatches,
+    ctx: &Context,
+    config: &Configuration,
+) -> Result<bool, std::io::Error> {
+    let title = match args.value_of("name") {
+        Some(title) if !config.views.contains_key(title) => title,
+        _ => return Ok(false),
+    };
+    let todo_raws = if ctx.is_single_file() {
+        SingleFile::read(ctx)?.todo_lists
+    } else if Path::new(ctx.folder_location.as_str()).is_dir() {
+        read_todo_lists(ctx)?
+            .into_iter()
+            .map(|(_, todo_raw)| todo_raw)
+            .collect()
+    } else {
+        vec![]
+    };
+    match todo_raws.iter().find(|t| same_title(title_of(t), title)) {
+        Some(todo_raw) => {
+            trace!("view todo list");
+            let width = match args.value_of("width") {
+                Some(_) => output_width(args).unwrap_or(usize::MAX),
+                None => output_width(args).unwrap_or(DEFAULT_WIDTH),
+            };
+            print!("{}", render(todo_raw, width, use_color()));
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Runs the view given as argument, or the default view of the Todo context
+///
+/// Returns the list arguments of the view and the number of Todo lists printed.
+pub fn run_view_command_process(
+    args: &ArgMatches,
+    ctx: &Context,
+    config: &Configuration,
+) -> Result<(ArgMatches<'static>, usize), std::io::Error> {
+    trace!("view subcommand");
+    let name = match args.value_of("name").or(ctx.default_view.as_deref()) {
+        Some(name) => name,
+        None => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "No view given and context \"{}\" has no default view",
+                    ctx.name
+                ),
+            ))
+        }
+    };
+    let list_args = match config.views.get(name) {
+        Some(list_args) => list_args,
+        None => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No view nor todo list is named \"{name}\""),
+            ))
+        }
+    };
+    debug!("view {name}: {list_args:?}");
+    let matches = match list_matches(list_args) {
+        Ok(matches) => matches,
+        Err(e) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("View \"{name}\" is invalid: {}", e.message),
+            ))
+        }
+    };
+    let count = list_command_process(&matches, config)?;
+    Ok((matches, count))
+}
+
+/// Returns list arguments parsed by the list command
+fn list_matches(list_args: &[String]) -> Result<ArgMatches<'static>, clap::Error> {
+    let mut command_line = vec!["list".to_string()];
+    command_line.extend(list_args.iter().cloned());
+    list_command().get_matches_from_safe(command_line)
+}
+
+fn unknown_view(name: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("Unknown view \"{name}\""),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn save_run_and_delete_views() {
+        let root = TempFolder::new("view");
+        let config_path = root.join("config.toml");
+        let config_path = config_path.to_str().unwrap();
+        std::fs::write(
+            config_path,
+            format!(
+                "active_ctx_name = \"work\"\n\n[[ctxs]]\nname = \"work\"\nide = \"vim\"\ntimezone = \"UTC\"\nfolder_location = \"{}\"\n",
+                root.display()
+            ),
+        )
+        .unwrap();
+
+        let save = view_command()
+            .get_matches_from(vec!["view", "save", "morning", "--", "--open", "--short"]);
+        view_command_process(&save, config_path, None).unwrap();
+        let default = view_command().get_matches_from(vec!["view", "default", "morning"]);
+        view_command_process(&default, config_path, None).unwrap();
+
+        let config = parse_configuration_file(Some(config_path), None).unwrap();
+        assert_eq!(config.views["morning"], vec!["--open", "--short"]);
+        assert_eq!(config.ctxs[0].default_view.as_deref(), Some("morning"));
+
+        let invalid =
+            view_command().get_matches_from(vec!["view", "save", "bad", "--", "--unknown"]);
+        assert!(view_command_process(&invalid, config_path, None).is_err());
+
+        let delete = view_command().get_matches_from(vec!["view", "delete", "morning"]);
+        view_command_process(&delete, config_path, None).unwrap();
+        let config = parse_configuration_file(Some(config_path), None).unwrap();
+        assert!(config.views.is_empty());
+        assert_eq!(config.ctxs[0].default_view, None);
+
+        let run = view_command().get_matches_from(vec!["view"]);
+        assert!(run_view_command_process(&run, &config.ctxs[0], &config).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! Keep Todo lists inside a project
+//!
+//! `todo init --local` creates a `.todo/` folder at the root of the current repository. Inside the
+//! repository, this workspace is the active Todo context (named `local`) whatever the configuration
+//! says, so Todo lists are versioned with the code. No configuration file is needed to use it.
+use clap::{crate_authors, App, Arg, ArgMatches};
+use log::{debug, trace};
+use std::path::{Path, PathBuf};
+
+/// Folder of a project-local workspace
+pub const WORKSPACE_FOLDER: &str = ".todo";
+
+/// Name of the Todo context of a workspace
+pub const WORKSPACE_CTX_NAME: &str = "local";
+
+/// Returns the Init command
+pub fn init_command() -> App<'static, 'static> {
+    App::new("init")
+        .about("Create a project-local Todo workspace")
+        .author(crate_authors!())
+        .arg(
+            Arg::with_name("local")
+                .long("local")
+                .help("Create .todo/ at the root of the current repository (or current folder)")
+                .required(true),
+        )
+}
+
+/// Creates the workspace folder of the current repository
+pub fn init_command_process(_args: &ArgMatches) -> Result<(), std::io::Error> {
+    trace!("init subcommand");
+    let cwd = std::env::current_dir()?;
+    let workspace = project_root(&cwd).join(WORKSPACE_FOLDER);
+    if workspace.is_dir() {
+        println!("Workspace already exists at {}", workspace.display());
+        return Ok(());
+    }
+    std::fs::create_dir_all(&workspace)?;
+    debug!("created {}", workspace.display());
+    println!("Created workspace at {}", workspace.display());
+    Ok(())
+}
+
+/// Returns the workspace folder of the closest folder above `dir` holding one
+pub fn find_workspace(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(WORKSPACE_FOLDER))
+        // the default configuration file is also named .todo
+        .find(|w| w.is_dir())
+}
+
+/// Returns the root of the git repository holding `dir`, or else `dir`
+fn project_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|d| d.join(".git").exists())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempFolder;
+
+    #[test]
+    fn find_workspace_of_repository() {
+        let root = TempFolder::new("workspace");
+        let src = root.join("repo/src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::create_dir_all(root.join("repo/.git")).unwrap();
+        // a configuration file is not a workspace
+        std::fs::write(root.join(WORKSPACE_FOLDER), "").unwrap();
+        assert_eq!(project_root(&src), root.join("repo"));
+        assert_eq!(find_workspace(&src), None);
+
+        std::fs::create_dir_all(root.join("repo").join(WORKSPACE_FOLDER)).unwrap();
+        assert_eq!(
+            find_workspace(&src),
+            Some(root.join("repo").join(WORKSPACE_FOLDER))
+        );
+    }
+}
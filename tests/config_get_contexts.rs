@@ -157,18 +157,21 @@ name: ctx1
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 --- Context ---
 name: ctx2
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 --- Context ---
 name: ctx3
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 "#,
     ));
@@ -204,18 +207,21 @@ name: ctx1
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 --- Context (active) ---
 name: ctx2
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 --- Context ---
 name: ctx3
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 "#,
     ));
@@ -251,18 +257,21 @@ name: ctx1
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 --- Context ---
 name: ctx2
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 --- Context (active) ---
 name: ctx3
 ide: 
 timezone: 
 folder location: 
+status: ✗ folder does not exist
 
 "#,
     ));